@@ -0,0 +1,149 @@
+//! Active learning: choosing the unlabeled lines whose labels would most
+//! improve a model, so that annotators label those first.
+//!
+//! An [`UncertaintySampler`] scores every line of an unlabeled [`Corpus`]
+//! by the uncertainty of a trained model about it, given as the model's
+//! probabilities of each label, and returns the lines it is least sure
+//! of. The model may be anything that gives such probabilities: a
+//! classifier of whole lines, or a tagger of each of their tokens:
+//!
+//! ```rust
+//! let sampler = UncertaintySampler::new(Strategy::Margin);
+//! // Lines for a classifier, by the softmax of its scores.
+//! let queries = sampler.select(&unlabeled, 100, |line| {
+//!   let scores: Vec<f64> = classifier.scores(line).into_iter().map(|(_, score)| score).collect();
+//!   active::softmax(&scores)
+//! });
+//! // Lines for a tagger, by the posteriors of each token's tags.
+//! let queries = sampler.select_sequences(&unlabeled, 100, |line| tagger.posteriors(line));
+//! for query in queries {
+//!   println!("{}:{}\t{:.3}\t{:?}", query.document, query.line, query.uncertainty, query.tokens);
+//! }
+//! ```
+//!
+//! [`UncertaintySampler`]: struct.UncertaintySampler.html
+//! [`Corpus`]: ../struct.Corpus.html
+use token::Token;
+use language::Language;
+use corpus::Line;
+use collection::Corpus;
+
+/// A measure of a model's uncertainty about an input, from its
+/// probability of each label.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Strategy {
+  /// One less the probability of the most probable label.
+  LeastConfidence,
+  /// One less the difference between the probabilities of the two most
+  /// probable labels, which favours inputs between two labels.
+  Margin,
+  /// The entropy of the probabilities, in bits, which weighs every
+  /// label.
+  Entropy,
+}
+
+impl Strategy {
+  /// Returns the uncertainty of a distribution over labels; zero for a
+  /// distribution of no labels.
+  pub fn uncertainty(&self, probabilities: &[f64]) -> f64 {
+    let mut sorted = probabilities.to_vec();
+    sorted.sort_by(|a, b| b.partial_cmp(a).unwrap_or(::std::cmp::Ordering::Equal));
+    match *self {
+      _ if sorted.is_empty() => 0.0,
+      Strategy::LeastConfidence => 1.0 - sorted[0],
+      Strategy::Margin => 1.0 - (sorted[0] - sorted.get(1).cloned().unwrap_or(0.0)),
+      Strategy::Entropy => probabilities.iter()
+        .filter(|&&p| p > 0.0)
+        .map(|&p| -p * p.log2())
+        .sum(),
+    }
+  }
+}
+
+/// A line of a corpus chosen for labeling.
+#[derive(Clone)]
+pub struct Query<'t, L: 't> {
+  /// The position of the line's document in the corpus.
+  pub document: usize,
+  /// The position of the line in its document.
+  pub line: usize,
+  /// The tokens of the line.
+  pub tokens: Line<'t, L>,
+  /// The model's uncertainty about the line.
+  pub uncertainty: f64,
+}
+
+/// Selects the lines of a corpus a model is most uncertain about, for
+/// labeling. Lines without tokens are never selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UncertaintySampler {
+  strategy: Strategy,
+}
+
+impl UncertaintySampler {
+  /// Creates a sampler measuring uncertainty by `strategy`.
+  pub fn new(strategy: Strategy) -> UncertaintySampler {
+    UncertaintySampler { strategy: strategy }
+  }
+
+  /// Returns the `k` lines of highest uncertainty, most uncertain first,
+  /// where `probabilities` gives the model's probability of each label
+  /// of a line. Ties are broken in favour of earlier lines.
+  pub fn select<'t, L, F>(&self, corpus: &'t Corpus<L>, k: usize, probabilities: F) -> Vec<Query<'t, L>>
+    where L: Language,
+          F: Fn(&[Token<'t, L>]) -> Vec<f64>
+  {
+    self.rank(corpus, k, |line| self.strategy.uncertainty(&probabilities(line)))
+  }
+
+  /// Returns the `k` lines of highest uncertainty, most uncertain first,
+  /// where `probabilities` gives the model's probability of each label of
+  /// each token of a line, as a tagger's posteriors do. The uncertainty of
+  /// a line is the mean uncertainty of its tokens, so that long lines are
+  /// not favoured merely for their length.
+  pub fn select_sequences<'t, L, F>(&self, corpus: &'t Corpus<L>, k: usize, probabilities: F) -> Vec<Query<'t, L>>
+    where L: Language,
+          F: Fn(&[Token<'t, L>]) -> Vec<Vec<f64>>
+  {
+    self.rank(corpus, k, |line| {
+      let tokens = probabilities(line);
+      if tokens.is_empty() {
+        return 0.0;
+      }
+      tokens.iter().map(|token| self.strategy.uncertainty(token)).sum::<f64>() / tokens.len() as f64
+    })
+  }
+
+  fn rank<'t, L, F>(&self, corpus: &'t Corpus<L>, k: usize, uncertainty: F) -> Vec<Query<'t, L>>
+    where L: Language,
+          F: Fn(&[Token<'t, L>]) -> f64
+  {
+    let mut queries: Vec<Query<'t, L>> = corpus.lines().into_iter()
+      .filter(|(_, _, tokens)| !tokens.is_empty())
+      .map(|(document, line, tokens)| {
+        let uncertainty = uncertainty(&tokens.to_vec());
+        Query { document: document, line: line, tokens: tokens, uncertainty: uncertainty }
+      })
+      .collect();
+    queries.sort_by(|a, b| {
+      b.uncertainty.partial_cmp(&a.uncertainty)
+        .unwrap_or(::std::cmp::Ordering::Equal)
+        .then((a.document, a.line).cmp(&(b.document, b.line)))
+    });
+    queries.truncate(k);
+    queries
+  }
+}
+
+/// Returns the softmax of scores, a distribution in which each is in
+/// proportion to the exponential of its score, as for the scores of a
+/// linear classifier or the log-probabilities of naive Bayes.
+pub fn softmax(scores: &[f64]) -> Vec<f64> {
+  let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+  if max.is_infinite() {
+    return vec![1.0 / scores.len() as f64; scores.len()];
+  }
+  let exponentials: Vec<f64> = scores.iter().map(|score| (score - max).exp()).collect();
+  let total: f64 = exponentials.iter().sum();
+  exponentials.into_iter().map(|e| e / total).collect()
+}
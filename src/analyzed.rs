@@ -0,0 +1,158 @@
+use language::{Language, DefaultLanguage};
+use corpus::{Document, Tokens};
+use conll;
+use error::LineParseError;
+use morphology::Features;
+
+use std::ops::Range;
+use std::collections::HashMap;
+
+/// A document whose tokens carry a lemma, a part-of-speech tag and
+/// morphological features alongside their surface form. Each layer is
+/// stored as its own array, and the `i`th entry of every layer describes
+/// the `i`th token of the document, so that features can be extracted
+/// from any combination of layers without chasing pointers.
+///
+/// Surface forms and lemmas are tokens of the document's language. Tags
+/// are interned: each is stored as an index into the document's tagset.
+///
+/// ```rust
+/// let treebank: AnalyzedDocument<English> =
+///   read_conllu(&fs::read_to_string("en_ewt-ud-train.conllu")?)?;
+/// for i in treebank.line(0) {
+///   println!("{}\t{}\t{}", treebank.surface()[i], treebank.lemmas()[i], treebank.tag(i));
+/// }
+/// ```
+pub struct AnalyzedDocument<L=DefaultLanguage>
+  where L: 'static
+{
+  surface: Document<L>,
+  lemmas: Document<L>,
+  tags: Vec<usize>,
+  tagset: Vec<String>,
+  features: Vec<Features>,
+  /// The start and end of each line in the token arrays.
+  lines: Vec<(usize, usize)>,
+}
+
+impl<L: Language> AnalyzedDocument<L> {
+  /// Creates a document from lines of `(surface, lemma, tag, features)`
+  /// quadruples. Surface forms and lemmas are stored as tokens of a
+  /// [`Document`], and so should not contain spaces.
+  ///
+  /// [`Document`]: struct.Document.html
+  pub fn new<S>(lines: &[Vec<(S, S, S, Features)>]) -> AnalyzedDocument<L>
+    where S: AsRef<str>
+  {
+    let mut surface = vec![];
+    let mut lemmas = vec![];
+    let mut tags = vec![];
+    let mut tagset: Vec<String> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut features = vec![];
+    let mut bounds = vec![];
+    for line in lines {
+      let start = tags.len();
+      let mut words = vec![];
+      let mut roots = vec![];
+      for (form, lemma, tag, feats) in line {
+        words.push(form.as_ref().as_bytes());
+        roots.push(lemma.as_ref().as_bytes());
+        let tag = tag.as_ref();
+        let id = *index.entry(tag.to_string()).or_insert_with(|| {
+          tagset.push(tag.to_string());
+          tagset.len() - 1
+        });
+        tags.push(id);
+        features.push(feats.clone());
+      }
+      surface.push(words.join(&b' '));
+      lemmas.push(roots.join(&b' '));
+      bounds.push((start, tags.len()));
+    }
+    AnalyzedDocument {
+      surface: surface.join(&b'\n').into(),
+      lemmas: lemmas.join(&b'\n').into(),
+      tags: tags,
+      tagset: tagset,
+      features: features,
+      lines: bounds,
+    }
+  }
+
+  /// Returns the number of tokens in the document.
+  pub fn len(&self) -> usize {
+    self.tags.len()
+  }
+
+  /// Returns `true` if the document has no tokens.
+  pub fn is_empty(&self) -> bool {
+    self.tags.is_empty()
+  }
+
+  /// Returns the number of lines in the document.
+  pub fn line_count(&self) -> usize {
+    self.lines.len()
+  }
+
+  /// Returns the indices of the tokens of line `l`.
+  pub fn line(&self, l: usize) -> Range<usize> {
+    let (start, end) = self.lines[l];
+    start..end
+  }
+
+  /// Returns the surface form of every token.
+  pub fn surface<'t>(&'t self) -> Tokens<'t, L> {
+    self.surface.tokens()
+  }
+
+  /// Returns the lemma of every token.
+  pub fn lemmas<'t>(&'t self) -> Tokens<'t, L> {
+    self.lemmas.tokens()
+  }
+
+  /// Returns the tag of token `i`.
+  pub fn tag(&self, i: usize) -> &str {
+    &self.tagset[self.tags[i]]
+  }
+
+  /// Returns the index of the tag of every token in the tagset.
+  pub fn tag_ids(&self) -> &[usize] {
+    &self.tags
+  }
+
+  /// Returns each distinct tag, in order of first occurrence.
+  pub fn tagset(&self) -> &[String] {
+    &self.tagset
+  }
+
+  /// Returns the morphological features of token `i`.
+  pub fn features(&self, i: usize) -> &Features {
+    &self.features[i]
+  }
+}
+
+/// Reads a file in the CoNLL-U format of the Universal Dependencies
+/// treebanks into an [`AnalyzedDocument`], one line per sentence, taking
+/// the universal part-of-speech tag of each word. Multiword tokens and
+/// empty nodes are skipped, leaving the syntactic words.
+///
+/// [`AnalyzedDocument`]: struct.AnalyzedDocument.html
+pub fn read_conllu<L: Language>(text: &str) -> Result<AnalyzedDocument<L>, LineParseError> {
+  let mut lines = vec![];
+  for block in conll::blocks(text) {
+    let mut line = vec![];
+    for (i, row) in block.rows.iter().enumerate() {
+      if row.len() != 10 {
+        return Err(block.error(i, "expected ten columns"));
+      }
+      if row[0].contains('-') || row[0].contains('.') {
+        continue;
+      }
+      let features = row[5].parse().map_err(|_| block.error(i, "malformed features"))?;
+      line.push((row[1], row[2], row[3], features));
+    }
+    lines.push(line);
+  }
+  Ok(AnalyzedDocument::new(&lines))
+}
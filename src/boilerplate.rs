@@ -0,0 +1,209 @@
+use std::collections::{HashMap, HashSet};
+
+/// The reason a line was classified as boilerplate.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Boilerplate {
+  /// Too much of the line's text is links.
+  Links,
+  /// The line occurs in too many documents.
+  Repeated,
+  /// Too few of the line's words are stopwords, as in navigation menus
+  /// and lists of tags.
+  Stopwords,
+}
+
+/// A line-level classifier of boilerplate in scraped web text, such as
+/// navigation menus, footers and cookie notices, for filtering corpora
+/// before tokenization.
+///
+/// Lines are classified by three heuristics: the share of their text
+/// within HTML `<a>` elements or URLs, the share of documents of the
+/// collection they occur in, and the share of their words that are
+/// stopwords. Blank lines are never boilerplate.
+///
+/// ```rust
+/// let filter = BoilerplateFilter::new()
+///   .with_stopwords(vec!["the", "of", "and", "a", "to", "in", "is", "it"]);
+/// let cleaned = filter.filter(&pages);
+/// let documents: Vec<Document<English>> = cleaned.into_iter().map(Document::from).collect();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoilerplateFilter {
+  max_link_density: f64,
+  max_repetition: f64,
+  min_stopword_ratio: f64,
+  stopwords: HashSet<String>,
+}
+
+impl BoilerplateFilter {
+  /// Creates a filter removing lines that are more than half links, and
+  /// lines occurring in more than a tenth of the documents, with no
+  /// stopwords.
+  pub fn new() -> BoilerplateFilter {
+    BoilerplateFilter {
+      max_link_density: 0.5,
+      max_repetition: 0.1,
+      min_stopword_ratio: 0.1,
+      stopwords: HashSet::new(),
+    }
+  }
+
+  /// Sets the greatest share of a line's text that may be links.
+  pub fn with_max_link_density(mut self, density: f64) -> BoilerplateFilter {
+    self.max_link_density = density;
+    self
+  }
+
+  /// Sets the greatest share of documents a line may occur in. Lines
+  /// occurring in only one document are never repeated.
+  pub fn with_max_repetition(mut self, share: f64) -> BoilerplateFilter {
+    self.max_repetition = share;
+    self
+  }
+
+  /// Sets the stopwords of the language, which are compared with the
+  /// lowercased words of each line. Without stopwords, the stopword
+  /// heuristic is not applied.
+  pub fn with_stopwords<I, S>(mut self, stopwords: I) -> BoilerplateFilter
+    where I: IntoIterator<Item=S>,
+          S: AsRef<str>
+  {
+    self.stopwords = stopwords.into_iter().map(|s| s.as_ref().to_lowercase()).collect();
+    self
+  }
+
+  /// Sets the least share of a line's words that must be stopwords.
+  pub fn with_min_stopword_ratio(mut self, ratio: f64) -> BoilerplateFilter {
+    self.min_stopword_ratio = ratio;
+    self
+  }
+
+  /// Returns the share of the visible characters of a line that are
+  /// within `<a>` elements or URLs. Characters within other HTML tags are
+  /// not visible.
+  pub fn link_density(&self, line: &str) -> f64 {
+    let (linked, total) = words(line).iter()
+      .fold((0, 0), |(linked, total), &(ref word, link)| {
+        let length = word.chars().count();
+        (if link || is_url(word) { linked + length } else { linked }, total + length)
+      });
+    if total == 0 { 0.0 } else { linked as f64 / total as f64 }
+  }
+
+  /// Returns the share of the words of a line that are stopwords, or
+  /// `None` if the filter has no stopwords or the line has no words.
+  pub fn stopword_ratio(&self, line: &str) -> Option<f64> {
+    let words = words(line);
+    if self.stopwords.is_empty() || words.is_empty() {
+      return None;
+    }
+    let stopwords = words.iter()
+      .filter(|&(word, _)| {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        self.stopwords.contains(&word)
+      })
+      .count();
+    Some(stopwords as f64 / words.len() as f64)
+  }
+
+  /// Classifies every line of every document, giving the reason each
+  /// boilerplate line is boilerplate, and `None` for every other line.
+  pub fn classify<S: AsRef<str>>(&self, documents: &[S]) -> Vec<Vec<Option<Boilerplate>>> {
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for document in documents {
+      let lines: HashSet<String> = document.as_ref().lines().map(normalize).collect();
+      for line in lines {
+        *occurrences.entry(line).or_insert(0) += 1;
+      }
+    }
+    let max_documents = self.max_repetition * documents.len() as f64;
+
+    documents.iter()
+      .map(|document| {
+        document.as_ref().lines()
+          .map(|line| {
+            if line.trim().is_empty() {
+              return None;
+            }
+            let documents = occurrences[&normalize(line)];
+            if self.link_density(line) > self.max_link_density {
+              Some(Boilerplate::Links)
+            } else if documents > 1 && documents as f64 > max_documents {
+              Some(Boilerplate::Repeated)
+            } else {
+              match self.stopword_ratio(line) {
+                Some(ratio) if ratio < self.min_stopword_ratio => Some(Boilerplate::Stopwords),
+                _ => None
+              }
+            }
+          })
+          .collect()
+      })
+      .collect()
+  }
+
+  /// Removes the boilerplate lines of every document.
+  pub fn filter<S: AsRef<str>>(&self, documents: &[S]) -> Vec<String> {
+    documents.iter()
+      .zip(self.classify(documents))
+      .map(|(document, verdicts)| {
+        document.as_ref().lines()
+          .zip(verdicts)
+          .filter(|&(_, verdict)| verdict.is_none())
+          .map(|(line, _)| line)
+          .collect::<Vec<_>>()
+          .join("\n")
+      })
+      .collect()
+  }
+}
+
+impl Default for BoilerplateFilter {
+  fn default() -> BoilerplateFilter {
+    BoilerplateFilter::new()
+  }
+}
+
+/// Returns a line with its whitespace collapsed and its case folded, so
+/// that trivially different repetitions compare equal.
+fn normalize(line: &str) -> String {
+  line.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Returns `true` if a word looks like a URL.
+fn is_url(word: &str) -> bool {
+  word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+}
+
+/// Returns the visible words of a line, outside of HTML tags, and whether
+/// each lies wholly within an `<a>` element.
+fn words(line: &str) -> Vec<(String, bool)> {
+  let mut words = vec![];
+  let mut word = String::new();
+  let mut linked = true;
+  let mut in_link = false;
+  let mut chars = line.chars();
+  while let Some(c) = chars.next() {
+    if c == '<' {
+      let tag: String = chars.by_ref().take_while(|&c| c != '>').collect();
+      let tag = tag.trim().to_lowercase();
+      if tag == "a" || tag.starts_with("a ") {
+        in_link = true;
+      } else if tag == "/a" {
+        in_link = false;
+      }
+    } else if c.is_whitespace() {
+      if !word.is_empty() {
+        words.push((::std::mem::take(&mut word), linked));
+      }
+      linked = true;
+    } else {
+      word.push(c);
+      linked &= in_link;
+    }
+  }
+  if !word.is_empty() {
+    words.push((word, linked));
+  }
+  words
+}
@@ -0,0 +1,258 @@
+use token::Token;
+use span::Span;
+use language::Language;
+use corpus::AsTokens;
+use gazetteer::Annotation;
+
+/// A condition on the annotation an [`AnnotationRule`] applies to, or
+/// for merging rules, on the first of the two annotations.
+///
+/// [`AnnotationRule`]: struct.AnnotationRule.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+  /// The token before the span is one of the words.
+  Before(Vec<String>),
+  /// The token after the span is one of the words.
+  After(Vec<String>),
+  /// Some token of the span is one of the words.
+  Contains(Vec<String>),
+  /// The span has at least this many tokens.
+  MinLength(usize),
+  /// The condition does not hold.
+  Not(Box<Condition>),
+}
+
+impl Condition {
+  /// Returns `true` if the condition holds of `span` in `line`.
+  pub fn holds<L: Language>(&self, line: &[Token<L>], span: Span) -> bool {
+    match *self {
+      Condition::Before(ref words) =>
+        span.start > 0 && is_one_of(&line[span.start - 1], words),
+      Condition::After(ref words) =>
+        span.end < line.len() && is_one_of(&line[span.end], words),
+      Condition::Contains(ref words) =>
+        span.of(line).iter().any(|token| is_one_of(token, words)),
+      Condition::MinLength(length) => span.len() >= length,
+      Condition::Not(ref condition) => !condition.holds(line, span),
+    }
+  }
+}
+
+/// What a rule does to the annotations it applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+  Relabel(String),
+  Remove,
+  /// Merges the annotation with the next, if the next has the given
+  /// label and only the given words lie between them.
+  Merge { separators: Vec<String>, right: String, label: String },
+  /// Extends the span over adjacent runs of the given words.
+  Extend { before: Vec<String>, after: Vec<String> },
+}
+
+/// A rule rewriting, merging or removing the annotations of a label that
+/// satisfy its conditions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotationRule {
+  label: String,
+  conditions: Vec<Condition>,
+  action: Action,
+}
+
+impl AnnotationRule {
+  /// Creates a rule relabeling annotations labeled `label` as `to`.
+  pub fn relabel(label: &str, to: &str) -> AnnotationRule {
+    AnnotationRule::new(label, Action::Relabel(to.to_string()))
+  }
+
+  /// Creates a rule removing annotations labeled `label`.
+  pub fn remove(label: &str) -> AnnotationRule {
+    AnnotationRule::new(label, Action::Remove)
+  }
+
+  /// Creates a rule merging an annotation labeled `left` with the next
+  /// annotation of the line, if it is labeled `right` and every token
+  /// between them is one of `separators`, into one annotation labeled
+  /// `label`. Annotations with no tokens between them are always merged.
+  pub fn merge<I, S>(left: &str, separators: I, right: &str, label: &str) -> AnnotationRule
+    where I: IntoIterator<Item=S>,
+          S: AsRef<str>
+  {
+    AnnotationRule::new(left, Action::Merge {
+      separators: strings(separators),
+      right: right.to_string(),
+      label: label.to_string(),
+    })
+  }
+
+  /// Creates a rule extending annotations labeled `label` over the run of
+  /// words among `before` immediately preceding them and the run of words
+  /// among `after` immediately following them, such as titles before
+  /// names. Spans are never extended over other annotations.
+  pub fn extend<I, J, S, T>(label: &str, before: I, after: J) -> AnnotationRule
+    where I: IntoIterator<Item=S>,
+          J: IntoIterator<Item=T>,
+          S: AsRef<str>,
+          T: AsRef<str>
+  {
+    AnnotationRule::new(label, Action::Extend { before: strings(before), after: strings(after) })
+  }
+
+  /// Adds a condition that annotations must satisfy for the rule to
+  /// apply to them.
+  pub fn when(mut self, condition: Condition) -> AnnotationRule {
+    self.conditions.push(condition);
+    self
+  }
+
+  fn new(label: &str, action: Action) -> AnnotationRule {
+    AnnotationRule { label: label.to_string(), conditions: vec![], action: action }
+  }
+
+  /// Returns `true` if the rule applies to `annotation` in `line`.
+  fn matches<L: Language>(&self, line: &[Token<L>], annotation: &Annotation) -> bool {
+    annotation.label == self.label
+      && self.conditions.iter().all(|condition| condition.holds(line, annotation.span))
+  }
+
+  /// Applies the rule to the annotations of a line, ordered by position.
+  fn apply<L: Language>(&self, line: &[Token<L>], annotations: Vec<Annotation>) -> Vec<Annotation> {
+    match self.action {
+      Action::Relabel(ref to) => annotations.into_iter()
+        .map(|annotation| if self.matches(line, &annotation) {
+          Annotation { label: to.clone(), span: annotation.span }
+        } else {
+          annotation
+        })
+        .collect(),
+      Action::Remove => annotations.into_iter()
+        .filter(|annotation| !self.matches(line, annotation))
+        .collect(),
+      Action::Merge { ref separators, ref right, ref label } => {
+        let mut merged: Vec<Annotation> = vec![];
+        for annotation in annotations {
+          if let Some(last) = merged.last_mut() {
+            let gap = Span::new(last.span.end, annotation.span.start);
+            if self.matches(line, last) && annotation.label == *right
+              && last.span.end <= annotation.span.start
+              && gap.of(line).iter().all(|token| is_one_of(token, separators))
+            {
+              last.span.end = annotation.span.end;
+              last.label = label.clone();
+              continue;
+            }
+          }
+          merged.push(annotation);
+        }
+        merged
+      },
+      Action::Extend { ref before, ref after } => {
+        let mut extended: Vec<Annotation> = vec![];
+        for i in 0..annotations.len() {
+          let mut annotation = annotations[i].clone();
+          if self.matches(line, &annotation) {
+            let floor = extended.last().map(|previous| previous.span.end).unwrap_or(0);
+            let ceiling = annotations.get(i + 1)
+              .map(|next| next.span.start)
+              .unwrap_or(line.len());
+            while annotation.span.start > floor
+              && is_one_of(&line[annotation.span.start - 1], before)
+            {
+              annotation.span.start -= 1;
+            }
+            while annotation.span.end < ceiling && is_one_of(&line[annotation.span.end], after) {
+              annotation.span.end += 1;
+            }
+          }
+          extended.push(annotation);
+        }
+        extended
+      }
+    }
+  }
+}
+
+/// A cascade of rules for post-processing span annotations, such as
+/// those of a [`DictionaryAnnotator`] or a named-entity tagger. Each rule
+/// is applied in turn to the annotations left by the rules before it.
+///
+/// ```rust
+/// let cascade = RuleCascade::new()
+///   .with_rule(AnnotationRule::extend("PER", vec!["Dr.", "Prof."], Vec::<&str>::new()))
+///   .with_rule(AnnotationRule::merge("PER", vec!["van", "der", "de"], "PER", "PER"))
+///   .with_rule(AnnotationRule::relabel("PER", "ORG").when(Condition::After(vec!["Inc.".to_string()])))
+///   .with_rule(AnnotationRule::remove("PER").when(Condition::Not(Box::new(Condition::MinLength(2)))));
+///
+/// // "Dr. [PER Ludwig] van der [PER Rohe]" becomes "[PER Dr. Ludwig van der Rohe]"
+/// let annotations = cascade.apply(line, annotator.annotate_line(line));
+/// ```
+///
+/// [`DictionaryAnnotator`]: struct.DictionaryAnnotator.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleCascade {
+  rules: Vec<AnnotationRule>,
+}
+
+impl RuleCascade {
+  /// Creates a cascade of no rules.
+  pub fn new() -> RuleCascade {
+    RuleCascade { rules: vec![] }
+  }
+
+  /// Adds a rule, applied after every rule added before it.
+  pub fn with_rule(mut self, rule: AnnotationRule) -> RuleCascade {
+    self.rules.push(rule);
+    self
+  }
+
+  /// Returns the rules of the cascade, in order of application.
+  pub fn rules(&self) -> &[AnnotationRule] {
+    &self.rules
+  }
+
+  /// Applies every rule to the annotations of a line, returning the
+  /// resulting annotations ordered by position.
+  pub fn apply<L: Language>(&self, line: &[Token<L>], mut annotations: Vec<Annotation>)
+      -> Vec<Annotation>
+  {
+    annotations.sort_by_key(|annotation| annotation.span);
+    self.rules.iter().fold(annotations, |annotations, rule| rule.apply(line, annotations))
+  }
+
+  /// Applies every rule to the annotations of every line of a document,
+  /// such as those returned by [`DictionaryAnnotator::annotate`].
+  ///
+  /// [`DictionaryAnnotator::annotate`]: struct.DictionaryAnnotator.html#method.annotate
+  pub fn apply_all<'t, I, L>(&self, lines: I, annotations: Vec<Vec<Annotation>>)
+      -> Vec<Vec<Annotation>>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>,
+          L: 't + Language
+  {
+    lines.into_iter()
+      .zip(annotations)
+      .map(|(line, annotations)| self.apply(&line.as_tokens(), annotations))
+      .collect()
+  }
+}
+
+impl Default for RuleCascade {
+  fn default() -> RuleCascade {
+    RuleCascade::new()
+  }
+}
+
+/// Returns `true` if a token is a word among `words`.
+fn is_one_of<L>(token: &Token<L>, words: &[String]) -> bool {
+  match *token {
+    Token::Word(ref word) => words.iter().any(|w| w.as_bytes() == word.as_bytes()),
+    _ => false
+  }
+}
+
+fn strings<I, S>(words: I) -> Vec<String>
+  where I: IntoIterator<Item=S>,
+        S: AsRef<str>
+{
+  words.into_iter().map(|word| word.as_ref().to_string()).collect()
+}
@@ -0,0 +1,413 @@
+use perceptron::Perceptron;
+use error::{ParseError, LineParseError};
+
+use std::fmt;
+use std::str::FromStr;
+use std::collections::HashMap;
+
+/// The direction in which a functor category seeks its argument.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Slash {
+  /// `/`: the argument is to the right.
+  Forward,
+  /// `\`: the argument is to the left.
+  Backward,
+}
+
+/// A combinatory categorial grammar category, such as `NP`, `S[dcl]` or
+/// `(S[dcl]\NP)/NP`.
+///
+/// ```rust
+/// let transitive: Category = "(S[dcl]\\NP)/NP".parse()?;
+/// assert_eq!(transitive.argument().unwrap().to_string(), "NP");
+/// assert_eq!(transitive.result().unwrap().to_string(), "S[dcl]\\NP");
+/// ```
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+  /// An atomic category, with an optional feature, like the `dcl` of
+  /// `S[dcl]`.
+  Atomic(String, Option<String>),
+  /// A functor category: a result, a slash, and an argument.
+  Functor(Box<Category>, Slash, Box<Category>),
+}
+
+impl Category {
+  /// Returns `true` if this category is atomic.
+  pub fn is_atomic(&self) -> bool {
+    match *self {
+      Category::Atomic(..) => true,
+      Category::Functor(..) => false,
+    }
+  }
+
+  /// Returns the result of a functor category.
+  pub fn result(&self) -> Option<&Category> {
+    match *self {
+      Category::Functor(ref result, _, _) => Some(result),
+      Category::Atomic(..) => None,
+    }
+  }
+
+  /// Returns the argument of a functor category.
+  pub fn argument(&self) -> Option<&Category> {
+    match *self {
+      Category::Functor(_, _, ref argument) => Some(argument),
+      Category::Atomic(..) => None,
+    }
+  }
+
+  /// Returns the slash of a functor category.
+  pub fn slash(&self) -> Option<Slash> {
+    match *self {
+      Category::Functor(_, slash, _) => Some(slash),
+      Category::Atomic(..) => None,
+    }
+  }
+
+  /// Returns the number of arguments this category takes before it is
+  /// atomic.
+  pub fn arity(&self) -> usize {
+    match *self {
+      Category::Functor(ref result, _, _) => 1 + result.arity(),
+      Category::Atomic(..) => 0,
+    }
+  }
+}
+
+impl fmt::Display for Category {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Category::Atomic(ref label, None) => write!(f, "{}", label),
+      Category::Atomic(ref label, Some(ref feature)) =>
+        write!(f, "{}[{}]", label, feature),
+      Category::Functor(ref result, slash, ref argument) => {
+        // Functors are bracketed as in CCGbank, except at the top level.
+        for (i, category) in [result, argument].iter().enumerate() {
+          if i == 1 {
+            write!(f, "{}", match slash { Slash::Forward => '/', Slash::Backward => '\\' })?;
+          }
+          if category.is_atomic() { write!(f, "{}", category)?; }
+          else { write!(f, "({})", category)?; }
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+impl FromStr for Category {
+  type Err = ParseError;
+
+  /// Reads a category in the notation of CCGbank. Slashes associate to
+  /// the left, so `S\NP/NP` is read as `(S\NP)/NP`.
+  fn from_str(s: &str) -> Result<Category, ParseError> {
+    let mut parser = CategoryParser { text: s, offset: 0 };
+    let category = parser.category()?;
+    if parser.offset != s.len() {
+      return Err(parser.error("unexpected text after category"));
+    }
+    Ok(category)
+  }
+}
+
+struct CategoryParser<'s> {
+  text: &'s str,
+  offset: usize,
+}
+
+impl<'s> CategoryParser<'s> {
+  fn error(&self, message: &'static str) -> ParseError {
+    ParseError { offset: self.offset, message: message }
+  }
+
+  fn rest(&self) -> &'s str {
+    &self.text[self.offset..]
+  }
+
+  fn category(&mut self) -> Result<Category, ParseError> {
+    let mut category = self.operand()?;
+    loop {
+      let slash = match self.rest().chars().next() {
+        Some('/') => Slash::Forward,
+        Some('\\') => Slash::Backward,
+        _ => return Ok(category)
+      };
+      self.offset += 1;
+      let argument = self.operand()?;
+      category = Category::Functor(Box::new(category), slash, Box::new(argument));
+    }
+  }
+
+  fn operand(&mut self) -> Result<Category, ParseError> {
+    if self.rest().starts_with('(') {
+      self.offset += 1;
+      let category = self.category()?;
+      if !self.rest().starts_with(')') {
+        return Err(self.error("expected `)`"));
+      }
+      self.offset += 1;
+      return Ok(category);
+    }
+
+    let rest = self.rest();
+    let end = rest.find(|c| "/\\()[]".contains(c) || char::is_whitespace(c))
+      .unwrap_or(rest.len());
+    if end == 0 {
+      return Err(self.error("expected a category"));
+    }
+    self.offset += end;
+
+    let mut feature = None;
+    if self.rest().starts_with('[') {
+      let close = match self.rest().find(']') {
+        Some(close) => close,
+        None => return Err(self.error("expected `]`"))
+      };
+      feature = Some(self.rest()[1..close].to_string());
+      self.offset += close + 1;
+    }
+    Ok(Category::Atomic(rest[..end].to_string(), feature))
+  }
+}
+
+/// The categories each word has been observed with, and how often.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CategoryLexicon {
+  words: HashMap<String, HashMap<Category, usize>>,
+  /// The total count of each category, over all words.
+  categories: HashMap<Category, usize>,
+}
+
+impl CategoryLexicon {
+  /// Creates an empty lexicon.
+  pub fn new() -> CategoryLexicon {
+    CategoryLexicon::default()
+  }
+
+  /// Reads a lexicon with one entry per line: a word, a category, and,
+  /// optionally, a count, separated by whitespace. Entries without a
+  /// count are counted once. Blank lines and lines beginning with `#`
+  /// are ignored.
+  ///
+  /// ```text
+  /// saw (S[dcl]\NP)/NP 412
+  /// saw N 3
+  /// ```
+  pub fn from_text(text: &str) -> Result<CategoryLexicon, LineParseError> {
+    let mut lexicon = CategoryLexicon::new();
+    for (i, line) in text.lines().enumerate() {
+      let error = |message| LineParseError { line: i + 1, message: message };
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let mut fields = line.split_whitespace();
+      let word = fields.next().ok_or_else(|| error("expected a word"))?;
+      let category = fields.next()
+        .ok_or_else(|| error("expected a category"))?
+        .parse()
+        .map_err(|_| error("malformed category"))?;
+      let count = match fields.next() {
+        Some(count) => count.parse().map_err(|_| error("malformed count"))?,
+        None => 1
+      };
+      if fields.next().is_some() {
+        return Err(error("unexpected text after count"));
+      }
+      lexicon.insert(word, category, count);
+    }
+    Ok(lexicon)
+  }
+
+  /// Records `count` more occurrences of `word` with `category`.
+  pub fn insert(&mut self, word: &str, category: Category, count: usize) {
+    *self.categories.entry(category.clone()).or_insert(0) += count;
+    *self.words.entry(word.to_string()).or_default()
+      .entry(category).or_insert(0) += count;
+  }
+
+  /// Returns the number of distinct words in the lexicon.
+  pub fn len(&self) -> usize {
+    self.words.len()
+  }
+
+  /// Returns `true` if the lexicon has no entries.
+  pub fn is_empty(&self) -> bool {
+    self.words.is_empty()
+  }
+
+  /// Returns the number of times `word` has been observed.
+  pub fn count(&self, word: &str) -> usize {
+    self.words.get(word).map(|categories| categories.values().sum()).unwrap_or(0)
+  }
+
+  /// Returns the categories of `word`, from most to least frequent.
+  pub fn categories(&self, word: &str) -> Vec<(&Category, usize)> {
+    let mut categories: Vec<(&Category, usize)> = self.words.get(word)
+      .map(|categories| categories.iter().map(|(c, &n)| (c, n)).collect())
+      .unwrap_or_default();
+    categories.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    categories
+  }
+
+  /// Returns every category observed at least `min_count` times in
+  /// total, from most to least frequent.
+  pub fn frequent(&self, min_count: usize) -> Vec<&Category> {
+    let mut categories: Vec<(&Category, usize)> = self.categories.iter()
+      .filter(|&(_, &n)| n >= min_count)
+      .map(|(c, &n)| (c, n))
+      .collect();
+    categories.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    categories.into_iter().map(|(c, _)| c).collect()
+  }
+}
+
+/// Words seen fewer times than this in the lexicon may be assigned any
+/// frequent category, rather than only those they were seen with.
+const RARE_WORD: usize = 20;
+
+/// Categories seen fewer times than this in the lexicon are never
+/// assigned to rare words.
+const RARE_CATEGORY: usize = 10;
+
+/// A supertagger, assigning a lexical [`Category`] to each word of a
+/// sentence: a greedy, left-to-right tagger scored by an averaged
+/// perceptron over features of the surrounding words and the two
+/// previous categories, following Clark and Curran (2007) in restricting
+/// frequent words to the categories the lexicon records for them.
+///
+/// ```rust
+/// let lexicon = CategoryLexicon::from_text(&fs::read_to_string("lexicon.txt")?)?;
+/// let tagger = Supertagger::train(lexicon, &training, 10);
+/// let categories = tagger.tag(&["John", "saw", "Mary"]);
+/// ```
+///
+/// [`Category`]: enum.Category.html
+#[derive(Debug, Clone)]
+pub struct Supertagger {
+  model: Perceptron<Category>,
+  lexicon: CategoryLexicon,
+  /// The categories permitted for rare and unknown words.
+  open: Vec<Category>,
+}
+
+impl Supertagger {
+  /// Trains a supertagger on sentences of `(word, category)` pairs, for
+  /// `iterations` passes. Categories are restricted by `lexicon`, which is
+  /// extended with the training data.
+  pub fn train<W>(mut lexicon: CategoryLexicon, sentences: &[Vec<(W, Category)>],
+                  iterations: usize) -> Supertagger
+    where W: AsRef<str>
+  {
+    for sentence in sentences {
+      for (word, category) in sentence {
+        lexicon.insert(word.as_ref(), category.clone(), 1);
+      }
+    }
+    let mut open: Vec<Category> = lexicon.frequent(RARE_CATEGORY)
+      .into_iter().cloned().collect();
+    if open.is_empty() {
+      open = lexicon.frequent(0).into_iter().cloned().collect();
+    }
+
+    let mut tagger = Supertagger {
+      model: Perceptron::new(),
+      lexicon: lexicon,
+      open: open,
+    };
+    for _ in 0..iterations {
+      for sentence in sentences {
+        let words: Vec<&str> = sentence.iter().map(|(w, _)| w.as_ref()).collect();
+        let mut history: Vec<Category> = vec![];
+        for (i, (_, gold)) in sentence.iter().enumerate() {
+          let features = features(&words, i, &history);
+          let guess = tagger.predict(&features, words[i]);
+          if guess.as_ref() != Some(gold) {
+            tagger.model.update(&features, gold, 1.0);
+            if let Some(ref guess) = guess {
+              tagger.model.update(&features, guess, -1.0);
+            }
+          }
+          tagger.model.tick();
+          history.push(gold.clone());
+        }
+      }
+    }
+    tagger.model.average();
+    tagger
+  }
+
+  /// Returns the categories permitted for `word`.
+  fn candidates(&self, word: &str) -> Vec<Category> {
+    if self.lexicon.count(word) >= RARE_WORD {
+      self.lexicon.categories(word).into_iter().map(|(c, _)| c.clone()).collect()
+    } else {
+      self.open.clone()
+    }
+  }
+
+  fn predict(&self, features: &[String], word: &str) -> Option<Category> {
+    self.model.predict(features, &self.candidates(word))
+  }
+
+  /// Assigns a category to each word of a sentence. Words that are
+  /// permitted no categories, which can only happen with an empty
+  /// lexicon, are assigned `None`.
+  pub fn tag<W: AsRef<str>>(&self, words: &[W]) -> Vec<Option<Category>> {
+    let words: Vec<&str> = words.iter().map(AsRef::as_ref).collect();
+    let mut history = vec![];
+    let mut tags = vec![];
+    for i in 0..words.len() {
+      let features = features(&words, i, &history);
+      let guess = self.predict(&features, words[i]);
+      history.push(guess.clone().unwrap_or_else(|| Category::Atomic(String::from("<none>"), None)));
+      tags.push(guess);
+    }
+    tags
+  }
+}
+
+/// Extracts the features of the word at position `i`, given the
+/// categories assigned to the words before it.
+fn features(words: &[&str], i: usize, history: &[Category]) -> Vec<String> {
+  let word = |offset: isize| {
+    let j = i as isize + offset;
+    if j < 0 { "<s>" } else { words.get(j as usize).cloned().unwrap_or("</s>") }
+  };
+  let previous = |n: usize| {
+    if i < n { String::from("<s>") } else { history[i - n].to_string() }
+  };
+
+  let w = word(0);
+  let lower = w.to_lowercase();
+  let chars: Vec<char> = lower.chars().collect();
+  let suffix = |n: usize| chars[chars.len().saturating_sub(n)..].iter().collect::<String>();
+  let prefix = |n: usize| chars[..n.min(chars.len())].iter().collect::<String>();
+  let shape = if w.chars().all(|c| c.is_numeric()) { "digits" }
+    else if w.starts_with(char::is_uppercase) { "capitalized" }
+    else if w.contains('-') { "hyphenated" }
+    else { "other" };
+  let (c1, c2) = (previous(1), previous(2));
+
+  vec![
+    String::from("bias"),
+    format!("w={}", w),
+    format!("lower={}", lower),
+    format!("shape={}", shape),
+    format!("prefix1={}", prefix(1)),
+    format!("prefix2={}", prefix(2)),
+    format!("prefix3={}", prefix(3)),
+    format!("suffix1={}", suffix(1)),
+    format!("suffix2={}", suffix(2)),
+    format!("suffix3={}", suffix(3)),
+    format!("w-1={}", word(-1)),
+    format!("w-2={}", word(-2)),
+    format!("w+1={}", word(1)),
+    format!("w+2={}", word(2)),
+    format!("c-1={}", c1),
+    format!("c-1,c-2={},{}", c1, c2),
+    format!("c-1,w={},{}", c1, w),
+    format!("w-1,w={},{}", word(-1), w),
+    format!("w,w+1={},{}", w, word(1)),
+  ]
+}
@@ -0,0 +1,722 @@
+//! Linear classification of text by features, such as the sentiment of
+//! a line, the language of a word or the part of speech of a token in
+//! context.
+//!
+//! A [`FeatureExtractor`] turns an input into features, strings such as
+//! `"w=dog"`, as the [`Perceptron`] expects, and a [`Classifier`] learns
+//! a weight for each feature and class, or a [`NaiveBayes`] classifier
+//! the probability of each feature in each class. Extractors for common
+//! inputs are provided, and any function returning features is an
+//! extractor:
+//!
+//! ```rust
+//! let examples: Vec<(&[Token<English>], Sentiment)> = reviews.iter()
+//!   .map(|&(ref line, sentiment)| (&line[..], sentiment))
+//!   .collect();
+//! let classifier = Classifier::train(BagOfWords::new().with_bigrams(), examples, 10);
+//! let review: Document<English> = "a dull and tedious film".into();
+//! println!("{:?}", classifier.classify(&review.tokens().to_vec()[..]));
+//! ```
+//!
+//! The scores of either classifier may be made probabilities by a
+//! [`ClassCalibration`] fit on held-out examples.
+//!
+//! [`FeatureExtractor`]: trait.FeatureExtractor.html
+//! [`Perceptron`]: ../perceptron/struct.Perceptron.html
+//! [`Classifier`]: struct.Classifier.html
+//! [`NaiveBayes`]: struct.NaiveBayes.html
+//! [`ClassCalibration`]: struct.ClassCalibration.html
+use token::Token;
+use language::Language;
+use subword::char_ngrams;
+use perceptron::Perceptron;
+
+use std::hash::Hash;
+use std::collections::{HashMap, HashSet};
+
+/// Extracts the features of inputs of type `T`, such as lines of tokens
+/// or words, for a [`Classifier`]. Each feature should name what it
+/// describes, as in `"w=dog"` or `"suffix=ing"`, as features of every
+/// kind share one space of weights.
+///
+/// [`Classifier`]: struct.Classifier.html
+pub trait FeatureExtractor<T: ?Sized> {
+  /// Returns the features of an input. A feature may be repeated, to
+  /// count it more than once.
+  fn features(&self, input: &T) -> Vec<String>;
+}
+
+impl<T: ?Sized, F> FeatureExtractor<T> for F
+  where F: Fn(&T) -> Vec<String>
+{
+  fn features(&self, input: &T) -> Vec<String> {
+    self(input)
+  }
+}
+
+/// Extracts the words of a line of tokens, and optionally its bigrams,
+/// as for classifying its topic or sentiment. `Null` tokens are skipped,
+/// and bigrams do not span them.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct BagOfWords {
+  bigrams: bool,
+}
+
+impl BagOfWords {
+  /// Creates an extractor of words alone.
+  pub fn new() -> BagOfWords {
+    BagOfWords::default()
+  }
+
+  /// Extracts the bigrams of the line as well, which capture some
+  /// negation and modification, as in *not good*.
+  pub fn with_bigrams(mut self) -> BagOfWords {
+    self.bigrams = true;
+    self
+  }
+}
+
+impl<'t, L: Language> FeatureExtractor<[Token<'t, L>]> for BagOfWords {
+  fn features(&self, line: &[Token<'t, L>]) -> Vec<String> {
+    let mut features: Vec<String> = line.iter()
+      .filter(|&&token| token != Token::Null)
+      .map(|token| format!("w={}", token))
+      .collect();
+    if self.bigrams {
+      features.extend(line.windows(2)
+        .filter(|pair| !pair.contains(&Token::Null))
+        .map(|pair| format!("b={} {}", pair[0], pair[1])));
+    }
+    features
+  }
+}
+
+/// Extracts the character n-grams of one up to `n` characters of each
+/// word of a text, lowercased and marked at the edges of words, as for
+/// identifying its language.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct CharacterNgrams {
+  n: usize,
+}
+
+impl CharacterNgrams {
+  /// Creates an extractor of n-grams of one up to `n` characters. Values
+  /// of `n` below `1` are treated as `1`.
+  pub fn new(n: usize) -> CharacterNgrams {
+    CharacterNgrams { n: n.max(1) }
+  }
+}
+
+impl FeatureExtractor<str> for CharacterNgrams {
+  fn features(&self, text: &str) -> Vec<String> {
+    let mut features = vec![];
+    for word in text.split_whitespace() {
+      let word = word.to_lowercase();
+      for n in 1..=self.n {
+        features.extend(char_ngrams(&word, n).with_boundaries('<', '>').map(|gram| format!("c={}", gram)));
+      }
+    }
+    features
+  }
+}
+
+/// Extracts the features of a token in its line, given as the line and
+/// the token's position, as for tagging its part of speech: the word, its
+/// lowercase form, prefix and suffixes, its shape, and the words around
+/// it, up to `window` on each side.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct WordContext {
+  window: usize,
+}
+
+impl WordContext {
+  /// Creates an extractor of the words up to two positions on each side.
+  pub fn new() -> WordContext {
+    WordContext { window: 2 }
+  }
+
+  /// Extracts the words up to `window` positions on each side.
+  pub fn with_window(mut self, window: usize) -> WordContext {
+    self.window = window;
+    self
+  }
+}
+
+impl Default for WordContext {
+  fn default() -> WordContext {
+    WordContext::new()
+  }
+}
+
+impl<'a, 't, L: Language> FeatureExtractor<(&'a [Token<'t, L>], usize)> for WordContext {
+  fn features(&self, input: &(&'a [Token<'t, L>], usize)) -> Vec<String> {
+    let (line, i) = *input;
+    let word = line.get(i).map_or(String::new(), |token| token.to_string());
+    let lower = word.to_lowercase();
+    let chars: Vec<char> = lower.chars().collect();
+    let mut features = vec![
+      "bias".to_string(),
+      format!("w={}", word),
+      format!("lower={}", lower),
+      format!("prefix={}", chars.iter().take(1).collect::<String>()),
+    ];
+    for length in 1..=3.min(chars.len()) {
+      features.push(format!("suffix{}={}", length, chars[chars.len() - length..].iter().collect::<String>()));
+    }
+    if word.chars().next().is_some_and(char::is_uppercase) {
+      features.push("capitalized".to_string());
+    }
+    if word.chars().any(|c| c.is_ascii_digit()) {
+      features.push("digit".to_string());
+    }
+    if word.contains('-') {
+      features.push("hyphen".to_string());
+    }
+    for offset in 1..=self.window {
+      let before = i.checked_sub(offset).and_then(|j| line.get(j))
+        .map_or("<s>".to_string(), |token| token.to_string());
+      let after = line.get(i + offset).map_or("</s>".to_string(), |token| token.to_string());
+      features.push(format!("w-{}={}", offset, before));
+      features.push(format!("w+{}={}", offset, after));
+    }
+    features
+  }
+}
+
+/// A linear classifier of inputs into classes `C`, over the features of
+/// an extractor `X`, trained as an averaged perceptron.
+///
+/// Training makes several passes over the examples, in order, updating
+/// the weights of the true and predicted classes of each misclassified
+/// example, and then averages the weights over every example seen, which
+/// makes the classifier robust to the order of the examples and to
+/// noise, almost as regularization does.
+///
+/// ```rust
+/// let examples: Vec<(&str, &str)> = vec![("the cat is on the mat", "en"), ("le chat est sur le tapis", "fr")];
+/// let classifier = Classifier::train(CharacterNgrams::new(3), examples, 5);
+/// assert_eq!(classifier.classify("the dog"), Some("en"));
+/// ```
+///
+/// A trained classifier may be trained further on new batches of
+/// examples by [`fit_partial`], which may hold new classes:
+///
+/// ```rust
+/// let mut classifier = Classifier::train(CharacterNgrams::new(3), examples, 5);
+/// classifier.fit_partial(vec![("der Hund ist auf der Matte", "de")], 5);
+/// ```
+///
+/// [`fit_partial`]: #method.fit_partial
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classifier<C, X>
+  where C: Hash + Eq
+{
+  extractor: X,
+  /// The classes of the training examples, in order of first appearance.
+  classes: Vec<C>,
+  /// The weights as trained so far, before averaging, from which
+  /// training continues.
+  training: Perceptron<C>,
+  /// The averaged weights, by which inputs are classified.
+  model: Perceptron<C>,
+}
+
+impl<C, X> Classifier<C, X>
+  where C: Hash + Eq + Clone
+{
+  /// Creates a classifier trained on no examples, which classifies
+  /// nothing until trained by [`fit_partial`].
+  ///
+  /// [`fit_partial`]: #method.fit_partial
+  pub fn new(extractor: X) -> Classifier<C, X> {
+    Classifier {
+      extractor: extractor,
+      classes: vec![],
+      training: Perceptron::new(),
+      model: Perceptron::new(),
+    }
+  }
+
+  /// Trains a classifier on labeled examples by `iterations` passes over
+  /// them. The features of each example are extracted once.
+  pub fn train<'e, I, T>(extractor: X, examples: I, iterations: usize) -> Classifier<C, X>
+    where I: IntoIterator<Item=(&'e T, C)>,
+          T: ?Sized + 'e,
+          X: FeatureExtractor<T>
+  {
+    let mut classifier = Classifier::new(extractor);
+    classifier.fit_partial(examples, iterations);
+    classifier
+  }
+
+  /// Continues training on a new batch of examples, by `iterations`
+  /// passes over them, from the weights trained so far, rather than
+  /// training from scratch. Classes not seen before are added. The
+  /// weights are averaged over every example seen in every batch, so a
+  /// small batch adjusts the classifier rather than replacing it.
+  pub fn fit_partial<'e, I, T>(&mut self, examples: I, iterations: usize)
+    where I: IntoIterator<Item=(&'e T, C)>,
+          T: ?Sized + 'e,
+          X: FeatureExtractor<T>
+  {
+    let classes = &mut self.classes;
+    let extractor = &self.extractor;
+    let examples: Vec<(Vec<String>, C)> = examples.into_iter()
+      .map(|(input, class)| {
+        if !classes.contains(&class) {
+          classes.push(class.clone());
+        }
+        (extractor.features(input), class)
+      })
+      .collect();
+    let model = &mut self.training;
+    for _ in 0..iterations {
+      for (features, gold) in &examples {
+        if let Some(guess) = model.predict(features, classes) {
+          if guess != *gold {
+            model.update(features, gold, 1.0);
+            model.update(features, &guess, -1.0);
+          }
+        }
+        model.tick();
+      }
+    }
+    self.model = self.training.clone();
+    self.model.average();
+  }
+
+  /// Returns the classes the classifier chooses among.
+  pub fn classes(&self) -> &[C] {
+    &self.classes
+  }
+
+  /// Returns the feature extractor.
+  pub fn extractor(&self) -> &X {
+    &self.extractor
+  }
+
+  /// Returns the weights of the classifier.
+  pub fn model(&self) -> &Perceptron<C> {
+    &self.model
+  }
+
+  /// Returns the score of each class for an input, in the order of
+  /// [`classes`].
+  ///
+  /// [`classes`]: #method.classes
+  pub fn scores<T>(&self, input: &T) -> Vec<(C, f64)>
+    where T: ?Sized,
+          X: FeatureExtractor<T>
+  {
+    let scores = self.model.scores(&self.extractor.features(input));
+    self.classes.iter()
+      .map(|class| (class.clone(), scores.get(class).cloned().unwrap_or(0.0)))
+      .collect()
+  }
+
+  /// Returns the class of the highest score for an input, breaking ties
+  /// in favor of the class seen first in training, or `None` if the
+  /// classifier was trained on no examples.
+  pub fn classify<T>(&self, input: &T) -> Option<C>
+    where T: ?Sized,
+          X: FeatureExtractor<T>
+  {
+    self.model.predict(&self.extractor.features(input), &self.classes)
+  }
+}
+
+/// A multinomial naive Bayes classifier of inputs into classes `C`, over
+/// the features of an extractor `X`, with additive smoothing.
+///
+/// Naive Bayes is trained by counting, in a single pass, so it trains
+/// faster than a [`Classifier`] and from fewer examples, though it is
+/// usually less accurate given many. As its counts are kept, it may be
+/// trained further on new batches of examples by [`fit_partial`], with the
+/// same result as training on every batch at once.
+///
+/// ```rust
+/// let mut classifier = NaiveBayes::train(BagOfWords::new(), examples);
+/// classifier.fit_partial(new_examples);
+/// println!("{:?}", classifier.classify(&line[..]));
+/// ```
+///
+/// [`Classifier`]: struct.Classifier.html
+/// [`fit_partial`]: #method.fit_partial
+#[derive(Debug, Clone, PartialEq)]
+pub struct NaiveBayes<C, X> {
+  extractor: X,
+  /// The classes of the training examples, in order of first appearance.
+  classes: Vec<C>,
+  /// The number of examples of each class.
+  examples: Vec<usize>,
+  /// The count of each feature in the examples of each class.
+  counts: Vec<HashMap<String, usize>>,
+  /// The total count of features in the examples of each class.
+  totals: Vec<usize>,
+  /// Every feature observed in any class.
+  features: HashSet<String>,
+  smoothing: f64,
+}
+
+impl<C, X> NaiveBayes<C, X>
+  where C: Eq + Clone
+{
+  /// Creates a classifier trained on no examples, with add-one
+  /// smoothing, which classifies nothing until trained by
+  /// [`fit_partial`].
+  ///
+  /// [`fit_partial`]: #method.fit_partial
+  pub fn new(extractor: X) -> NaiveBayes<C, X> {
+    NaiveBayes {
+      extractor: extractor,
+      classes: vec![],
+      examples: vec![],
+      counts: vec![],
+      totals: vec![],
+      features: HashSet::new(),
+      smoothing: 1.0,
+    }
+  }
+
+  /// Trains a classifier on labeled examples.
+  pub fn train<'e, I, T>(extractor: X, examples: I) -> NaiveBayes<C, X>
+    where I: IntoIterator<Item=(&'e T, C)>,
+          T: ?Sized + 'e,
+          X: FeatureExtractor<T>
+  {
+    let mut classifier = NaiveBayes::new(extractor);
+    classifier.fit_partial(examples);
+    classifier
+  }
+
+  /// Adds `smoothing` to the count of every feature in every class, such
+  /// as `1.0` for add-one smoothing, or less for large feature sets.
+  pub fn with_smoothing(mut self, smoothing: f64) -> NaiveBayes<C, X> {
+    self.smoothing = smoothing;
+    self
+  }
+
+  /// Counts a new batch of examples along with those counted so far.
+  /// Classes not seen before are added.
+  pub fn fit_partial<'e, I, T>(&mut self, examples: I)
+    where I: IntoIterator<Item=(&'e T, C)>,
+          T: ?Sized + 'e,
+          X: FeatureExtractor<T>
+  {
+    for (input, class) in examples {
+      let c = match self.classes.iter().position(|known| *known == class) {
+        Some(c) => c,
+        None => {
+          self.classes.push(class);
+          self.examples.push(0);
+          self.counts.push(HashMap::new());
+          self.totals.push(0);
+          self.classes.len() - 1
+        }
+      };
+      self.examples[c] += 1;
+      for feature in self.extractor.features(input) {
+        *self.counts[c].entry(feature.clone()).or_insert(0) += 1;
+        self.totals[c] += 1;
+        self.features.insert(feature);
+      }
+    }
+  }
+
+  /// Returns the classes the classifier chooses among.
+  pub fn classes(&self) -> &[C] {
+    &self.classes
+  }
+
+  /// Returns the feature extractor.
+  pub fn extractor(&self) -> &X {
+    &self.extractor
+  }
+
+  /// Returns the log-probability of each class and the features of an
+  /// input together, in the order of [`classes`]. Features never seen in
+  /// training are ignored.
+  ///
+  /// [`classes`]: #method.classes
+  pub fn scores<T>(&self, input: &T) -> Vec<(C, f64)>
+    where T: ?Sized,
+          X: FeatureExtractor<T>
+  {
+    let features: Vec<String> = self.extractor.features(input).into_iter()
+      .filter(|feature| self.features.contains(feature))
+      .collect();
+    let examples: usize = self.examples.iter().sum();
+    let types = self.features.len() as f64;
+    self.classes.iter().enumerate()
+      .map(|(c, class)| {
+        let prior = (self.examples[c] as f64 / examples as f64).ln();
+        let denominator = self.totals[c] as f64 + self.smoothing * types;
+        let likelihood: f64 = features.iter()
+          .map(|feature| {
+            let count = self.counts[c].get(feature).cloned().unwrap_or(0) as f64;
+            ((count + self.smoothing) / denominator).ln()
+          })
+          .sum();
+        (class.clone(), prior + likelihood)
+      })
+      .collect()
+  }
+
+  /// Returns the most probable class of an input, breaking ties in favor
+  /// of the class seen first in training, or `None` if the classifier
+  /// was trained on no examples.
+  pub fn classify<T>(&self, input: &T) -> Option<C>
+    where T: ?Sized,
+          X: FeatureExtractor<T>
+  {
+    let mut best: Option<(C, f64)> = None;
+    for (class, score) in self.scores(input) {
+      match best {
+        Some((_, best)) if best >= score => {},
+        _ => best = Some((class, score))
+      }
+    }
+    best.map(|(class, _)| class)
+  }
+}
+
+/// A method of calibrating scores as probabilities.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Calibration {
+  /// Platt scaling: a sigmoid of the score, `1 / (1 + exp(A s + B))`, fit
+  /// by maximum likelihood with Platt's smoothed targets. It needs little
+  /// data, but assumes that probability rises with the score as a
+  /// sigmoid does.
+  Platt,
+  /// Isotonic regression: the nondecreasing step function of the score
+  /// nearest the outcomes, by pooling adjacent violators, interpolated
+  /// between steps. It assumes only that probability rises with the
+  /// score, but needs more data than Platt scaling not to overfit.
+  Isotonic,
+}
+
+/// A map from the scores of a binary decision, such as whether an input
+/// is of a class, to the probability that it holds, fit on held-out
+/// examples, so that scores that are not probabilities, such as those of
+/// a perceptron, or that are badly calibrated ones, such as those of
+/// naive Bayes, can be used as probabilities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibrator {
+  map: CalibrationMap,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CalibrationMap {
+  Sigmoid { a: f64, b: f64 },
+  /// The bounds of the scores of each step, and its probability, in
+  /// order of score.
+  Steps(Vec<(f64, f64, f64)>),
+}
+
+impl Calibrator {
+  /// Fits a calibrator to the scores of held-out examples and whether the
+  /// decision held for each. Without examples, every score has a
+  /// probability of one half.
+  pub fn fit(examples: &[(f64, bool)], method: Calibration) -> Calibrator {
+    let map = match method {
+      Calibration::Platt => {
+        let (a, b) = platt(examples);
+        CalibrationMap::Sigmoid { a: a, b: b }
+      },
+      Calibration::Isotonic => CalibrationMap::Steps(isotonic(examples)),
+    };
+    Calibrator { map: map }
+  }
+
+  /// Returns the probability of a score.
+  pub fn probability(&self, score: f64) -> f64 {
+    match self.map {
+      CalibrationMap::Sigmoid { a, b } => sigmoid(-(a * score + b)),
+      CalibrationMap::Steps(ref steps) => {
+        let next = steps.iter().position(|&(low, _, _)| low > score);
+        match next {
+          _ if steps.is_empty() => 0.5,
+          Some(0) => steps[0].2,
+          Some(n) => {
+            let (_, high, before) = steps[n - 1];
+            let (low, _, after) = steps[n];
+            if score <= high {
+              before
+            } else {
+              before + (after - before) * (score - high) / (low - high)
+            }
+          },
+          None => steps[steps.len() - 1].2
+        }
+      }
+    }
+  }
+}
+
+/// Calibrated probabilities of the classes of a multiclass classifier,
+/// by a [`Calibrator`] of the score of each class against the rest, fit
+/// on the scores of held-out examples and normalized to sum to one:
+///
+/// ```rust
+/// let calibration = ClassCalibration::fit(
+///   held_out.iter().map(|&(input, gold)| (classifier.scores(input), gold)),
+///   Calibration::Platt);
+/// for (class, probability) in calibration.probabilities(&classifier.scores(input)) {
+///   println!("{}\t{:.3}", class, probability);
+/// }
+/// ```
+///
+/// The scores may be those of a [`Classifier`], a [`NaiveBayes`]
+/// classifier, or any model that scores each class.
+///
+/// [`Calibrator`]: struct.Calibrator.html
+/// [`Classifier`]: struct.Classifier.html
+/// [`NaiveBayes`]: struct.NaiveBayes.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassCalibration<C> {
+  calibrators: Vec<(C, Calibrator)>,
+}
+
+impl<C: Eq + Clone> ClassCalibration<C> {
+  /// Fits a calibrator for each class to the scores of held-out examples,
+  /// given with the true class of each.
+  pub fn fit<I>(examples: I, method: Calibration) -> ClassCalibration<C>
+    where I: IntoIterator<Item=(Vec<(C, f64)>, C)>
+  {
+    let mut classes: Vec<(C, Vec<(f64, bool)>)> = vec![];
+    for (scores, gold) in examples {
+      for (class, score) in scores {
+        let held = class == gold;
+        match classes.iter().position(|(known, _)| *known == class) {
+          Some(c) => classes[c].1.push((score, held)),
+          None => classes.push((class, vec![(score, held)]))
+        }
+      }
+    }
+    ClassCalibration {
+      calibrators: classes.into_iter()
+        .map(|(class, examples)| (class, Calibrator::fit(&examples, method)))
+        .collect(),
+    }
+  }
+
+  /// Returns the calibrated probability of each class of the scores, in
+  /// their order. Classes without a calibrator, as they were never
+  /// scored in the held-out examples, have a probability of zero. If
+  /// every class does, so does every probability.
+  pub fn probabilities(&self, scores: &[(C, f64)]) -> Vec<(C, f64)> {
+    let mut probabilities: Vec<(C, f64)> = scores.iter()
+      .map(|&(ref class, score)| {
+        let probability = self.calibrators.iter()
+          .find(|&(known, _)| known == class)
+          .map_or(0.0, |(_, calibrator)| calibrator.probability(score));
+        (class.clone(), probability)
+      })
+      .collect();
+    let total: f64 = probabilities.iter().map(|&(_, probability)| probability).sum();
+    if total > 0.0 {
+      for &mut (_, ref mut probability) in &mut probabilities {
+        *probability /= total;
+      }
+    }
+    probabilities
+  }
+}
+
+fn sigmoid(x: f64) -> f64 {
+  if x >= 0.0 {
+    1.0 / (1.0 + (-x).exp())
+  } else {
+    let e = x.exp();
+    e / (1.0 + e)
+  }
+}
+
+/// Fits the parameters `A` and `B` of Platt scaling by Newton's method
+/// with backtracking, as improved by Lin, Lin and Weng (2007).
+fn platt(examples: &[(f64, bool)]) -> (f64, f64) {
+  let positives = examples.iter().filter(|&&(_, held)| held).count() as f64;
+  let negatives = examples.len() as f64 - positives;
+  let high = (positives + 1.0) / (positives + 2.0);
+  let low = 1.0 / (negatives + 2.0);
+  let targets: Vec<f64> = examples.iter().map(|&(_, held)| if held { high } else { low }).collect();
+  // The negative log-likelihood of the targets.
+  let objective = |a: f64, b: f64| -> f64 {
+    examples.iter().zip(&targets)
+      .map(|(&(score, _), &t)| {
+        let x = score * a + b;
+        if x >= 0.0 { t * x + (-x).exp().ln_1p() } else { (t - 1.0) * x + x.exp().ln_1p() }
+      })
+      .sum()
+  };
+
+  let (mut a, mut b) = (0.0, ((negatives + 1.0) / (positives + 1.0)).ln());
+  let mut value = objective(a, b);
+  for _ in 0..100 {
+    let (mut h11, mut h22, mut h21, mut g1, mut g2) = (1e-12, 1e-12, 0.0, 0.0, 0.0);
+    for (&(score, _), &t) in examples.iter().zip(&targets) {
+      let p = sigmoid(-(score * a + b));
+      let d2 = p * (1.0 - p);
+      h11 += score * score * d2;
+      h22 += d2;
+      h21 += score * d2;
+      let d1 = t - p;
+      g1 += score * d1;
+      g2 += d1;
+    }
+    if g1.abs() < 1e-5 && g2.abs() < 1e-5 {
+      break;
+    }
+    let determinant = h11 * h22 - h21 * h21;
+    let da = -(h22 * g1 - h21 * g2) / determinant;
+    let db = -(-h21 * g1 + h11 * g2) / determinant;
+    let descent = g1 * da + g2 * db;
+    let mut step = 1.0;
+    while step >= 1e-10 {
+      let next = objective(a + step * da, b + step * db);
+      if next < value + 1e-4 * step * descent {
+        a += step * da;
+        b += step * db;
+        value = next;
+        break;
+      }
+      step /= 2.0;
+    }
+    if step < 1e-10 {
+      break;
+    }
+  }
+  (a, b)
+}
+
+/// Fits a nondecreasing step function to the outcomes of examples by
+/// pooling adjacent violators, returning the bounds of the scores of each
+/// step and its probability.
+fn isotonic(examples: &[(f64, bool)]) -> Vec<(f64, f64, f64)> {
+  let mut sorted = examples.to_vec();
+  sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(::std::cmp::Ordering::Equal));
+  // The bounds, sum of outcomes and number of examples of each block.
+  let mut blocks: Vec<(f64, f64, f64, f64)> = vec![];
+  for (score, held) in sorted {
+    let outcome = if held { 1.0 } else { 0.0 };
+    match blocks.last_mut() {
+      // Examples of equal scores must share a probability.
+      Some(&mut (_, high, ref mut sum, ref mut count)) if high == score => {
+        *sum += outcome;
+        *count += 1.0;
+      },
+      _ => blocks.push((score, score, outcome, 1.0))
+    }
+    while blocks.len() > 1 {
+      let (low, _, sum, count) = blocks[blocks.len() - 2];
+      let (_, high, next_sum, next_count) = blocks[blocks.len() - 1];
+      if sum / count < next_sum / next_count {
+        break;
+      }
+      blocks.pop();
+      let last = blocks.len() - 1;
+      blocks[last] = (low, high, sum + next_sum, count + next_count);
+    }
+  }
+  blocks.into_iter().map(|(low, high, sum, count)| (low, high, sum / count)).collect()
+}
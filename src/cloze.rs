@@ -0,0 +1,162 @@
+use token::Token;
+use language::Language;
+use corpus::{Document, Line};
+use analyzed::AnalyzedDocument;
+use readability::FrequencyBands;
+
+use std::collections::HashSet;
+
+/// A cloze item: a line with one of its tokens, the answer, masked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClozeItem<'t, L: Language> {
+  /// The index of the line in its document.
+  pub line: usize,
+  /// The position of the answer in the line.
+  pub position: usize,
+  pub answer: Token<'t, L>,
+  /// The tokens of the line, including the answer.
+  pub tokens: Line<'t, L>,
+}
+
+impl<'t, L: Language> ClozeItem<'t, L> {
+  /// Returns the line with its tokens separated by spaces, and the answer
+  /// replaced by `blank`.
+  ///
+  /// ```rust
+  /// for item in generator.generate(&reader) {
+  ///   println!("{}\t{}", item.masked("____"), item.answer);
+  /// }
+  /// ```
+  pub fn masked(&self, blank: &str) -> String {
+    self.tokens.iter().enumerate()
+      .map(|(i, token)| if i == self.position { blank.to_string() } else { token.to_string() })
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+}
+
+/// Produces [`ClozeItem`]s from documents, masking one content word of
+/// each line: a token containing a letter or digit that is not a
+/// stopword. By default, the first content word of each line is masked;
+/// the choice can be narrowed to words of a frequency band, for items of
+/// controlled difficulty, or to words of given part-of-speech tags.
+/// Lines with too few eligible words yield no item.
+///
+/// ```rust
+/// let generator = ClozeGenerator::new()
+///   .with_stopwords(stopwords.lines())
+///   .with_position(1)
+///   .with_band(FrequencyBands::new(&reference, vec![1000, 3000]), Some(1))
+///   .with_tags(vec!["NOUN", "VERB"]);
+/// for item in generator.generate_analyzed(&treebank) {
+///   println!("{}\t{}", item.masked("____"), item.answer);
+/// }
+/// ```
+///
+/// [`ClozeItem`]: struct.ClozeItem.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClozeGenerator {
+  position: usize,
+  /// Stopwords, with ASCII case folded.
+  stopwords: HashSet<Vec<u8>>,
+  band: Option<(FrequencyBands, Option<usize>)>,
+  tags: Option<HashSet<String>>,
+}
+
+impl ClozeGenerator {
+  /// Creates a generator masking the first content word of each line,
+  /// with no stopwords.
+  pub fn new() -> ClozeGenerator {
+    ClozeGenerator::default()
+  }
+
+  /// Masks the `n`th eligible word of each line, counting from zero.
+  pub fn with_position(mut self, n: usize) -> ClozeGenerator {
+    self.position = n;
+    self
+  }
+
+  /// Sets the words never masked, whatever their case.
+  pub fn with_stopwords<I, S>(mut self, words: I) -> ClozeGenerator
+    where I: IntoIterator<Item=S>,
+          S: AsRef<[u8]>
+  {
+    self.stopwords = words.into_iter().map(|word| word.as_ref().to_ascii_lowercase()).collect();
+    self
+  }
+
+  /// Masks only words in the band of the given index and no smaller one,
+  /// or, for `None`, words in no band: the rarest.
+  pub fn with_band(mut self, bands: FrequencyBands, band: Option<usize>) -> ClozeGenerator {
+    self.band = Some((bands, band));
+    self
+  }
+
+  /// Masks only words with one of the given part-of-speech tags. Tags
+  /// constrain only items generated from analyzed documents, by
+  /// [`generate_analyzed`].
+  ///
+  /// [`generate_analyzed`]: #method.generate_analyzed
+  pub fn with_tags<I, S>(mut self, tags: I) -> ClozeGenerator
+    where I: IntoIterator<Item=S>,
+          S: Into<String>
+  {
+    self.tags = Some(tags.into_iter().map(Into::into).collect());
+    self
+  }
+
+  /// Produces an item from each line of a document with enough eligible
+  /// words, in order.
+  pub fn generate<'t, L: Language>(&self, document: &'t Document<L>) -> Vec<ClozeItem<'t, L>> {
+    document.lines().iter().enumerate()
+      .filter_map(|(l, line)| self.item(l, line, |_| true))
+      .collect()
+  }
+
+  /// Produces an item from each line of an analyzed document with enough
+  /// eligible words, in order, masking surface forms.
+  pub fn generate_analyzed<'t, L: Language>(&self, document: &'t AnalyzedDocument<L>)
+      -> Vec<ClozeItem<'t, L>>
+  {
+    let surface = document.surface();
+    (0..document.line_count())
+      .filter_map(|l| {
+        let range = document.line(l);
+        let start = range.start;
+        self.item(l, surface.slice(range), |i| match self.tags {
+          Some(ref tags) => tags.contains(document.tag(start + i)),
+          None => true
+        })
+      })
+      .collect()
+  }
+
+  /// Masks the eligible word of a line, of which `tagged` tells whether
+  /// the word at each position has an allowed tag.
+  fn item<'t, L, F>(&self, l: usize, line: Line<'t, L>, tagged: F) -> Option<ClozeItem<'t, L>>
+    where L: Language,
+          F: Fn(usize) -> bool
+  {
+    let (position, answer) = line.iter().enumerate()
+      .filter(|&(i, ref token)| self.eligible(token) && tagged(i))
+      .nth(self.position)?;
+    Some(ClozeItem { line: l, position: position, answer: answer, tokens: line })
+  }
+
+  /// Returns `true` if a token is a content word within the band.
+  fn eligible<L>(&self, token: &Token<L>) -> bool {
+    let word = match *token {
+      Token::Word(ref word) => word.as_bytes(),
+      _ => return false
+    };
+    if !word.iter().any(|b| b.is_ascii_alphanumeric() || *b >= 0x80)
+      || self.stopwords.contains(&word.to_ascii_lowercase())
+    {
+      return false;
+    }
+    match self.band {
+      Some((ref bands, band)) => bands.band(word) == band,
+      None => true
+    }
+  }
+}
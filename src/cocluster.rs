@@ -0,0 +1,213 @@
+//! Spectral co-clustering: clusters of terms and documents together, from
+//! the singular vectors of a normalized term-document matrix (Dhillon,
+//! 2001).
+//!
+//! The matrix `A` is scaled to `D₁^-½ A D₂^-½`, where `D₁` and `D₂` hold
+//! the sums of its rows and columns, and its second to `ℓ + 1`th singular
+//! vectors, for `ℓ = ⌈log₂ k⌉`, embed the terms and documents in one
+//! space, as the rows of `D₁^-½ U` and `D₂^-½ V`. Clustering these rows
+//! together by k-means partitions the matrix into `k` blocks of terms
+//! with the documents in which they are most used, which suits a first
+//! look at how a corpus is organized:
+//!
+//! ```rust
+//! let matrix = TermDocumentMatrix::new(documents.iter().map(|document| document.tokens()))
+//!   .with_weighting(Weighting::TfIdf);
+//! let rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+//! let clustering = CoClustering::new(&matrix, 5, rng);
+//! for cluster in 0..clustering.clusters() {
+//!   let terms: Vec<String> = clustering.terms(cluster).into_iter()
+//!     .filter_map(|term| matrix.term(term))
+//!     .map(|term| term.to_string())
+//!     .collect();
+//!   println!("{}\t{}\t{}", cluster, terms.join(" "), clustering.documents(cluster).len());
+//! }
+//! ```
+//!
+//! The weights of the matrix must not be negative, as those of every
+//! [`Weighting`] are not.
+//!
+//! [`Weighting`]: ../enum.Weighting.html
+use language::Language;
+use termdoc::TermDocumentMatrix;
+use lsa::{self, Svd};
+
+use rand::Rng;
+
+/// The largest number of rounds of k-means.
+const MAX_ITERATIONS: usize = 100;
+
+/// A partition of the terms and documents of a term-document matrix into
+/// joint clusters.
+///
+/// Terms and documents without any weight, such as terms of every
+/// document weighted by [`Weighting::TfIdf`], have no place in the
+/// embedding and belong to no cluster.
+///
+/// [`Weighting::TfIdf`]: ../enum.Weighting.html#variant.TfIdf
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoClustering {
+  clusters: usize,
+  /// The cluster of each term.
+  terms: Vec<Option<usize>>,
+  /// The cluster of each document.
+  documents: Vec<Option<usize>>,
+}
+
+impl CoClustering {
+  /// Partitions the terms and documents of a matrix, weighted as it is,
+  /// into `k` clusters, or as many as it has weighted terms and documents
+  /// if fewer. The clusters of k-means are seeded by k-means++ from the
+  /// random number generator, which is taken by value; seeding it makes
+  /// the clustering reproducible. A cluster may be left empty, or hold
+  /// only terms or only documents.
+  pub fn new<'t, L, R>(matrix: &TermDocumentMatrix<'t, L>, k: usize, mut rng: R) -> CoClustering
+    where L: Language,
+          R: Rng
+  {
+    let columns: Vec<Vec<(usize, f64)>> = (0..matrix.documents()).map(|d| matrix.column(d)).collect();
+    let mut row_sums = vec![0.0; matrix.terms()];
+    let column_sums: Vec<f64> = columns.iter()
+      .map(|column| {
+        for &(t, weight) in column {
+          row_sums[t] += weight;
+        }
+        column.iter().map(|&(_, weight)| weight).sum()
+      })
+      .collect();
+    let scale = |sum: f64| if sum > 0.0 { 1.0 / sum.sqrt() } else { 0.0 };
+    let normalized: Vec<Vec<(usize, f64)>> = columns.iter().zip(&column_sums)
+      .map(|(column, &sum)| {
+        column.iter().map(|&(t, weight)| (t, weight * scale(row_sums[t]) * scale(sum))).collect()
+      })
+      .collect();
+
+    // The embedding, skipping the first singular vectors, which only
+    // reflect the sums of rows and columns.
+    let dimensions = (k.max(2) as f64).log2().ceil() as usize;
+    let Svd { left, right, .. } = lsa::svd(&normalized, matrix.terms(), dimensions + 1);
+    let embed = |vectors: Vec<Vec<f64>>, sums: &[f64]| -> Vec<Option<Vec<f64>>> {
+      vectors.into_iter().zip(sums)
+        .map(|(vector, &sum)| {
+          if sum > 0.0 {
+            Some(vector.iter().skip(1).map(|x| x * scale(sum)).collect())
+          } else {
+            None
+          }
+        })
+        .collect()
+    };
+    let terms = embed(left, &row_sums);
+    let documents = embed(right, &column_sums);
+
+    let points: Vec<&[f64]> = terms.iter().chain(&documents)
+      .filter_map(|point| point.as_ref().map(|point| &point[..]))
+      .collect();
+    let k = k.min(points.len());
+    let mut assignments = kmeans(&points, k, &mut rng).into_iter();
+    let mut assign = |points: &[Option<Vec<f64>>]| -> Vec<Option<usize>> {
+      points.iter().map(|point| point.as_ref().and_then(|_| assignments.next())).collect()
+    };
+    let terms = assign(&terms);
+    let documents = assign(&documents);
+    CoClustering {
+      clusters: k,
+      terms: terms,
+      documents: documents,
+    }
+  }
+
+  /// Returns the number of clusters.
+  pub fn clusters(&self) -> usize {
+    self.clusters
+  }
+
+  /// Returns the cluster of a term, by its row.
+  pub fn term_cluster(&self, term: usize) -> Option<usize> {
+    self.terms.get(term).cloned().and_then(|cluster| cluster)
+  }
+
+  /// Returns the cluster of a document, by its column.
+  pub fn document_cluster(&self, document: usize) -> Option<usize> {
+    self.documents.get(document).cloned().and_then(|cluster| cluster)
+  }
+
+  /// Returns the terms of a cluster, in order.
+  pub fn terms(&self, cluster: usize) -> Vec<usize> {
+    members(&self.terms, cluster)
+  }
+
+  /// Returns the documents of a cluster, in order.
+  pub fn documents(&self, cluster: usize) -> Vec<usize> {
+    members(&self.documents, cluster)
+  }
+}
+
+fn members(assignments: &[Option<usize>], cluster: usize) -> Vec<usize> {
+  assignments.iter().enumerate()
+    .filter(|&(_, &assignment)| assignment == Some(cluster))
+    .map(|(i, _)| i)
+    .collect()
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+  a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// Assigns each point to one of `k` clusters by Lloyd's algorithm, from
+/// centroids chosen by k-means++: each next centroid is a point drawn in
+/// proportion to its squared distance from the nearest centroid so far.
+fn kmeans<R: Rng>(points: &[&[f64]], k: usize, rng: &mut R) -> Vec<usize> {
+  if k == 0 {
+    return vec![];
+  }
+  let mut centroids: Vec<Vec<f64>> = vec![points[rng.gen_range(0, points.len())].to_vec()];
+  while centroids.len() < k {
+    let distances: Vec<f64> = points.iter()
+      .map(|point| centroids.iter().map(|centroid| distance(point, centroid)).fold(f64::INFINITY, f64::min))
+      .collect();
+    let total: f64 = distances.iter().sum();
+    let next = if total > 0.0 {
+      let mut target = rng.gen::<f64>() * total;
+      distances.iter()
+        .position(|&d| {
+          target -= d;
+          target < 0.0
+        })
+        .unwrap_or(points.len() - 1)
+    } else {
+      rng.gen_range(0, points.len())
+    };
+    centroids.push(points[next].to_vec());
+  }
+
+  let nearest = |centroids: &[Vec<f64>], point: &[f64]| -> usize {
+    centroids.iter().enumerate()
+      .map(|(c, centroid)| (c, distance(point, centroid)))
+      .fold((0, f64::INFINITY), |best, candidate| if candidate.1 < best.1 { candidate } else { best })
+      .0
+  };
+  let mut assignments: Vec<usize> = points.iter().map(|point| nearest(&centroids, point)).collect();
+  for _ in 0..MAX_ITERATIONS {
+    // A cluster left empty keeps its centroid.
+    let mut sums = vec![vec![0.0; points[0].len()]; k];
+    let mut counts = vec![0usize; k];
+    for (point, &c) in points.iter().zip(&assignments) {
+      counts[c] += 1;
+      for (x, y) in sums[c].iter_mut().zip(point.iter()) {
+        *x += y;
+      }
+    }
+    for ((centroid, sum), &count) in centroids.iter_mut().zip(sums).zip(&counts) {
+      if count > 0 {
+        *centroid = sum.into_iter().map(|x| x / count as f64).collect();
+      }
+    }
+    let next: Vec<usize> = points.iter().map(|point| nearest(&centroids, point)).collect();
+    if next == assignments {
+      break;
+    }
+    assignments = next;
+  }
+  assignments
+}
@@ -0,0 +1,101 @@
+use language::{Language, DefaultLanguage};
+use corpus::{Document, Line, Tokens};
+
+use std::slice;
+use std::iter::FromIterator;
+
+/// A collection of documents in language `L`, such as the articles of a
+/// newspaper or the reviews of a site, for processing that treats each
+/// document as a unit, such as weighting terms by the documents they
+/// occur in.
+///
+/// ```rust
+/// let corpus: Corpus<English> = paths.iter()
+///   .map(|path| Document::from_reader(File::open(path)?))
+///   .collect::<io::Result<_>>()?;
+/// let tfidf = TfIdf::new().with_sublinear_tf(true).fit(&corpus);
+/// ```
+pub struct Corpus<L=DefaultLanguage>
+  where L: 'static
+{
+  documents: Vec<Document<L>>,
+}
+
+impl<L: Language> Corpus<L> {
+  /// Creates a corpus of no documents.
+  pub fn new() -> Corpus<L> {
+    Corpus { documents: vec![] }
+  }
+
+  /// Adds a document to the end of the corpus.
+  pub fn add(&mut self, document: Document<L>) {
+    self.documents.push(document);
+  }
+
+  /// Returns the documents of the corpus, in the order they were added.
+  pub fn documents(&self) -> &[Document<L>] {
+    &self.documents
+  }
+
+  /// Returns the document at a position, if there is one.
+  pub fn get(&self, document: usize) -> Option<&Document<L>> {
+    self.documents.get(document)
+  }
+
+  /// Returns the number of documents.
+  pub fn len(&self) -> usize {
+    self.documents.len()
+  }
+
+  /// Returns `true` if the corpus has no documents.
+  pub fn is_empty(&self) -> bool {
+    self.documents.is_empty()
+  }
+
+  /// Returns an iterator over the documents, in order.
+  pub fn iter(&self) -> slice::Iter<'_, Document<L>> {
+    self.documents.iter()
+  }
+
+  /// Returns the tokens of each document, in order.
+  pub fn tokens<'t>(&'t self) -> Vec<Tokens<'t, L>> {
+    self.documents.iter().map(|document| document.tokens()).collect()
+  }
+
+  /// Returns every line of every document, in order, with the position
+  /// of its document and its position in the document.
+  pub fn lines<'t>(&'t self) -> Vec<(usize, usize, Line<'t, L>)> {
+    self.documents.iter().enumerate()
+      .flat_map(|(d, document)| {
+        document.lines().iter().enumerate().map(move |(l, line)| (d, l, line))
+      })
+      .collect()
+  }
+}
+
+impl<L: Language> Default for Corpus<L> {
+  fn default() -> Corpus<L> {
+    Corpus::new()
+  }
+}
+
+impl<L: Language> From<Vec<Document<L>>> for Corpus<L> {
+  fn from(documents: Vec<Document<L>>) -> Corpus<L> {
+    Corpus { documents: documents }
+  }
+}
+
+impl<L: Language> FromIterator<Document<L>> for Corpus<L> {
+  fn from_iter<I: IntoIterator<Item=Document<L>>>(documents: I) -> Corpus<L> {
+    Corpus { documents: documents.into_iter().collect() }
+  }
+}
+
+impl<'c, L: Language> IntoIterator for &'c Corpus<L> {
+  type Item = &'c Document<L>;
+  type IntoIter = slice::Iter<'c, Document<L>>;
+
+  fn into_iter(self) -> slice::Iter<'c, Document<L>> {
+    self.documents.iter()
+  }
+}
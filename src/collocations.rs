@@ -0,0 +1,226 @@
+//! Collocations: sequences of words that occur together more often than
+//! chance, such as *strong tea* or *by and large*.
+//!
+//! A [`CollocationFinder`] counts the n-grams of a corpus, usually its
+//! bigrams or trigrams, and scores each by an association [`Measure`]
+//! between its words:
+//!
+//! ```rust
+//! let finder = CollocationFinder::new(corpus.lines(), 2)
+//!   .with_min_count(5);
+//! for collocation in finder.top_k(Measure::LogLikelihood, 20) {
+//!   println!("{}\t{}\t{:.1}", collocation.tokens.iter().join(" "), collocation.count, collocation.score);
+//! }
+//! ```
+//!
+//! Each measure compares the counts of the contingency table of an
+//! n-gram's words: of the n-grams of the corpus with each combination of
+//! its words and other words in each position, against the counts
+//! expected if words were independent of their neighbours.
+//!
+//! [`CollocationFinder`]: struct.CollocationFinder.html
+//! [`Measure`]: enum.Measure.html
+use token::Token;
+use language::Language;
+use corpus::AsTokens;
+
+use std::collections::HashMap;
+
+/// A measure of the association of the words of an n-gram, of its
+/// observed count `O` and the count `E` expected if its words were
+/// independent.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Measure {
+  /// Pointwise mutual information: `log₂(O / E)`. It favours rare
+  /// n-grams, so is best used with a minimum count.
+  Pmi,
+  /// The t-score: `(O - E) / √O`. It favours frequent n-grams.
+  TScore,
+  /// Pearson's chi-square statistic over the contingency table.
+  ChiSquare,
+  /// Dunning's log-likelihood statistic `G²` over the contingency table.
+  LogLikelihood,
+}
+
+/// An n-gram scored by a [`Measure`].
+///
+/// [`Measure`]: enum.Measure.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collocation<'t, L> {
+  pub tokens: Vec<Token<'t, L>>,
+  /// The number of occurrences of the n-gram.
+  pub count: usize,
+  pub score: f64,
+}
+
+/// The n-grams of a corpus, counted for scoring as collocations.
+///
+/// N-grams are counted within lines, and never span a `Null` token. Along
+/// with each n-gram, the finder counts the n-grams sharing its words in
+/// each combination of positions, from which its contingency table is
+/// derived; the count of each word is its count in that position of an
+/// n-gram.
+#[derive(Debug, Clone)]
+pub struct CollocationFinder<'t, L> {
+  n: usize,
+  /// The number of n-grams with the given tokens in the positions that
+  /// are not `None`, for every combination of positions.
+  counts: HashMap<Vec<Option<Token<'t, L>>>, usize>,
+  /// The number of n-grams.
+  total: usize,
+  min_count: usize,
+  min_word_count: usize,
+}
+
+impl<'t, L: Language> CollocationFinder<'t, L> {
+  /// Counts the n-grams of `n` tokens of each line. Values of `n` below
+  /// `2` are treated as `2`.
+  pub fn new<I>(lines: I, n: usize) -> CollocationFinder<'t, L>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let n = n.max(2);
+    let mut counts = HashMap::new();
+    let mut total = 0;
+    for line in lines {
+      for window in line.as_tokens().windows(n) {
+        if window.contains(&Token::Null) {
+          continue;
+        }
+        total += 1;
+        for mask in 1..1usize << n {
+          let key = window.iter().enumerate()
+            .map(|(i, &token)| if mask & (1 << i) != 0 { Some(token) } else { None })
+            .collect();
+          *counts.entry(key).or_insert(0) += 1;
+        }
+      }
+    }
+    CollocationFinder {
+      n: n,
+      counts: counts,
+      total: total,
+      min_count: 1,
+      min_word_count: 1,
+    }
+  }
+
+  /// Scores only n-grams that occur at least `count` times.
+  pub fn with_min_count(mut self, count: usize) -> CollocationFinder<'t, L> {
+    self.min_count = count;
+    self
+  }
+
+  /// Scores only n-grams of which every word occurs at least `count`
+  /// times in its position.
+  pub fn with_min_word_count(mut self, count: usize) -> CollocationFinder<'t, L> {
+    self.min_word_count = count;
+    self
+  }
+
+  /// Returns the length of the n-grams.
+  pub fn n(&self) -> usize {
+    self.n
+  }
+
+  /// Returns the number of n-grams counted.
+  pub fn total(&self) -> usize {
+    self.total
+  }
+
+  /// Returns the number of occurrences of an n-gram.
+  pub fn count(&self, tokens: &[Token<'t, L>]) -> usize {
+    let key: Vec<Option<Token<'t, L>>> = tokens.iter().cloned().map(Some).collect();
+    self.counts.get(&key).cloned().unwrap_or(0)
+  }
+
+  /// Scores every n-gram that passes the frequency filters by `measure`,
+  /// in descending order of score.
+  pub fn score(&self, measure: Measure) -> Vec<Collocation<'t, L>> {
+    let full = (1usize << self.n) - 1;
+    let mut collocations: Vec<Collocation<'t, L>> = self.counts.iter()
+      .filter(|&(key, &count)| key.iter().all(Option::is_some) && count >= self.min_count)
+      .filter_map(|(key, &count)| {
+        let tokens: Vec<Token<'t, L>> = key.iter().map(|token| token.unwrap()).collect();
+        let words: Vec<usize> = (0..self.n).map(|i| self.marginal(&tokens, 1 << i)).collect();
+        if words.iter().any(|&count| count < self.min_word_count) {
+          return None;
+        }
+        let score = self.measure(measure, &tokens, &words, full);
+        Some(Collocation { tokens: tokens, count: count, score: score })
+      })
+      .collect();
+    collocations.sort_by(|a, b| {
+      b.score.partial_cmp(&a.score)
+        .unwrap_or(::std::cmp::Ordering::Equal)
+        .then(a.tokens.cmp(&b.tokens))
+    });
+    collocations
+  }
+
+  /// Returns the `k` n-grams of the highest score by `measure`.
+  pub fn top_k(&self, measure: Measure, k: usize) -> Vec<Collocation<'t, L>> {
+    let mut collocations = self.score(measure);
+    collocations.truncate(k);
+    collocations
+  }
+
+  /// Returns the number of n-grams with the tokens of `tokens` in the
+  /// positions of the bits of `mask`, and any tokens elsewhere.
+  fn marginal(&self, tokens: &[Token<'t, L>], mask: usize) -> usize {
+    if mask == 0 {
+      return self.total;
+    }
+    let key: Vec<Option<Token<'t, L>>> = tokens.iter().enumerate()
+      .map(|(i, &token)| if mask & (1 << i) != 0 { Some(token) } else { None })
+      .collect();
+    self.counts.get(&key).cloned().unwrap_or(0)
+  }
+
+  /// Scores an n-gram, of which `words` are the counts of its tokens in
+  /// their positions.
+  fn measure(&self, measure: Measure, tokens: &[Token<'t, L>], words: &[usize], full: usize) -> f64 {
+    let total = self.total as f64;
+    // The count of the cell of the contingency table in which the
+    // positions of the bits of `cell` hold the n-gram's tokens and the
+    // others hold other tokens, by inclusion and exclusion, and its
+    // expected count.
+    let observed = |cell: usize| -> f64 {
+      let mut count = 0.0;
+      for mask in cell..=full {
+        if mask & cell == cell {
+          let sign = if (mask & !cell).count_ones() & 1 == 0 { 1.0 } else { -1.0 };
+          count += sign * self.marginal(tokens, mask) as f64;
+        }
+      }
+      count
+    };
+    let expected = |cell: usize| -> f64 {
+      words.iter().enumerate()
+        .map(|(i, &count)| {
+          let p = count as f64 / total;
+          if cell & (1 << i) != 0 { p } else { 1.0 - p }
+        })
+        .product::<f64>() * total
+    };
+    match measure {
+      Measure::Pmi => (observed(full) / expected(full)).log2(),
+      Measure::TScore => {
+        let o = observed(full);
+        (o - expected(full)) / o.sqrt()
+      },
+      Measure::ChiSquare => (0..=full)
+        .map(|cell| {
+          let e = expected(cell);
+          if e > 0.0 { (observed(cell) - e).powi(2) / e } else { 0.0 }
+        })
+        .sum(),
+      Measure::LogLikelihood => 2.0 * (0..=full)
+        .map(|cell| {
+          let (o, e) = (observed(cell), expected(cell));
+          if o > 0.0 && e > 0.0 { o * (o / e).ln() } else { 0.0 }
+        })
+        .sum::<f64>(),
+    }
+  }
+}
@@ -0,0 +1,142 @@
+use token::Token;
+use language::Language;
+
+use std::collections::HashMap;
+
+/// A frequency-based splitter for compounding languages, following
+/// Koehn and Knight (2003). A word is split into known parts if the
+/// geometric mean of the parts' frequencies exceeds the frequency of
+/// the word itself. For example, given sufficiently frequent `Arbeit`
+/// and `Markt`, the token `Arbeitsmarkt` is split into `Arbeit` and
+/// `markt`.
+///
+/// ```rust
+/// let splitter = CompoundSplitter::new(training.tokens());
+/// let split = splitter.transform(testing.tokens()).join(" ");
+/// ```
+///
+/// Split parts are views into the original token, so the filler letters
+/// joining the parts of a compound (like the `s` of `Arbeitsmarkt`) are
+/// omitted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompoundSplitter {
+  /// Frequencies of each word, with ASCII letters lowercased.
+  counts: HashMap<Vec<u8>, usize>,
+  min_length: usize,
+  fillers: Vec<Vec<u8>>,
+}
+
+impl CompoundSplitter {
+  /// Creates a compound splitter from the word frequencies of `tokens`,
+  /// with parts of at least three bytes, joined by the German fillers
+  /// `s` and `es`.
+  pub fn new<'t, T, L>(tokens: T) -> CompoundSplitter
+    where L: Language + 't,
+          T: IntoIterator<Item=Token<'t, L>>
+  {
+    let mut counts = HashMap::new();
+    for token in tokens {
+      if let Token::Word(word) = token {
+        *counts.entry(word.as_bytes().to_ascii_lowercase()).or_insert(0) += 1;
+      }
+    }
+    CompoundSplitter {
+      counts: counts,
+      min_length: 3,
+      fillers: vec![b"s".to_vec(), b"es".to_vec()],
+    }
+  }
+
+  /// Sets the minimum length, in bytes, of each part of a compound.
+  pub fn with_min_length(mut self, length: usize) -> CompoundSplitter {
+    self.min_length = length;
+    self
+  }
+
+  /// Sets the filler strings permitted between the parts of a compound.
+  pub fn with_fillers<I, S>(mut self, fillers: I) -> CompoundSplitter
+    where I: IntoIterator<Item=S>,
+          S: Into<Vec<u8>>
+  {
+    self.fillers = fillers.into_iter().map(Into::into).collect();
+    self
+  }
+
+  fn count(&self, part: &[u8]) -> usize {
+    self.counts.get(&part.to_ascii_lowercase()).cloned().unwrap_or(0)
+  }
+
+  /// Splits a token into its parts. Tokens that are not compounds, as
+  /// well as `Null` and `Unknown` tokens, are returned unchanged.
+  pub fn split<'t, L>(&self, token: Token<'t, L>) -> Vec<Token<'t, L>>
+    where L: Language
+  {
+    let bytes = match token {
+      Token::Word(word) => word.as_bytes(),
+      _ => return vec![token]
+    };
+
+    let whole = self.count(bytes) as f64;
+    let best = self.splits(bytes).into_iter()
+      .map(|parts| {
+        let logs = parts.iter()
+          .map(|part| (self.count(part) as f64).ln())
+          .sum::<f64>();
+        ((logs / parts.len() as f64).exp(), parts)
+      })
+      .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    match best {
+      Some((mean, parts)) if mean > whole =>
+        parts.into_iter().map(Token::from).collect(),
+      _ => vec![token]
+    }
+  }
+
+  /// Enumerates every way of dividing `bytes` into two or more known
+  /// parts, each optionally followed by a filler.
+  fn splits<'t>(&self, bytes: &'t [u8]) -> Vec<Vec<&'t [u8]>> {
+    let mut splits = vec![];
+    for end in self.min_length..bytes.len() {
+      // Never split inside a multi-byte UTF-8 character.
+      if bytes[end] & 0b1100_0000 == 0b1000_0000 {
+        continue;
+      }
+      let head = &bytes[..end];
+      if self.count(head) == 0 {
+        continue;
+      }
+      let fillers = Some(&b""[..]).into_iter()
+        .chain(self.fillers.iter().map(|filler| &filler[..]));
+      for filler in fillers {
+        if !bytes[end..].starts_with(filler) {
+          continue;
+        }
+        let rest = &bytes[end + filler.len()..];
+        if rest.len() < self.min_length {
+          continue;
+        }
+        if self.count(rest) > 0 {
+          splits.push(vec![head, rest]);
+        }
+        for mut tail in self.splits(rest) {
+          tail.insert(0, head);
+          splits.push(tail);
+        }
+      }
+    }
+    splits
+  }
+
+  /// Consumes an iterator over tokens, and produces an iterator over
+  /// tokens in which every compound is replaced by its parts.
+  pub fn transform<'s, 't, T, L>(&'s self, tokens: T)
+      -> impl 's + Iterator<Item=Token<'t, L>>
+    where L: Language + 't,
+          T: 's + IntoIterator<Item=Token<'t, L>>,
+          't: 's
+  {
+    IntoIterator::into_iter(tokens)
+      .flat_map(move |token| self.split(token))
+  }
+}
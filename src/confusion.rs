@@ -0,0 +1,205 @@
+//! Confusion networks for combining the hypotheses of one or more
+//! systems.
+//!
+//! A confusion network is a sequence of slots, each holding the
+//! alternative tokens that hypotheses place at that point (or none, for
+//! hypotheses that skip it), weighted by the hypotheses supporting them.
+//! Hypotheses are aligned to the network one at a time by edit distance,
+//! beginning with a backbone. Reading off the heaviest alternative of
+//! each slot gives a consensus hypothesis, which may differ from every
+//! input hypothesis.
+//!
+//! ```rust
+//! let hypotheses = vec![
+//!   (vec!["the", "cat", "sat"], 0.7),
+//!   (vec!["a", "cat", "sat"], 0.5),
+//!   (vec!["the", "cat", "sit", "down"], 0.8),
+//!   (vec!["the", "bat", "sat", "down"], 0.6),
+//! ];
+//! let network = ConfusionNetwork::from_hypotheses(&hypotheses);
+//! assert_eq!(network.consensus(), vec!["the", "cat", "sat", "down"]);
+//! ```
+use eval::levenshtein;
+
+/// A position of a confusion network: each alternative token, or `None`
+/// for skipping the position, with the total weight of the hypotheses
+/// choosing it.
+pub type Slot<T> = Vec<(Option<T>, f64)>;
+
+/// A confusion network over tokens of type `T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfusionNetwork<T> {
+  slots: Vec<Slot<T>>,
+  /// The total weight of the hypotheses added.
+  total: f64,
+}
+
+/// A step of the alignment of a hypothesis to a network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+  /// The hypothesis places its `j`th token in slot `i`.
+  Align(usize, usize),
+  /// The hypothesis skips slot `i`.
+  Skip(usize),
+  /// The `j`th token of the hypothesis needs a new slot.
+  Insert(usize),
+}
+
+impl<T: Clone + PartialEq> ConfusionNetwork<T> {
+  /// Creates an empty network.
+  pub fn new() -> ConfusionNetwork<T> {
+    ConfusionNetwork { slots: vec![], total: 0.0 }
+  }
+
+  /// Builds a network from weighted hypotheses, such as an n-best list
+  /// with posterior probabilities. The backbone is the hypothesis of
+  /// least weighted edit distance to the others, and the rest are
+  /// aligned to the network in order of decreasing weight.
+  pub fn from_hypotheses<H>(hypotheses: &[(H, f64)]) -> ConfusionNetwork<T>
+    where H: AsRef<[T]>
+  {
+    let mut network = ConfusionNetwork::new();
+    let risk = |i: usize| -> f64 {
+      hypotheses.iter()
+        .map(|(other, weight)| {
+          weight * levenshtein(hypotheses[i].0.as_ref(), other.as_ref()) as f64
+        })
+        .sum()
+    };
+    let risks: Vec<f64> = (0..hypotheses.len()).map(risk).collect();
+    let backbone = match (0..hypotheses.len()).min_by(|&a, &b| {
+      risks[a].partial_cmp(&risks[b]).unwrap_or(::std::cmp::Ordering::Equal)
+    }) {
+      Some(backbone) => backbone,
+      None => return network
+    };
+
+    let mut order: Vec<usize> = (0..hypotheses.len()).filter(|&i| i != backbone).collect();
+    order.sort_by(|&a, &b| {
+      hypotheses[b].1.partial_cmp(&hypotheses[a].1).unwrap_or(::std::cmp::Ordering::Equal)
+    });
+    for i in Some(backbone).into_iter().chain(order) {
+      network.add(hypotheses[i].0.as_ref(), hypotheses[i].1);
+    }
+    network
+  }
+
+  /// Returns the slots of the network, in order.
+  pub fn slots(&self) -> &[Slot<T>] {
+    &self.slots
+  }
+
+  /// Returns the total weight of the hypotheses added to the network.
+  pub fn total(&self) -> f64 {
+    self.total
+  }
+
+  /// Aligns a hypothesis of the given weight to the network, and adds it.
+  /// The alignment minimizes the number of slots in which the hypothesis
+  /// has no existing alternative, the slots it skips, and the new slots
+  /// its remaining tokens need.
+  pub fn add(&mut self, hypothesis: &[T], weight: f64) {
+    let steps = self.align(hypothesis);
+    let total = self.total;
+    let mut slots = Vec::with_capacity(steps.len());
+    let mut old = ::std::mem::take(&mut self.slots).into_iter();
+    for step in steps {
+      match step {
+        Step::Align(_, j) => {
+          let mut slot = old.next().expect("aligned slot");
+          vote(&mut slot, Some(hypothesis[j].clone()), weight);
+          slots.push(slot);
+        },
+        Step::Skip(_) => {
+          let mut slot = old.next().expect("skipped slot");
+          vote(&mut slot, None, weight);
+          slots.push(slot);
+        },
+        Step::Insert(j) => {
+          let mut slot = vec![];
+          if total > 0.0 {
+            slot.push((None, total));
+          }
+          vote(&mut slot, Some(hypothesis[j].clone()), weight);
+          slots.push(slot);
+        },
+      }
+    }
+    self.slots = slots;
+    self.total += weight;
+  }
+
+  /// Finds the alignment of least cost of a hypothesis to the slots.
+  fn align(&self, hypothesis: &[T]) -> Vec<Step> {
+    let (n, m) = (self.slots.len(), hypothesis.len());
+    let has = |i: usize, j: usize| {
+      self.slots[i].iter().any(|(token, _)| token.as_ref() == Some(&hypothesis[j]))
+    };
+    let mut cost = vec![vec![0; m + 1]; n + 1];
+    for (i, row) in cost.iter_mut().enumerate() {
+      row[0] = i;
+    }
+    cost[0] = (0..=m).collect();
+    for i in 1..=n {
+      for j in 1..=m {
+        let substitute = cost[i - 1][j - 1] + if has(i - 1, j - 1) { 0 } else { 1 };
+        cost[i][j] = substitute.min(cost[i - 1][j] + 1).min(cost[i][j - 1] + 1);
+      }
+    }
+
+    // Of equally good alignments, prefer matches, then skips and new
+    // slots, and only then substitutions.
+    let mut steps = vec![];
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+      if i > 0 && j > 0 && has(i - 1, j - 1) && cost[i][j] == cost[i - 1][j - 1] {
+        steps.push(Step::Align(i - 1, j - 1));
+        i -= 1;
+        j -= 1;
+      } else if i > 0 && cost[i][j] == cost[i - 1][j] + 1 {
+        steps.push(Step::Skip(i - 1));
+        i -= 1;
+      } else if j > 0 && cost[i][j] == cost[i][j - 1] + 1 {
+        steps.push(Step::Insert(j - 1));
+        j -= 1;
+      } else {
+        steps.push(Step::Align(i - 1, j - 1));
+        i -= 1;
+        j -= 1;
+      }
+    }
+    steps.reverse();
+    steps
+  }
+
+  /// Returns the heaviest alternative of each slot, omitting slots in
+  /// which skipping is heaviest. Ties go to the alternative added first.
+  pub fn consensus(&self) -> Vec<T> {
+    self.slots.iter()
+      .filter_map(|slot| {
+        let mut best: Option<&(Option<T>, f64)> = None;
+        for alternative in slot {
+          match best {
+            Some(&(_, weight)) if weight >= alternative.1 => {},
+            _ => best = Some(alternative)
+          }
+        }
+        best.and_then(|(token, _)| token.clone())
+      })
+      .collect()
+  }
+}
+
+impl<T: Clone + PartialEq> Default for ConfusionNetwork<T> {
+  fn default() -> ConfusionNetwork<T> {
+    ConfusionNetwork::new()
+  }
+}
+
+/// Adds `weight` to an alternative of a slot.
+fn vote<T: PartialEq>(slot: &mut Slot<T>, token: Option<T>, weight: f64) {
+  match slot.iter_mut().find(|(t, _)| *t == token) {
+    Some(alternative) => alternative.1 += weight,
+    None => slot.push((token, weight))
+  }
+}
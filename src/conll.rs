@@ -0,0 +1,99 @@
+use span::Span;
+use error::LineParseError;
+
+/// The rows of one sentence of a CoNLL file, split into columns.
+pub(crate) struct Block<'s> {
+  /// The (one-based) line number of each row.
+  pub lines: Vec<usize>,
+  pub rows: Vec<Vec<&'s str>>,
+}
+
+impl<'s> Block<'s> {
+  pub fn error(&self, row: usize, message: &'static str) -> LineParseError {
+    LineParseError { line: self.lines[row], message: message }
+  }
+
+  /// Returns column `column` of every row.
+  pub fn column(&self, column: usize) -> Result<Vec<&'s str>, LineParseError> {
+    self.rows.iter().enumerate()
+      .map(|(i, row)| row.get(column).cloned()
+                         .ok_or_else(|| self.error(i, "missing column")))
+      .collect()
+  }
+}
+
+/// Splits a CoNLL file into sentences, which are separated by blank
+/// lines. Lines beginning with `#`, such as the `#begin document` lines
+/// of CoNLL-2012, are skipped.
+pub(crate) fn blocks<'s>(text: &'s str) -> Vec<Block<'s>> {
+  let mut blocks = vec![];
+  for document in documents(text) {
+    blocks.extend(document);
+  }
+  blocks
+}
+
+/// Splits a CoNLL-2012 file into documents, which begin with a
+/// `#begin document` line, and each document into sentences. Sentences
+/// before the first such line form a document of their own.
+pub(crate) fn documents<'s>(text: &'s str) -> Vec<Vec<Block<'s>>> {
+  let mut documents = vec![];
+  let mut document = vec![];
+  let mut block = Block { lines: vec![], rows: vec![] };
+  for (i, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      if !block.rows.is_empty() {
+        document.push(block);
+        block = Block { lines: vec![], rows: vec![] };
+      }
+      if line.starts_with("#begin document") && !document.is_empty() {
+        documents.push(document);
+        document = vec![];
+      }
+      continue;
+    }
+    block.lines.push(i + 1);
+    block.rows.push(line.split_whitespace().collect());
+  }
+  if !block.rows.is_empty() {
+    document.push(block);
+  }
+  if !document.is_empty() {
+    documents.push(document);
+  }
+  documents
+}
+
+/// Decodes a column of labeled brackets, such as the argument columns of
+/// CoNLL-2005 and CoNLL-2012, in which `(ARG0*` opens a span, `*)`
+/// closes it, and `(V*)` is a span of a single token. Returns each span
+/// with its label, or the row at which the brackets are unbalanced.
+pub(crate) fn bracket_spans(column: &[&str]) -> Result<Vec<(String, Span)>, usize> {
+  let mut open: Vec<(String, usize)> = vec![];
+  let mut spans = vec![];
+  for (row, cell) in column.iter().enumerate() {
+    let mut rest = *cell;
+    while let Some(c) = rest.chars().next() {
+      match c {
+        '(' => {
+          let end = rest[1..].find(&['(', '*', ')'][..])
+            .map_or(rest.len(), |end| end + 1);
+          open.push((rest[1..end].to_string(), row));
+          rest = &rest[end..];
+        },
+        ')' => {
+          let (label, start) = open.pop().ok_or(row)?;
+          spans.push((label, Span::new(start, row + 1)));
+          rest = &rest[1..];
+        },
+        _ => rest = &rest[c.len_utf8()..],
+      }
+    }
+  }
+  if !open.is_empty() {
+    return Err(column.len().saturating_sub(1));
+  }
+  spans.sort_by_key(|&(_, span)| span);
+  Ok(spans)
+}
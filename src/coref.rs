@@ -0,0 +1,298 @@
+use span::Span;
+use conll;
+use error::LineParseError;
+
+use std::collections::{HashMap, HashSet};
+
+/// A mention of an entity: a span of one line of a document.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Mention {
+  /// The index of the line containing the mention.
+  pub line: usize,
+  pub span: Span,
+}
+
+impl Mention {
+  /// Creates a mention of `span` in line `line`.
+  pub fn new(line: usize, span: Span) -> Mention {
+    Mention { line: line, span: span }
+  }
+}
+
+/// A coreference chain: the mentions of a single entity, in document
+/// order.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Chain {
+  /// The identifier of the entity.
+  pub id: usize,
+  pub mentions: Vec<Mention>,
+}
+
+/// A document annotated with coreference chains.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct CorefDocument {
+  /// The words of each line of the document.
+  pub lines: Vec<Vec<String>>,
+  pub chains: Vec<Chain>,
+}
+
+/// Reads the coreference annotations of a file in the format of the
+/// CoNLL-2011 and CoNLL-2012 shared tasks, in which the last column of
+/// each row opens and closes mentions of numbered entities: `(12` opens a
+/// mention of entity 12, `12)` closes it, `(12)` is a mention of a single
+/// word, and a word beginning or ending several mentions lists them
+/// separated by `|`.
+///
+/// Each `#begin document` line begins a new document.
+pub fn read_coref_conll(text: &str) -> Result<Vec<CorefDocument>, LineParseError> {
+  conll::documents(text).into_iter().map(|blocks| {
+    let mut lines = vec![];
+    let mut chains: HashMap<usize, Vec<Mention>> = HashMap::new();
+    for (l, block) in blocks.iter().enumerate() {
+      lines.push(block.column(3)?.iter().map(|w| w.to_string()).collect());
+      let mut open: HashMap<usize, Vec<usize>> = HashMap::new();
+      for (i, row) in block.rows.iter().enumerate() {
+        let cell = row.last().cloned().unwrap_or("-");
+        if cell == "-" {
+          continue;
+        }
+        for part in cell.split('|') {
+          let opens = part.starts_with('(');
+          let closes = part.ends_with(')');
+          let id: usize = part.trim_matches(|c| c == '(' || c == ')').parse()
+            .map_err(|_| block.error(i, "malformed coreference cell"))?;
+          if opens {
+            open.entry(id).or_default().push(i);
+          }
+          if closes {
+            let start = open.get_mut(&id).and_then(|starts| starts.pop())
+              .ok_or_else(|| block.error(i, "mention closed before it is opened"))?;
+            chains.entry(id).or_default().push(Mention::new(l, Span::new(start, i + 1)));
+          }
+        }
+      }
+      if open.values().any(|starts| !starts.is_empty()) {
+        return Err(block.error(block.rows.len() - 1, "mention left open"));
+      }
+    }
+
+    let mut chains: Vec<Chain> = chains.into_iter()
+      .map(|(id, mut mentions)| {
+        mentions.sort();
+        Chain { id: id, mentions: mentions }
+      })
+      .collect();
+    chains.sort_by_key(|chain| chain.id);
+    Ok(CorefDocument { lines: lines, chains: chains })
+  }).collect()
+}
+
+/// The precision and recall of a system's output against a key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrecisionRecall {
+  pub precision: f64,
+  pub recall: f64,
+}
+
+impl PrecisionRecall {
+  /// Returns the harmonic mean of precision and recall, or zero if both
+  /// are zero.
+  pub fn f1(&self) -> f64 {
+    let sum = self.precision + self.recall;
+    if sum == 0.0 { 0.0 } else { 2.0 * self.precision * self.recall / sum }
+  }
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+  if denominator == 0.0 { 0.0 } else { numerator / denominator }
+}
+
+/// Returns the chain containing each mention.
+fn index(chains: &[Chain]) -> HashMap<Mention, usize> {
+  let mut index = HashMap::new();
+  for (c, chain) in chains.iter().enumerate() {
+    for &mention in &chain.mentions {
+      index.insert(mention, c);
+    }
+  }
+  index
+}
+
+/// Scores coreference chains with the link-based MUC metric (Vilain et
+/// al., 1995): the fraction of the links needed to connect each key
+/// chain that the response also makes, and vice versa.
+pub fn muc(key: &[Chain], response: &[Chain]) -> PrecisionRecall {
+  fn recall(key: &[Chain], response: &[Chain]) -> f64 {
+    let index = index(response);
+    let (mut numerator, mut denominator) = (0, 0);
+    for chain in key {
+      // The mentions missing from the response each form a partition.
+      let mut partitions = HashSet::new();
+      let mut missing = 0;
+      for mention in &chain.mentions {
+        match index.get(mention) {
+          Some(&c) => { partitions.insert(c); },
+          None => missing += 1,
+        }
+      }
+      numerator += chain.mentions.len() - (partitions.len() + missing).min(chain.mentions.len());
+      denominator += chain.mentions.len().saturating_sub(1);
+    }
+    ratio(numerator as f64, denominator as f64)
+  }
+  PrecisionRecall {
+    precision: recall(response, key),
+    recall: recall(key, response),
+  }
+}
+
+/// Scores coreference chains with the mention-based B³ metric (Bagga and
+/// Baldwin, 1998): the average, over mentions of the key, of the
+/// fraction of each mention's key chain found in its response chain,
+/// and vice versa.
+pub fn b_cubed(key: &[Chain], response: &[Chain]) -> PrecisionRecall {
+  fn recall(key: &[Chain], response: &[Chain]) -> f64 {
+    let index = index(response);
+    let (mut numerator, mut denominator) = (0.0, 0);
+    for chain in key {
+      for mention in &chain.mentions {
+        denominator += 1;
+        if let Some(&c) = index.get(mention) {
+          let shared = overlap(&chain.mentions, &response[c].mentions);
+          numerator += shared as f64 / chain.mentions.len() as f64;
+        }
+      }
+    }
+    ratio(numerator, denominator as f64)
+  }
+  PrecisionRecall {
+    precision: recall(response, key),
+    recall: recall(key, response),
+  }
+}
+
+/// Scores coreference chains with the entity-based CEAF metric (Luo,
+/// 2005), using the φ₄ similarity: key and response chains are aligned
+/// one-to-one to maximize their total similarity, which is divided by
+/// the number of key chains for recall, and response chains for
+/// precision.
+pub fn ceaf_e(key: &[Chain], response: &[Chain]) -> PrecisionRecall {
+  let total = ceaf(key, response, |k, r| {
+    2.0 * overlap(k, r) as f64 / (k.len() + r.len()) as f64
+  });
+  PrecisionRecall {
+    precision: ratio(total, response.len() as f64),
+    recall: ratio(total, key.len() as f64),
+  }
+}
+
+/// Scores coreference chains with the mention-based CEAF metric (Luo,
+/// 2005), using the φ₃ similarity: the number of mentions shared by the
+/// aligned chains, divided by the number of key mentions for recall,
+/// and response mentions for precision.
+pub fn ceaf_m(key: &[Chain], response: &[Chain]) -> PrecisionRecall {
+  let total = ceaf(key, response, |k, r| overlap(k, r) as f64);
+  let mentions = |chains: &[Chain]| chains.iter().map(|c| c.mentions.len()).sum::<usize>();
+  PrecisionRecall {
+    precision: ratio(total, mentions(response) as f64),
+    recall: ratio(total, mentions(key) as f64),
+  }
+}
+
+/// Returns the CoNLL score: the average F₁ of MUC, B³ and entity-based
+/// CEAF, as in the CoNLL-2011 and CoNLL-2012 shared tasks.
+pub fn conll_f1(key: &[Chain], response: &[Chain]) -> f64 {
+  (muc(key, response).f1() + b_cubed(key, response).f1()
+    + ceaf_e(key, response).f1()) / 3.0
+}
+
+/// Returns the number of mentions shared by two chains.
+fn overlap(a: &[Mention], b: &[Mention]) -> usize {
+  a.iter().filter(|mention| b.contains(mention)).count()
+}
+
+/// Returns the greatest total similarity of a one-to-one alignment of key
+/// and response chains.
+fn ceaf<F>(key: &[Chain], response: &[Chain], similarity: F) -> f64
+  where F: Fn(&[Mention], &[Mention]) -> f64
+{
+  let weights: Vec<Vec<f64>> = key.iter()
+    .map(|k| response.iter().map(|r| similarity(&k.mentions, &r.mentions)).collect())
+    .collect();
+  max_assignment(&weights)
+}
+
+/// Solves the assignment problem for a (possibly rectangular) matrix of
+/// non-negative weights with the Hungarian algorithm, returning the
+/// greatest total weight of a matching of rows to columns.
+fn max_assignment(weights: &[Vec<f64>]) -> f64 {
+  let rows = weights.len();
+  let columns = weights.first().map_or(0, |row| row.len());
+  if rows == 0 || columns == 0 {
+    return 0.0;
+  }
+
+  // Minimize the cost of a square matrix, padded with zero weights.
+  let n = rows.max(columns);
+  let max = weights.iter().flat_map(|row| row.iter().cloned()).fold(0.0, f64::max);
+  let cost = |i: usize, j: usize| {
+    max - if i < rows && j < columns { weights[i][j] } else { 0.0 }
+  };
+
+  // Potentials and matches are indexed from one; zero is a sentinel.
+  let mut u = vec![0.0; n + 1];
+  let mut v = vec![0.0; n + 1];
+  let mut matched = vec![0; n + 1];
+  let mut way = vec![0; n + 1];
+  for i in 1..=n {
+    matched[0] = i;
+    let mut j0 = 0;
+    let mut minimum = vec![f64::INFINITY; n + 1];
+    let mut used = vec![false; n + 1];
+    loop {
+      used[j0] = true;
+      let i0 = matched[j0];
+      let mut delta = f64::INFINITY;
+      let mut j1 = 0;
+      for j in 1..=n {
+        if used[j] {
+          continue;
+        }
+        let reduced = cost(i0 - 1, j - 1) - u[i0] - v[j];
+        if reduced < minimum[j] {
+          minimum[j] = reduced;
+          way[j] = j0;
+        }
+        if minimum[j] < delta {
+          delta = minimum[j];
+          j1 = j;
+        }
+      }
+      for j in 0..=n {
+        if used[j] {
+          u[matched[j]] += delta;
+          v[j] -= delta;
+        } else {
+          minimum[j] -= delta;
+        }
+      }
+      j0 = j1;
+      if matched[j0] == 0 {
+        break;
+      }
+    }
+    loop {
+      let j1 = way[j0];
+      matched[j0] = matched[j1];
+      j0 = j1;
+      if j0 == 0 {
+        break;
+      }
+    }
+  }
+
+  (1..=n)
+    .filter(|&j| matched[j] - 1 < rows && j - 1 < columns)
+    .map(|j| weights[matched[j] - 1][j - 1])
+    .sum()
+}
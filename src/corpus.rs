@@ -1,12 +1,24 @@
 use token::Token;
 use language::{Language, DefaultLanguage};
+use sampling::quotas;
 
 use std::io;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::hash;
 use itertools::Itertools;
-use std::mem;
+use std::slice;
 use std::convert::TryFrom;
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::str;
+use std::fmt;
+use std::error::Error;
+use std::ops::{self, Range};
+use rand::Rng;
+#[cfg(feature = "mmap")]
+use std::path::Path;
+#[cfg(feature = "mmap")]
+use std::fs::File;
 
 /// A unigram is a single token.
 pub type Unigram<'t, L> = Token<'t, L>;
@@ -15,34 +27,500 @@ pub type Unigram<'t, L> = Token<'t, L>;
 /// the same corpora).
 pub type Bigram<'t, L>  = (Token<'t, L>, Token<'t, L>);
 
-/// A line is a slice of tokens.
-pub type Line<'t, L> = &'t[Token<'t, L>];
+/// A line is a view of the tokens of one line of a document.
+pub type Line<'t, L> = Tokens<'t, L>;
 
 /// The `Document` type, parameterized by a Language.
+///
+/// A document owns its bytes, and records each token and line as a span
+/// of indices: of bytes for tokens, and of tokens for lines. Tokens are
+/// constructed from their spans as they are read, as slices of the
+/// document's bytes, so that they are never copied.
 pub struct Document<L=DefaultLanguage>
   where L: 'static
 {
-  #[allow(dead_code)]
-  bytes: Vec<u8>,
-  tokens: Vec<Token<'static, L>>,
-  lines: Vec<Line<'static, L>>,
+  bytes: Storage,
+  /// The span of bytes of every token, in order.
+  tokens: Vec<(u32, u32)>,
+  /// The span of tokens of every line, in order.
+  lines: Vec<(u32, u32)>,
+  language: PhantomData<L>,
+}
+
+/// The bytes of a document.
+enum Storage {
+  Owned(Vec<u8>),
+  #[cfg(feature = "mmap")]
+  Mapped(::memmap2::Mmap),
+}
+
+impl ops::Deref for Storage {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    match *self {
+      Storage::Owned(ref bytes) => bytes,
+      #[cfg(feature = "mmap")]
+      Storage::Mapped(ref map) => map,
+    }
+  }
+}
+
+impl<L> Document<L> {
+  /// Returns a view of the tokens in the document.
+  pub fn tokens<'t>(&'t self) -> Tokens<'t, L> {
+    Tokens { bytes: &self.bytes, spans: &self.tokens, language: PhantomData }
+  }
+
+  /// Returns a view of the lines in the document.
+  pub fn lines<'t>(&'t self) -> Lines<'t, L> {
+    Lines { tokens: self.tokens(), spans: &self.lines }
+  }
+
+  /// Partitions the lines of the document into consecutive portions, such
+  /// as training, validation and test sets, in the given proportions,
+  /// which need not sum to `1`. Each portion's number of lines is rounded
+  /// by the largest remainder method, so that every line is in exactly
+  /// one portion.
+  ///
+  /// ```rust
+  /// let portions = corpus.split(&[0.8, 0.1, 0.1]);
+  /// let model = Model::train(&portions[0], 3);
+  /// println!("validation perplexity: {}", model.perplexity(&portions[1]));
+  /// ```
+  pub fn split<'t>(&'t self, proportions: &[f64]) -> Vec<Vec<Line<'t, L>>> {
+    let order: Vec<usize> = (0..self.lines.len()).collect();
+    self.portions(&order, proportions)
+  }
+
+  /// Partitions the lines of the document as [`split`] does, but
+  /// assigning them to portions at random, for corpora whose lines are
+  /// ordered by source or time. Each portion keeps its lines in the order
+  /// of the document. The random number generator is taken by value;
+  /// seeding it makes the split reproducible.
+  ///
+  /// ```rust
+  /// let rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+  /// let portions = corpus.split_shuffled(&[0.8, 0.1, 0.1], rng);
+  /// ```
+  ///
+  /// [`split`]: #method.split
+  pub fn split_shuffled<'t, R: Rng>(&'t self, proportions: &[f64], mut rng: R)
+      -> Vec<Vec<Line<'t, L>>>
+  {
+    let mut order: Vec<usize> = (0..self.lines.len()).collect();
+    rng.shuffle(&mut order);
+    self.portions(&order, proportions)
+  }
+
+  /// Divides lines, in the given order, into consecutive portions of the
+  /// given proportions, each in the order of the document.
+  fn portions<'t>(&'t self, order: &[usize], proportions: &[f64]) -> Vec<Vec<Line<'t, L>>> {
+    let weights: Vec<f64> = proportions.iter().map(|&proportion| proportion.max(0.0)).collect();
+    let lines = self.lines();
+    let mut start = 0;
+    quotas(&weights, order.len()).into_iter()
+      .map(|size| {
+        let mut portion = order[start..start + size].to_vec();
+        portion.sort();
+        start += size;
+        portion.into_iter().filter_map(|l| lines.get(l)).collect()
+      })
+      .collect()
+  }
+}
+
+/// A view of a sequence of tokens of a [`Document`], such as all of its
+/// tokens or those of one of its lines. It is read like a slice of
+/// tokens, but each token is constructed from its span as it is read.
+/// Use [`to_vec`] for a slice of tokens.
+///
+/// [`Document`]: struct.Document.html
+/// [`to_vec`]: #method.to_vec
+pub struct Tokens<'t, L: 't> {
+  bytes: &'t [u8],
+  spans: &'t [(u32, u32)],
+  /// Views are shared between threads as slices of tokens are.
+  language: PhantomData<&'t L>,
+}
+
+impl<'t, L> Tokens<'t, L> {
+  /// Returns the number of tokens.
+  pub fn len(&self) -> usize {
+    self.spans.len()
+  }
+
+  /// Returns `true` if there are no tokens.
+  pub fn is_empty(&self) -> bool {
+    self.spans.is_empty()
+  }
+
+  /// Returns the token at index `i`, or `None` if it is out of bounds.
+  pub fn get(&self, i: usize) -> Option<Token<'t, L>> {
+    self.spans.get(i).map(|&span| token(self.bytes, span))
+  }
+
+  /// Returns the first token, or `None` if there are none.
+  pub fn first(&self) -> Option<Token<'t, L>> {
+    self.get(0)
+  }
+
+  /// Returns the last token, or `None` if there are none.
+  pub fn last(&self) -> Option<Token<'t, L>> {
+    self.len().checked_sub(1).and_then(|i| self.get(i))
+  }
+
+  /// Returns a view of the tokens in a range of indices.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the range is out of bounds, as slicing does.
+  pub fn slice(&self, range: Range<usize>) -> Tokens<'t, L> {
+    Tokens { bytes: self.bytes, spans: &self.spans[range], language: PhantomData }
+  }
+
+  /// Returns an iterator over the tokens.
+  pub fn iter(&self) -> TokenIter<'t, L> {
+    TokenIter { bytes: self.bytes, spans: self.spans.iter(), language: PhantomData }
+  }
+
+  /// Returns an iterator over every window of `n` adjacent tokens, as
+  /// views, as `slice::windows` does.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `n` is `0`.
+  pub fn windows(&self, n: usize) -> impl 't + Iterator<Item=Tokens<'t, L>> {
+    let bytes = self.bytes;
+    self.spans.windows(n).map(move |spans| Tokens { bytes: bytes, spans: spans, language: PhantomData })
+  }
+
+  /// Returns the tokens as a vector.
+  pub fn to_vec(&self) -> Vec<Token<'t, L>> {
+    self.iter().collect()
+  }
+}
+
+impl<'t, L> Clone for Tokens<'t, L> {
+  fn clone(&self) -> Tokens<'t, L> {
+    *self
+  }
+}
+
+impl<'t, L> Copy for Tokens<'t, L> {}
+
+impl<'t, L> IntoIterator for Tokens<'t, L> {
+  type Item = Token<'t, L>;
+  type IntoIter = TokenIter<'t, L>;
+
+  fn into_iter(self) -> TokenIter<'t, L> {
+    self.iter()
+  }
+}
+
+impl<'t, L> fmt::Debug for Tokens<'t, L> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+impl<'t, L: Language> PartialEq for Tokens<'t, L> {
+  /// Views are equal if their tokens are equal, wherever they are.
+  fn eq(&self, other: &Tokens<'t, L>) -> bool {
+    self.iter().eq(other.iter())
+  }
+}
+
+impl<'t, L: Language> Eq for Tokens<'t, L> {}
+
+impl<'t, L: Language> hash::Hash for Tokens<'t, L> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    state.write_usize(self.len());
+    for token in self.iter() {
+      token.hash(state);
+    }
+  }
+}
+
+/// An iterator over the tokens of a [`Tokens`] view.
+///
+/// [`Tokens`]: struct.Tokens.html
+pub struct TokenIter<'t, L: 't> {
+  bytes: &'t [u8],
+  spans: slice::Iter<'t, (u32, u32)>,
+  language: PhantomData<&'t L>,
+}
+
+impl<'t, L> Clone for TokenIter<'t, L> {
+  fn clone(&self) -> TokenIter<'t, L> {
+    TokenIter { bytes: self.bytes, spans: self.spans.clone(), language: PhantomData }
+  }
+}
+
+impl<'t, L> Iterator for TokenIter<'t, L> {
+  type Item = Token<'t, L>;
+
+  fn next(&mut self) -> Option<Token<'t, L>> {
+    self.spans.next().map(|&span| token(self.bytes, span))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.spans.size_hint()
+  }
+}
+
+impl<'t, L> DoubleEndedIterator for TokenIter<'t, L> {
+  fn next_back(&mut self) -> Option<Token<'t, L>> {
+    self.spans.next_back().map(|&span| token(self.bytes, span))
+  }
+}
+
+impl<'t, L> ExactSizeIterator for TokenIter<'t, L> {}
+
+/// Sequences of tokens that can be read as a slice of tokens: [`Line`]
+/// and [`Tokens`] views and references to them, which are copied into a
+/// vector, and slices and vectors of tokens, which are borrowed.
+///
+/// [`Line`]: type.Line.html
+/// [`Tokens`]: struct.Tokens.html
+pub trait AsTokens<'t, L: Language> {
+  /// Returns the tokens as a slice.
+  fn as_tokens(&self) -> Cow<'_, [Token<'t, L>]>;
+}
+
+impl<'t, L: Language> AsTokens<'t, L> for Tokens<'t, L> {
+  fn as_tokens(&self) -> Cow<'_, [Token<'t, L>]> {
+    Cow::Owned(self.to_vec())
+  }
+}
+
+impl<'t, L: Language> AsTokens<'t, L> for &Tokens<'t, L> {
+  fn as_tokens(&self) -> Cow<'_, [Token<'t, L>]> {
+    Cow::Owned(self.to_vec())
+  }
+}
+
+impl<'t, L, T> AsTokens<'t, L> for T
+  where L: Language,
+        T: AsRef<[Token<'t, L>]> + ?Sized
+{
+  fn as_tokens(&self) -> Cow<'_, [Token<'t, L>]> {
+    Cow::Borrowed(self.as_ref())
+  }
+}
+
+/// Returns the token of a span of bytes.
+fn token<L>(bytes: &[u8], (start, end): (u32, u32)) -> Token<'_, L> {
+  bytes[start as usize..end as usize].into()
+}
+
+/// A view of the lines of a [`Document`], each a [`Line`] view of its
+/// tokens.
+///
+/// [`Document`]: struct.Document.html
+/// [`Line`]: type.Line.html
+pub struct Lines<'t, L: 't> {
+  tokens: Tokens<'t, L>,
+  spans: &'t [(u32, u32)],
+}
+
+impl<'t, L> Lines<'t, L> {
+  /// Returns the number of lines.
+  pub fn len(&self) -> usize {
+    self.spans.len()
+  }
+
+  /// Returns `true` if there are no lines.
+  pub fn is_empty(&self) -> bool {
+    self.spans.is_empty()
+  }
+
+  /// Returns line `i`, or `None` if it is out of bounds.
+  pub fn get(&self, i: usize) -> Option<Line<'t, L>> {
+    self.spans.get(i).map(|&(start, end)| self.tokens.slice(start as usize..end as usize))
+  }
+
+  /// Returns a view of the lines in a range of indices.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the range is out of bounds, as slicing does.
+  pub fn slice(&self, range: Range<usize>) -> Lines<'t, L> {
+    Lines { tokens: self.tokens, spans: &self.spans[range] }
+  }
+
+  /// Returns an iterator over the lines.
+  pub fn iter(&self) -> LineIter<'t, L> {
+    LineIter { tokens: self.tokens, spans: self.spans.iter() }
+  }
 }
 
+impl<'t, L> Clone for Lines<'t, L> {
+  fn clone(&self) -> Lines<'t, L> {
+    *self
+  }
+}
+
+impl<'t, L> Copy for Lines<'t, L> {}
+
+impl<'t, L> IntoIterator for Lines<'t, L> {
+  type Item = Line<'t, L>;
+  type IntoIter = LineIter<'t, L>;
+
+  fn into_iter(self) -> LineIter<'t, L> {
+    self.iter()
+  }
+}
+
+impl<'t, L> fmt::Debug for Lines<'t, L> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+/// An iterator over the lines of a [`Lines`] view.
+///
+/// [`Lines`]: struct.Lines.html
+pub struct LineIter<'t, L: 't> {
+  tokens: Tokens<'t, L>,
+  spans: slice::Iter<'t, (u32, u32)>,
+}
+
+impl<'t, L> Clone for LineIter<'t, L> {
+  fn clone(&self) -> LineIter<'t, L> {
+    LineIter { tokens: self.tokens, spans: self.spans.clone() }
+  }
+}
+
+impl<'t, L> Iterator for LineIter<'t, L> {
+  type Item = Line<'t, L>;
+
+  fn next(&mut self) -> Option<Line<'t, L>> {
+    self.spans.next().map(|&(start, end)| self.tokens.slice(start as usize..end as usize))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.spans.size_hint()
+  }
+}
+
+impl<'t, L> DoubleEndedIterator for LineIter<'t, L> {
+  fn next_back(&mut self) -> Option<Line<'t, L>> {
+    self.spans.next_back().map(|&(start, end)| self.tokens.slice(start as usize..end as usize))
+  }
+}
+
+impl<'t, L> ExactSizeIterator for LineIter<'t, L> {}
+
 impl<L> Document<L> {
-  /// Returns a slice of tokens in the document.
-  pub fn tokens<'t>(&'t self) -> &'t [Token<'t, L>] {
-    &self.tokens[..]
+  /// Creates a document from bytes that should be UTF-8, handling any
+  /// invalid sequences according to `policy` rather than carrying them
+  /// into tokens, and reports how many bytes were affected.
+  ///
+  /// ```rust
+  /// let (document, report): (Document<English>, _) =
+  ///   Document::from_utf8(fs::read("crawl.txt")?, Utf8Policy::Replace)?;
+  /// println!("replaced {} invalid bytes", report.invalid_bytes);
+  /// ```
+  pub fn from_utf8(bytes: Vec<u8>, policy: Utf8Policy)
+      -> Result<(Document<L>, Utf8Report), InvalidUtf8>
+  {
+    let (bytes, report) = decode_utf8(bytes, policy)?;
+    Ok((bytes.into(), report))
   }
 
-  /// Returns a slice of lines in the document.
-  pub fn lines<'t>(&'t self) -> &'t [&'t [Token<'t, L>]] {
-    &self.lines[..]
+  /// Creates a document from a value implementing the [`Read`] trait as
+  /// [`from_utf8`] does. Invalid UTF-8 rejected by the policy is reported
+  /// as an error of kind `InvalidData`.
+  ///
+  /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+  /// [`from_utf8`]: #method.from_utf8
+  pub fn read_utf8<R: io::Read>(mut reader: R, policy: Utf8Policy)
+      -> io::Result<(Document<L>, Utf8Report)>
+  {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    Document::from_utf8(bytes, policy)
+      .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
   }
 }
 
+/// What to do with invalid UTF-8 when loading a document.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Utf8Policy {
+  /// Replace each invalid sequence with `U+FFFD REPLACEMENT CHARACTER`.
+  Replace,
+  /// Remove invalid sequences.
+  Strip,
+  /// Fail at the first invalid sequence.
+  Reject,
+}
+
+/// How much invalid UTF-8 a [`Utf8Policy`] replaced or removed.
+///
+/// [`Utf8Policy`]: enum.Utf8Policy.html
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Utf8Report {
+  /// The number of invalid sequences.
+  pub invalid_sequences: usize,
+  /// The number of bytes in invalid sequences.
+  pub invalid_bytes: usize,
+}
 
-impl<I: io::Read, L> TryFrom<I> for Document<L> {
-  type Error = io::Error;
+/// An error arising when bytes are not valid UTF-8.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+  /// The byte offset of the first invalid sequence.
+  pub offset: usize,
+}
+
+impl fmt::Display for InvalidUtf8 {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "invalid UTF-8 at offset {}", self.offset)
+  }
+}
+
+impl Error for InvalidUtf8 {
+  fn description(&self) -> &str {
+    "invalid UTF-8"
+  }
+}
+
+/// Validates bytes as UTF-8, replacing or removing invalid sequences
+/// according to `policy`. Valid input is returned without copying.
+pub fn decode_utf8(bytes: Vec<u8>, policy: Utf8Policy)
+    -> Result<(Vec<u8>, Utf8Report), InvalidUtf8>
+{
+  let mut report = Utf8Report::default();
+  if str::from_utf8(&bytes).is_ok() {
+    return Ok((bytes, report));
+  }
+  let mut decoded = vec![];
+  let mut rest = &bytes[..];
+  loop {
+    let error = match str::from_utf8(rest) {
+      Ok(_) => {
+        decoded.extend_from_slice(rest);
+        return Ok((decoded, report));
+      },
+      Err(error) => error
+    };
+    let valid = error.valid_up_to();
+    let invalid = error.error_len().unwrap_or(rest.len() - valid);
+    if policy == Utf8Policy::Reject {
+      return Err(InvalidUtf8 { offset: bytes.len() - rest.len() + valid });
+    }
+    decoded.extend_from_slice(&rest[..valid]);
+    if policy == Utf8Policy::Replace {
+      decoded.extend_from_slice("\u{fffd}".as_bytes());
+    }
+    report.invalid_sequences += 1;
+    report.invalid_bytes += invalid;
+    rest = &rest[valid + invalid..];
+  }
+}
+
+impl<L> Document<L> {
   /// Creates a document from a value implementing the [`Read`] trait by
   /// reading bytes until all bytes have been read. For example:
   ///
@@ -52,13 +530,13 @@ impl<I: io::Read, L> TryFrom<I> for Document<L> {
   ///     .map(Result::unwrap)
   ///     .take(2);
   /// 
-  /// let original:    Document<French>  = files.next().unwrap().try_into()?;
-  /// let translation: Document<English> = files.next().unwrap().try_into()?;
+  /// let original:    Document<French>  = Document::from_reader(files.next().unwrap())?;
+  /// let translation: Document<English> = Document::from_reader(files.next().unwrap())?;
   /// ```
   /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
-  fn try_from(mut i: I) -> Result<Document<L>, io::Error> {
+  pub fn from_reader<R: io::Read>(mut reader: R) -> io::Result<Document<L>> {
     let mut bytes = vec![];
-    i.read_to_end(&mut bytes)?;
+    reader.read_to_end(&mut bytes)?;
     Ok(bytes.into())
   }
 }
@@ -66,68 +544,287 @@ impl<I: io::Read, L> TryFrom<I> for Document<L> {
 
 impl<I: Into<Vec<u8>>, L> From<I> for Document<L> {
   /// Creates a document from any value which can be interpreted as a
-  /// vector of bytes.
+  /// vector of bytes, splitting it into lines at newlines and into
+  /// tokens at spaces. Invalid UTF-8 is replaced as by
+  /// [`Utf8Policy::Replace`].
   ///
   /// ```rust
   ///
   /// let english: Document<English> = "The soup pleased the dog.".into();
   /// let fthishr: Document<Fthishr> = "Zhiidh or thir o vozir.".into();
   /// ```
-  /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+  ///
+  /// [`Utf8Policy::Replace`]: enum.Utf8Policy.html#variant.Replace
   fn from(i: I) -> Document<L> {
-    // Unsafe is used in this function to extend the lifetimes of tokens
-    // derived from the `Document` byte vector to that of the lifetime of
-    // the entire program. This is necessary because `Document`
-    // self-borrows; the `tokens` and `lines` fields contain pointers
-    // into the `bytes` field.
-    //
-    // Accordingly, these fields are kept private and the `tokens()` and
-    // `lines()` methods provide a safe interface that constrains
-    // the lifetimes of returned values to that of the document they
-    // belong to.
-    //
-    // It is important that after creating the `Document` value, that its
-    // `bytes` and `tokens` vectors are never pushed to. Exceeding the
-    // internal capacity of either of these vectors would force a
-    // reallocation of their backing memory store and thereby invalidate
-    // pointers into those vectors. Accordingly, no interface is
-    // provided for extending a `Document` with additional tokens after it
-    // is initialized.
-
-    let bytes = i.into();
-
-    let mut tokens = vec![];
-    let mut lines = vec![];
-
-    for sentence in bytes.split(|&c| c == b'\n') {
-      let s = tokens.len();
-      tokens.extend(sentence.split(|&c| c == b' ')
-        .filter(|w| !w.is_empty())
-        .map(|w| unsafe {mem::transmute::<Token<L>,_>(w.into())}));
-      let e = tokens.len();
-      lines.push((s,e));
+    Document::with_tokenizer(i, &WhitespaceTokenizer)
+  }
+}
+
+impl<L> Document<L> {
+  /// Creates a document from any value which can be interpreted as a
+  /// vector of bytes, split into tokens and lines by a [`Tokenizer`].
+  /// Tokens remain slices of the document's bytes, which are decoded as
+  /// UTF-8 beforehand, replacing invalid sequences with `U+FFFD`. Tokens
+  /// split within a character keep only its whole characters when read
+  /// with [`Word::as_str`].
+  ///
+  /// ```rust
+  /// let english: Document<English> =
+  ///   Document::with_tokenizer("The soup pleased the dog.", &PunctuationTokenizer);
+  /// assert_eq!(english.tokens().len(), 6);
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// Panics if the tokenizer returns a token span outside the bytes, or
+  /// a line span outside the tokens, or if the document has more than
+  /// `u32::MAX` bytes or tokens.
+  ///
+  /// [`Tokenizer`]: trait.Tokenizer.html
+  /// [`Word::as_str`]: struct.Word.html#method.as_str
+  pub fn with_tokenizer<I, T>(i: I, tokenizer: &T) -> Document<L>
+    where I: Into<Vec<u8>>,
+          T: Tokenizer + ?Sized
+  {
+    let bytes = match decode_utf8(i.into(), Utf8Policy::Replace) {
+      Ok((bytes, _)) => bytes,
+      Err(_) => unreachable!("replacing invalid UTF-8 cannot fail")
+    };
+    Document::tokenize(Storage::Owned(bytes), tokenizer)
+  }
+
+  /// Creates a document from a file by mapping it into memory rather than
+  /// reading it, splitting it into lines at newlines and into tokens at
+  /// spaces. Pages of the file are loaded as its tokens are read, so
+  /// corpora larger than memory can be processed; only the tokens and
+  /// lines themselves are held in memory. A file that is not valid UTF-8
+  /// is reported as an error of kind `InvalidData`, since it cannot be
+  /// decoded without copying it.
+  ///
+  /// ```rust
+  /// let crawl: Document<English> = unsafe { Document::from_mmap("crawl.txt")? };
+  /// ```
+  ///
+  /// # Safety
+  ///
+  /// The file must not be modified or truncated, by this or any other
+  /// process, while the document exists.
+  #[cfg(feature = "mmap")]
+  pub unsafe fn from_mmap<P: AsRef<Path>>(path: P) -> io::Result<Document<L>> {
+    Document::from_mmap_with_tokenizer(path, &WhitespaceTokenizer)
+  }
+
+  /// Creates a document from a file by mapping it into memory, as
+  /// [`from_mmap`] does, split into tokens and lines by a [`Tokenizer`].
+  ///
+  /// # Safety
+  ///
+  /// The file must not be modified or truncated, by this or any other
+  /// process, while the document exists.
+  ///
+  /// [`from_mmap`]: #method.from_mmap
+  /// [`Tokenizer`]: trait.Tokenizer.html
+  #[cfg(feature = "mmap")]
+  pub unsafe fn from_mmap_with_tokenizer<P, T>(path: P, tokenizer: &T) -> io::Result<Document<L>>
+    where P: AsRef<Path>,
+          T: Tokenizer + ?Sized
+  {
+    let map = ::memmap2::Mmap::map(&File::open(path)?)?;
+    if let Err(error) = str::from_utf8(&map) {
+      let error = InvalidUtf8 { offset: error.valid_up_to() };
+      return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+    }
+    Ok(Document::tokenize(Storage::Mapped(map), tokenizer))
+  }
+
+  /// Splits the bytes of a document into tokens and lines.
+  fn tokenize<T>(bytes: Storage, tokenizer: &T) -> Document<L>
+    where T: Tokenizer + ?Sized
+  {
+    let (tokens, lines) = tokenizer.tokenize(&bytes);
+    let tokens: Vec<(u32, u32)> = tokens.into_iter()
+      .map(|span| {
+        assert!(bytes.get(span.clone()).is_some(), "token span outside the bytes");
+        index_span(span)
+      })
+      .collect();
+    let count = tokens.len();
+    let lines = lines.into_iter()
+      .map(|span| {
+        assert!(span.start <= span.end && span.end <= count, "line span outside the tokens");
+        index_span(span)
+      })
+      .collect();
+    Document { bytes: bytes, tokens: tokens, lines: lines, language: PhantomData }
+  }
+}
+
+/// Returns a span of indices as the pair of indices a document stores.
+///
+/// # Panics
+///
+/// Panics if an index exceeds `u32::MAX`.
+fn index_span(span: Range<usize>) -> (u32, u32) {
+  match (u32::try_from(span.start), u32::try_from(span.end)) {
+    (Ok(start), Ok(end)) => (start, end),
+    _ => panic!("documents of more than 4 GiB or 2^32 tokens are not supported")
+  }
+}
+
+/// The serialized form of a document: its text, and the spans of its
+/// tokens and lines, so that it is read without tokenizing it again.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Document")]
+struct SerializedDocument<T> {
+  text: T,
+  tokens: Vec<TokenSpan>,
+  lines: Vec<LineSpan>,
+}
+
+/// Tokenizes the text of a serialized document into its recorded spans.
+#[cfg(feature = "serde")]
+struct RecordedSpans<'s>(&'s [TokenSpan], &'s [LineSpan]);
+
+#[cfg(feature = "serde")]
+impl<'s> Tokenizer for RecordedSpans<'s> {
+  fn tokenize(&self, _: &[u8]) -> (Vec<TokenSpan>, Vec<LineSpan>) {
+    (self.0.to_vec(), self.1.to_vec())
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<L> ::serde::Serialize for Document<L> {
+  fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::Error;
+    let text = str::from_utf8(&self.bytes).map_err(S::Error::custom)?;
+    let span = |&(start, end): &(u32, u32)| start as usize..end as usize;
+    let tokens = self.tokens.iter().map(span).collect();
+    let lines = self.lines.iter().map(span).collect();
+    let document = SerializedDocument { text: text, tokens: tokens, lines: lines };
+    ::serde::Serialize::serialize(&document, serializer)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, L> ::serde::Deserialize<'de> for Document<L> {
+  fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Document<L>, D::Error> {
+    use serde::de::Error;
+    let document: SerializedDocument<String> = ::serde::Deserialize::deserialize(deserializer)?;
+    if document.tokens.iter().any(|span| document.text.get(span.clone()).is_none()) {
+      return Err(D::Error::custom("token span outside the text or within a character"));
     }
+    if document.lines.iter().any(|span| span.start > span.end || span.end > document.tokens.len()) {
+      return Err(D::Error::custom("line span outside the tokens"));
+    }
+    let spans = RecordedSpans(&document.tokens, &document.lines);
+    Ok(Document::with_tokenizer(document.text, &spans))
+  }
+}
+
+/// The bytes of one token, as a range of indices into a document.
+pub type TokenSpan = Range<usize>;
+
+/// The tokens of one line, as a range of indices into the tokens of a
+/// document.
+pub type LineSpan = Range<usize>;
+
+/// A method of splitting text into tokens and lines, for creating
+/// documents with [`Document::with_tokenizer`].
+///
+/// [`Document::with_tokenizer`]: struct.Document.html#method.with_tokenizer
+pub trait Tokenizer {
+  /// Returns the span of every token of `bytes`, in order, and the span
+  /// of tokens of every line, in order.
+  fn tokenize(&self, bytes: &[u8]) -> (Vec<TokenSpan>, Vec<LineSpan>);
+}
+
+/// Splits lines at newlines, and tokens at spaces. This is the tokenizer
+/// of documents created with `From`.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+  fn tokenize(&self, bytes: &[u8]) -> (Vec<TokenSpan>, Vec<LineSpan>) {
+    split(bytes, false)
+  }
+}
 
-    let lines = lines.iter().map(|&(s,e)|
-      unsafe{mem::transmute(&tokens[s..e])}).collect_vec();
+/// Splits lines at newlines, and tokens at spaces, tabs and carriage
+/// returns, with each ASCII punctuation mark as a token of its own.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct PunctuationTokenizer;
 
-    Document {
-      bytes: bytes,
-      tokens: tokens,
-      lines: lines,
+impl Tokenizer for PunctuationTokenizer {
+  fn tokenize(&self, bytes: &[u8]) -> (Vec<TokenSpan>, Vec<LineSpan>) {
+    split(bytes, true)
+  }
+}
+
+/// Splits bytes into lines at newlines, and into tokens at spaces. If
+/// `punctuation` holds, tokens are also split at tabs and carriage
+/// returns, and each ASCII punctuation mark is a token of its own.
+fn split(bytes: &[u8], punctuation: bool) -> (Vec<TokenSpan>, Vec<LineSpan>) {
+  let mut tokens = vec![];
+  let mut lines = vec![];
+  let mut offset = 0;
+  for line in bytes.split(|&c| c == b'\n') {
+    let first = tokens.len();
+    let mut start = offset;
+    for (i, &c) in line.iter().enumerate() {
+      let i = offset + i;
+      if c == b' ' || (punctuation && (c == b'\t' || c == b'\r')) {
+        if start < i {
+          tokens.push(start..i);
+        }
+        start = i + 1;
+      } else if punctuation && c.is_ascii_punctuation() {
+        if start < i {
+          tokens.push(start..i);
+        }
+        tokens.push(i..i + 1);
+        start = i + 1;
+      }
     }
+    let end = offset + line.len();
+    if start < end {
+      tokens.push(start..end);
+    }
+    lines.push(first..tokens.len());
+    offset = end + 1;
   }
+  (tokens, lines)
+}
+
+/// A set of tokens, such as a vocabulary, against which tokens can be
+/// checked by [`unk`].
+///
+/// [`unk`]: fn.unk.html
+pub trait TokenSet<'t, L> {
+  /// Returns `true` if the set contains the token.
+  fn contains(&self, token: &Token<'t, L>) -> bool;
 }
 
-/// Consumes an iterator over tokens and a vocabulary, and produces
-/// an iterator over tokens in which all unknown tokens (tokens that are 
-/// not in the given vocabulary) are replaced with [`Token::Unknown`].
+impl<'t, L, S> TokenSet<'t, L> for HashSet<Token<'t, L>, S>
+  where L: Language,
+        S: hash::BuildHasher
+{
+  fn contains(&self, token: &Token<'t, L>) -> bool {
+    HashSet::contains(self, token)
+  }
+}
+
+/// Consumes an iterator over tokens and a vocabulary, such as a
+/// `HashSet` or a [`Vocabulary`], and produces an iterator over tokens in
+/// which all unknown tokens (tokens that are not in the given
+/// vocabulary) are replaced with [`Token::Unknown`].
+/// [`Vocabulary`]: struct.Vocabulary.html
 /// [`Token::Unknown`]: enum.Token.html#variant.Unknown
-pub fn unk<'t, T, L, S>(tokens: T, vocabulary: &'t  HashSet<Token<'t, L>, S>)
+pub fn unk<'t, T, L, V>(tokens: T, vocabulary: &'t V)
     -> impl 't + Iterator<Item=Token<'t, L>>
   where L: Language + 't,
         T: 't + IntoIterator<Item=Token<'t, L>>,
-        S: hash::BuildHasher
+        V: TokenSet<'t, L> + ?Sized
 {
   IntoIterator::into_iter(tokens)
     .map(move |word| 
@@ -149,8 +846,233 @@ pub fn unigrams<'t, T, L>(tokens: T)
 pub fn bigrams<'t, T, L>(tokens: T)
     -> impl Iterator<Item=Bigram<'t, L>>
   where L: Language + 't,
-        T: IntoIterator<Item=&'t Token<'t, L>> {
-  IntoIterator::into_iter(tokens).cloned().tuple_windows::<(_,_)>()
+        T: IntoIterator<Item=Token<'t, L>> {
+  IntoIterator::into_iter(tokens).tuple_windows::<(_,_)>()
+}
+
+/// Chainable adapters for iterators over tokens, as methods. Each
+/// behaves as the free function of the same purpose:
+///
+/// ```rust
+/// let vocabulary: HashSet<Token<English>> = training.tokens().iter().collect();
+/// let bigrams = testing.tokens().iter()
+///   .normalized(|token| if numbers.contains(&token) { number } else { token })
+///   .unked(&vocabulary)
+///   .padded()
+///   .bigrams();
+/// ```
+pub trait TokenIterExt<'t, L>: Sized + Iterator<Item=Token<'t, L>>
+  where L: Language + 't
+{
+  /// Replaces every token not in the vocabulary with
+  /// [`Token::Unknown`], as [`unk`] does.
+  ///
+  /// [`Token::Unknown`]: enum.Token.html#variant.Unknown
+  /// [`unk`]: fn.unk.html
+  fn unked<V>(self, vocabulary: &'t V) -> impl 't + Iterator<Item=Token<'t, L>>
+    where Self: 't,
+          V: TokenSet<'t, L> + ?Sized
+  {
+    unk(self, vocabulary)
+  }
+
+  /// Surrounds the tokens with [`Token::Null`].
+  ///
+  /// [`Token::Null`]: enum.Token.html#variant.Null
+  fn padded(self) -> impl Iterator<Item=Token<'t, L>> {
+    use std::iter::once;
+    once(Token::Null).chain(self).chain(once(Token::Null))
+  }
+
+  /// Surrounds the tokens with `n - 1` [`Token::Null`], the context of
+  /// the first token in a model of n-grams of order `n`.
+  ///
+  /// [`Token::Null`]: enum.Token.html#variant.Null
+  fn padded_n(self, n: usize) -> impl Iterator<Item=Token<'t, L>> {
+    use std::iter::repeat_n;
+    let boundary = n.saturating_sub(1);
+    repeat_n(Token::Null, boundary).chain(self).chain(repeat_n(Token::Null, boundary))
+  }
+
+  /// Produces every bigram (pair of adjacent tokens), as [`bigrams`]
+  /// does.
+  ///
+  /// [`bigrams`]: fn.bigrams.html
+  fn bigrams(self) -> impl Iterator<Item=Bigram<'t, L>> {
+    self.tuple_windows::<(_,_)>()
+  }
+
+  /// Produces every k-skip-n-gram, as [`skipgrams`] does.
+  ///
+  /// [`skipgrams`]: fn.skipgrams.html
+  fn skipgrams(self, k: usize, n: usize) -> Skipgrams<'t, Self, L> {
+    skipgrams(self, k, n)
+  }
+
+  /// Replaces every token with its normal form under `normalize`, such
+  /// as a token standing for a class of tokens.
+  fn normalized<F>(self, normalize: F) -> impl Iterator<Item=Token<'t, L>>
+    where F: FnMut(Token<'t, L>) -> Token<'t, L>
+  {
+    self.map(normalize)
+  }
+}
+
+impl<'t, L, I> TokenIterExt<'t, L> for I
+  where L: Language + 't,
+        I: Iterator<Item=Token<'t, L>>
+{}
+
+/// Consumes an iterator over tokens and produces an iterator over all
+/// n-grams (windows of `N` adjacent tokens) in the input stream, as
+/// arrays. Inputs of fewer than `N` tokens produce no n-grams.
+///
+/// ```rust
+/// let training: Document = "the cat sat on the mat".into();
+/// let trigrams: Vec<[Token; 3]> = ngrams::<_, _, 3>(training.tokens()).collect();
+/// assert_eq!(trigrams.len(), 4);
+/// ```
+pub fn ngrams<'t, T, L, const N: usize>(tokens: T) -> Ngrams<'t, T::IntoIter, L, N>
+  where L: Language + 't,
+        T: IntoIterator<Item=Token<'t, L>>
+{
+  Ngrams {
+    tokens: IntoIterator::into_iter(tokens),
+    window: [Token::Null; N],
+    filled: 0,
+  }
+}
+
+/// An iterator over the n-grams of a stream of tokens, produced by
+/// [`ngrams`].
+///
+/// [`ngrams`]: fn.ngrams.html
+#[derive(Debug, Clone)]
+pub struct Ngrams<'t, I, L: 't, const N: usize> {
+  tokens: I,
+  /// The last `N` tokens of the input, once `filled` reaches `N`.
+  window: [Token<'t, L>; N],
+  filled: usize,
+}
+
+impl<'t, I, L, const N: usize> Iterator for Ngrams<'t, I, L, N>
+  where L: Language + 't,
+        I: Iterator<Item=Token<'t, L>>
+{
+  type Item = [Token<'t, L>; N];
+
+  fn next(&mut self) -> Option<[Token<'t, L>; N]> {
+    if N == 0 {
+      return None;
+    }
+    while self.filled < N {
+      self.window[self.filled] = self.tokens.next()?;
+      self.filled += 1;
+      if self.filled == N {
+        return Some(self.window);
+      }
+    }
+    self.window.rotate_left(1);
+    self.window[N - 1] = self.tokens.next()?;
+    Some(self.window)
+  }
+}
+
+/// Consumes an iterator over tokens and produces an iterator over all
+/// k-skip-n-grams in the input stream (Guthrie et al., 2006): every
+/// sequence of `n` tokens in input order, skipping at most `k` tokens in
+/// total between them. They are produced in order of their first token,
+/// and then in order of the positions of the rest, so that a `k` of `0`
+/// produces the same n-grams as [`ngrams`].
+///
+/// ```rust
+/// let training: Document = "insurgents killed in ongoing fighting".into();
+/// let skip_bigrams: Vec<Vec<Token>> = skipgrams(training.tokens(), 2, 2).collect();
+/// assert_eq!(skip_bigrams.len(), 9);
+/// ```
+///
+/// [`ngrams`]: fn.ngrams.html
+pub fn skipgrams<'t, T, L>(tokens: T, k: usize, n: usize) -> Skipgrams<'t, T::IntoIter, L>
+  where L: Language + 't,
+        T: IntoIterator<Item=Token<'t, L>>
+{
+  Skipgrams {
+    tokens: IntoIterator::into_iter(tokens),
+    k: k,
+    n: n,
+    window: VecDeque::new(),
+    offsets: None,
+  }
+}
+
+/// An iterator over the k-skip-n-grams of a stream of tokens, produced
+/// by [`skipgrams`].
+///
+/// [`skipgrams`]: fn.skipgrams.html
+#[derive(Debug, Clone)]
+pub struct Skipgrams<'t, I, L: 't> {
+  tokens: I,
+  k: usize,
+  n: usize,
+  /// The next `n + k` tokens of the input, from the first token of the
+  /// current skip-grams.
+  window: VecDeque<Token<'t, L>>,
+  /// The positions in the window of the tokens of the last skip-gram
+  /// produced, or `None` if none was produced from the first token of
+  /// the window.
+  offsets: Option<Vec<usize>>,
+}
+
+impl<'t, I, L> Iterator for Skipgrams<'t, I, L>
+  where L: Language + 't,
+        I: Iterator<Item=Token<'t, L>>
+{
+  type Item = Vec<Token<'t, L>>;
+
+  fn next(&mut self) -> Option<Vec<Token<'t, L>>> {
+    if self.n == 0 {
+      return None;
+    }
+    loop {
+      let advanced = match self.offsets {
+        None => {
+          while self.window.len() < self.n + self.k {
+            match self.tokens.next() {
+              Some(token) => self.window.push_back(token),
+              None => break
+            }
+          }
+          if self.window.len() < self.n {
+            return None;
+          }
+          self.offsets = Some((0..self.n).collect());
+          true
+        },
+        Some(ref mut offsets) => {
+          // Advance the last position that can move right, and place the
+          // positions after it just after it.
+          let last = self.window.len() - 1;
+          let n = self.n;
+          match (1..n).rev().find(|&j| offsets[j] + (n - j) <= last) {
+            Some(j) => {
+              offsets[j] += 1;
+              for i in j + 1..n {
+                offsets[i] = offsets[i - 1] + 1;
+              }
+              true
+            },
+            None => false
+          }
+        }
+      };
+      if advanced {
+        let window = &self.window;
+        return self.offsets.as_ref().map(|offsets| offsets.iter().map(|&i| window[i]).collect());
+      }
+      self.window.pop_front();
+      self.offsets = None;
+    }
+  }
 }
 
 /// Consumes an interator over lines, and produces an iterator over
@@ -177,38 +1099,66 @@ pub fn bigrams<'t, T, L>(tokens: T)
 /// [`Token::Null`]: enum.Token.html#variant.Null
 pub fn padded<'t, I, L: 't + Language>(lines: I)
     -> impl 't + Iterator<Item=Token<'t, L>>
-  where I: 't + IntoIterator<Item=&'t &'t[Token<'t, L>]>
+  where I: 't + IntoIterator<Item=Line<'t, L>>
 {
   use std::iter::once;
   once(Token::Null).chain(
     IntoIterator::into_iter(lines)
-      .map(|sentence| sentence.iter().cloned().chain(once(Token::Null)))
-      .flatten())
+      .flat_map(|sentence| sentence.iter().chain(once(Token::Null))))
+}
+
+/// Consumes an iterator over lines, and produces an iterator over all
+/// tokens in the document, with `n - 1` [`Token::Null`] values inserted
+/// at sentence boundaries, for models of n-grams of order `n`. Every line
+/// is preceded by a full context of boundary tokens, and no n-gram spans
+/// two lines. [`padded`] is `padded_n` of order `2`.
+///
+/// ```rust
+/// let testing: Document = "The soup pleased the dog.
+///                          The cat caught the rat.".into();
+///
+/// assert_eq!(padded_n(testing.lines(), 3).join(" "),
+///   "ε ε The soup pleased the dog. ε ε The cat caught the rat. ε ε");
+/// ```
+///
+/// [`Token::Null`]: enum.Token.html#variant.Null
+/// [`padded`]: fn.padded.html
+pub fn padded_n<'t, I, L: 't + Language>(lines: I, n: usize)
+    -> impl 't + Iterator<Item=Token<'t, L>>
+  where I: 't + IntoIterator<Item=Line<'t, L>>
+{
+  use std::iter::repeat_n;
+  let boundary = n.saturating_sub(1);
+  repeat_n(Token::Null, boundary).chain(
+    IntoIterator::into_iter(lines)
+      .flat_map(move |sentence| {
+        sentence.iter().chain(repeat_n(Token::Null, boundary))
+      }))
 }
 
 impl<'t, L: 't> IntoIterator for &'t Document<L> {
-  type Item = &'t &'t [Token<'t, L>];
-  type IntoIter = ::std::slice::Iter<'t, &'t [Token<'t, L>]>;
+  type Item = Line<'t, L>;
+  type IntoIter = LineIter<'t, L>;
 
   /// Convert a reference to a document into an iterator over lines
   /// in the document.
-  fn into_iter(self) -> Self::IntoIter {
-    IntoIterator::into_iter(self.lines())
+  fn into_iter(self) -> LineIter<'t, L> {
+    self.lines().iter()
   }
 }
 
-impl<'t, L: 't + Language> Into<&'t [&'t [Token<'t, L>]]> for &'t Document<L> {
-  /// Convert a reference to a document into a reference to a slice of all
-  /// lines in the document.
-  fn into(self) -> &'t [&'t [Token<'t, L>]] {
-    self.lines()
+impl<'t, L: 't> From<&'t Document<L>> for Lines<'t, L> {
+  /// Convert a reference to a document into a view of all lines in the
+  /// document.
+  fn from(document: &'t Document<L>) -> Lines<'t, L> {
+    document.lines()
   }
 }
 
-impl<'t, L: 't + Language> Into<&'t [Token<'t, L>]> for &'t Document<L> {
-  /// Convert a reference to a document into a reference to a slice of all
-  /// tokens in the document.
-  fn into(self) -> &'t [Token<'t, L>] {
-    self.tokens()
+impl<'t, L: 't> From<&'t Document<L>> for Tokens<'t, L> {
+  /// Convert a reference to a document into a view of all tokens in the
+  /// document.
+  fn from(document: &'t Document<L>) -> Tokens<'t, L> {
+    document.tokens()
   }
 }
@@ -0,0 +1,362 @@
+//! Downloading and caching of public corpora and lexicons.
+//!
+//! A [`DataManager`] keeps named [`Resource`]s in a local data directory,
+//! fetching each the first time it is asked for, and checking it against
+//! its SHA-256 checksum whenever it is used. The [`catalogue`] names a
+//! few well-known resources; others can be added:
+//!
+//! ```rust
+//! let mut data = DataManager::from_env();
+//! data.register(Resource::new("my-lexicon", "https://example.org/lexicon.tsv")
+//!   .with_sha256("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"));
+//!
+//! let treebank: Document<English> = data.document("ud-english-ewt-train")?;
+//! let stopwords = fs::read_to_string(data.path("stopwords-en")?)?;
+//! ```
+//!
+//! A resource without a known checksum is trusted when first fetched:
+//! its checksum is recorded beside it, and later uses check the cached
+//! file against the recorded checksum.
+//!
+//! [`DataManager`]: struct.DataManager.html
+//! [`Resource`]: struct.Resource.html
+//! [`catalogue`]: fn.catalogue.html
+use corpus::Document;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::env;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::collections::HashMap;
+
+/// A named file that can be fetched from a URL.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Resource {
+  /// The name of the resource, which is also the name of its file in the
+  /// data directory.
+  pub name: String,
+  pub url: String,
+  /// The SHA-256 checksum of the file, in lowercase hexadecimal, if known.
+  pub sha256: Option<String>,
+}
+
+impl Resource {
+  /// Creates a resource fetched from `url`, with no known checksum.
+  pub fn new(name: &str, url: &str) -> Resource {
+    Resource { name: name.to_string(), url: url.to_string(), sha256: None }
+  }
+
+  /// Sets the checksum the fetched file must have.
+  pub fn with_sha256(mut self, sha256: &str) -> Resource {
+    self.sha256 = Some(sha256.to_lowercase());
+    self
+  }
+}
+
+/// Returns the well-known resources every [`DataManager`] starts with:
+///
+/// - `ud-english-ewt-train`, `ud-english-ewt-dev` and
+///   `ud-english-ewt-test`: the English Web Treebank of Universal
+///   Dependencies, in CoNLL-U format.
+/// - `stopwords-en`, `stopwords-fr` and `stopwords-de`: the stopword lists
+///   of the stopwords-iso project, one word per line.
+/// - `frequency-en-50k`, `frequency-fr-50k` and `frequency-de-50k`: the
+///   50,000 most frequent words of the OpenSubtitles 2018 corpus, with
+///   their counts, from the FrequencyWords project.
+///
+/// These are fetched from their upstream repositories, which may change
+/// them, so they have no fixed checksums.
+///
+/// [`DataManager`]: struct.DataManager.html
+pub fn catalogue() -> Vec<Resource> {
+  let ewt = "https://raw.githubusercontent.com/UniversalDependencies/UD_English-EWT/master";
+  let stopwords = "https://raw.githubusercontent.com/stopwords-iso";
+  let frequency = "https://raw.githubusercontent.com/hermitdave/FrequencyWords/master/content/2018";
+  let mut resources = vec![];
+  for split in &["train", "dev", "test"] {
+    resources.push(Resource::new(&format!("ud-english-ewt-{}", split),
+                                 &format!("{}/en_ewt-ud-{}.conllu", ewt, split)));
+  }
+  for language in &["en", "fr", "de"] {
+    resources.push(Resource::new(&format!("stopwords-{}", language),
+                                 &format!("{0}/stopwords-{1}/master/stopwords-{1}.txt", stopwords, language)));
+    resources.push(Resource::new(&format!("frequency-{}-50k", language),
+                                 &format!("{0}/{1}/{1}_50k.txt", frequency, language)));
+  }
+  resources
+}
+
+/// A method of fetching the file at a URL.
+pub trait Fetch {
+  /// Writes the file at `url` to `destination`.
+  fn fetch(&self, url: &str, destination: &Path) -> io::Result<()>;
+}
+
+impl<F> Fetch for F
+  where F: Fn(&str, &Path) -> io::Result<()>
+{
+  fn fetch(&self, url: &str, destination: &Path) -> io::Result<()> {
+    self(url, destination)
+  }
+}
+
+/// Fetches files by running `curl`, which must be on the `PATH`.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Curl;
+
+impl Fetch for Curl {
+  fn fetch(&self, url: &str, destination: &Path) -> io::Result<()> {
+    let status = Command::new("curl")
+      .args(["--fail", "--silent", "--show-error", "--location", "--output"])
+      .arg(destination)
+      .arg(url)
+      .status()?;
+    if status.success() {
+      Ok(())
+    } else {
+      Err(io::Error::other(format!("curl exited with {}", status)))
+    }
+  }
+}
+
+/// An error arising when a resource cannot be provided.
+#[derive(Debug)]
+pub enum DataError {
+  /// No resource has the given name.
+  Unknown(String),
+  /// The resource could not be fetched, or the data directory could not
+  /// be read or written.
+  Io(io::Error),
+  /// The file of a resource does not have the expected checksum.
+  Checksum {
+    name: String,
+    expected: String,
+    actual: String,
+  },
+}
+
+impl fmt::Display for DataError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DataError::Unknown(ref name) => write!(f, "no resource named `{}`", name),
+      DataError::Io(ref error) => write!(f, "{}", error),
+      DataError::Checksum { ref name, ref expected, ref actual } =>
+        write!(f, "`{}` has checksum {}, not {}", name, actual, expected),
+    }
+  }
+}
+
+impl Error for DataError {
+  fn description(&self) -> &str {
+    match *self {
+      DataError::Unknown(_) => "no such resource",
+      DataError::Io(_) => "input/output error",
+      DataError::Checksum { .. } => "checksum mismatch",
+    }
+  }
+}
+
+impl From<io::Error> for DataError {
+  fn from(error: io::Error) -> DataError {
+    DataError::Io(error)
+  }
+}
+
+/// A cache of resources in a local data directory.
+pub struct DataManager {
+  directory: PathBuf,
+  resources: HashMap<String, Resource>,
+  fetcher: Box<dyn Fetch>,
+}
+
+impl DataManager {
+  /// Creates a manager of the resources of the [`catalogue`], cached in
+  /// `directory` and fetched with [`Curl`].
+  ///
+  /// [`catalogue`]: fn.catalogue.html
+  /// [`Curl`]: struct.Curl.html
+  pub fn new<P: Into<PathBuf>>(directory: P) -> DataManager {
+    DataManager {
+      directory: directory.into(),
+      resources: catalogue().into_iter().map(|r| (r.name.clone(), r)).collect(),
+      fetcher: Box::new(Curl),
+    }
+  }
+
+  /// Creates a manager caching resources in the directory named by the
+  /// `NLPTK_DATA` environment variable, or else in `.nlptk_data` in the
+  /// home directory, or else in the working directory.
+  pub fn from_env() -> DataManager {
+    let directory = match env::var_os("NLPTK_DATA") {
+      Some(directory) => PathBuf::from(directory),
+      None => {
+        let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+        home.map(PathBuf::from).unwrap_or_default().join(".nlptk_data")
+      }
+    };
+    DataManager::new(directory)
+  }
+
+  /// Sets how resources are fetched, such as from a local mirror.
+  pub fn with_fetcher<F: 'static + Fetch>(mut self, fetcher: F) -> DataManager {
+    self.fetcher = Box::new(fetcher);
+    self
+  }
+
+  /// Adds a resource, replacing any resource of the same name.
+  pub fn register(&mut self, resource: Resource) {
+    self.resources.insert(resource.name.clone(), resource);
+  }
+
+  /// Returns the data directory.
+  pub fn directory(&self) -> &Path {
+    &self.directory
+  }
+
+  /// Returns the resource of the given name.
+  pub fn resource(&self, name: &str) -> Option<&Resource> {
+    self.resources.get(name)
+  }
+
+  /// Returns `true` if the file of a resource is in the data directory.
+  pub fn is_cached(&self, name: &str) -> bool {
+    self.directory.join(name).is_file()
+  }
+
+  /// Returns the path of the file of a resource, fetching it if it is not
+  /// yet in the data directory, after checking its checksum. A fetched
+  /// file with the wrong checksum is deleted.
+  pub fn path(&self, name: &str) -> Result<PathBuf, DataError> {
+    let resource = self.resources.get(name).ok_or_else(|| DataError::Unknown(name.to_string()))?;
+    let path = self.directory.join(name);
+    let recorded = self.directory.join(format!("{}.sha256", name));
+    if path.is_file() {
+      let expected = match resource.sha256 {
+        Some(ref sha256) => Some(sha256.clone()),
+        None => fs::read_to_string(&recorded).ok().map(|sha256| sha256.trim().to_string())
+      };
+      if let Some(expected) = expected {
+        verify(name, &fs::read(&path)?, &expected)?;
+      }
+      return Ok(path);
+    }
+
+    fs::create_dir_all(&self.directory)?;
+    let partial = self.directory.join(format!("{}.part", name));
+    let fetched = self.fetcher.fetch(&resource.url, &partial)
+      .and_then(|_| fs::read(&partial));
+    let bytes = match fetched {
+      Ok(bytes) => bytes,
+      Err(error) => {
+        let _ = fs::remove_file(&partial);
+        return Err(DataError::Io(error));
+      }
+    };
+    let actual = hex(&sha256(&bytes));
+    if let Some(ref expected) = resource.sha256 {
+      if let Err(error) = verify(name, &bytes, expected) {
+        let _ = fs::remove_file(&partial);
+        return Err(error);
+      }
+    }
+    fs::write(&recorded, &actual)?;
+    fs::rename(&partial, &path)?;
+    Ok(path)
+  }
+
+  /// Returns a resource as a document, fetching it if necessary.
+  pub fn document<L>(&self, name: &str) -> Result<Document<L>, DataError> {
+    Ok(Document::from(fs::read(self.path(name)?)?))
+  }
+
+  /// Removes the file of a resource from the data directory, if it is
+  /// there.
+  pub fn remove(&self, name: &str) -> io::Result<()> {
+    for path in &[self.directory.join(name), self.directory.join(format!("{}.sha256", name))] {
+      if path.is_file() {
+        fs::remove_file(path)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Checks that bytes have the expected checksum.
+fn verify(name: &str, bytes: &[u8], expected: &str) -> Result<(), DataError> {
+  let actual = hex(&sha256(bytes));
+  if actual == expected {
+    Ok(())
+  } else {
+    Err(DataError::Checksum { name: name.to_string(), expected: expected.to_string(), actual: actual })
+  }
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The round constants of SHA-256.
+const K: [u32; 64] = [
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Returns the SHA-256 digest of bytes.
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+  let mut state: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+  ];
+  let mut message = bytes.to_vec();
+  message.push(0x80);
+  while message.len() % 64 != 56 {
+    message.push(0);
+  }
+  message.extend_from_slice(&((bytes.len() as u64) * 8).to_be_bytes());
+
+  for block in message.chunks(64) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.chunks(4).enumerate() {
+      w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for i in 16..64 {
+      let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+      let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+      w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    for i in 0..64 {
+      let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+      let choice = (e & f) ^ (!e & g);
+      let t1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(K[i]).wrapping_add(w[i]);
+      let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+      let majority = (a & b) ^ (a & c) ^ (b & c);
+      let t2 = s0.wrapping_add(majority);
+      h = g;
+      g = f;
+      f = e;
+      e = d.wrapping_add(t1);
+      d = c;
+      c = b;
+      b = a;
+      a = t1.wrapping_add(t2);
+    }
+    for (word, value) in state.iter_mut().zip(&[a, b, c, d, e, f, g, h]) {
+      *word = word.wrapping_add(*value);
+    }
+  }
+
+  let mut digest = [0; 32];
+  for (bytes, word) in digest.chunks_mut(4).zip(&state) {
+    bytes.copy_from_slice(&word.to_be_bytes());
+  }
+  digest
+}
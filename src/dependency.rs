@@ -0,0 +1,252 @@
+use std::fmt;
+use std::error::Error;
+use std::collections::VecDeque;
+
+/// A `DepGraph` is a labeled dependency tree over the tokens of a
+/// sentence. Tokens are identified by their (zero-based) position in the
+/// sentence; each token has at most one head, and exactly one token, the
+/// root, has none.
+///
+/// A `DepGraph` stores only the structure of the tree. The tokens it
+/// describes are kept alongside it, for instance, in a [`Line`] of the
+/// same length.
+///
+/// ```rust
+/// // the dog barked
+/// let graph = DepGraph::new(vec![Some(1), Some(2), None],
+///                           vec!["det", "nsubj", "root"])?;
+/// assert_eq!(graph.root(), 2);
+/// assert_eq!(graph.dependents(2), &[1]);
+/// assert!(graph.is_projective());
+/// ```
+///
+/// [`Line`]: type.Line.html
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DepGraph {
+  heads: Vec<Option<usize>>,
+  labels: Vec<String>,
+  /// The dependents of each token, in sentence order.
+  dependents: Vec<Vec<usize>>,
+  root: usize,
+}
+
+/// The reason a set of heads and labels does not form a [`DepGraph`].
+/// [`DepGraph`]: struct.DepGraph.html
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum DepGraphError {
+  /// The number of heads differs from the number of labels.
+  LengthMismatch,
+  /// The sentence has no tokens.
+  Empty,
+  /// The head of the given token is not a position in the sentence.
+  HeadOutOfRange(usize),
+  /// No token is the root.
+  NoRoot,
+  /// More than one token is a root; the given token is the second.
+  MultipleRoots(usize),
+  /// The given token is its own ancestor.
+  Cycle(usize),
+}
+
+impl fmt::Display for DepGraphError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DepGraphError::LengthMismatch =>
+        write!(f, "the number of heads and labels differ"),
+      DepGraphError::Empty =>
+        write!(f, "a dependency graph must have at least one token"),
+      DepGraphError::HeadOutOfRange(i) =>
+        write!(f, "the head of token {} is out of range", i),
+      DepGraphError::NoRoot =>
+        write!(f, "no token is the root"),
+      DepGraphError::MultipleRoots(i) =>
+        write!(f, "token {} is a second root", i),
+      DepGraphError::Cycle(i) =>
+        write!(f, "token {} is its own ancestor", i),
+    }
+  }
+}
+
+impl Error for DepGraphError {
+  fn description(&self) -> &str {
+    "invalid dependency graph"
+  }
+}
+
+impl DepGraph {
+  /// Creates a dependency graph from the head and label of each token,
+  /// checking that the result is a tree: that there is exactly one root,
+  /// and that no token is its own ancestor.
+  pub fn new<S>(heads: Vec<Option<usize>>, labels: Vec<S>)
+      -> Result<DepGraph, DepGraphError>
+    where S: Into<String>
+  {
+    if heads.len() != labels.len() {
+      return Err(DepGraphError::LengthMismatch);
+    }
+    if heads.is_empty() {
+      return Err(DepGraphError::Empty);
+    }
+
+    let mut root = None;
+    let mut dependents = vec![vec![]; heads.len()];
+    for (i, &head) in heads.iter().enumerate() {
+      match head {
+        Some(h) if h >= heads.len() || h == i =>
+          return Err(if h == i { DepGraphError::Cycle(i) }
+                     else { DepGraphError::HeadOutOfRange(i) }),
+        Some(h) => dependents[h].push(i),
+        None if root.is_some() => return Err(DepGraphError::MultipleRoots(i)),
+        None => root = Some(i),
+      }
+    }
+    let root = root.ok_or(DepGraphError::NoRoot)?;
+
+    // With a single root and every head in range, the graph is a tree
+    // exactly when every token is reachable from the root.
+    let graph = DepGraph {
+      heads: heads,
+      labels: labels.into_iter().map(Into::into).collect(),
+      dependents: dependents,
+      root: root,
+    };
+    let mut reached = vec![false; graph.len()];
+    for i in graph.preorder() {
+      reached[i] = true;
+    }
+    match reached.iter().position(|&r| !r) {
+      Some(i) => Err(DepGraphError::Cycle(i)),
+      None => Ok(graph)
+    }
+  }
+
+  /// Returns the number of tokens in the sentence.
+  pub fn len(&self) -> usize {
+    self.heads.len()
+  }
+
+  /// Always returns `false`; a dependency graph has at least one token.
+  pub fn is_empty(&self) -> bool {
+    self.heads.is_empty()
+  }
+
+  /// Returns the position of the root token.
+  pub fn root(&self) -> usize {
+    self.root
+  }
+
+  /// Returns the head of each token.
+  pub fn heads(&self) -> &[Option<usize>] {
+    &self.heads
+  }
+
+  /// Returns the head of token `i`, or `None` if it is the root.
+  pub fn head(&self, i: usize) -> Option<usize> {
+    self.heads[i]
+  }
+
+  /// Returns the label of the arc to token `i` from its head.
+  pub fn label(&self, i: usize) -> &str {
+    &self.labels[i]
+  }
+
+  /// Returns the dependents of token `i`, in sentence order.
+  pub fn dependents(&self, i: usize) -> &[usize] {
+    &self.dependents[i]
+  }
+
+  /// Returns every `(head, dependent)` arc in the graph.
+  pub fn arcs<'g>(&'g self) -> impl 'g + Iterator<Item=(usize, usize)> {
+    self.heads.iter().enumerate()
+      .filter_map(|(d, &h)| h.map(|h| (h, d)))
+  }
+
+  /// Returns `true` if token `ancestor` dominates token `i`; every token
+  /// dominates itself.
+  pub fn dominates(&self, ancestor: usize, i: usize) -> bool {
+    i == ancestor || self.ancestors(i).any(|a| a == ancestor)
+  }
+
+  /// Returns every arc that is crossed by another arc. An arc from `h`
+  /// to `d` is non-projective if some token between `h` and `d` is not
+  /// dominated by `h`.
+  pub fn non_projective_arcs(&self) -> Vec<(usize, usize)> {
+    self.arcs()
+      .filter(|&(h, d)| {
+        let (low, high) = if h < d { (h, d) } else { (d, h) };
+        (low + 1..high).any(|i| !self.dominates(h, i))
+      })
+      .collect()
+  }
+
+  /// Returns `true` if no arcs cross when drawn above the sentence.
+  pub fn is_projective(&self) -> bool {
+    self.non_projective_arcs().is_empty()
+  }
+
+  /// Produces an iterator over the ancestors of token `i`, from its head
+  /// up to the root.
+  pub fn ancestors<'g>(&'g self, i: usize) -> impl 'g + Iterator<Item=usize> {
+    let mut current = i;
+    ::std::iter::from_fn(move || {
+      let head = self.heads[current];
+      if let Some(h) = head { current = h; }
+      head
+    })
+  }
+
+  /// Produces a depth-first, pre-order iterator over the tokens of the
+  /// tree, visiting each head before its dependents, and dependents in
+  /// sentence order.
+  pub fn preorder<'g>(&'g self) -> Preorder<'g> {
+    Preorder { graph: self, stack: vec![self.root] }
+  }
+
+  /// Produces an iterator over the tokens of the subtree headed by token
+  /// `i`, in pre-order.
+  pub fn subtree<'g>(&'g self, i: usize) -> Preorder<'g> {
+    Preorder { graph: self, stack: vec![i] }
+  }
+
+  /// Produces a breadth-first iterator over the tokens of the tree,
+  /// starting from the root.
+  pub fn breadth_first<'g>(&'g self) -> BreadthFirst<'g> {
+    let mut queue = VecDeque::new();
+    queue.push_back(self.root);
+    BreadthFirst { graph: self, queue: queue }
+  }
+}
+
+/// A depth-first, pre-order traversal of a [`DepGraph`].
+/// [`DepGraph`]: struct.DepGraph.html
+pub struct Preorder<'g> {
+  graph: &'g DepGraph,
+  stack: Vec<usize>,
+}
+
+impl<'g> Iterator for Preorder<'g> {
+  type Item = usize;
+
+  fn next(&mut self) -> Option<usize> {
+    let i = self.stack.pop()?;
+    self.stack.extend(self.graph.dependents[i].iter().rev());
+    Some(i)
+  }
+}
+
+/// A breadth-first traversal of a [`DepGraph`].
+/// [`DepGraph`]: struct.DepGraph.html
+pub struct BreadthFirst<'g> {
+  graph: &'g DepGraph,
+  queue: VecDeque<usize>,
+}
+
+impl<'g> Iterator for BreadthFirst<'g> {
+  type Item = usize;
+
+  fn next(&mut self) -> Option<usize> {
+    let i = self.queue.pop_front()?;
+    self.queue.extend(self.graph.dependents[i].iter());
+    Some(i)
+  }
+}
@@ -0,0 +1,275 @@
+//! Frequencies of words over time, for diachronic analysis.
+//!
+//! A [`TimedCorpus`] holds documents with optional timestamps, in any
+//! unit, such as years or seconds since the Unix epoch. Timestamps are
+//! grouped into periods of a given width, in which the frequency of each
+//! word is counted; [`TimedCorpus::changes`] tests every word for a
+//! significant change of its frequency across periods by the chi-square
+//! test of homogeneity.
+//!
+//! ```rust
+//! let mut corpus: TimedCorpus<English> = TimedCorpus::new();
+//! for (year, path) in articles {
+//!   corpus.add(Document::from_reader(File::open(path)?)?, Some(year));
+//! }
+//!
+//! // Words whose usage changed between decades.
+//! for change in corpus.changes(10, 50).iter().take(20) {
+//!   println!("{}\tchi2 = {:.1}\tp = {:.3}", change.token, change.chi_square, change.p_value);
+//! }
+//! for observation in corpus.time_series("wireless", 10) {
+//!   println!("{}s\t{:.1} per million", observation.start, observation.per_million());
+//! }
+//! ```
+//!
+//! [`TimedCorpus`]: struct.TimedCorpus.html
+//! [`TimedCorpus::changes`]: struct.TimedCorpus.html#method.changes
+use token::Token;
+use language::{Language, DefaultLanguage};
+use corpus::Document;
+
+use std::collections::{BTreeMap, HashMap};
+
+/// A collection of documents in language `L`, each with an optional
+/// timestamp. Documents without timestamps belong to no period, and are
+/// left out of every time series.
+pub struct TimedCorpus<L=DefaultLanguage>
+  where L: 'static
+{
+  documents: Vec<(Option<i64>, Document<L>)>,
+}
+
+/// The occurrences of a word in one period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Observation {
+  /// The first timestamp of the period.
+  pub start: i64,
+  /// The number of occurrences of the word in the period.
+  pub count: usize,
+  /// The number of tokens in the period.
+  pub tokens: usize,
+}
+
+impl Observation {
+  /// Returns the number of occurrences of the word per million tokens
+  /// of the period.
+  pub fn per_million(&self) -> f64 {
+    if self.tokens == 0 { 0.0 } else { self.count as f64 * 1e6 / self.tokens as f64 }
+  }
+}
+
+/// The result of testing a word for a change of frequency across
+/// periods.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageChange<'t, L> {
+  pub token: Token<'t, L>,
+  /// The chi-square statistic of the homogeneity of the word's
+  /// frequency across periods.
+  pub chi_square: f64,
+  /// The degrees of freedom of the test: one less than the number of
+  /// periods.
+  pub degrees_of_freedom: usize,
+  /// The probability of a statistic at least as large if the word's
+  /// frequency did not change.
+  pub p_value: f64,
+  /// The occurrences of the word in each period, in order.
+  pub series: Vec<Observation>,
+}
+
+impl<L: Language> TimedCorpus<L> {
+  /// Creates a corpus of no documents.
+  pub fn new() -> TimedCorpus<L> {
+    TimedCorpus { documents: vec![] }
+  }
+
+  /// Adds a document, with its timestamp if it has one.
+  pub fn add(&mut self, document: Document<L>, timestamp: Option<i64>) {
+    self.documents.push((timestamp, document));
+  }
+
+  /// Returns the documents of the corpus with their timestamps, in the
+  /// order they were added.
+  pub fn documents(&self) -> &[(Option<i64>, Document<L>)] {
+    &self.documents
+  }
+
+  /// Returns the number of documents.
+  pub fn len(&self) -> usize {
+    self.documents.len()
+  }
+
+  /// Returns `true` if the corpus has no documents.
+  pub fn is_empty(&self) -> bool {
+    self.documents.is_empty()
+  }
+
+  /// Returns the occurrences of a word in every period of `width`
+  /// timestamps holding a document, in order. Periods begin at multiples
+  /// of the width; widths below `1` are treated as `1`.
+  pub fn time_series(&self, word: &str, width: i64) -> Vec<Observation> {
+    let (totals, counts) = self.count(width);
+    match counts.get(&Token::from(word.as_bytes())) {
+      Some(counts) => series(&totals, counts),
+      None => series(&totals, &vec![0; totals.len()])
+    }
+  }
+
+  /// Tests every word occurring at least `min_count` times in the
+  /// periods of `width` timestamps for a change of its frequency, and
+  /// returns the results in descending order of the chi-square
+  /// statistic. Nothing is returned unless at least two periods hold
+  /// tokens.
+  pub fn changes<'t>(&'t self, width: i64, min_count: usize) -> Vec<UsageChange<'t, L>> {
+    let (totals, counts) = self.count(width);
+    let periods = totals.len();
+    if periods < 2 {
+      return vec![];
+    }
+    let tokens: usize = totals.iter().map(|&(_, tokens)| tokens).sum();
+    let mut changes: Vec<UsageChange<'t, L>> = counts.into_iter()
+      .filter_map(|(token, counts)| {
+        let occurrences: usize = counts.iter().sum();
+        if occurrences < min_count {
+          return None;
+        }
+        let mut chi_square = 0.0;
+        for (&(_, period), &count) in totals.iter().zip(&counts) {
+          let share = period as f64 / tokens as f64;
+          for &(observed, expected) in &[
+            (count as f64, occurrences as f64 * share),
+            ((period - count) as f64, (tokens - occurrences) as f64 * share),
+          ] {
+            if expected > 0.0 {
+              chi_square += (observed - expected).powi(2) / expected;
+            }
+          }
+        }
+        Some(UsageChange {
+          token: token,
+          chi_square: chi_square,
+          degrees_of_freedom: periods - 1,
+          p_value: chi_square_p_value(chi_square, periods - 1),
+          series: series(&totals, &counts),
+        })
+      })
+      .collect();
+    changes.sort_by(|a, b| {
+      b.chi_square.partial_cmp(&a.chi_square)
+        .unwrap_or(::std::cmp::Ordering::Equal)
+        .then(a.token.cmp(&b.token))
+    });
+    changes
+  }
+
+  /// Returns the start and number of tokens of every period holding a
+  /// document, in order, and the count of every word in each period.
+  fn count<'t>(&'t self, width: i64) -> PeriodCounts<'t, L> {
+    let width = width.max(1);
+    let mut periods: BTreeMap<i64, Vec<&'t Document<L>>> = BTreeMap::new();
+    for &(timestamp, ref document) in &self.documents {
+      if let Some(timestamp) = timestamp {
+        periods.entry(timestamp.div_euclid(width) * width).or_default().push(document);
+      }
+    }
+    let mut totals = vec![];
+    let mut counts: HashMap<Token<'t, L>, Vec<usize>> = HashMap::new();
+    for (p, (&start, documents)) in periods.iter().enumerate() {
+      let mut tokens = 0;
+      for token in documents.iter().flat_map(|document| document.tokens()) {
+        if let Token::Word(_) = token {
+          counts.entry(token).or_insert_with(|| vec![0; periods.len()])[p] += 1;
+          tokens += 1;
+        }
+      }
+      totals.push((start, tokens));
+    }
+    (totals, counts)
+  }
+}
+
+impl<L: Language> Default for TimedCorpus<L> {
+  fn default() -> TimedCorpus<L> {
+    TimedCorpus::new()
+  }
+}
+
+/// The start and number of tokens of each period, and the count of each
+/// word in each period.
+type PeriodCounts<'t, L> = (Vec<(i64, usize)>, HashMap<Token<'t, L>, Vec<usize>>);
+
+/// Pairs the counts of a word with the periods they were counted in.
+fn series(totals: &[(i64, usize)], counts: &[usize]) -> Vec<Observation> {
+  totals.iter().zip(counts)
+    .map(|(&(start, tokens), &count)| Observation { start: start, count: count, tokens: tokens })
+    .collect()
+}
+
+/// Returns the probability that a chi-square distributed variable with
+/// `degrees_of_freedom` degrees of freedom is at least `statistic`.
+pub fn chi_square_p_value(statistic: f64, degrees_of_freedom: usize) -> f64 {
+  if degrees_of_freedom == 0 || statistic <= 0.0 {
+    return 1.0;
+  }
+  upper_gamma(degrees_of_freedom as f64 / 2.0, statistic / 2.0)
+}
+
+/// Returns the regularized upper incomplete gamma function `Q(a, x)`, by
+/// its series for `x < a + 1` and its continued fraction otherwise, after
+/// Press et al., _Numerical Recipes_.
+fn upper_gamma(a: f64, x: f64) -> f64 {
+  let scale = (-x + a * x.ln() - ln_gamma(a)).exp();
+  if x < a + 1.0 {
+    let mut term = 1.0 / a;
+    let mut sum = term;
+    let mut n = a;
+    for _ in 0..500 {
+      n += 1.0;
+      term *= x / n;
+      sum += term;
+      if term.abs() < sum.abs() * 1e-15 {
+        break;
+      }
+    }
+    (1.0 - sum * scale).max(0.0)
+  } else {
+    let tiny = 1e-300;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / tiny;
+    let mut d = 1.0 / b;
+    let mut fraction = d;
+    for i in 1..500 {
+      let an = -(i as f64) * (i as f64 - a);
+      b += 2.0;
+      d = an * d + b;
+      if d.abs() < tiny {
+        d = tiny;
+      }
+      c = b + an / c;
+      if c.abs() < tiny {
+        c = tiny;
+      }
+      d = 1.0 / d;
+      let delta = d * c;
+      fraction *= delta;
+      if (delta - 1.0).abs() < 1e-15 {
+        break;
+      }
+    }
+    fraction * scale
+  }
+}
+
+/// Returns the natural logarithm of the gamma function, by the Lanczos
+/// approximation.
+fn ln_gamma(x: f64) -> f64 {
+  let coefficients = [
+    76.18009172947146, -86.50532032941677, 24.01409824083091,
+    -1.231739572450155, 0.1208650973866179e-2, -0.5395239384953e-5,
+  ];
+  let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+  let mut series = 1.000000000190015;
+  for (i, &coefficient) in coefficients.iter().enumerate() {
+    series += coefficient / (x + 1.0 + i as f64);
+  }
+  -tmp + (2.5066282746310005 * series / x).ln()
+}
@@ -0,0 +1,148 @@
+use token::Token;
+use language::{Language, DefaultLanguage};
+use corpus::{Document, Lines};
+use frequency::FrequencyList;
+use error::LineParseError;
+
+use std::ops::Range;
+
+/// A conversation of turns, each a sequence of lines spoken by one
+/// speaker. The lines of every turn are stored in one [`Document`], in
+/// order, so that the whole conversation can be processed as a document
+/// and each turn or speaker viewed separately.
+///
+/// ```rust
+/// let dialogue: Dialogue<English> = read_transcript(&fs::read_to_string("interview.txt")?)?;
+/// for speaker in dialogue.speakers() {
+///   let frequencies = dialogue.frequencies_of(speaker);
+///   println!("{} spoke {} tokens of {} words", speaker, frequencies.total(), frequencies.len());
+/// }
+/// ```
+///
+/// [`Document`]: struct.Document.html
+pub struct Dialogue<L=DefaultLanguage>
+  where L: 'static
+{
+  document: Document<L>,
+  /// The speakers, in order of their first turn.
+  speakers: Vec<String>,
+  /// The speaker of each turn, and its lines in the document.
+  turns: Vec<(usize, Range<usize>)>,
+}
+
+impl<L: Language> Dialogue<L> {
+  /// Creates a dialogue from `(speaker, lines)` pairs, one per turn.
+  /// Newlines within a line are replaced by spaces.
+  pub fn new<S, U>(turns: &[(S, Vec<U>)]) -> Dialogue<L>
+    where S: AsRef<str>,
+          U: AsRef<str>
+  {
+    let mut speakers: Vec<String> = vec![];
+    let mut bounds = vec![];
+    let mut lines: Vec<String> = vec![];
+    for (speaker, utterances) in turns {
+      let speaker = speaker.as_ref();
+      let id = match speakers.iter().position(|known| known == speaker) {
+        Some(id) => id,
+        None => {
+          speakers.push(speaker.to_string());
+          speakers.len() - 1
+        }
+      };
+      let start = lines.len();
+      lines.extend(utterances.iter().map(|line| line.as_ref().replace('\n', " ")));
+      bounds.push((id, start..lines.len()));
+    }
+    Dialogue { document: lines.join("\n").into(), speakers: speakers, turns: bounds }
+  }
+
+  /// Returns the document of the lines of every turn, in order.
+  pub fn document(&self) -> &Document<L> {
+    &self.document
+  }
+
+  /// Returns the speakers, in order of their first turn.
+  pub fn speakers(&self) -> &[String] {
+    &self.speakers
+  }
+
+  /// Returns the number of turns.
+  pub fn len(&self) -> usize {
+    self.turns.len()
+  }
+
+  /// Returns `true` if the dialogue has no turns.
+  pub fn is_empty(&self) -> bool {
+    self.turns.is_empty()
+  }
+
+  /// Returns the speaker of turn `t`.
+  pub fn speaker(&self, t: usize) -> &str {
+    &self.speakers[self.turns[t].0]
+  }
+
+  /// Returns the lines of turn `t`.
+  pub fn turn<'t>(&'t self, t: usize) -> Lines<'t, L> {
+    self.document.lines().slice(self.turns[t].1.clone())
+  }
+
+  /// Returns the speaker and lines of every turn, in order.
+  pub fn turns<'t>(&'t self) -> impl 't + Iterator<Item=(&'t str, Lines<'t, L>)> {
+    (0..self.turns.len()).map(move |t| (self.speaker(t), self.turn(t)))
+  }
+
+  /// Returns the tokens of every turn of a speaker, in order.
+  pub fn tokens_of<'t>(&'t self, speaker: &str) -> Vec<Token<'t, L>> {
+    self.turns()
+      .filter(|&(s, _)| s == speaker)
+      .flat_map(|(_, lines)| lines.iter().flat_map(|line| line.iter()))
+      .collect()
+  }
+
+  /// Returns the frequency list of the tokens of a speaker.
+  pub fn frequencies_of<'t>(&'t self, speaker: &str) -> FrequencyList<'t, L> {
+    FrequencyList::from_tokens(self.tokens_of(speaker))
+  }
+}
+
+/// Reads a transcript of `speaker: utterance` lines into a
+/// [`Dialogue`]. Each labeled line begins a turn; unlabeled lines
+/// continue the turn before them, and blank lines are skipped. A label is
+/// the text before the first colon of a line, if it is followed by
+/// whitespace or the end of the line, and is of at most four words.
+///
+/// ```text
+/// A: Did you see the game?
+/// B: No, I was
+///    working late.
+/// ```
+///
+/// [`Dialogue`]: struct.Dialogue.html
+pub fn read_transcript<L: Language>(text: &str) -> Result<Dialogue<L>, LineParseError> {
+  let mut turns: Vec<(&str, Vec<&str>)> = vec![];
+  for (i, line) in text.lines().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    match label(line) {
+      Some((speaker, "")) => turns.push((speaker, vec![])),
+      Some((speaker, utterance)) => turns.push((speaker, vec![utterance])),
+      None => match turns.last_mut() {
+        Some(&mut (_, ref mut lines)) => lines.push(line.trim()),
+        None => return Err(LineParseError { line: i + 1, message: "utterance without a speaker" })
+      }
+    }
+  }
+  Ok(Dialogue::new(&turns))
+}
+
+/// Splits a line into its speaker label and utterance, if it is labeled.
+fn label(line: &str) -> Option<(&str, &str)> {
+  let colon = line.find(':')?;
+  let (speaker, rest) = (line[..colon].trim(), &line[colon + 1..]);
+  let separated = rest.is_empty() || rest.starts_with(char::is_whitespace);
+  if speaker.is_empty() || !separated || speaker.split_whitespace().count() > 4 {
+    return None;
+  }
+  Some((speaker, rest.trim()))
+}
@@ -0,0 +1,169 @@
+use token::Token;
+use language::Language;
+
+use std::io;
+use std::collections::HashMap;
+
+/// How evenly a word of a [`DispersionList`] is spread across the parts
+/// of a corpus.
+///
+/// [`DispersionList`]: struct.DispersionList.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dispersion<'t, L> {
+  pub token: Token<'t, L>,
+  /// The number of times the word occurs.
+  pub frequency: usize,
+  /// The number of parts in which the word occurs.
+  pub range: usize,
+  /// Gries's deviation of proportions: half the sum of the differences
+  /// between the share of the word's occurrences in each part and the
+  /// share of the corpus in that part. It is `0` for a word spread in
+  /// proportion to the sizes of the parts, and approaches `1` for a word
+  /// confined to one small part.
+  pub dp: f64,
+  /// Juilland's D: one less the coefficient of variation of the word's
+  /// relative frequency in each part, divided by the square root of one
+  /// less the number of parts. It is `1` for a word spread evenly, and
+  /// `0` for a word confined to one part.
+  pub juilland_d: f64,
+  /// Church and Gale's burstiness: the mean number of occurrences of the
+  /// word in the parts in which it occurs. A word that occurs once in
+  /// many parts has a burstiness near `1`; a word that is repeated
+  /// within the few parts it occurs in has a greater one.
+  pub burstiness: f64,
+}
+
+/// The dispersion of every word of a corpus divided into parts, such as
+/// documents, chapters or speakers, since frequency alone does not tell
+/// a word used throughout the corpus from one used often in a few parts.
+///
+/// Words are ordered by descending frequency, and words of equal
+/// frequency in byte order. `Null` tokens are not counted.
+///
+/// ```rust
+/// let list = DispersionList::from_parts(documents.iter().map(Document::tokens));
+/// for dispersion in list.dispersions().iter().take(20) {
+///   println!("{}\t{}\tDP = {:.2}\tD = {:.2}", dispersion.token, dispersion.frequency,
+///            dispersion.dp, dispersion.juilland_d);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DispersionList<'t, L> {
+  dispersions: Vec<Dispersion<'t, L>>,
+  /// The index of each word in `dispersions`.
+  index: HashMap<Token<'t, L>, usize>,
+  /// The number of tokens of each part.
+  sizes: Vec<usize>,
+}
+
+impl<'t, L: Language> DispersionList<'t, L> {
+  /// Measures the dispersion of every word across parts, each a
+  /// sequence of tokens.
+  pub fn from_parts<I, P>(parts: I) -> DispersionList<'t, L>
+    where I: IntoIterator<Item=P>,
+          P: IntoIterator<Item=Token<'t, L>>
+  {
+    let mut sizes = vec![];
+    // The count of each word in each part in which it occurs.
+    let mut counts: HashMap<Token<'t, L>, Vec<(usize, usize)>> = HashMap::new();
+    for (p, part) in parts.into_iter().enumerate() {
+      let mut size = 0;
+      for token in part {
+        if token == Token::Null {
+          continue;
+        }
+        size += 1;
+        let occurrences = counts.entry(token).or_default();
+        match occurrences.last_mut() {
+          Some(&mut (part, ref mut count)) if part == p => *count += 1,
+          _ => occurrences.push((p, 1))
+        }
+      }
+      sizes.push(size);
+    }
+
+    let total: usize = sizes.iter().sum();
+    let mut dispersions: Vec<Dispersion<'t, L>> = counts.into_iter()
+      .map(|(token, occurrences)| dispersion(token, &occurrences, &sizes, total))
+      .collect();
+    dispersions.sort_by(|a, b| b.frequency.cmp(&a.frequency).then(a.token.cmp(&b.token)));
+    let index = dispersions.iter().enumerate().map(|(i, dispersion)| (dispersion.token, i)).collect();
+    DispersionList { dispersions: dispersions, index: index, sizes: sizes }
+  }
+
+  /// Returns the words of the list, in descending order of frequency.
+  pub fn dispersions(&self) -> &[Dispersion<'t, L>] {
+    &self.dispersions
+  }
+
+  /// Returns the dispersion of a word, or `None` if it does not occur.
+  pub fn get(&self, token: &Token<'t, L>) -> Option<&Dispersion<'t, L>> {
+    self.index.get(token).map(|&i| &self.dispersions[i])
+  }
+
+  /// Returns the number of tokens of each part.
+  pub fn sizes(&self) -> &[usize] {
+    &self.sizes
+  }
+
+  /// Returns the number of distinct words.
+  pub fn len(&self) -> usize {
+    self.dispersions.len()
+  }
+
+  /// Returns `true` if no words were counted.
+  pub fn is_empty(&self) -> bool {
+    self.dispersions.is_empty()
+  }
+
+  /// Writes the list as tab-separated values, with a header row of
+  /// `word`, `frequency`, `range`, `dp`, `juilland_d` and `burstiness`,
+  /// followed by a row per word.
+  pub fn write_tsv<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "word\tfrequency\trange\tdp\tjuilland_d\tburstiness")?;
+    for dispersion in &self.dispersions {
+      writeln!(writer, "{}\t{}\t{}\t{:.4}\t{:.4}\t{:.4}",
+               dispersion.token, dispersion.frequency, dispersion.range,
+               dispersion.dp, dispersion.juilland_d, dispersion.burstiness)?;
+    }
+    Ok(())
+  }
+}
+
+/// Measures the dispersion of a word from its count in each part in
+/// which it occurs, and the sizes of all parts. Parts of no tokens have
+/// a relative frequency of `0`; a corpus of one part has a Juilland's D
+/// of `1`.
+fn dispersion<'t, L>(token: Token<'t, L>, occurrences: &[(usize, usize)],
+                     sizes: &[usize], total: usize) -> Dispersion<'t, L>
+{
+  let frequency: usize = occurrences.iter().map(|&(_, count)| count).sum();
+  let parts = sizes.len() as f64;
+
+  // Parts in which the word does not occur each contribute their share
+  // of the corpus to the sum of differences, so the sum starts at `1`
+  // and is corrected for the parts in which it does.
+  let mut deviation = 1.0;
+  let (mut sum, mut sum_of_squares) = (0.0, 0.0);
+  for &(part, count) in occurrences {
+    let share = sizes[part] as f64 / total as f64;
+    deviation += (count as f64 / frequency as f64 - share).abs() - share;
+    let relative = count as f64 / sizes[part] as f64;
+    sum += relative;
+    sum_of_squares += relative * relative;
+  }
+  let mean = sum / parts;
+  let deviation_of_relative = (sum_of_squares / parts - mean * mean).max(0.0).sqrt();
+  let juilland_d = if parts < 2.0 { 1.0 } else {
+    1.0 - deviation_of_relative / mean / (parts - 1.0).sqrt()
+  };
+
+  Dispersion {
+    token: token,
+    frequency: frequency,
+    range: occurrences.len(),
+    dp: (deviation / 2.0).max(0.0),
+    juilland_d: juilland_d,
+    burstiness: frequency as f64 / occurrences.len() as f64,
+  }
+}
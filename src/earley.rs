@@ -0,0 +1,256 @@
+use grammar::{Pcfg, Rule, Symbol};
+use forest::{Forest, ForestNode};
+
+use std::collections::{HashMap, HashSet};
+
+/// An Earley item: a rule, how many of its symbols have been recognized,
+/// and the position at which recognition of the rule began.
+type Item = (usize, usize, usize);
+
+/// Ways of deriving the symbols before the dot of an item, as sequences
+/// of spanned symbols.
+type Splits = Vec<Vec<(Symbol, usize, usize)>>;
+
+/// A chart parser for arbitrary context-free grammars, following Earley
+/// (1970) with the treatment of epsilon rules of Aycock and Horspool
+/// (2002). Unlike a CKY parser, it requires no binarization of the
+/// grammar, and accepts unary cycles and epsilon rules.
+///
+/// ```rust
+/// let grammar = Pcfg::new("S", vec![
+///   (Rule::new("S", vec![Nonterminal("NP".into()), Nonterminal("VP".into())]), 1.0),
+///   (Rule::new("NP", vec![Terminal("dogs".into())]), 1.0),
+///   (Rule::new("VP", vec![Terminal("bark".into()), Nonterminal("ADV".into())]), 1.0),
+///   (Rule::new("ADV", vec![]), 1.0),
+/// ]);
+/// let forest = EarleyParser::new(&grammar).parse(&["dogs", "bark"]).unwrap();
+/// assert_eq!(forest.edges().len(), 4);
+/// ```
+///
+/// Rules are matched against words exactly; to parse unknown words with
+/// a grammar induced with a rare-word threshold, replace them with
+/// [`UNKNOWN_WORD`] beforehand.
+///
+/// [`UNKNOWN_WORD`]: constant.UNKNOWN_WORD.html
+#[derive(Debug, Clone)]
+pub struct EarleyParser<'g> {
+  grammar: &'g Pcfg,
+  /// The indices of the rules rewriting each nonterminal.
+  expansions: HashMap<&'g str, Vec<usize>>,
+  nullable: HashSet<&'g str>,
+}
+
+impl<'g> EarleyParser<'g> {
+  /// Creates a parser for `grammar`.
+  pub fn new(grammar: &'g Pcfg) -> EarleyParser<'g> {
+    let mut expansions: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, (rule, _)) in grammar.rules().iter().enumerate() {
+      expansions.entry(&rule.lhs[..]).or_default().push(i);
+    }
+
+    // A nonterminal is nullable if one of its rules consists entirely of
+    // nullable nonterminals.
+    let mut nullable = HashSet::new();
+    loop {
+      let before = nullable.len();
+      for (rule, _) in grammar.rules() {
+        let all_nullable = rule.rhs.iter().all(|symbol| match *symbol {
+          Symbol::Nonterminal(ref label) => nullable.contains(&label[..]),
+          Symbol::Terminal(_) => false,
+        });
+        if all_nullable {
+          nullable.insert(&rule.lhs[..]);
+        }
+      }
+      if nullable.len() == before {
+        break;
+      }
+    }
+
+    EarleyParser {
+      grammar: grammar,
+      expansions: expansions,
+      nullable: nullable,
+    }
+  }
+
+  fn rule(&self, i: usize) -> &'g Rule {
+    &self.grammar.rules()[i].0
+  }
+
+  /// Returns `true` if `set` contains a completed item for `label` that
+  /// began at `origin`.
+  fn completes(&self, set: &HashSet<Item>, label: &str, origin: usize) -> bool {
+    match self.expansions.get(label) {
+      Some(rules) => rules.iter()
+        .any(|&r| set.contains(&(r, self.rule(r).rhs.len(), origin))),
+      None => false
+    }
+  }
+
+  /// Parses a sentence, returning a forest of every derivation of it from
+  /// the grammar's start symbol, or `None` if it has none.
+  pub fn parse<S: AsRef<str>>(&self, words: &[S]) -> Option<Forest> {
+    let words: Vec<&str> = words.iter().map(AsRef::as_ref).collect();
+    let sets = self.recognize(&words);
+    let start = self.grammar.start();
+    if !self.completes(&sets[words.len()], start, 0) {
+      return None;
+    }
+
+    let mut builder = Builder {
+      parser: self,
+      words: &words,
+      sets: &sets,
+      forest: Forest::new(),
+      splits: HashMap::new(),
+    };
+    let root = builder.build(Symbol::Nonterminal(start.to_string()), 0, words.len());
+    builder.forest.set_root(root);
+    Some(builder.forest)
+  }
+
+  /// Returns `true` if the grammar derives the sentence.
+  pub fn recognizes<S: AsRef<str>>(&self, words: &[S]) -> bool {
+    self.parse(words).is_some()
+  }
+
+  /// Computes the Earley item sets of a sentence.
+  fn recognize(&self, words: &[&str]) -> Vec<HashSet<Item>> {
+    let mut sets: Vec<HashSet<Item>> = vec![HashSet::new(); words.len() + 1];
+    if let Some(rules) = self.expansions.get(self.grammar.start()) {
+      for &r in rules {
+        sets[0].insert((r, 0, 0));
+      }
+    }
+
+    for k in 0..=words.len() {
+      // Items added to this set by scanning, before it is processed.
+      let mut order: Vec<Item> = sets[k].iter().cloned().collect();
+      let mut next = 0;
+      while next < order.len() {
+        let (r, dot, origin) = order[next];
+        next += 1;
+        let rule = self.rule(r);
+        let mut added = vec![];
+        match rule.rhs.get(dot) {
+          // Predict.
+          Some(Symbol::Nonterminal(label)) => {
+            for &p in self.expansions.get(&label[..]).map_or(&[][..], |v| &v[..]) {
+              added.push((p, 0, k));
+            }
+            if self.nullable.contains(&label[..]) {
+              added.push((r, dot + 1, origin));
+            }
+          },
+          // Scan.
+          Some(Symbol::Terminal(word)) => {
+            if k < words.len() && words[k] == word {
+              sets[k + 1].insert((r, dot + 1, origin));
+            }
+          },
+          // Complete.
+          None => {
+            for &(s, d, o) in &sets[origin] {
+              if let Some(Symbol::Nonterminal(label)) = self.rule(s).rhs.get(d) {
+                if *label == rule.lhs {
+                  added.push((s, d + 1, o));
+                }
+              }
+            }
+          },
+        }
+        for item in added {
+          if sets[k].insert(item) {
+            order.push(item);
+          }
+        }
+      }
+    }
+    sets
+  }
+}
+
+/// Constructs a forest from the item sets of a recognized sentence.
+struct Builder<'p, 'g: 'p> {
+  parser: &'p EarleyParser<'g>,
+  words: &'p [&'p str],
+  sets: &'p [HashSet<Item>],
+  forest: Forest,
+  /// Memoized splits of each item, by the position of its item set.
+  splits: HashMap<(Item, usize), Splits>,
+}
+
+impl<'p, 'g> Builder<'p, 'g> {
+  /// Adds the node for `symbol` spanning `start..end`, and, if it is new,
+  /// every hyperedge deriving it.
+  fn build(&mut self, symbol: Symbol, start: usize, end: usize) -> usize {
+    let node = ForestNode { symbol: symbol.clone(), start: start, end: end };
+    if let Some(i) = self.forest.find(&node) {
+      return i;
+    }
+    let head = self.forest.add_node(node);
+    let label = match symbol {
+      Symbol::Nonterminal(label) => label,
+      Symbol::Terminal(_) => return head,
+    };
+
+    let rules = self.parser.expansions.get(&label[..]).cloned().unwrap_or_default();
+    for r in rules {
+      let rule = self.parser.rule(r);
+      let item = (r, rule.rhs.len(), start);
+      if !self.sets[end].contains(&item) {
+        continue;
+      }
+      for split in self.splits(item, end) {
+        let tails = split.into_iter()
+          .map(|(symbol, start, end)| self.build(symbol, start, end))
+          .collect();
+        let weight = self.parser.grammar.rules()[r].1;
+        self.forest.add_edge(head, tails, rule.clone(), weight);
+      }
+    }
+    head
+  }
+
+  /// Enumerates the ways the symbols before the dot of `item`, which is
+  /// in the item set at `end`, span the words from the item's origin to
+  /// `end`.
+  fn splits(&mut self, item: Item, end: usize) -> Splits {
+    let (r, dot, origin) = item;
+    if let Some(splits) = self.splits.get(&(item, end)) {
+      return splits.clone();
+    }
+
+    let mut splits = vec![];
+    if dot == 0 {
+      if end == origin {
+        splits.push(vec![]);
+      }
+    } else {
+      let symbol = &self.parser.rule(r).rhs[dot - 1];
+      for middle in origin..=end {
+        if !self.sets[middle].contains(&(r, dot - 1, origin))
+            || !self.spans(symbol, middle, end) {
+          continue;
+        }
+        for mut split in self.splits((r, dot - 1, origin), middle) {
+          split.push((symbol.clone(), middle, end));
+          splits.push(split);
+        }
+      }
+    }
+    self.splits.insert((item, end), splits.clone());
+    splits
+  }
+
+  /// Returns `true` if `symbol` derives the words from `start` to `end`.
+  fn spans(&self, symbol: &Symbol, start: usize, end: usize) -> bool {
+    match *symbol {
+      Symbol::Terminal(ref word) =>
+        end == start + 1 && self.words[start] == word,
+      Symbol::Nonterminal(ref label) =>
+        self.parser.completes(&self.sets[end], label, start),
+    }
+  }
+}
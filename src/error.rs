@@ -0,0 +1,46 @@
+use std::fmt;
+use std::error::Error;
+
+/// The reason a string is not well-formed, such as a bracketed tree, a
+/// category or a template.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ParseError {
+  /// The byte offset in the input at which the error was detected.
+  pub offset: usize,
+  /// A description of the problem.
+  pub message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} at byte {}", self.message, self.offset)
+  }
+}
+
+impl Error for ParseError {
+  fn description(&self) -> &str {
+    self.message
+  }
+}
+
+/// The reason a text of one record per line, such as a CoNLL file or a
+/// lexicon, is not well-formed.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct LineParseError {
+  /// The (one-based) line on which the error was detected.
+  pub line: usize,
+  /// A description of the problem.
+  pub message: &'static str,
+}
+
+impl fmt::Display for LineParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} on line {}", self.message, self.line)
+  }
+}
+
+impl Error for LineParseError {
+  fn description(&self) -> &str {
+    self.message
+  }
+}
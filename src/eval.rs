@@ -0,0 +1,414 @@
+//! Evaluation of system output against references, one line at a time.
+//!
+//! Each metric accumulates sufficient statistics as pairs of hypothesis
+//! and reference lines are added, so that a test set need never be held
+//! in memory: [`evaluate`] consumes parallel iterators of lines, and
+//! [`evaluate_readers`] reads them from a pair of files.
+//!
+//! ```rust
+//! let hypotheses = BufReader::new(File::open("output.txt")?);
+//! let references = BufReader::new(File::open("reference.txt")?);
+//! let bleu = evaluate_readers(Bleu::new(), hypotheses, references)?;
+//! println!("BLEU: {:.2}", 100.0 * bleu.score());
+//! ```
+//!
+//! Language models are evaluated on held-out text instead, by the
+//! [`cross_entropy`] of a model on its lines, for comparing models of
+//! different orders or smoothing methods on the same text:
+//!
+//! ```rust
+//! for &smoothing in &[Smoothing::WittenBell, Smoothing::KneserNey, Smoothing::GoodTuring] {
+//!   let model = Model::train(&training, 3).with_smoothing(smoothing);
+//!   let evaluation = cross_entropy(&model, &held_out);
+//!   println!("{:?}: {:.3} bits, perplexity {:.1}", smoothing, evaluation.bits(),
+//!            evaluation.perplexity());
+//! }
+//! ```
+//!
+//! [`evaluate`]: fn.evaluate.html
+//! [`evaluate_readers`]: fn.evaluate_readers.html
+//! [`cross_entropy`]: fn.cross_entropy.html
+use language::Language;
+use corpus::AsTokens;
+use ngram::Model;
+
+use std::io::{self, BufRead};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::error::Error;
+use std::fmt;
+
+/// A corpus-level metric, accumulated one line at a time. Each metric
+/// reports its score with an inherent `score` method.
+pub trait Metric<T> {
+  /// Adds a hypothesis line and its reference to the statistics.
+  fn add(&mut self, hypothesis: &[T], reference: &[T]);
+}
+
+/// An error arising when there are more hypotheses than references, or
+/// more references than hypotheses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthMismatch {
+  /// The number of lines in the shorter of the two.
+  pub lines: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "hypotheses and references differ in length after {} lines", self.lines)
+  }
+}
+
+impl Error for LengthMismatch {
+  fn description(&self) -> &str {
+    "hypotheses and references differ in length"
+  }
+}
+
+/// Adds each pair of hypothesis and reference lines to a metric, and
+/// returns it.
+pub fn evaluate<M, H, R, T, A, B>(mut metric: M, hypotheses: H, references: R)
+    -> Result<M, LengthMismatch>
+  where M: Metric<T>,
+        H: IntoIterator<Item=A>,
+        R: IntoIterator<Item=B>,
+        A: AsRef<[T]>,
+        B: AsRef<[T]>
+{
+  let mut hypotheses = hypotheses.into_iter();
+  let mut references = references.into_iter();
+  let mut lines = 0;
+  loop {
+    match (hypotheses.next(), references.next()) {
+      (Some(hypothesis), Some(reference)) => {
+        metric.add(hypothesis.as_ref(), reference.as_ref());
+        lines += 1;
+      },
+      (None, None) => return Ok(metric),
+      _ => return Err(LengthMismatch { lines: lines })
+    }
+  }
+}
+
+/// Reads hypothesis and reference lines from a pair of readers, splitting
+/// them into words at whitespace, and adds them to a metric. Only one
+/// line of each is held in memory at a time. A difference in the number
+/// of lines is reported as an error of kind `InvalidData`.
+pub fn evaluate_readers<M, H, R>(mut metric: M, hypotheses: H, references: R) -> io::Result<M>
+  where M: Metric<String>,
+        H: BufRead,
+        R: BufRead
+{
+  let words = |line: String| -> Vec<String> {
+    line.split_whitespace().map(String::from).collect()
+  };
+  let mut hypotheses = hypotheses.lines();
+  let mut references = references.lines();
+  let mut lines = 0;
+  loop {
+    match (hypotheses.next(), references.next()) {
+      (Some(hypothesis), Some(reference)) => {
+        metric.add(&words(hypothesis?), &words(reference?));
+        lines += 1;
+      },
+      (None, None) => return Ok(metric),
+      _ => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                     LengthMismatch { lines: lines }))
+    }
+  }
+}
+
+/// How [`Bleu`] scores orders of n-grams without matches, which would
+/// otherwise make the score zero, as they often do for single sentences
+/// (Chen and Cherry, 2014). An order of which the hypotheses have no
+/// n-grams at all is smoothed as if they had one.
+///
+/// [`Bleu`]: struct.Bleu.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BleuSmoothing {
+  /// No smoothing: an order without matches makes the score zero.
+  None,
+  /// Counts `epsilon` matches for an order without any.
+  Epsilon(f64),
+  /// Adds one to the matches and the total of every order above
+  /// unigrams (Lin and Och, 2004).
+  AddOne,
+  /// Takes the precision of the `k`th order without matches as
+  /// `1 / 2ᵏ` matches over its total, as in the NIST implementation.
+  Exponential,
+}
+
+/// The BLEU score (Papineni et al., 2002): the geometric mean of the
+/// clipped n-gram precisions of the hypotheses, up to a maximum order,
+/// multiplied by a brevity penalty when the hypotheses are shorter than
+/// the references.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bleu {
+  /// The clipped matches and the total of the hypothesis n-grams of each
+  /// order.
+  matches: Vec<(usize, usize)>,
+  hypothesis_length: usize,
+  reference_length: usize,
+  smoothing: BleuSmoothing,
+}
+
+impl Bleu {
+  /// Creates a BLEU accumulator over n-grams of up to four tokens.
+  pub fn new() -> Bleu {
+    Bleu::with_order(4)
+  }
+
+  /// Creates a BLEU accumulator over n-grams of up to `order` tokens.
+  pub fn with_order(order: usize) -> Bleu {
+    Bleu {
+      matches: vec![(0, 0); order.max(1)],
+      hypothesis_length: 0,
+      reference_length: 0,
+      smoothing: BleuSmoothing::None,
+    }
+  }
+
+  /// Smooths the precisions of the score with the given method. Only the
+  /// score is smoothed, so smoothing may be chosen before or after lines
+  /// are added.
+  pub fn with_smoothing(mut self, smoothing: BleuSmoothing) -> Bleu {
+    self.smoothing = smoothing;
+    self
+  }
+
+  /// Returns the clipped n-gram precision of each order, unsmoothed.
+  pub fn precisions(&self) -> Vec<f64> {
+    self.matches.iter()
+      .map(|&(matched, total)| if total == 0 { 0.0 } else { matched as f64 / total as f64 })
+      .collect()
+  }
+
+  /// Returns the BLEU score, between `0` and `1`; without smoothing, it
+  /// is zero if any order has no matches.
+  pub fn score(&self) -> f64 {
+    let mut decay = 1.0;
+    let mut log_sum = 0.0;
+    for (o, &(matched, total)) in self.matches.iter().enumerate() {
+      let (matched, total) = (matched as f64, total as f64);
+      let precision = match self.smoothing {
+        BleuSmoothing::AddOne if o > 0 => (matched + 1.0) / (total + 1.0),
+        _ if matched > 0.0 => matched / total,
+        BleuSmoothing::Epsilon(epsilon) => epsilon / total.max(1.0),
+        BleuSmoothing::Exponential => {
+          decay *= 2.0;
+          1.0 / (decay * total.max(1.0))
+        },
+        _ => return 0.0
+      };
+      log_sum += precision.ln();
+    }
+    self.brevity_penalty() * (log_sum / self.matches.len() as f64).exp()
+  }
+
+  /// Returns the brevity penalty.
+  pub fn brevity_penalty(&self) -> f64 {
+    let (c, r) = (self.hypothesis_length as f64, self.reference_length as f64);
+    if c >= r { 1.0 }
+    else if c == 0.0 { 0.0 }
+    else { (1.0 - r / c).exp() }
+  }
+}
+
+impl Default for Bleu {
+  fn default() -> Bleu {
+    Bleu::new()
+  }
+}
+
+impl<T: Hash + Eq> Metric<T> for Bleu {
+  fn add(&mut self, hypothesis: &[T], reference: &[T]) {
+    self.hypothesis_length += hypothesis.len();
+    self.reference_length += reference.len();
+    for (o, counts) in self.matches.iter_mut().enumerate() {
+      let n = o + 1;
+      let mut available: HashMap<&[T], usize> = HashMap::new();
+      for gram in reference.windows(n) {
+        *available.entry(gram).or_insert(0) += 1;
+      }
+      for gram in hypothesis.windows(n) {
+        counts.1 += 1;
+        if let Some(remaining) = available.get_mut(gram) {
+          if *remaining > 0 {
+            *remaining -= 1;
+            counts.0 += 1;
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Accumulates the BLEU score of candidate lines against their reference
+/// lines over n-grams of up to `max_n` tokens, comparing tokens rather
+/// than their text. The accumulator is returned, to report the score with
+/// or without smoothing, and its precisions and brevity penalty.
+///
+/// ```rust
+/// let bleu = bleu(output.lines(), reference.lines(), 4)?
+///   .with_smoothing(BleuSmoothing::AddOne);
+/// println!("BLEU: {:.2}", 100.0 * bleu.score());
+/// ```
+pub fn bleu<'t, L, H, R>(candidates: H, references: R, max_n: usize) -> Result<Bleu, LengthMismatch>
+  where L: 't + Language,
+        H: IntoIterator,
+        H::Item: AsTokens<'t, L>,
+        R: IntoIterator,
+        R::Item: AsTokens<'t, L>
+{
+  evaluate(Bleu::with_order(max_n),
+           candidates.into_iter().map(|line| line.as_tokens().into_owned()),
+           references.into_iter().map(|line| line.as_tokens().into_owned()))
+}
+
+/// The word error rate: the number of substitutions, insertions and
+/// deletions needed to turn the hypotheses into the references, divided
+/// by the number of reference words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordErrorRate {
+  pub errors: usize,
+  pub reference_length: usize,
+}
+
+impl WordErrorRate {
+  /// Creates an empty accumulator.
+  pub fn new() -> WordErrorRate {
+    WordErrorRate::default()
+  }
+
+  /// Returns the word error rate, which exceeds `1` if the hypotheses
+  /// have many insertions, and is zero if there are no reference words.
+  pub fn score(&self) -> f64 {
+    if self.reference_length == 0 { 0.0 }
+    else { self.errors as f64 / self.reference_length as f64 }
+  }
+}
+
+impl<T: PartialEq> Metric<T> for WordErrorRate {
+  fn add(&mut self, hypothesis: &[T], reference: &[T]) {
+    self.errors += levenshtein(hypothesis, reference);
+    self.reference_length += reference.len();
+  }
+}
+
+/// Token accuracy, for taggers and other systems producing one output
+/// per reference token: the fraction of positions at which the
+/// hypothesis and reference agree. Positions present in only one of them
+/// count as errors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Accuracy {
+  pub correct: usize,
+  pub total: usize,
+}
+
+impl Accuracy {
+  /// Creates an empty accumulator.
+  pub fn new() -> Accuracy {
+    Accuracy::default()
+  }
+
+  /// Returns the accuracy, or zero if no tokens have been added.
+  pub fn score(&self) -> f64 {
+    if self.total == 0 { 0.0 } else { self.correct as f64 / self.total as f64 }
+  }
+}
+
+impl<T: PartialEq> Metric<T> for Accuracy {
+  fn add(&mut self, hypothesis: &[T], reference: &[T]) {
+    self.correct += hypothesis.iter().zip(reference).filter(|(h, r)| h == r).count();
+    self.total += hypothesis.len().max(reference.len());
+  }
+}
+
+/// The cross-entropy of a language model on lines of held-out text, as
+/// computed by [`cross_entropy`].
+///
+/// [`cross_entropy`]: fn.cross_entropy.html
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CrossEntropy {
+  /// The natural logarithm of the probability of the text: the sum of
+  /// the log-probabilities of its tokens and line endings.
+  pub log_probability: f64,
+  /// The number of tokens scored, counting the end of each line.
+  pub tokens: usize,
+  /// The number of tokens scored that the model never observed in
+  /// training.
+  pub unknown: usize,
+}
+
+impl CrossEntropy {
+  /// Returns the cross-entropy in bits per token, or zero if no tokens
+  /// were scored. It is infinite if the model gave any token a
+  /// probability of zero.
+  pub fn bits(&self) -> f64 {
+    if self.tokens == 0 { 0.0 }
+    else { -self.log_probability / self.tokens as f64 / ::std::f64::consts::LN_2 }
+  }
+
+  /// Returns the perplexity: two to the power of the cross-entropy in
+  /// bits, or one if no tokens were scored.
+  pub fn perplexity(&self) -> f64 {
+    if self.tokens == 0 { 1.0 } else { (-self.log_probability / self.tokens as f64).exp() }
+  }
+
+  /// Returns the share of the tokens scored that the model never
+  /// observed in training, or zero if no tokens were scored.
+  pub fn unknown_share(&self) -> f64 {
+    if self.tokens == 0 { 0.0 } else { self.unknown as f64 / self.tokens as f64 }
+  }
+}
+
+/// Measures the cross-entropy of a language model on lines of tokens,
+/// such as those of a held-out [`Document`].
+///
+/// Each line is padded as in training: its first token is conditioned
+/// on the beginning of the line, and the end of the line is scored as a
+/// token after its last. Tokens the model never observed are scored as
+/// its one unseen token, and counted in [`CrossEntropy::unknown`], since
+/// models whose training vocabularies differ are only comparable on text
+/// without them. Log-probabilities are summed, so that long texts do not
+/// underflow.
+///
+/// Some models give unseen tokens no probability: those smoothed by
+/// `MaximumLikelihood` or `AddK(0.0)`, and by `GoodTuring` when no token
+/// was observed exactly once. A single such token makes the cross-entropy
+/// and perplexity infinite, as they are by definition.
+///
+/// [`Document`]: ../struct.Document.html
+/// [`CrossEntropy::unknown`]: struct.CrossEntropy.html#structfield.unknown
+pub fn cross_entropy<'t, L, I>(model: &Model<'t, L>, lines: I) -> CrossEntropy
+  where L: 't + Language,
+        I: IntoIterator,
+        I::Item: AsTokens<'t, L>
+{
+  let mut evaluation = CrossEntropy::default();
+  for line in lines {
+    for token in model.diagnose(&line.as_tokens()).tokens {
+      evaluation.log_probability += token.log_probability;
+      evaluation.tokens += 1;
+      if token.oov {
+        evaluation.unknown += 1;
+      }
+    }
+  }
+  evaluation
+}
+
+/// Returns the number of insertions, deletions and substitutions needed
+/// to turn `a` into `b`.
+pub(crate) fn levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+  let mut previous: Vec<usize> = (0..=b.len()).collect();
+  for (i, x) in a.iter().enumerate() {
+    let mut current = vec![i + 1; b.len() + 1];
+    for (j, y) in b.iter().enumerate() {
+      let substitute = previous[j] + if x == y { 0 } else { 1 };
+      current[j + 1] = substitute.min(previous[j + 1] + 1).min(current[j] + 1);
+    }
+    previous = current;
+  }
+  previous[b.len()]
+}
@@ -0,0 +1,354 @@
+use grammar::{Rule, Symbol};
+use tree::Tree;
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// A node of a [`Forest`]: a symbol spanning the words from `start` up to
+/// (but not including) `end`.
+/// [`Forest`]: struct.Forest.html
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ForestNode {
+  pub symbol: Symbol,
+  pub start: usize,
+  pub end: usize,
+}
+
+/// A hyperedge of a [`Forest`], deriving the node `head` from the nodes
+/// `tails` by a single application of `rule`.
+/// [`Forest`]: struct.Forest.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hyperedge {
+  pub head: usize,
+  pub tails: Vec<usize>,
+  pub rule: Rule,
+  /// The probability of `rule`.
+  pub weight: f64,
+}
+
+/// A packed parse forest: a hypergraph compactly encoding every parse of
+/// a sentence, in which each way of deriving a node from its children is
+/// a separate incoming hyperedge. Forests of grammars with unary or
+/// epsilon cycles may themselves be cyclic.
+///
+/// Nodes are identified by their index in [`nodes`], and hyperedges by
+/// their index in [`edges`].
+///
+/// The derivations of a forest can be enumerated from most to least
+/// probable, for instance, to pass the best few parses to a reranker:
+///
+/// ```rust
+/// let forest = EarleyParser::new(&grammar).parse(&words).unwrap();
+/// for (tree, p) in forest.k_best(10) {
+///   println!("{:.3e} {}", p, tree);
+/// }
+/// ```
+///
+/// [`nodes`]: #method.nodes
+/// [`edges`]: #method.edges
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forest {
+  nodes: Vec<ForestNode>,
+  edges: Vec<Hyperedge>,
+  /// The hyperedges deriving each node.
+  incoming: Vec<Vec<usize>>,
+  index: HashMap<ForestNode, usize>,
+  root: Option<usize>,
+}
+
+impl Forest {
+  /// Creates an empty forest.
+  pub fn new() -> Forest {
+    Forest {
+      nodes: vec![],
+      edges: vec![],
+      incoming: vec![],
+      index: HashMap::new(),
+      root: None,
+    }
+  }
+
+  /// Adds a node to the forest, unless it is already present, and
+  /// returns its index.
+  pub fn add_node(&mut self, node: ForestNode) -> usize {
+    if let Some(&i) = self.index.get(&node) {
+      return i;
+    }
+    let i = self.nodes.len();
+    self.index.insert(node.clone(), i);
+    self.nodes.push(node);
+    self.incoming.push(vec![]);
+    i
+  }
+
+  /// Adds a hyperedge deriving node `head` from nodes `tails`, and
+  /// returns its index.
+  pub fn add_edge(&mut self, head: usize, tails: Vec<usize>, rule: Rule,
+                  weight: f64) -> usize {
+    let i = self.edges.len();
+    self.incoming[head].push(i);
+    self.edges.push(Hyperedge {
+      head: head,
+      tails: tails,
+      rule: rule,
+      weight: weight,
+    });
+    i
+  }
+
+  /// Sets the node from which every derivation in the forest begins.
+  pub fn set_root(&mut self, root: usize) {
+    self.root = Some(root);
+  }
+
+  /// Returns the root node of the forest, if it has been set.
+  pub fn root(&self) -> Option<usize> {
+    self.root
+  }
+
+  /// Returns every node of the forest.
+  pub fn nodes(&self) -> &[ForestNode] {
+    &self.nodes
+  }
+
+  /// Returns every hyperedge of the forest.
+  pub fn edges(&self) -> &[Hyperedge] {
+    &self.edges
+  }
+
+  /// Returns the index of `node`, if it is in the forest.
+  pub fn find(&self, node: &ForestNode) -> Option<usize> {
+    self.index.get(node).cloned()
+  }
+
+  /// Returns the indices of the hyperedges deriving node `i`. Terminal
+  /// nodes have none.
+  pub fn incoming(&self, i: usize) -> &[usize] {
+    &self.incoming[i]
+  }
+
+  /// Returns `true` if some node can be derived from itself.
+  pub fn is_cyclic(&self) -> bool {
+    // 0: unvisited, 1: on the stack, 2: finished.
+    fn visit(forest: &Forest, i: usize, state: &mut [u8]) -> bool {
+      match state[i] {
+        1 => return true,
+        2 => return false,
+        _ => state[i] = 1
+      }
+      for &e in &forest.incoming[i] {
+        for &tail in &forest.edges[e].tails {
+          if visit(forest, tail, state) {
+            return true;
+          }
+        }
+      }
+      state[i] = 2;
+      false
+    }
+    let mut state = vec![0; self.nodes.len()];
+    (0..self.nodes.len()).any(|i| visit(self, i, &mut state))
+  }
+
+  /// Produces an iterator over the derivations of the root node, as trees
+  /// with their probabilities, from most to least probable. Derivations
+  /// are extracted lazily, following Huang and Chiang (2005, algorithm 3),
+  /// so taking the first `k` costs little more than finding the best.
+  ///
+  /// In a cyclic forest, the hyperedges closing each cycle are ignored,
+  /// so derivations of a node from itself are not produced; nor, where
+  /// cycles overlap, are some derivations sharing those hyperedges. A
+  /// forest with no root has no derivations.
+  pub fn derivations<'f>(&'f self) -> Derivations<'f> {
+    Derivations {
+      extractor: Extractor::new(self),
+      next: 0,
+    }
+  }
+
+  /// Returns the `k` most probable derivations of the root node.
+  pub fn k_best(&self, k: usize) -> Vec<(Tree, f64)> {
+    self.derivations().take(k).collect()
+  }
+
+  /// Returns the most probable derivation of the root node.
+  pub fn best(&self) -> Option<(Tree, f64)> {
+    self.derivations().next()
+  }
+}
+
+impl Default for Forest {
+  fn default() -> Forest {
+    Forest::new()
+  }
+}
+
+/// The derivations of a [`Forest`], from most to least probable.
+/// [`Forest`]: struct.Forest.html
+pub struct Derivations<'f> {
+  extractor: Extractor<'f>,
+  next: usize,
+}
+
+impl<'f> Iterator for Derivations<'f> {
+  type Item = (Tree, f64);
+
+  fn next(&mut self) -> Option<(Tree, f64)> {
+    let root = self.extractor.forest.root?;
+    let probability = self.extractor.kth(root, self.next)?;
+    let tree = self.extractor.tree(root, self.next);
+    self.next += 1;
+    Some((tree, probability))
+  }
+}
+
+/// A derivation of a node: a hyperedge, and the rank of the derivation
+/// used for each of its tails.
+#[derive(Debug, Clone, PartialEq)]
+struct Candidate {
+  probability: f64,
+  edge: usize,
+  ranks: Vec<usize>,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+  fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Candidate {
+  fn cmp(&self, other: &Candidate) -> Ordering {
+    self.probability.partial_cmp(&other.probability)
+      .unwrap_or(Ordering::Equal)
+      .then_with(|| other.edge.cmp(&self.edge))
+      .then_with(|| other.ranks.cmp(&self.ranks))
+  }
+}
+
+/// The state of lazy k-best extraction over a forest.
+struct Extractor<'f> {
+  forest: &'f Forest,
+  /// Whether each hyperedge may be used; hyperedges closing a cycle are
+  /// excluded.
+  usable: Vec<bool>,
+  /// The derivations of each node found so far, from most probable.
+  found: Vec<Vec<Candidate>>,
+  /// The frontier of candidate derivations of each node, or `None` if
+  /// it has not yet been initialized.
+  frontier: Vec<Option<BinaryHeap<Candidate>>>,
+  seen: Vec<HashSet<(usize, Vec<usize>)>>,
+}
+
+impl<'f> Extractor<'f> {
+  fn new(forest: &'f Forest) -> Extractor<'f> {
+    let n = forest.nodes.len();
+    let mut extractor = Extractor {
+      forest: forest,
+      usable: vec![true; forest.edges.len()],
+      found: vec![vec![]; n],
+      frontier: vec![None; n],
+      seen: vec![HashSet::new(); n],
+    };
+    if let Some(root) = forest.root {
+      let mut state = vec![0; n];
+      extractor.break_cycles(root, &mut state);
+    }
+    extractor
+  }
+
+  /// Marks the hyperedges leading back to a node on the current path as
+  /// unusable, leaving an acyclic forest.
+  fn break_cycles(&mut self, i: usize, state: &mut [u8]) {
+    state[i] = 1;
+    for &e in &self.forest.incoming[i] {
+      for &tail in &self.forest.edges[e].tails {
+        match state[tail] {
+          0 => self.break_cycles(tail, state),
+          1 => self.usable[e] = false,
+          _ => {}
+        }
+      }
+    }
+    state[i] = 2;
+  }
+
+  /// Returns the probability of the `j`th best derivation of node `v`,
+  /// finding it if necessary.
+  fn kth(&mut self, v: usize, j: usize) -> Option<f64> {
+    if self.forest.incoming[v].is_empty() {
+      // Terminals have exactly one, empty, derivation.
+      return match self.forest.nodes[v].symbol {
+        Symbol::Terminal(_) if j == 0 => Some(1.0),
+        _ => None
+      };
+    }
+
+    if self.frontier[v].is_none() {
+      let mut frontier = BinaryHeap::new();
+      for &e in &self.forest.incoming[v] {
+        if !self.usable[e] {
+          continue;
+        }
+        let ranks = vec![0; self.forest.edges[e].tails.len()];
+        if let Some(candidate) = self.candidate(e, ranks) {
+          self.seen[v].insert((e, candidate.ranks.clone()));
+          frontier.push(candidate);
+        }
+      }
+      self.frontier[v] = Some(frontier);
+    }
+
+    while self.found[v].len() <= j {
+      // Before taking the next derivation, add the successors of the last
+      // one: each way of using the next-best derivation of one tail.
+      if let Some(last) = self.found[v].last().cloned() {
+        for i in 0..last.ranks.len() {
+          let mut ranks = last.ranks.clone();
+          ranks[i] += 1;
+          if self.seen[v].contains(&(last.edge, ranks.clone())) {
+            continue;
+          }
+          if let Some(candidate) = self.candidate(last.edge, ranks) {
+            self.seen[v].insert((last.edge, candidate.ranks.clone()));
+            self.frontier[v].as_mut().unwrap().push(candidate);
+          }
+        }
+      }
+      match self.frontier[v].as_mut().unwrap().pop() {
+        Some(best) => self.found[v].push(best),
+        None => break
+      }
+    }
+    self.found[v].get(j).map(|candidate| candidate.probability)
+  }
+
+  /// Scores the derivation using hyperedge `e` with the given ranks of
+  /// derivations of its tails, if they all exist.
+  fn candidate(&mut self, e: usize, ranks: Vec<usize>) -> Option<Candidate> {
+    let forest = self.forest;
+    let mut probability = forest.edges[e].weight;
+    for (&tail, &rank) in forest.edges[e].tails.iter().zip(&ranks) {
+      probability *= self.kth(tail, rank)?;
+    }
+    Some(Candidate { probability: probability, edge: e, ranks: ranks })
+  }
+
+  /// Builds the tree of the `j`th best derivation of node `v`, which must
+  /// already have been found.
+  fn tree(&self, v: usize, j: usize) -> Tree {
+    match self.forest.nodes[v].symbol {
+      Symbol::Terminal(ref word) => Tree::Leaf(word.clone()),
+      Symbol::Nonterminal(ref label) => {
+        let candidate = &self.found[v][j];
+        let children = self.forest.edges[candidate.edge].tails.iter()
+          .zip(&candidate.ranks)
+          .map(|(&tail, &rank)| self.tree(tail, rank))
+          .collect();
+        Tree::Node(label.clone(), children)
+      }
+    }
+  }
+}
@@ -0,0 +1,362 @@
+use token::Token;
+use language::Language;
+use corpus::Document;
+
+use std::io;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::collections::HashMap;
+
+/// A word of a [`FrequencyList`], with its rank and frequencies.
+///
+/// [`FrequencyList`]: struct.FrequencyList.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frequency<'t, L> {
+  /// The rank of the word, from `1` for the most frequent.
+  pub rank: usize,
+  pub token: Token<'t, L>,
+  /// The number of times the word occurs.
+  pub count: usize,
+  /// The number of times the word occurs per million tokens.
+  pub per_million: f64,
+  /// The percentage of tokens that are this word or a word of higher
+  /// rank.
+  pub coverage: f64,
+}
+
+/// A ranked list of the words of a text, with their absolute counts,
+/// relative frequencies per million tokens, and the cumulative
+/// percentage of the text they cover, for lexicography.
+///
+/// Words are ranked by descending count, and words of equal count in
+/// byte order, so that lists of the same text are identical. `Null`
+/// tokens, such as the padding of lines, are not counted.
+///
+/// ```rust
+/// let list = FrequencyList::from_document(&corpus);
+/// list.write_tsv(&mut File::create("frequencies.tsv")?)?;
+///
+/// // How many words must a learner know to understand 95% of the text?
+/// println!("{:?}", list.coverage_rank(95.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyList<'t, L> {
+  frequencies: Vec<Frequency<'t, L>>,
+  total: usize,
+}
+
+impl<'t, L: Language> FrequencyList<'t, L> {
+  /// Creates the frequency list of a sequence of tokens.
+  pub fn from_tokens<I>(tokens: I) -> FrequencyList<'t, L>
+    where I: IntoIterator<Item=Token<'t, L>>
+  {
+    let mut counts: HashMap<Token<'t, L>, usize> = HashMap::new();
+    for token in tokens {
+      if token != Token::Null {
+        *counts.entry(token).or_insert(0) += 1;
+      }
+    }
+    let mut counts: Vec<(Token<'t, L>, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let total: usize = counts.iter().map(|&(_, count)| count).sum();
+    let mut covered = 0;
+    let frequencies = counts.into_iter()
+      .enumerate()
+      .map(|(i, (token, count))| {
+        covered += count;
+        Frequency {
+          rank: i + 1,
+          token: token,
+          count: count,
+          per_million: count as f64 * 1e6 / total as f64,
+          coverage: covered as f64 * 100.0 / total as f64,
+        }
+      })
+      .collect();
+    FrequencyList { frequencies: frequencies, total: total }
+  }
+
+  /// Creates the frequency list of the tokens of a document.
+  pub fn from_document(document: &'t Document<L>) -> FrequencyList<'t, L> {
+    FrequencyList::from_tokens(document.tokens())
+  }
+
+  /// Returns the words of the list, in order of rank.
+  pub fn frequencies(&self) -> &[Frequency<'t, L>] {
+    &self.frequencies
+  }
+
+  /// Returns the number of tokens counted.
+  pub fn total(&self) -> usize {
+    self.total
+  }
+
+  /// Returns the number of distinct words counted.
+  pub fn len(&self) -> usize {
+    self.frequencies.len()
+  }
+
+  /// Returns `true` if no words were counted.
+  pub fn is_empty(&self) -> bool {
+    self.frequencies.is_empty()
+  }
+
+  /// Returns the least rank whose words cover at least `percentage` of
+  /// the tokens, or `None` if the percentage exceeds `100`.
+  pub fn coverage_rank(&self, percentage: f64) -> Option<usize> {
+    self.frequencies.iter()
+      .find(|frequency| frequency.coverage >= percentage)
+      .map(|frequency| frequency.rank)
+  }
+
+  /// Writes the list as tab-separated values, with a header row of
+  /// `rank`, `word`, `count`, `per_million` and `coverage`, followed by a
+  /// row per word. Coverage is written as a percentage.
+  pub fn write_tsv<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "rank\tword\tcount\tper_million\tcoverage")?;
+    for frequency in &self.frequencies {
+      writeln!(writer, "{}\t{}\t{}\t{:.2}\t{:.2}",
+               frequency.rank, frequency.token, frequency.count,
+               frequency.per_million, frequency.coverage)?;
+    }
+    Ok(())
+  }
+}
+
+/// A frequency distribution: the number of times each distinct item of
+/// a sequence occurs, such as the tokens of a document, the lengths of
+/// its lines, or its n-grams.
+///
+/// ```rust
+/// let words: FreqDist<Token<English>> = training.tokens().iter().collect();
+/// for (word, count) in words.most_common(10) {
+///   println!("{}\t{}", word, count);
+/// }
+/// println!("{} of {} words occur once", words.hapaxes().len(), words.len());
+/// ```
+///
+/// Items of equal count are ordered by their first occurrence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreqDist<T: Hash + Eq> {
+  /// The distinct items, in order of their first occurrence.
+  items: Vec<T>,
+  counts: Vec<usize>,
+  index: HashMap<T, usize>,
+  total: usize,
+}
+
+impl<T: Hash + Eq + Clone> FreqDist<T> {
+  /// Creates an empty distribution.
+  pub fn new() -> FreqDist<T> {
+    FreqDist { items: vec![], counts: vec![], index: HashMap::new(), total: 0 }
+  }
+
+  /// Counts an occurrence of an item, and returns its count.
+  pub fn add(&mut self, item: T) -> usize {
+    let next = self.items.len();
+    let i = *self.index.entry(item.clone()).or_insert(next);
+    if i == next {
+      self.items.push(item);
+      self.counts.push(0);
+    }
+    self.counts[i] += 1;
+    self.total += 1;
+    self.counts[i]
+  }
+
+  /// Returns the number of times an item occurred.
+  pub fn count(&self, item: &T) -> usize {
+    self.index.get(item).map_or(0, |&i| self.counts[i])
+  }
+
+  /// Returns the share of all occurrences that are of an item, or `0` if
+  /// the distribution is empty.
+  pub fn frequency(&self, item: &T) -> f64 {
+    if self.total == 0 { 0.0 } else { self.count(item) as f64 / self.total as f64 }
+  }
+
+  /// Returns the number of occurrences of every item.
+  pub fn total(&self) -> usize {
+    self.total
+  }
+
+  /// Returns the number of distinct items.
+  pub fn len(&self) -> usize {
+    self.items.len()
+  }
+
+  /// Returns `true` if no items were counted.
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  /// Returns every distinct item with its count, in order of first
+  /// occurrence.
+  pub fn iter<'d>(&'d self) -> impl 'd + Iterator<Item=(&'d T, usize)> {
+    self.items.iter().zip(self.counts.iter().cloned())
+  }
+
+  /// Returns every distinct item with its share of all occurrences, in
+  /// order of first occurrence.
+  pub fn frequencies<'d>(&'d self) -> impl 'd + Iterator<Item=(&'d T, f64)> {
+    let total = self.total as f64;
+    self.iter().map(move |(item, count)| (item, count as f64 / total))
+  }
+
+  /// Returns the `k` most frequent items with their counts, most frequent
+  /// first.
+  pub fn most_common(&self, k: usize) -> Vec<(&T, usize)> {
+    let mut common: Vec<(&T, usize)> = self.iter().collect();
+    // The sort is stable, so items of equal count stay in order of first
+    // occurrence.
+    common.sort_by_key(|&(_, count)| ::std::cmp::Reverse(count));
+    common.truncate(k);
+    common
+  }
+
+  /// Returns the items that occurred exactly once, in order of
+  /// occurrence.
+  pub fn hapaxes(&self) -> Vec<&T> {
+    self.iter().filter(|&(_, count)| count == 1).map(|(item, _)| item).collect()
+  }
+}
+
+impl<T: Hash + Eq + Clone> Default for FreqDist<T> {
+  fn default() -> FreqDist<T> {
+    FreqDist::new()
+  }
+}
+
+impl<T: Hash + Eq + Clone> FromIterator<T> for FreqDist<T> {
+  fn from_iter<I: IntoIterator<Item=T>>(items: I) -> FreqDist<T> {
+    let mut distribution = FreqDist::new();
+    distribution.extend(items);
+    distribution
+  }
+}
+
+impl<T: Hash + Eq + Clone> Extend<T> for FreqDist<T> {
+  fn extend<I: IntoIterator<Item=T>>(&mut self, items: I) {
+    for item in items {
+      self.add(item);
+    }
+  }
+}
+
+/// A conditional frequency distribution: a [`FreqDist`] of items for
+/// each of a set of conditions, such as the tokens following each token,
+/// the tokens of each language, or the words of each document.
+///
+/// ```rust
+/// let following = ConditionalFreqDist::from_bigrams(training.tokens().iter().padded().bigrams());
+/// for (word, count) in following.get(&the).map_or(vec![], |words| words.most_common(5)) {
+///   println!("the {}\t{}", word, count);
+/// }
+/// println!("P(cat | the) = {}", following.frequency(&the, &cat));
+/// ```
+///
+/// Conditions are ordered by their first occurrence.
+///
+/// [`FreqDist`]: struct.FreqDist.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionalFreqDist<C: Hash + Eq, T: Hash + Eq> {
+  /// The distinct conditions, in order of their first occurrence.
+  conditions: Vec<C>,
+  distributions: Vec<FreqDist<T>>,
+  index: HashMap<C, usize>,
+}
+
+impl<C: Hash + Eq + Clone, T: Hash + Eq + Clone> ConditionalFreqDist<C, T> {
+  /// Creates an empty distribution.
+  pub fn new() -> ConditionalFreqDist<C, T> {
+    ConditionalFreqDist { conditions: vec![], distributions: vec![], index: HashMap::new() }
+  }
+
+  /// Creates the distribution of `(condition, item)` pairs, such as the
+  /// bigrams of a document, in which the first token of each is the
+  /// condition of the second.
+  pub fn from_bigrams<I>(pairs: I) -> ConditionalFreqDist<C, T>
+    where I: IntoIterator<Item=(C, T)>
+  {
+    pairs.into_iter().collect()
+  }
+
+  /// Counts an occurrence of an item under a condition, and returns its
+  /// count under the condition.
+  pub fn add(&mut self, condition: C, item: T) -> usize {
+    let next = self.conditions.len();
+    let i = *self.index.entry(condition.clone()).or_insert(next);
+    if i == next {
+      self.conditions.push(condition);
+      self.distributions.push(FreqDist::new());
+    }
+    self.distributions[i].add(item)
+  }
+
+  /// Returns the distribution of items under a condition, or `None` if
+  /// it never occurred.
+  pub fn get(&self, condition: &C) -> Option<&FreqDist<T>> {
+    self.index.get(condition).map(|&i| &self.distributions[i])
+  }
+
+  /// Returns the number of times an item occurred under a condition.
+  pub fn count(&self, condition: &C, item: &T) -> usize {
+    self.get(condition).map_or(0, |distribution| distribution.count(item))
+  }
+
+  /// Returns the share of the occurrences under a condition that are of
+  /// an item: its maximum-likelihood probability given the condition, or
+  /// `0` if the condition never occurred.
+  pub fn frequency(&self, condition: &C, item: &T) -> f64 {
+    self.get(condition).map_or(0.0, |distribution| distribution.frequency(item))
+  }
+
+  /// Returns the distinct conditions, in order of first occurrence.
+  pub fn conditions(&self) -> &[C] {
+    &self.conditions
+  }
+
+  /// Returns every condition with its distribution, in order of first
+  /// occurrence.
+  pub fn iter<'d>(&'d self) -> impl 'd + Iterator<Item=(&'d C, &'d FreqDist<T>)> {
+    self.conditions.iter().zip(&self.distributions)
+  }
+
+  /// Returns the number of occurrences under every condition.
+  pub fn total(&self) -> usize {
+    self.distributions.iter().map(FreqDist::total).sum()
+  }
+
+  /// Returns the number of distinct conditions.
+  pub fn len(&self) -> usize {
+    self.conditions.len()
+  }
+
+  /// Returns `true` if nothing was counted.
+  pub fn is_empty(&self) -> bool {
+    self.conditions.is_empty()
+  }
+}
+
+impl<C: Hash + Eq + Clone, T: Hash + Eq + Clone> Default for ConditionalFreqDist<C, T> {
+  fn default() -> ConditionalFreqDist<C, T> {
+    ConditionalFreqDist::new()
+  }
+}
+
+impl<C: Hash + Eq + Clone, T: Hash + Eq + Clone> FromIterator<(C, T)> for ConditionalFreqDist<C, T> {
+  fn from_iter<I: IntoIterator<Item=(C, T)>>(pairs: I) -> ConditionalFreqDist<C, T> {
+    let mut distribution = ConditionalFreqDist::new();
+    distribution.extend(pairs);
+    distribution
+  }
+}
+
+impl<C: Hash + Eq + Clone, T: Hash + Eq + Clone> Extend<(C, T)> for ConditionalFreqDist<C, T> {
+  fn extend<I: IntoIterator<Item=(C, T)>>(&mut self, pairs: I) {
+    for (condition, item) in pairs {
+      self.add(condition, item);
+    }
+  }
+}
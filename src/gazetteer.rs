@@ -0,0 +1,176 @@
+use token::Token;
+use span::Span;
+use language::{Language, DefaultLanguage};
+use corpus::AsTokens;
+
+use std::marker::PhantomData;
+use std::collections::HashMap;
+
+/// A span of a line matched by an entry of a dictionary, labeled with the
+/// type of the dictionary, such as `PERSON` or `DRUG`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Annotation {
+  pub label: String,
+  pub span: Span,
+}
+
+/// An annotator of lines in language `L`, which tags the phrases listed
+/// in its dictionaries, for lightweight entity and terminology tagging.
+///
+/// Each line is scanned from left to right, and at each position the
+/// longest entry of any dictionary is matched; the scan resumes after the
+/// match, so annotations never overlap. An entry listed in several
+/// dictionaries is labeled by the dictionary of highest priority, or, of
+/// equal priorities, the one added first.
+///
+/// ```rust
+/// let mut annotator: DictionaryAnnotator<English> = DictionaryAnnotator::new()
+///   .with_case_folding(true);
+/// annotator.add_dictionary("CITY", 1, vec!["New York", "York"]);
+/// annotator.add_dictionary("ORG", 2, vec!["New York Times"]);
+///
+/// // "the new york times is based in new york" is annotated as
+/// // "the [ORG new york times] is based in [CITY new york]"
+/// let annotations = annotator.annotate(&document);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DictionaryAnnotator<L: Language=DefaultLanguage> {
+  /// The label, priority and order of addition of each entry, whose words
+  /// are joined by spaces.
+  entries: HashMap<Vec<u8>, (String, usize, usize)>,
+  /// The number of dictionaries added.
+  dictionaries: usize,
+  /// The number of words in the longest entry.
+  longest: usize,
+  fold_case: bool,
+  language: PhantomData<L>,
+}
+
+impl<L: Language> DictionaryAnnotator<L> {
+  /// Creates an annotator with no dictionaries, which matches entries
+  /// with regard to case.
+  pub fn new() -> DictionaryAnnotator<L> {
+    DictionaryAnnotator {
+      entries: HashMap::new(),
+      dictionaries: 0,
+      longest: 0,
+      fold_case: false,
+      language: PhantomData,
+    }
+  }
+
+  /// Sets whether entries are matched without regard to case, as suits
+  /// the language.
+  pub fn with_case_folding(mut self, fold_case: bool) -> DictionaryAnnotator<L> {
+    self.fold_case = fold_case;
+    let entries = ::std::mem::take(&mut self.entries);
+    for (key, (label, priority, order)) in entries {
+      let key = self.fold(&key);
+      self.insert(key, label, priority, order);
+    }
+    self
+  }
+
+  /// Adds a dictionary of entries, each one or more words separated by
+  /// whitespace, whose matches are labeled `label`. Of entries listed in
+  /// several dictionaries, the dictionary of higher priority labels the
+  /// match.
+  pub fn add_dictionary<I, S>(&mut self, label: &str, priority: usize, entries: I)
+    where I: IntoIterator<Item=S>,
+          S: AsRef<str>
+  {
+    let order = self.dictionaries;
+    self.dictionaries += 1;
+    for entry in entries {
+      let words: Vec<&str> = entry.as_ref().split_whitespace().collect();
+      if words.is_empty() {
+        continue;
+      }
+      self.longest = self.longest.max(words.len());
+      let key = self.fold(words.join(" ").as_bytes());
+      self.insert(key, label.to_string(), priority, order);
+    }
+  }
+
+  /// Returns the number of distinct entries of every dictionary.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns `true` if the annotator has no entries.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Annotates the matches of a line, in order. `Null` and `Unknown`
+  /// tokens match no entry.
+  pub fn annotate_line(&self, line: &[Token<L>]) -> Vec<Annotation> {
+    let mut annotations = vec![];
+    let mut start = 0;
+    while start < line.len() {
+      let mut key = vec![];
+      let mut best = None;
+      for (end, token) in line.iter().enumerate().skip(start).take(self.longest) {
+        let word = match *token {
+          Token::Word(ref word) => word.as_bytes(),
+          _ => break
+        };
+        if end > start {
+          key.push(b' ');
+        }
+        key.extend_from_slice(&self.fold(word));
+        if let Some((label, _, _)) = self.entries.get(&key) {
+          best = Some((label, end + 1));
+        }
+      }
+      match best {
+        Some((label, end)) => {
+          annotations.push(Annotation { label: label.clone(), span: Span::new(start, end) });
+          start = end;
+        },
+        None => start += 1
+      }
+    }
+    annotations
+  }
+
+  /// Annotates the matches of every line of a document, such as a
+  /// [`Document`].
+  ///
+  /// [`Document`]: struct.Document.html
+  pub fn annotate<'t, I>(&self, lines: I) -> Vec<Vec<Annotation>>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>,
+          L: 't
+  {
+    lines.into_iter().map(|line| self.annotate_line(&line.as_tokens())).collect()
+  }
+
+  /// Adds an entry, unless it is already labeled by a dictionary of
+  /// higher priority, or of equal priority and added earlier.
+  fn insert(&mut self, key: Vec<u8>, label: String, priority: usize, order: usize) {
+    let replace = match self.entries.get(&key) {
+      Some(&(_, p, o)) => priority > p || (priority == p && order < o),
+      None => true
+    };
+    if replace {
+      self.entries.insert(key, (label, priority, order));
+    }
+  }
+
+  /// Returns the characters of a word, lowercased if the annotator folds
+  /// case.
+  fn fold(&self, word: &[u8]) -> Vec<u8> {
+    if self.fold_case {
+      String::from_utf8_lossy(word).to_lowercase().into_bytes()
+    } else {
+      word.to_vec()
+    }
+  }
+}
+
+impl<L: Language> Default for DictionaryAnnotator<L> {
+  fn default() -> DictionaryAnnotator<L> {
+    DictionaryAnnotator::new()
+  }
+}
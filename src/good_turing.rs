@@ -0,0 +1,236 @@
+//! Simple Good-Turing estimation of the probabilities of items from
+//! their counts, after Gale and Sampson, _Good-Turing Frequency
+//! Estimation Without Tears_.
+//!
+//! Good-Turing estimation reserves for unseen items the share of all
+//! occurrences that are of items seen once, and adjusts the count `r` of
+//! every seen item to `r* = (r + 1) n(r + 1) / n(r)`, where `n(r)` is the
+//! number of distinct items seen `r` times. As `n(r)` is noisy, and zero
+//! for many large counts, the simple Good-Turing method smooths it by a
+//! line fitted to `log n(r)` against `log r`, and uses the smoothed
+//! estimate once it no longer differs significantly from the unsmoothed
+//! one.
+//!
+//! ```rust
+//! let words: FreqDist<Token<English>> = training.tokens().iter().collect();
+//! let estimator = SimpleGoodTuring::from_freq_dist(&words);
+//! println!("unseen words: {:.4}", estimator.unseen_probability());
+//! for (word, probability) in estimator.probabilities(&words).into_iter().take(10) {
+//!   println!("{}\t{:.6}", word, probability);
+//! }
+//! ```
+//!
+//! The same estimates discount the counts of an n-gram [`Model`]
+//! smoothed by [`Smoothing::GoodTuring`].
+//!
+//! [`Model`]: ../ngram/struct.Model.html
+//! [`Smoothing::GoodTuring`]: ../ngram/enum.Smoothing.html#variant.GoodTuring
+use frequency::FreqDist;
+
+use std::hash::Hash;
+use std::collections::BTreeMap;
+
+/// Simple Good-Turing estimates of the adjusted counts and probabilities
+/// of items, from the number of distinct items observed each number of
+/// times.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimpleGoodTuring {
+  /// Each observed count, in ascending order, with the number of items
+  /// observed that many times and its adjusted count.
+  estimates: Vec<(usize, usize, f64)>,
+  /// The intercept and slope of the line fitted to the logarithm of the
+  /// averaged counts of counts against the logarithm of the count, if at
+  /// least two distinct counts were observed.
+  fit: Option<(f64, f64)>,
+  /// The number of occurrences of every item.
+  total: usize,
+  /// The sum of the adjusted counts of every item.
+  adjusted_total: f64,
+}
+
+impl SimpleGoodTuring {
+  /// Creates an estimator from `(count, items)` pairs: the number of
+  /// distinct items observed each number of times. Pairs of the same
+  /// count are summed, and pairs of a zero count or of no items are
+  /// ignored.
+  pub fn new<I>(count_of_counts: I) -> SimpleGoodTuring
+    where I: IntoIterator<Item=(usize, usize)>
+  {
+    let mut table: BTreeMap<usize, usize> = BTreeMap::new();
+    for (count, items) in count_of_counts {
+      if count > 0 && items > 0 {
+        *table.entry(count).or_insert(0) += items;
+      }
+    }
+    let table: Vec<(usize, usize)> = table.into_iter().collect();
+    let total = table.iter().map(|&(count, items)| count * items).sum();
+    let fit = fit(&table);
+
+    // Turing estimates are used while there are items of the next count
+    // and they differ from the smoothed estimates by more than 1.96
+    // standard deviations; once they do not, smoothed estimates are used
+    // for every greater count.
+    let mut smoothed = false;
+    let mut estimates = Vec::with_capacity(table.len());
+    for (i, &(count, items)) in table.iter().enumerate() {
+      let r = count as f64;
+      let next = match table.get(i + 1) {
+        Some(&(next, items)) if next == count + 1 => Some(items as f64),
+        _ => None
+      };
+      let adjusted = match (next, fit) {
+        (Some(next), Some((_, slope))) if !smoothed => {
+          let n = items as f64;
+          let turing = (r + 1.0) * next / n;
+          let deviation = ((r + 1.0).powi(2) * next / n.powi(2) * (1.0 + next / n)).sqrt();
+          if (turing - smoothed_count(r, slope)).abs() > 1.96 * deviation {
+            turing
+          } else {
+            smoothed = true;
+            smoothed_count(r, slope)
+          }
+        },
+        (None, Some((_, slope))) => {
+          smoothed = true;
+          smoothed_count(r, slope)
+        },
+        (_, Some((_, slope))) => smoothed_count(r, slope),
+        (Some(next), None) => (r + 1.0) * next / items as f64,
+        (None, None) => r
+      };
+      estimates.push((count, items, adjusted));
+    }
+    let adjusted_total = estimates.iter().map(|&(_, items, adjusted)| items as f64 * adjusted).sum();
+
+    SimpleGoodTuring {
+      estimates: estimates,
+      fit: fit,
+      total: total,
+      adjusted_total: adjusted_total,
+    }
+  }
+
+  /// Creates an estimator from the count of each distinct item.
+  pub fn from_counts<I>(counts: I) -> SimpleGoodTuring
+    where I: IntoIterator<Item=usize>
+  {
+    SimpleGoodTuring::new(counts.into_iter().map(|count| (count, 1)))
+  }
+
+  /// Creates an estimator from the counts of a frequency distribution.
+  pub fn from_freq_dist<T: Hash + Eq + Clone>(dist: &FreqDist<T>) -> SimpleGoodTuring {
+    SimpleGoodTuring::from_counts(dist.iter().map(|(_, count)| count))
+  }
+
+  /// Returns the adjusted count `r*` of an item observed `count` times.
+  /// Counts not observed are adjusted by the fitted line, or left as they
+  /// are if there is none; a count of `0` is adjusted to `0`, as unseen
+  /// items share [`unseen_probability`] instead.
+  ///
+  /// [`unseen_probability`]: #method.unseen_probability
+  pub fn adjusted_count(&self, count: usize) -> f64 {
+    if count == 0 {
+      return 0.0;
+    }
+    match self.estimates.binary_search_by_key(&count, |&(count, _, _)| count) {
+      Ok(i) => self.estimates[i].2,
+      Err(_) => match self.fit {
+        Some((_, slope)) => smoothed_count(count as f64, slope),
+        None => count as f64
+      }
+    }
+  }
+
+  /// Returns the probability of an item observed `count` times: its
+  /// adjusted count, as a share of the probability of seen items. A count
+  /// of `0` gives the probability shared by every unseen item.
+  pub fn probability(&self, count: usize) -> f64 {
+    if count == 0 {
+      return self.unseen_probability();
+    }
+    if self.adjusted_total == 0.0 {
+      return 0.0;
+    }
+    (1.0 - self.unseen_probability()) * self.adjusted_count(count) / self.adjusted_total
+  }
+
+  /// Returns the probability shared by every unseen item: the share of
+  /// all occurrences that are of items observed once, or `1` if nothing
+  /// was observed.
+  pub fn unseen_probability(&self) -> f64 {
+    if self.total == 0 {
+      return 1.0;
+    }
+    match self.estimates.first() {
+      Some(&(1, items, _)) => items as f64 / self.total as f64,
+      _ => 0.0
+    }
+  }
+
+  /// Returns every item of a frequency distribution with its estimated
+  /// probability, in order of first occurrence.
+  pub fn probabilities<'d, T>(&self, dist: &'d FreqDist<T>) -> Vec<(&'d T, f64)>
+    where T: Hash + Eq + Clone
+  {
+    dist.iter().map(|(item, count)| (item, self.probability(count))).collect()
+  }
+
+  /// Returns each observed count, in ascending order, with the number of
+  /// items observed that many times and its adjusted count.
+  pub fn estimates(&self) -> &[(usize, usize, f64)] {
+    &self.estimates
+  }
+
+  /// Returns the slope of the line fitted to the logarithm of the
+  /// averaged counts of counts against the logarithm of the count, or
+  /// `None` if fewer than two distinct counts were observed. Gale and
+  /// Sampson advise against the estimates if it is not below `-1`.
+  pub fn slope(&self) -> Option<f64> {
+    self.fit.map(|(_, slope)| slope)
+  }
+
+  /// Returns the intercept of the fitted line, or `None` if fewer than
+  /// two distinct counts were observed.
+  pub fn intercept(&self) -> Option<f64> {
+    self.fit.map(|(intercept, _)| intercept)
+  }
+
+  /// Returns the number of occurrences of every item.
+  pub fn total(&self) -> usize {
+    self.total
+  }
+}
+
+/// Returns the adjusted count of `r` by the fitted line
+/// `log n(r) = a + b log r`, which is `r (1 + 1/r)^(b + 1)`.
+fn smoothed_count(r: f64, slope: f64) -> f64 {
+  r * (1.0 + 1.0 / r).powf(slope + 1.0)
+}
+
+/// Fits `log Z(r) = a + b log r` by least squares, where `Z(r)` averages
+/// the number of items observed `r` times over the gap between the
+/// neighbouring observed counts. Returns the intercept and slope, or
+/// `None` if fewer than two distinct counts were observed.
+fn fit(table: &[(usize, usize)]) -> Option<(f64, f64)> {
+  if table.len() < 2 {
+    return None;
+  }
+  let points: Vec<(f64, f64)> = table.iter().enumerate()
+    .map(|(i, &(count, items))| {
+      let previous = if i == 0 { 0.0 } else { table[i - 1].0 as f64 };
+      let next = match table.get(i + 1) {
+        Some(&(next, _)) => next as f64,
+        None => 2.0 * count as f64 - previous
+      };
+      let averaged = 2.0 * items as f64 / (next - previous);
+      ((count as f64).ln(), averaged.ln())
+    })
+    .collect();
+  let n = points.len() as f64;
+  let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / n;
+  let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+  let covariance: f64 = points.iter().map(|&(x, y)| (x - mean_x) * (y - mean_y)).sum();
+  let variance: f64 = points.iter().map(|&(x, _)| (x - mean_x).powi(2)).sum();
+  let slope = covariance / variance;
+  Some((mean_y - slope * mean_x, slope))
+}
@@ -0,0 +1,381 @@
+use tree::Tree;
+
+use std::fmt;
+use std::collections::HashMap;
+
+/// A symbol on the right-hand side of a grammar rule.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Symbol {
+  /// A nonterminal, which is further expanded by rules.
+  Nonterminal(String),
+  /// A terminal: a word.
+  Terminal(String),
+}
+
+impl fmt::Display for Symbol {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Symbol::Nonterminal(ref label) => write!(f, "{}", label),
+      Symbol::Terminal(ref word) => write!(f, "{:?}", word),
+    }
+  }
+}
+
+/// A context-free rule, rewriting a nonterminal as a sequence of
+/// symbols. A rule with an empty right-hand side is an epsilon rule.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rule {
+  /// The nonterminal rewritten by this rule.
+  pub lhs: String,
+  /// The symbols it is rewritten as.
+  pub rhs: Vec<Symbol>,
+}
+
+impl Rule {
+  /// Creates a rule rewriting `lhs` as `rhs`.
+  pub fn new<S: Into<String>>(lhs: S, rhs: Vec<Symbol>) -> Rule {
+    Rule { lhs: lhs.into(), rhs: rhs }
+  }
+}
+
+impl fmt::Display for Rule {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} ->", self.lhs)?;
+    if self.rhs.is_empty() {
+      return write!(f, " ε");
+    }
+    for symbol in &self.rhs {
+      write!(f, " {}", symbol)?;
+    }
+    Ok(())
+  }
+}
+
+/// A probabilistic context-free grammar, in which the probabilities of
+/// the rules rewriting each nonterminal sum to one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pcfg {
+  start: String,
+  rules: Vec<(Rule, f64)>,
+  /// The indices in `rules` of the rules rewriting each nonterminal.
+  expansions: HashMap<String, Vec<usize>>,
+}
+
+impl Pcfg {
+  /// Creates a grammar from weighted rules. The rules are not checked,
+  /// nor are their probabilities normalized.
+  pub fn new<S: Into<String>>(start: S, rules: Vec<(Rule, f64)>) -> Pcfg {
+    let mut expansions: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, (rule, _)) in rules.iter().enumerate() {
+      expansions.entry(rule.lhs.clone()).or_default().push(i);
+    }
+    Pcfg { start: start.into(), rules: rules, expansions: expansions }
+  }
+
+  /// Returns the start symbol of the grammar.
+  pub fn start(&self) -> &str {
+    &self.start
+  }
+
+  /// Returns every rule of the grammar, with its probability.
+  pub fn rules(&self) -> &[(Rule, f64)] {
+    &self.rules
+  }
+
+  /// Produces an iterator over the rules rewriting `lhs`, with their
+  /// probabilities.
+  pub fn expansions<'g>(&'g self, lhs: &str)
+      -> impl 'g + Iterator<Item=&'g (Rule, f64)>
+  {
+    self.expansions.get(lhs)
+      .map(|indices| &indices[..])
+      .unwrap_or(&[])
+      .iter()
+      .map(move |&i| &self.rules[i])
+  }
+
+  /// Returns the probability of `rule`, or `0` if it is not in the
+  /// grammar.
+  pub fn probability(&self, rule: &Rule) -> f64 {
+    self.expansions(&rule.lhs)
+      .find(|&(r, _)| r == rule)
+      .map(|&(_, p)| p)
+      .unwrap_or(0.0)
+  }
+}
+
+/// The start symbol of grammars induced by a [`GrammarInducer`], which
+/// rewrites to the root label of each training tree.
+/// [`GrammarInducer`]: struct.GrammarInducer.html
+pub const ROOT: &str = "ROOT";
+
+/// The terminal standing in for rare and unknown words in grammars
+/// induced with a rare-word threshold.
+pub const UNKNOWN_WORD: &str = "<unk>";
+
+/// Induces a [`Pcfg`] from a treebank by relative-frequency estimation,
+/// optionally transforming the trees first, following Klein and Manning
+/// (2003):
+///
+/// * **Vertical markovization** annotates each phrasal label with the
+///   labels of its ancestors, so that, for instance, subject and object
+///   noun phrases (`NP^S` and `NP^VP`) expand differently.
+/// * **Binarization** with **horizontal markovization** splits wide
+///   constituents into binary rules whose intermediate symbols, like
+///   `@NP|DT,JJ`, remember only the last few siblings generated.
+///
+/// Rare rules are smoothed by additive smoothing, and rare words by
+/// pooling their counts into the [`UNKNOWN_WORD`] terminal:
+///
+/// ```rust
+/// let trees = read_trees(&fs::read_to_string("wsj_0001.mrg")?)?;
+/// let grammar = GrammarInducer::new()
+///   .vertical(2)
+///   .horizontal(1)
+///   .rare_words(1)
+///   .induce(&trees);
+///
+/// for &(ref rule, p) in grammar.expansions("NP^S") {
+///   println!("{:.3} {}", p, rule);
+/// }
+/// ```
+///
+/// Labels produced by these transformations can be removed from parsed
+/// trees with [`restore`].
+///
+/// [`Pcfg`]: struct.Pcfg.html
+/// [`UNKNOWN_WORD`]: constant.UNKNOWN_WORD.html
+/// [`restore`]: fn.restore.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrammarInducer {
+  vertical: usize,
+  horizontal: Option<usize>,
+  binarize: bool,
+  additive: f64,
+  rare: usize,
+}
+
+impl GrammarInducer {
+  /// Creates an inducer that reads rules directly off the trees, with no
+  /// transformation or smoothing.
+  pub fn new() -> GrammarInducer {
+    GrammarInducer {
+      vertical: 1,
+      horizontal: None,
+      binarize: false,
+      additive: 0.0,
+      rare: 0,
+    }
+  }
+
+  /// Annotates each phrasal label with the labels of up to `order - 1`
+  /// ancestors. An order of `1` leaves labels unannotated.
+  pub fn vertical(mut self, order: usize) -> GrammarInducer {
+    self.vertical = order.max(1);
+    self
+  }
+
+  /// Binarizes the trees, with intermediate symbols remembering up to
+  /// `order` previously generated siblings.
+  pub fn horizontal(mut self, order: usize) -> GrammarInducer {
+    self.binarize = true;
+    self.horizontal = Some(order);
+    self
+  }
+
+  /// Binarizes the trees, with intermediate symbols remembering every
+  /// previously generated sibling.
+  pub fn binarize(mut self) -> GrammarInducer {
+    self.binarize = true;
+    self.horizontal = None;
+    self
+  }
+
+  /// Adds `lambda` to the count of every observed rule before
+  /// normalization, shifting probability from frequent to rare
+  /// expansions of each nonterminal.
+  pub fn additive(mut self, lambda: f64) -> GrammarInducer {
+    self.additive = lambda;
+    self
+  }
+
+  /// Pools the lexical rules of words occurring at most `threshold`
+  /// times into rules producing [`UNKNOWN_WORD`], so that the grammar
+  /// can tag unseen words. Each occurrence of a rare word is counted as
+  /// [`UNKNOWN_WORD`] only, so rare words get no rules of their own and
+  /// are parsed as unknown words are.
+  /// [`UNKNOWN_WORD`]: constant.UNKNOWN_WORD.html
+  pub fn rare_words(mut self, threshold: usize) -> GrammarInducer {
+    self.rare = threshold;
+    self
+  }
+
+  /// Applies this inducer's markovization and binarization to a tree.
+  pub fn transform<T: fmt::Display>(&self, tree: &Tree<T>) -> Tree {
+    let annotated = annotate(tree, &[], self.vertical);
+    if self.binarize { binarize(annotated, self.horizontal) }
+    else { annotated }
+  }
+
+  /// Induces a grammar from a collection of trees.
+  pub fn induce<'a, I, T>(&self, trees: I) -> Pcfg
+    where I: IntoIterator<Item=&'a Tree<T>>,
+          T: 'a + fmt::Display
+  {
+    let trees: Vec<Tree> = trees.into_iter()
+      .map(|tree| self.transform(tree))
+      .collect();
+
+    let mut words = HashMap::new();
+    for tree in &trees {
+      for word in tree.leaves() {
+        *words.entry(word.clone()).or_insert(0) += 1;
+      }
+    }
+
+    let mut counts: HashMap<Rule, f64> = HashMap::new();
+    for tree in &trees {
+      if let Some(label) = tree.label() {
+        let top = Rule::new(ROOT, vec![Symbol::Nonterminal(label.to_string())]);
+        *counts.entry(top).or_insert(0.0) += 1.0;
+      }
+      count_rules(tree, &mut |rule| {
+        let rule = match rule.rhs[..] {
+          [Symbol::Terminal(ref word)] if words[word] <= self.rare =>
+            Rule::new(rule.lhs.clone(), vec![Symbol::Terminal(UNKNOWN_WORD.to_string())]),
+          _ => rule,
+        };
+        *counts.entry(rule).or_insert(0.0) += 1.0;
+      });
+    }
+
+    let mut totals: HashMap<String, (f64, usize)> = HashMap::new();
+    for (rule, &count) in &counts {
+      let total = totals.entry(rule.lhs.clone()).or_insert((0.0, 0));
+      total.0 += count;
+      total.1 += 1;
+    }
+
+    let mut rules: Vec<(Rule, f64)> = counts.into_iter()
+      .map(|(rule, count)| {
+        let (total, types) = totals[&rule.lhs];
+        let p = (count + self.additive) / (total + self.additive * types as f64);
+        (rule, p)
+      })
+      .collect();
+    rules.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Pcfg::new(ROOT, rules)
+  }
+}
+
+impl Default for GrammarInducer {
+  fn default() -> GrammarInducer {
+    GrammarInducer::new()
+  }
+}
+
+/// Calls `f` with every rule used in `tree`.
+fn count_rules<F: FnMut(Rule)>(tree: &Tree, f: &mut F) {
+  if let Tree::Node(ref label, ref children) = *tree {
+    let rhs = children.iter()
+      .map(|child| match *child {
+        Tree::Leaf(ref word) => Symbol::Terminal(word.clone()),
+        Tree::Node(ref label, _) => Symbol::Nonterminal(label.clone()),
+      })
+      .collect();
+    f(Rule::new(label.clone(), rhs));
+    for child in children {
+      count_rules(child, f);
+    }
+  }
+}
+
+/// Annotates each phrasal label in `tree` with up to `order - 1` of its
+/// ancestors, given in `ancestors` from the nearest outward.
+fn annotate<T: fmt::Display>(tree: &Tree<T>, ancestors: &[&str], order: usize) -> Tree {
+  match *tree {
+    Tree::Leaf(ref word) => Tree::Leaf(word.to_string()),
+    Tree::Node(ref label, ref children) => {
+      let annotated =
+        if tree.is_preterminal() { label.clone() }
+        else {
+          let mut annotated = label.clone();
+          for ancestor in ancestors.iter().take(order - 1) {
+            annotated.push('^');
+            annotated.push_str(ancestor);
+          }
+          annotated
+        };
+      let mut inner = vec![&label[..]];
+      inner.extend_from_slice(ancestors);
+      Tree::Node(annotated,
+        children.iter().map(|child| annotate(child, &inner, order)).collect())
+    }
+  }
+}
+
+/// Right-binarizes every node with more than two children, introducing
+/// intermediate nodes labeled `@X|A,B` that remember up to `horizontal`
+/// previously generated siblings.
+fn binarize(tree: Tree, horizontal: Option<usize>) -> Tree {
+  match tree {
+    Tree::Leaf(word) => Tree::Leaf(word),
+    Tree::Node(label, children) => {
+      let mut children: Vec<Tree> = children.into_iter()
+        .map(|child| binarize(child, horizontal))
+        .collect();
+      if children.len() <= 2 {
+        return Tree::Node(label, children);
+      }
+
+      let names: Vec<String> = children.iter()
+        .map(|child| child.label().map(String::from)
+                          .unwrap_or_else(|| child.to_string()))
+        .collect();
+      let intermediate = |generated: usize| {
+        let first = horizontal.map(|h| generated.saturating_sub(h)).unwrap_or(0);
+        format!("@{}|{}", label, names[first..generated].join(","))
+      };
+
+      // Build the chain of intermediate nodes from the right.
+      let last = children.pop().unwrap();
+      let penultimate = children.pop().unwrap();
+      let mut node = Tree::Node(intermediate(children.len()),
+                                vec![penultimate, last]);
+      while children.len() > 1 {
+        let child = children.pop().unwrap();
+        node = Tree::Node(intermediate(children.len()), vec![child, node]);
+      }
+      let first = children.pop().unwrap();
+      Tree::Node(label, vec![first, node])
+    }
+  }
+}
+
+/// Undoes the transformations of a [`GrammarInducer`] on a tree,
+/// splicing out intermediate `@` nodes and removing `^` annotations.
+/// [`GrammarInducer`]: struct.GrammarInducer.html
+pub fn restore<T>(tree: Tree<T>) -> Tree<T> {
+  fn children<T>(tree: Tree<T>, out: &mut Vec<Tree<T>>) {
+    match tree {
+      Tree::Node(label, grandchildren) if label.starts_with('@') =>
+        for child in grandchildren { children(child, out) },
+      tree => out.push(restore(tree)),
+    }
+  }
+  match tree {
+    Tree::Leaf(word) => Tree::Leaf(word),
+    Tree::Node(label, kids) => {
+      let mut restored = vec![];
+      for kid in kids {
+        children(kid, &mut restored);
+      }
+      let label = match label.find('^') {
+        Some(i) => label[..i].to_string(),
+        None => label
+      };
+      Tree::Node(label, restored)
+    }
+  }
+}
@@ -0,0 +1,326 @@
+use corpus::Document;
+
+use std::fmt;
+
+/// A division of a book introduced by a heading.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Division {
+  Volume,
+  Book,
+  Part,
+  Chapter,
+}
+
+/// A heading introducing a division of a book, such as `CHAPTER XII.`,
+/// `Chapter the Twelfth` or `BOOK II: THE GOLDEN THREAD`. Headings are
+/// displayed in a normal form, such as `Chapter 12` or
+/// `Book 2. THE GOLDEN THREAD`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Heading {
+  pub division: Division,
+  pub number: usize,
+  pub title: Option<String>,
+}
+
+impl Heading {
+  /// Parses a line consisting of a heading: the name of a division,
+  /// followed by its number in arabic or roman numerals or in words, and
+  /// optionally by a title. A title must be separated from the number by
+  /// punctuation, unless the line is in capitals, so that sentences such
+  /// as "Chapter 3 of the book" are not mistaken for headings.
+  pub fn parse(line: &str) -> Option<Heading> {
+    let line = line.trim();
+    let mut words = line.splitn(2, char::is_whitespace);
+    let division = match words.next()?.to_lowercase().as_str() {
+      "volume" | "vol." => Division::Volume,
+      "book" => Division::Book,
+      "part" => Division::Part,
+      "chapter" => Division::Chapter,
+      _ => return None
+    };
+    let mut rest = words.next()?.trim_start();
+    if rest.get(..4).is_some_and(|the| the.eq_ignore_ascii_case("the ")) {
+      rest = rest[4..].trim_start();
+    }
+    let end = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+    let number = number(&rest[..end])?;
+    let remainder = rest[end..].trim_start();
+    let title = remainder.trim_start_matches(|c: char| is_separator(c) || c.is_whitespace()).trim();
+    let separated = remainder.starts_with(is_separator);
+    if !title.is_empty() && !separated && line.chars().any(char::is_lowercase) {
+      return None;
+    }
+    Some(Heading {
+      division: division,
+      number: number,
+      title: if title.is_empty() { None } else { Some(title.to_string()) },
+    })
+  }
+}
+
+impl fmt::Display for Heading {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:?} {}", self.division, self.number)?;
+    match self.title {
+      Some(ref title) => write!(f, ". {}", title),
+      None => Ok(())
+    }
+  }
+}
+
+/// A cleaner of the plain-text ebooks of [Project Gutenberg][gutenberg],
+/// for literary analysis.
+///
+/// The license boilerplate before the `*** START OF THE PROJECT GUTENBERG
+/// EBOOK ***` marker (or, in older files, the `*END*THE SMALL PRINT!`
+/// marker) and from the `End of the Project Gutenberg EBook` notice or
+/// the `*** END OF THE PROJECT GUTENBERG EBOOK ***` marker onwards is
+/// removed, along with the credits of the transcribers at the start of
+/// the text. Files lacking either marker are kept from their start or to
+/// their end. Chapter headings are then rewritten in the normal form of
+/// [`Heading`].
+///
+/// ```rust
+/// let cleaner = GutenbergCleaner::new().with_paragraph_joining(true);
+/// let text = fs::read_to_string("pg1400.txt")?;
+/// for (heading, chapter) in cleaner.chapters::<English>(&text) {
+///   println!("{:?}: {} tokens", heading, chapter.tokens().len());
+/// }
+/// ```
+///
+/// [gutenberg]: https://www.gutenberg.org/
+/// [`Heading`]: struct.Heading.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GutenbergCleaner {
+  normalize_headings: bool,
+  join_paragraphs: bool,
+}
+
+impl GutenbergCleaner {
+  /// Creates a cleaner that normalizes headings, and keeps the lines of
+  /// the text as they are wrapped.
+  pub fn new() -> GutenbergCleaner {
+    GutenbergCleaner { normalize_headings: true, join_paragraphs: false }
+  }
+
+  /// Sets whether headings are rewritten in their normal form.
+  pub fn with_heading_normalization(mut self, normalize: bool) -> GutenbergCleaner {
+    self.normalize_headings = normalize;
+    self
+  }
+
+  /// Sets whether the hard-wrapped lines of each paragraph are joined
+  /// into one line, and the blank lines between paragraphs removed, so
+  /// that each line of a [`Document`] is a paragraph. Headings are kept
+  /// on lines of their own.
+  ///
+  /// [`Document`]: struct.Document.html
+  pub fn with_paragraph_joining(mut self, join: bool) -> GutenbergCleaner {
+    self.join_paragraphs = join;
+    self
+  }
+
+  /// Returns the text of an ebook without the license boilerplate before
+  /// and after it, or the credits of its transcribers.
+  pub fn strip<'t>(&self, text: &'t str) -> &'t str {
+    let mut start = 0;
+    let mut end = text.len();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+      let marker = line.trim().to_uppercase();
+      if is_header_end(&marker) {
+        start = offset + line.len();
+      } else if is_footer_start(&marker) {
+        end = offset;
+        break;
+      }
+      offset += line.len();
+    }
+    skip_credits(&text[start..end]).trim_matches(|c| c == '\r' || c == '\n')
+  }
+
+  /// Strips an ebook, and normalizes its headings and joins its
+  /// paragraphs as the cleaner is configured.
+  pub fn clean(&self, text: &str) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut paragraph: Vec<&str> = vec![];
+    for line in self.strip(text).lines() {
+      let heading = Heading::parse(line);
+      let normalized = match heading {
+        Some(ref heading) if self.normalize_headings => heading.to_string(),
+        _ => line.trim_end().to_string()
+      };
+      if !self.join_paragraphs {
+        lines.push(normalized);
+      } else if heading.is_some() || line.trim().is_empty() {
+        if !paragraph.is_empty() {
+          lines.push(paragraph.join(" "));
+          paragraph.clear();
+        }
+        if heading.is_some() {
+          lines.push(normalized.trim().to_string());
+        }
+      } else {
+        paragraph.push(line.trim());
+      }
+    }
+    if !paragraph.is_empty() {
+      lines.push(paragraph.join(" "));
+    }
+    lines.join("\n")
+  }
+
+  /// Cleans an ebook into a [`Document`].
+  ///
+  /// [`Document`]: struct.Document.html
+  pub fn document<L>(&self, text: &str) -> Document<L> {
+    self.clean(text).into()
+  }
+
+  /// Cleans an ebook and divides it at its headings into a [`Document`]
+  /// per division, each paired with its heading. The text before the
+  /// first heading, such as the title page, has no heading. Divisions
+  /// with no text, such as the entries of a table of contents, are
+  /// omitted.
+  ///
+  /// [`Document`]: struct.Document.html
+  pub fn chapters<L>(&self, text: &str) -> Vec<(Option<Heading>, Document<L>)> {
+    let mut chapters = vec![];
+    let mut heading = None;
+    let mut body: Vec<&str> = vec![];
+    let cleaned = self.clean(text);
+    for line in cleaned.lines() {
+      match Heading::parse(line) {
+        Some(next) => {
+          push_chapter(&mut chapters, heading, &body);
+          heading = Some(next);
+          body.clear();
+        },
+        None => body.push(line)
+      }
+    }
+    push_chapter(&mut chapters, heading, &body);
+    chapters
+  }
+}
+
+impl Default for GutenbergCleaner {
+  fn default() -> GutenbergCleaner {
+    GutenbergCleaner::new()
+  }
+}
+
+/// Adds a division to a list of chapters, unless it has no text.
+fn push_chapter<L>(chapters: &mut Vec<(Option<Heading>, Document<L>)>,
+                   heading: Option<Heading>, body: &[&str])
+{
+  if body.iter().any(|line| !line.trim().is_empty()) {
+    let text = body.join("\n").trim_matches('\n').to_string();
+    chapters.push((heading, text.into()));
+  }
+}
+
+/// Returns `true` if a line, in capitals, is the marker ending the
+/// license header.
+fn is_header_end(line: &str) -> bool {
+  (line.starts_with("***") && (line.contains("START OF THE PROJECT GUTENBERG")
+                               || line.contains("START OF THIS PROJECT GUTENBERG")))
+    || (line.starts_with("*END*") && line.contains("THE SMALL PRINT"))
+}
+
+/// Returns `true` if a line, in capitals, begins the license footer.
+fn is_footer_start(line: &str) -> bool {
+  (line.starts_with("***") && (line.contains("END OF THE PROJECT GUTENBERG")
+                               || line.contains("END OF THIS PROJECT GUTENBERG")))
+    || line.starts_with("END OF THE PROJECT GUTENBERG")
+    || line.starts_with("END OF THIS PROJECT GUTENBERG")
+    || line.starts_with("END OF PROJECT GUTENBERG")
+}
+
+/// Returns a text without the paragraph crediting its transcribers, if
+/// it begins with one.
+fn skip_credits(text: &str) -> &str {
+  let body = text.trim_start();
+  let first = body.lines().next().unwrap_or("").to_uppercase();
+  let credits = ["PRODUCED BY", "E-TEXT PREPARED BY", "ETEXT PREPARED BY", "TRANSCRIBED BY"];
+  if !credits.iter().any(|credit| first.starts_with(credit)) {
+    return text;
+  }
+  let mut offset = 0;
+  for line in body.split_inclusive('\n') {
+    if line.trim().is_empty() {
+      return &body[offset..];
+    }
+    offset += line.len();
+  }
+  ""
+}
+
+/// Returns `true` if a character may separate the number of a heading
+/// from its title.
+fn is_separator(c: char) -> bool {
+  c == '.' || c == ':' || c == '-' || c == '\u{2014}' || c == '\u{2013}'
+}
+
+const CARDINALS: [&str; 20] = [
+  "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+  "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen",
+  "eighteen", "nineteen", "twenty",
+];
+
+const ORDINALS: [&str; 20] = [
+  "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth",
+  "tenth", "eleventh", "twelfth", "thirteenth", "fourteenth", "fifteenth", "sixteenth",
+  "seventeenth", "eighteenth", "nineteenth", "twentieth",
+];
+
+/// Parses a number in arabic numerals, in canonical roman numerals, or
+/// as a cardinal or ordinal word up to twenty.
+fn number(word: &str) -> Option<usize> {
+  if word.is_empty() {
+    return None;
+  }
+  if let Ok(number) = word.parse() {
+    return Some(number);
+  }
+  let lower = word.to_lowercase();
+  if let Some(i) = CARDINALS.iter().chain(&ORDINALS).position(|&w| w == lower) {
+    return Some(i % 20 + 1);
+  }
+  let numerals = [('m', 1000), ('d', 500), ('c', 100), ('l', 50), ('x', 10), ('v', 5), ('i', 1)];
+  let values: Option<Vec<usize>> = lower.chars()
+    .map(|c| numerals.iter().find(|&&(n, _)| n == c).map(|&(_, value)| value))
+    .collect();
+  let values = values?;
+  let mut number = 0;
+  for (i, &value) in values.iter().enumerate() {
+    if values.get(i + 1).is_some_and(|&next| next > value) {
+      number -= value as isize;
+    } else {
+      number += value as isize;
+    }
+  }
+  // Reject sequences of numerals that are not canonical, such as "IIX",
+  // which are more likely words.
+  if number > 0 && roman(number as usize) == lower {
+    Some(number as usize)
+  } else {
+    None
+  }
+}
+
+/// Returns a number in lowercase roman numerals.
+fn roman(mut number: usize) -> String {
+  let numerals = [
+    (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"), (100, "c"), (90, "xc"),
+    (50, "l"), (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+  ];
+  let mut roman = String::new();
+  for &(value, numeral) in &numerals {
+    while number >= value {
+      roman.push_str(numeral);
+      number -= value;
+    }
+  }
+  roman
+}
@@ -0,0 +1,236 @@
+use tree::Tree;
+use dependency::DepGraph;
+
+use std::collections::HashMap;
+
+/// The order in which the children of a node are searched for its head.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Direction {
+  /// Search from the first child to the last.
+  LeftToRight,
+  /// Search from the last child to the first.
+  RightToLeft,
+}
+
+/// A single head-finding rule: search the children in `direction` for
+/// the first label in `priorities`, trying each label in turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+  direction: Direction,
+  priorities: Vec<String>,
+}
+
+/// A table of head-percolation rules, determining which child of each
+/// constituent is its head. The head of a constituent is found by trying
+/// each of its label's rules in order; if no rule matches, the first
+/// child in the direction of the label's first rule is the head.
+///
+/// ```rust
+/// let tree: Tree = "(S (NP (DT the) (NN dog)) (VP (VBD barked)))".parse()?;
+/// let graph = tree.to_dependencies(&HeadRules::collins());
+/// assert_eq!(graph.root(), 2); // barked
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadRules {
+  rules: HashMap<String, Vec<Rule>>,
+  /// Whether to use Collins' special rules for noun phrases.
+  collins_np: bool,
+}
+
+impl HeadRules {
+  /// Creates an empty rule table, in which the head of every constituent
+  /// is its first child.
+  pub fn new() -> HeadRules {
+    HeadRules { rules: HashMap::new(), collins_np: false }
+  }
+
+  /// Adds a rule for constituents labeled `label`, after any existing
+  /// rules for that label.
+  pub fn add<S: AsRef<str>>(&mut self, label: &str, direction: Direction,
+                            priorities: &[S]) {
+    self.rules.entry(label.to_string()).or_default().push(Rule {
+      direction: direction,
+      priorities: priorities.iter().map(|p| p.as_ref().to_string()).collect(),
+    });
+  }
+
+  /// The head rules of Collins (1999, appendix A) for the Penn Treebank,
+  /// including the special treatment of noun phrases.
+  pub fn collins() -> HeadRules {
+    use self::Direction::*;
+    let table: &[(&str, Direction, &[&str])] = &[
+      ("ADJP", LeftToRight, &["NNS", "QP", "NN", "$", "ADVP", "JJ", "VBN",
+        "VBG", "ADJP", "JJR", "NP", "JJS", "DT", "FW", "RBR", "RBS", "SBAR",
+        "RB"]),
+      ("ADVP", RightToLeft, &["RB", "RBR", "RBS", "FW", "ADVP", "TO", "CD",
+        "JJR", "JJ", "IN", "NP", "JJS", "NN"]),
+      ("CONJP", RightToLeft, &["CC", "RB", "IN"]),
+      ("FRAG", RightToLeft, &[]),
+      ("INTJ", LeftToRight, &[]),
+      ("LST", RightToLeft, &["LS", ":"]),
+      ("NAC", LeftToRight, &["NN", "NNS", "NNP", "NNPS", "NP", "NAC", "EX",
+        "$", "CD", "QP", "PRP", "VBG", "JJ", "JJS", "JJR", "ADJP", "FW"]),
+      ("PP", RightToLeft, &["IN", "TO", "VBG", "VBN", "RP", "FW"]),
+      ("PRN", LeftToRight, &[]),
+      ("PRT", RightToLeft, &["RP"]),
+      ("QP", LeftToRight, &["$", "IN", "NNS", "NN", "JJ", "RB", "DT", "CD",
+        "NCD", "QP", "JJR", "JJS"]),
+      ("RRC", RightToLeft, &["VP", "NP", "ADVP", "ADJP", "PP"]),
+      ("S", LeftToRight, &["TO", "IN", "VP", "S", "SBAR", "ADJP", "UCP",
+        "NP"]),
+      ("SBAR", LeftToRight, &["WHNP", "WHPP", "WHADVP", "WHADJP", "IN", "DT",
+        "S", "SQ", "SINV", "SBAR", "FRAG"]),
+      ("SBARQ", LeftToRight, &["SQ", "S", "SINV", "SBARQ", "FRAG"]),
+      ("SINV", LeftToRight, &["VBZ", "VBD", "VBP", "VB", "MD", "VP", "S",
+        "SINV", "ADJP", "NP"]),
+      ("SQ", LeftToRight, &["VBZ", "VBD", "VBP", "VB", "MD", "VP", "SQ"]),
+      ("UCP", RightToLeft, &[]),
+      ("VP", LeftToRight, &["TO", "VBD", "VBN", "MD", "VBZ", "VB", "VBG",
+        "VBP", "VP", "ADJP", "NN", "NNS", "NP"]),
+      ("WHADJP", LeftToRight, &["CC", "WRB", "JJ", "ADJP"]),
+      ("WHADVP", RightToLeft, &["CC", "WRB"]),
+      ("WHNP", LeftToRight, &["WDT", "WP", "WP$", "WHADJP", "WHPP", "WHNP"]),
+      ("WHPP", RightToLeft, &["IN", "TO", "FW"]),
+    ];
+
+    let mut rules = HeadRules::new();
+    for &(label, direction, priorities) in table {
+      rules.add(label, direction, priorities);
+    }
+    rules.collins_np = true;
+    rules
+  }
+
+  /// Returns the index of the head child among `children`, which are the
+  /// children of a constituent labeled `label`. A constituent without
+  /// children is assigned a head index of `0`.
+  pub fn head_child<T>(&self, label: &str, children: &[Tree<T>]) -> usize {
+    if children.is_empty() {
+      return 0;
+    }
+    let labels: Vec<&str> = children.iter()
+      .map(|child| child.label().map(base_label).unwrap_or(""))
+      .collect();
+    let label = base_label(label);
+
+    if self.collins_np && (label == "NP" || label == "NX") {
+      return collins_np_head(&labels);
+    }
+
+    let rules = match self.rules.get(label) {
+      Some(rules) => rules,
+      None => return 0
+    };
+    for rule in rules {
+      for priority in &rule.priorities {
+        if let Some(i) = search(&labels, rule.direction, |l| l == priority) {
+          return i;
+        }
+      }
+    }
+    match rules.first().map(|rule| rule.direction) {
+      Some(Direction::RightToLeft) => labels.len() - 1,
+      _ => 0
+    }
+  }
+}
+
+impl Default for HeadRules {
+  fn default() -> HeadRules {
+    HeadRules::new()
+  }
+}
+
+/// Strips function tags and indices from a treebank label, such that
+/// `NP-SBJ-1` becomes `NP`. Labels beginning with `-`, like `-NONE-`,
+/// are left intact.
+pub fn base_label(label: &str) -> &str {
+  if label.starts_with('-') {
+    return label;
+  }
+  label.split(&['-', '='][..]).next().unwrap_or(label)
+}
+
+/// Finds the first label satisfying `predicate`, searching in `direction`.
+fn search<F>(labels: &[&str], direction: Direction, predicate: F) -> Option<usize>
+  where F: Fn(&str) -> bool
+{
+  match direction {
+    Direction::LeftToRight => labels.iter().position(|l| predicate(l)),
+    Direction::RightToLeft => labels.iter().rposition(|l| predicate(l)),
+  }
+}
+
+/// Collins' special head rule for noun phrases.
+fn collins_np_head(labels: &[&str]) -> usize {
+  use self::Direction::*;
+  let last = labels.len() - 1;
+  if labels[last] == "POS" {
+    return last;
+  }
+  let one_of = |set: &'static [&'static str]| move |l: &str| set.contains(&l);
+  search(labels, RightToLeft, one_of(&["NN", "NNP", "NNPS", "NNS", "NX", "POS", "JJR"]))
+    .or_else(|| search(labels, LeftToRight, one_of(&["NP"])))
+    .or_else(|| search(labels, RightToLeft, one_of(&["$", "ADJP", "PRN"])))
+    .or_else(|| search(labels, RightToLeft, one_of(&["CD"])))
+    .or_else(|| search(labels, RightToLeft, one_of(&["JJ", "JJS", "RB", "QP"])))
+    .unwrap_or(last)
+}
+
+impl<T> Tree<T> {
+  /// Converts this constituency tree to a dependency tree over its
+  /// leaves, using `rules` to find the head of each constituent. The
+  /// head word of each non-head child depends on the head word of its
+  /// parent, with an arc labeled by the child's label, without function
+  /// tags. Leaves attached directly to non-preterminal nodes are labeled
+  /// `dep`, and the root is labeled `root`.
+  ///
+  /// Panics if the tree has no leaves.
+  pub fn to_dependencies(&self, rules: &HeadRules) -> DepGraph {
+    let length = self.leaves().len();
+    let mut heads = vec![None; length];
+    let mut labels = vec![String::from("root"); length];
+    convert(self, rules, &mut 0, &mut heads, &mut labels);
+    DepGraph::new(heads, labels)
+      .expect("a tree with leaves has a dependency tree")
+  }
+}
+
+/// Assigns heads to the leaves of `tree`, which begin at position
+/// `*next`, and returns the position of the tree's lexical head, if it
+/// has any leaves.
+fn convert<T>(tree: &Tree<T>, rules: &HeadRules, next: &mut usize,
+              heads: &mut [Option<usize>], labels: &mut [String])
+    -> Option<usize>
+{
+  match *tree {
+    Tree::Leaf(_) => {
+      *next += 1;
+      Some(*next - 1)
+    },
+    Tree::Node(ref label, ref children) => {
+      let lexical: Vec<Option<usize>> = children.iter()
+        .map(|child| convert(child, rules, next, heads, labels))
+        .collect();
+      if children.is_empty() {
+        return None;
+      }
+      let head_child = rules.head_child(label, children);
+      let head = lexical[head_child]
+        .or_else(|| lexical.iter().filter_map(|&h| h).next())?;
+      for (&dependent, child) in lexical.iter().zip(children) {
+        match dependent {
+          Some(dependent) if dependent != head => {
+            heads[dependent] = Some(head);
+            labels[dependent] = match child.label() {
+              Some(label) => base_label(label).to_string(),
+              None => String::from("dep")
+            };
+          },
+          _ => {}
+        }
+      }
+      Some(head)
+    }
+  }
+}
@@ -0,0 +1,146 @@
+//! Inference in hidden Markov models, generic over the model.
+//!
+//! As in the [`viterbi`] module, states and positions are identified by
+//! index and scores are supplied by closures: `transition(previous,
+//! next)`, in which `previous` is `None` at the first position, and
+//! `emission(position, state)`. Here the scores must be log-probabilities,
+//! as the algorithms sum probabilities over paths rather than choosing
+//! the best; any model whose scores are log-probabilities, whether of
+//! tags, alignments or segments, may be built on them.
+//!
+//! [`viterbi`] finds the most probable path, and [`forward_backward`] the
+//! probability of each state at each position, and the expected number
+//! of each transition, as the E-step of training by Baum-Welch needs:
+//!
+//! ```rust
+//! let posteriors = hmm::forward_backward(tags.len(), words.len(),
+//!   |previous, next| transitions.log_probability(previous, next),
+//!   |position, tag| emissions.log_probability(tag, words[position])).unwrap();
+//! for (word, probabilities) in words.iter().zip(&posteriors.states) {
+//!   println!("{}\t{:?}", word, probabilities);
+//! }
+//! ```
+//!
+//! [`viterbi`]: fn.viterbi.html
+//! [`forward_backward`]: fn.forward_backward.html
+
+pub use viterbi::first_order as viterbi;
+
+/// The posterior distributions over the states of a sequence, given all
+/// of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posteriors {
+  /// The log-probability of the sequence, over every path of states.
+  pub log_likelihood: f64,
+  /// The probability of each state at each position: `states[i][s]` for
+  /// state `s` at position `i`.
+  pub states: Vec<Vec<f64>>,
+  /// The expected number of transitions between each pair of states over
+  /// the sequence: `transitions[p][s]` from state `p` to state `s`.
+  pub transitions: Vec<Vec<f64>>,
+}
+
+/// Returns the forward log-probabilities of a sequence of `length`
+/// positions over `states` states: `forward[i][s]` is the log-probability
+/// of the observations up to position `i` with state `s` at `i`. Returns
+/// `None` if there are no states to choose from.
+pub fn forward<T, E>(states: usize, length: usize, transition: T, emission: E)
+    -> Option<Vec<Vec<f64>>>
+  where T: Fn(Option<usize>, usize) -> f64,
+        E: Fn(usize, usize) -> f64
+{
+  if states == 0 {
+    return None;
+  }
+  let mut alpha = vec![vec![f64::NEG_INFINITY; states]; length];
+  if length == 0 {
+    return Some(alpha);
+  }
+  for (s, score) in alpha[0].iter_mut().enumerate() {
+    *score = transition(None, s) + emission(0, s);
+  }
+  for i in 1..length {
+    for s in 0..states {
+      let score = log_sum_exp((0..states).map(|p| alpha[i - 1][p] + transition(Some(p), s)));
+      alpha[i][s] = score + emission(i, s);
+    }
+  }
+  Some(alpha)
+}
+
+/// Returns the backward log-probabilities of a sequence of `length`
+/// positions over `states` states: `backward[i][s]` is the
+/// log-probability of the observations after position `i` given state `s`
+/// at `i`. Returns `None` if there are no states to choose from.
+pub fn backward<T, E>(states: usize, length: usize, transition: T, emission: E)
+    -> Option<Vec<Vec<f64>>>
+  where T: Fn(Option<usize>, usize) -> f64,
+        E: Fn(usize, usize) -> f64
+{
+  if states == 0 {
+    return None;
+  }
+  let mut beta = vec![vec![0.0; states]; length];
+  for i in (0..length.saturating_sub(1)).rev() {
+    for s in 0..states {
+      beta[i][s] = log_sum_exp((0..states)
+        .map(|n| transition(Some(s), n) + emission(i + 1, n) + beta[i + 1][n]));
+    }
+  }
+  Some(beta)
+}
+
+/// Computes the [`Posteriors`] of a sequence of `length` positions over
+/// `states` states by the forward-backward algorithm, in time
+/// proportional to its length and the square of the number of states.
+///
+/// Returns `None` if there are no states to choose from. An empty
+/// sequence has a log-likelihood of zero. A sequence of which every path
+/// has probability zero has a log-likelihood of negative infinity, and
+/// posteriors of zero.
+///
+/// [`Posteriors`]: struct.Posteriors.html
+pub fn forward_backward<T, E>(states: usize, length: usize, transition: T, emission: E)
+    -> Option<Posteriors>
+  where T: Fn(Option<usize>, usize) -> f64,
+        E: Fn(usize, usize) -> f64
+{
+  let alpha = forward(states, length, &transition, &emission)?;
+  let beta = backward(states, length, &transition, &emission)?;
+  let log_likelihood = match alpha.last() {
+    Some(last) => log_sum_exp(last.iter().cloned()),
+    None => 0.0
+  };
+  let probability = |score: f64| {
+    if log_likelihood == f64::NEG_INFINITY { 0.0 } else { (score - log_likelihood).exp() }
+  };
+
+  let posteriors = alpha.iter().zip(&beta)
+    .map(|(a, b)| a.iter().zip(b).map(|(a, b)| probability(a + b)).collect())
+    .collect();
+  let mut transitions = vec![vec![0.0; states]; states];
+  for i in 1..length {
+    for (p, row) in transitions.iter_mut().enumerate() {
+      for (s, expected) in row.iter_mut().enumerate() {
+        *expected += probability(alpha[i - 1][p] + transition(Some(p), s) + emission(i, s) + beta[i][s]);
+      }
+    }
+  }
+
+  Some(Posteriors {
+    log_likelihood: log_likelihood,
+    states: posteriors,
+    transitions: transitions,
+  })
+}
+
+/// Returns the log of the sum of the exponentials of scores, without
+/// overflow or underflow; negative infinity if there are none.
+fn log_sum_exp<I: Iterator<Item=f64>>(scores: I) -> f64 {
+  let scores: Vec<f64> = scores.collect();
+  let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+  if max.is_infinite() {
+    return max;
+  }
+  max + scores.iter().map(|score| (score - max).exp()).sum::<f64>().ln()
+}
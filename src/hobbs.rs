@@ -0,0 +1,338 @@
+use tree::Tree;
+use span::Span;
+use heads::base_label;
+use coref::{Chain, Mention};
+
+use std::iter::once;
+use std::collections::VecDeque;
+
+/// The grammatical number of a noun phrase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Number { Singular, Plural }
+
+/// The grammatical gender of a pronoun.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Gender { Masculine, Feminine, Neuter, Common }
+
+/// Returns the number and gender of a third-person pronoun.
+fn pronoun(word: &str) -> Option<(Number, Gender)> {
+  use self::Number::*;
+  use self::Gender::*;
+  match &word.to_lowercase()[..] {
+    "he" | "him" | "his" | "himself" => Some((Singular, Masculine)),
+    "she" | "her" | "hers" | "herself" => Some((Singular, Feminine)),
+    "it" | "its" | "itself" => Some((Singular, Neuter)),
+    "they" | "them" | "their" | "theirs" | "themselves" => Some((Plural, Common)),
+    _ => None
+  }
+}
+
+/// A node of a tree, flattened for navigation.
+#[derive(Debug)]
+struct Node<'t> {
+  /// The label, without function tags, or `None` for a leaf.
+  label: Option<&'t str>,
+  word: Option<&'t str>,
+  span: Span,
+  parent: Option<usize>,
+  children: Vec<usize>,
+}
+
+/// The nodes of a tree, in pre-order, so that the root is node `0`.
+struct Nodes<'t> {
+  nodes: Vec<Node<'t>>,
+}
+
+impl<'t> Nodes<'t> {
+  fn new<T: AsRef<str>>(tree: &'t Tree<T>) -> Nodes<'t> {
+    fn visit<'t, T: AsRef<str>>(tree: &'t Tree<T>, parent: Option<usize>,
+                                next: &mut usize, nodes: &mut Vec<Node<'t>>) -> usize {
+      let i = nodes.len();
+      nodes.push(Node {
+        label: tree.label().map(base_label),
+        word: match *tree { Tree::Leaf(ref word) => Some(word.as_ref()), _ => None },
+        span: Span::new(*next, *next),
+        parent: parent,
+        children: vec![],
+      });
+      if tree.is_leaf() {
+        *next += 1;
+      }
+      for child in tree.children() {
+        let c = visit(child, Some(i), next, nodes);
+        nodes[i].children.push(c);
+      }
+      nodes[i].span.end = *next;
+      i
+    }
+    let mut nodes = vec![];
+    visit(tree, None, &mut 0, &mut nodes);
+    Nodes { nodes: nodes }
+  }
+
+  fn label(&self, i: usize) -> &str {
+    self.nodes[i].label.unwrap_or("")
+  }
+
+  fn is_np(&self, i: usize) -> bool {
+    self.label(i) == "NP"
+  }
+
+  fn is_s(&self, i: usize) -> bool {
+    let label = self.label(i);
+    label == "S" || label == "SINV" || label == "SQ"
+  }
+
+  /// Returns the number of the noun phrase `i`, if it can be determined:
+  /// coordinated noun phrases are plural, and otherwise the number is
+  /// that of the rightmost noun or pronoun among its children.
+  fn number(&self, i: usize) -> Option<(Number, Option<Gender>)> {
+    let children = &self.nodes[i].children;
+    if children.iter().any(|&c| self.label(c) == "CC") {
+      return Some((Number::Plural, None));
+    }
+    for &c in children.iter().rev() {
+      match self.label(c) {
+        "NNS" | "NNPS" => return Some((Number::Plural, None)),
+        "NN" | "NNP" => return Some((Number::Singular, None)),
+        "PRP" => {
+          let word = self.nodes[c].children.first()
+            .and_then(|&leaf| self.nodes[leaf].word);
+          return word.and_then(pronoun).map(|(n, g)| (n, Some(g)));
+        },
+        "NP" => return self.number(c),
+        _ => {}
+      }
+    }
+    None
+  }
+}
+
+/// A baseline pronoun resolver following the syntactic search of Hobbs
+/// (1978): the antecedent of a third-person pronoun is the first noun
+/// phrase agreeing with it in number (and, for pronouns, gender) that is
+/// met in a left-to-right, breadth-first search of the parse, moving up
+/// the tree from the pronoun, and then through the parses of preceding
+/// sentences, most recent first.
+///
+/// ```rust
+/// let trees = read_trees("
+///   (S (NP (NNP John)) (VP (VBD saw) (NP (NNP Bill))))
+///   (S (NP (PRP He)) (VP (VBD waved)))")?;
+/// let resolutions = HobbsResolver::new().resolve(&trees);
+/// assert_eq!(resolutions[0].1, Mention::new(0, Span::new(0, 1))); // John
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HobbsResolver {
+  lookback: usize,
+}
+
+impl HobbsResolver {
+  /// Creates a resolver that searches the three sentences preceding each
+  /// pronoun.
+  pub fn new() -> HobbsResolver {
+    HobbsResolver { lookback: 3 }
+  }
+
+  /// Sets the number of preceding sentences searched for antecedents.
+  pub fn with_lookback(mut self, sentences: usize) -> HobbsResolver {
+    self.lookback = sentences;
+    self
+  }
+
+  /// Resolves every third-person pronoun in a document, given as the
+  /// parse of each of its lines. Returns the mention of each resolved
+  /// pronoun with the mention of its antecedent noun phrase.
+  pub fn resolve<T: AsRef<str>>(&self, trees: &[Tree<T>]) -> Vec<(Mention, Mention)> {
+    let parses: Vec<Nodes> = trees.iter().map(Nodes::new).collect();
+    let mut resolutions = vec![];
+    for (line, nodes) in parses.iter().enumerate() {
+      for (i, node) in nodes.nodes.iter().enumerate() {
+        let agreement = match node.word.and_then(pronoun) {
+          Some(agreement) => agreement,
+          None => continue
+        };
+        let tag = node.parent.map_or("", |p| nodes.label(p));
+        if tag != "PRP" && tag != "PRP$" {
+          continue;
+        }
+        let pronoun = Mention::new(line, node.span);
+        if let Some(antecedent) = self.search(&parses, line, i, agreement) {
+          resolutions.push((pronoun, antecedent));
+        }
+      }
+    }
+    resolutions
+  }
+
+  /// Resolves the pronouns of a document, and groups them with their
+  /// antecedents into chains. Chains are numbered in order of their
+  /// first mention.
+  pub fn chains<T: AsRef<str>>(&self, trees: &[Tree<T>]) -> Vec<Chain> {
+    let mut chains: Vec<Vec<Mention>> = vec![];
+    for (pronoun, antecedent) in self.resolve(trees) {
+      match chains.iter().position(|chain| chain.contains(&antecedent)) {
+        Some(c) => chains[c].push(pronoun),
+        None => chains.push(vec![antecedent, pronoun]),
+      }
+    }
+    for chain in &mut chains {
+      chain.sort();
+    }
+    chains.sort();
+    chains.into_iter().enumerate()
+      .map(|(id, mentions)| Chain { id: id, mentions: mentions })
+      .collect()
+  }
+
+  /// Searches for the antecedent of the pronoun at leaf `leaf` of line
+  /// `line`.
+  fn search(&self, parses: &[Nodes], line: usize, leaf: usize,
+            agreement: (Number, Gender)) -> Option<Mention> {
+    let nodes = &parses[line];
+    let agrees = |nodes: &Nodes, i: usize| match nodes.number(i) {
+      Some((number, _)) if number != agreement.0 => false,
+      Some((_, Some(gender))) => gender == agreement.1,
+      _ => true
+    };
+    let found = |i: usize| Some(Mention::new(line, nodes.nodes[i].span));
+
+    // 1. Begin at the noun phrase immediately dominating the pronoun.
+    let preterminal = nodes.nodes[leaf].parent?;
+    let start = match nodes.nodes[preterminal].parent {
+      Some(p) if nodes.is_np(p) => p,
+      _ => preterminal
+    };
+
+    // 2–3. Go up to the first NP or S, and search to the left of the path
+    // for noun phrases with an NP or S between them and it.
+    let (mut x, mut path) = match climb(nodes, start) {
+      Some(found) => found,
+      None => return self.search_previous(parses, line, &agrees)
+    };
+    for candidate in breadth_first(nodes, x, path, Side::Left, false) {
+      if nodes.is_np(candidate) && intervening(nodes, candidate, x)
+          && agrees(nodes, candidate) {
+        return found(candidate);
+      }
+    }
+
+    loop {
+      // 4–5. Go up to the next NP or S; at the top of the sentence, search
+      // the previous sentences instead.
+      let (next, next_path) = match climb(nodes, x) {
+        Some(next) => next,
+        None => return self.search_previous(parses, line, &agrees)
+      };
+      x = next;
+      path = next_path;
+
+      // 6. Propose an NP, unless the path enters it through a nominal.
+      let nominal = match path.map(|p| nodes.label(p)) {
+        Some(label) => label.starts_with("NN") || label == "NX" || label == "NML",
+        None => false
+      };
+      if nodes.is_np(x) && !nominal && agrees(nodes, x) {
+        return found(x);
+      }
+
+      // 7. Search to the left of the path.
+      for candidate in breadth_first(nodes, x, path, Side::Left, false) {
+        if nodes.is_np(candidate) && agrees(nodes, candidate) {
+          return found(candidate);
+        }
+      }
+
+      // 8. At an S, search to the right of the path, without entering
+      // any NP or S.
+      if nodes.is_s(x) {
+        for candidate in breadth_first(nodes, x, path, Side::Right, true) {
+          if nodes.is_np(candidate) && agrees(nodes, candidate) {
+            return found(candidate);
+          }
+        }
+      }
+    }
+  }
+
+  /// Searches the sentences preceding line `line`, most recent first, for
+  /// the leftmost, shallowest agreeing noun phrase.
+  fn search_previous<F>(&self, parses: &[Nodes], line: usize, agrees: &F)
+      -> Option<Mention>
+    where F: Fn(&Nodes, usize) -> bool
+  {
+    for previous in (line.saturating_sub(self.lookback)..line).rev() {
+      let earlier = &parses[previous];
+      let root = once(0);
+      for candidate in root.chain(breadth_first(earlier, 0, None, Side::All, false)) {
+        if earlier.is_np(candidate) && agrees(earlier, candidate) {
+          return Some(Mention::new(previous, earlier.nodes[candidate].span));
+        }
+      }
+    }
+    None
+  }
+}
+
+impl Default for HobbsResolver {
+  fn default() -> HobbsResolver {
+    HobbsResolver::new()
+  }
+}
+
+/// Climbs from node `i` to the nearest NP or S above it, returning that
+/// node and its child on the path from `i`.
+fn climb(nodes: &Nodes, mut i: usize) -> Option<(usize, Option<usize>)> {
+  loop {
+    let parent = nodes.nodes[i].parent?;
+    if nodes.is_np(parent) || nodes.is_s(parent) {
+      return Some((parent, Some(i)));
+    }
+    i = parent;
+  }
+}
+
+/// Returns `true` if an NP or S lies strictly between node `i` and its
+/// ancestor `top`.
+fn intervening(nodes: &Nodes, i: usize, top: usize) -> bool {
+  let mut current = nodes.nodes[i].parent;
+  while let Some(c) = current {
+    if c == top {
+      return false;
+    }
+    if nodes.is_np(c) || nodes.is_s(c) {
+      return true;
+    }
+    current = nodes.nodes[c].parent;
+  }
+  false
+}
+
+/// Which children of the starting node a search covers, relative to the
+/// child on the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side { Left, Right, All }
+
+/// Produces the nodes below `top` in breadth-first, left-to-right order,
+/// beginning with the children of `top` on the given side of `path`.
+/// If `stop` is set, the search does not descend below NP or S nodes.
+fn breadth_first<'n>(nodes: &'n Nodes, top: usize, path: Option<usize>, side: Side,
+                     stop: bool) -> impl 'n + Iterator<Item=usize> {
+  let children = &nodes.nodes[top].children;
+  let position = path.and_then(|p| children.iter().position(|&c| c == p));
+  let mut queue: VecDeque<usize> = children.iter().enumerate()
+    .filter(|&(i, _)| match (side, position) {
+      (Side::Left, Some(p)) => i < p,
+      (Side::Right, Some(p)) => i > p,
+      _ => true
+    })
+    .map(|(_, &c)| c)
+    .collect();
+  ::std::iter::from_fn(move || {
+    let i = queue.pop_front()?;
+    if !(stop && (nodes.is_np(i) || nodes.is_s(i))) {
+      queue.extend(nodes.nodes[i].children.iter().cloned());
+    }
+    Some(i)
+  })
+}
@@ -0,0 +1,159 @@
+use token::Token;
+use language::{Language, DefaultLanguage};
+use corpus::Document;
+use vocabulary::EncodedDocument;
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// The dense integer id of a token in an [`Interner`]. Ids compare,
+/// hash and copy as integers, so count tables and models keyed by ids
+/// never hash the characters of words.
+///
+/// [`Interner`]: struct.Interner.html
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TokenId(pub u32);
+
+impl TokenId {
+  /// The id of `Token::Null` in every interner.
+  pub const NULL: TokenId = TokenId(0);
+
+  /// The id of `Token::Unknown` in every interner.
+  pub const UNKNOWN: TokenId = TokenId(1);
+
+  /// Returns the id as an index, such as into a table of counts.
+  pub fn index(self) -> usize {
+    self.0 as usize
+  }
+}
+
+/// A mapping of each distinct word in language `L` to a dense
+/// [`TokenId`], for counting-heavy workloads. Ids [`TokenId::NULL`] and
+/// [`TokenId::UNKNOWN`] are always `Token::Null` and `Token::Unknown`;
+/// words are numbered from `2` in the order they were first interned.
+///
+/// The interner owns the characters of its words, so ids remain valid
+/// after the documents they were interned from are dropped, and the ids
+/// of several documents interned by one interner agree.
+///
+/// ```rust
+/// let mut interner: Interner<English> = Interner::new();
+/// let training = training.intern(&mut interner);
+/// let counts = training.counts();
+/// let the = interner.get(&Token::from(&b"the"[..])).unwrap();
+/// println!("'the' occurs {} times", counts[the.index()]);
+/// ```
+///
+/// [`TokenId`]: struct.TokenId.html
+/// [`TokenId::NULL`]: struct.TokenId.html#associatedconstant.NULL
+/// [`TokenId::UNKNOWN`]: struct.TokenId.html#associatedconstant.UNKNOWN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interner<L: Language=DefaultLanguage> {
+  words: Vec<Box<str>>,
+  ids: HashMap<Box<str>, TokenId>,
+  language: PhantomData<L>,
+}
+
+impl<L: Language> Interner<L> {
+  /// Creates an interner of only `Token::Null` and `Token::Unknown`.
+  pub fn new() -> Interner<L> {
+    Interner { words: vec![], ids: HashMap::new(), language: PhantomData }
+  }
+
+  /// Returns the id of a token, interning it if it is new.
+  ///
+  /// # Panics
+  ///
+  /// Panics if more than `u32::MAX` distinct tokens are interned.
+  pub fn intern(&mut self, token: &Token<L>) -> TokenId {
+    let word = match *token {
+      Token::Word(ref word) => word.as_str(),
+      Token::Null => return TokenId::NULL,
+      Token::Unknown => return TokenId::UNKNOWN,
+    };
+    if let Some(&id) = self.ids.get(word) {
+      return id;
+    }
+    let index = self.words.len() as u64 + 2;
+    assert!(index <= u32::MAX as u64, "interned more than u32::MAX tokens");
+    let id = TokenId(index as u32);
+    self.words.push(word.into());
+    self.ids.insert(word.into(), id);
+    id
+  }
+
+  /// Returns the id of a token, or `None` if it has not been interned.
+  pub fn get(&self, token: &Token<L>) -> Option<TokenId> {
+    match *token {
+      Token::Word(ref word) => self.ids.get(word.as_str()).cloned(),
+      Token::Null => Some(TokenId::NULL),
+      Token::Unknown => Some(TokenId::UNKNOWN),
+    }
+  }
+
+  /// Returns the token of an id, borrowing its characters from the
+  /// interner, or `None` if no token has the id.
+  pub fn resolve<'i>(&'i self, id: TokenId) -> Option<Token<'i, L>> {
+    match id {
+      TokenId::NULL => Some(Token::Null),
+      TokenId::UNKNOWN => Some(Token::Unknown),
+      TokenId(id) => self.words.get(id as usize - 2).map(|word| Token::from(word.as_bytes()))
+    }
+  }
+
+  /// Returns the tokens of a sequence of ids, resolving ids of no token
+  /// to `Token::Unknown`.
+  pub fn decode<'i>(&'i self, ids: &[TokenId]) -> Vec<Token<'i, L>> {
+    ids.iter().map(|&id| self.resolve(id).unwrap_or(Token::Unknown)).collect()
+  }
+
+  /// Returns the number of ids, including those of `Token::Null` and
+  /// `Token::Unknown`.
+  pub fn len(&self) -> usize {
+    self.words.len() + 2
+  }
+
+  /// Returns `true` if no words have been interned.
+  pub fn is_empty(&self) -> bool {
+    self.words.is_empty()
+  }
+}
+
+impl<L: Language> Default for Interner<L> {
+  fn default() -> Interner<L> {
+    Interner::new()
+  }
+}
+
+impl<L: Language> Document<L> {
+  /// Produces the lines of the document as ids of an [`Interner`],
+  /// interning every word that is new to it.
+  ///
+  /// [`Interner`]: struct.Interner.html
+  pub fn intern(&self, interner: &mut Interner<L>) -> IdDocument {
+    EncodedDocument::from_lines(self.lines().iter()
+      .map(|line| line.iter().map(|token| interner.intern(&token)).collect::<Vec<_>>()))
+  }
+}
+
+/// Lines of tokens as ids of an [`Interner`], produced by
+/// [`Document::intern`].
+///
+/// [`Interner`]: struct.Interner.html
+/// [`Document::intern`]: struct.Document.html#method.intern
+pub type IdDocument = EncodedDocument<TokenId>;
+
+impl EncodedDocument<TokenId> {
+  /// Returns the number of occurrences of each id in the document,
+  /// indexed by [`TokenId::index`], up to the greatest id occurring.
+  ///
+  /// [`TokenId::index`]: struct.TokenId.html#method.index
+  pub fn counts(&self) -> Vec<usize> {
+    let mut counts = vec![0; self.ids().iter().max().map_or(0, |id| id.index() + 1)];
+    for id in self.ids() {
+      counts[id.index()] += 1;
+    }
+    counts
+  }
+}
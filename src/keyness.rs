@@ -0,0 +1,209 @@
+use token::Token;
+use language::Language;
+use diachronic::chi_square_p_value;
+
+use std::io;
+use std::collections::HashMap;
+
+/// A word whose frequency in the focus corpus of a [`KeynessReport`]
+/// differs significantly from its frequency in the reference corpus.
+///
+/// [`KeynessReport`]: struct.KeynessReport.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword<'t, L> {
+  pub token: Token<'t, L>,
+  /// The number of occurrences of the word in the focus corpus.
+  pub focus_count: usize,
+  /// The number of occurrences of the word in the reference corpus.
+  pub reference_count: usize,
+  /// Dunning's log-likelihood statistic `G²` of the difference between
+  /// the word's frequencies in the two corpora.
+  pub log_likelihood: f64,
+  /// The probability of a statistic at least as large if the word were
+  /// equally frequent in both corpora, by the chi-square distribution of
+  /// one degree of freedom.
+  pub p_value: f64,
+  /// Hardie's log ratio: the binary logarithm of the word's relative
+  /// frequency in the focus corpus divided by its relative frequency in
+  /// the reference corpus, with a count of `0` taken as `0.5`. Each unit
+  /// is a doubling, so `1` means twice as frequent in the focus corpus
+  /// and `-1` half as frequent, however large the corpora.
+  pub log_ratio: f64,
+}
+
+impl<'t, L> Keyword<'t, L> {
+  /// Returns `true` if the word is more frequent in the focus corpus than
+  /// in the reference corpus, and `false` if it is less frequent.
+  pub fn is_positive(&self) -> bool {
+    self.log_ratio > 0.0
+  }
+}
+
+/// The words significantly over- and under-represented in a focus
+/// corpus compared to a reference corpus, ranked by log-likelihood: the
+/// words that most distinguish the focus corpus first, whichever corpus
+/// they are more frequent in. The effect size of each, its
+/// [`log_ratio`], tells how large the difference is, as the
+/// log-likelihood grows with the size of the corpora as well.
+///
+/// Words of equal log-likelihood are ordered in byte order. `Null`
+/// tokens are not counted.
+///
+/// ```rust
+/// let report = KeynessReport::compare(speeches.tokens(), news.tokens(), 0.001);
+/// for keyword in report.keywords().iter().filter(|keyword| keyword.is_positive()).take(20) {
+///   println!("{}\tG2 = {:.1}\tlog ratio = {:.2}", keyword.token, keyword.log_likelihood,
+///            keyword.log_ratio);
+/// }
+/// report.write_tsv(&mut File::create("keywords.tsv")?)?;
+/// ```
+///
+/// [`log_ratio`]: struct.Keyword.html#structfield.log_ratio
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeynessReport<'t, L> {
+  keywords: Vec<Keyword<'t, L>>,
+  focus_tokens: usize,
+  reference_tokens: usize,
+}
+
+impl<'t, L: Language> KeynessReport<'t, L> {
+  /// Compares the tokens of a focus corpus with those of a reference
+  /// corpus, keeping the words whose difference in frequency has a
+  /// p-value of at most `significance`, such as `0.05` or `0.001`; a
+  /// significance of `1` keeps every word. Nothing is kept if either
+  /// corpus is empty.
+  pub fn compare<F, R>(focus: F, reference: R, significance: f64) -> KeynessReport<'t, L>
+    where F: IntoIterator<Item=Token<'t, L>>,
+          R: IntoIterator<Item=Token<'t, L>>
+  {
+    let mut counts: HashMap<Token<'t, L>, (usize, usize)> = HashMap::new();
+    let (mut focus_tokens, mut reference_tokens) = (0, 0);
+    for token in focus {
+      if token != Token::Null {
+        counts.entry(token).or_insert((0, 0)).0 += 1;
+        focus_tokens += 1;
+      }
+    }
+    for token in reference {
+      if token != Token::Null {
+        counts.entry(token).or_insert((0, 0)).1 += 1;
+        reference_tokens += 1;
+      }
+    }
+
+    let mut keywords = vec![];
+    if focus_tokens > 0 && reference_tokens > 0 {
+      let (c, d) = (focus_tokens as f64, reference_tokens as f64);
+      for (token, (focus_count, reference_count)) in counts {
+        let (a, b) = (focus_count as f64, reference_count as f64);
+        let expected_focus = c * (a + b) / (c + d);
+        let expected_reference = d * (a + b) / (c + d);
+        let term = |observed: f64, expected: f64| {
+          if observed > 0.0 { observed * (observed / expected).ln() } else { 0.0 }
+        };
+        let log_likelihood = 2.0 * (term(a, expected_focus) + term(b, expected_reference));
+        let p_value = chi_square_p_value(log_likelihood, 1);
+        if p_value > significance {
+          continue;
+        }
+        keywords.push(Keyword {
+          token: token,
+          focus_count: focus_count,
+          reference_count: reference_count,
+          log_likelihood: log_likelihood,
+          p_value: p_value,
+          log_ratio: ((a.max(0.5) / c) / (b.max(0.5) / d)).log2(),
+        });
+      }
+    }
+    keywords.sort_by(|a, b| {
+      b.log_likelihood.partial_cmp(&a.log_likelihood)
+        .unwrap_or(::std::cmp::Ordering::Equal)
+        .then(a.token.cmp(&b.token))
+    });
+
+    KeynessReport {
+      keywords: keywords,
+      focus_tokens: focus_tokens,
+      reference_tokens: reference_tokens,
+    }
+  }
+
+  /// Returns the significant words, in descending order of
+  /// log-likelihood.
+  pub fn keywords(&self) -> &[Keyword<'t, L>] {
+    &self.keywords
+  }
+
+  /// Returns the number of tokens of the focus corpus.
+  pub fn focus_tokens(&self) -> usize {
+    self.focus_tokens
+  }
+
+  /// Returns the number of tokens of the reference corpus.
+  pub fn reference_tokens(&self) -> usize {
+    self.reference_tokens
+  }
+
+  /// Returns the number of significant words.
+  pub fn len(&self) -> usize {
+    self.keywords.len()
+  }
+
+  /// Returns `true` if no word is significant.
+  pub fn is_empty(&self) -> bool {
+    self.keywords.is_empty()
+  }
+
+  /// Writes the report as tab-separated values, with a header row of
+  /// `word`, `focus_count`, `reference_count`, `log_likelihood`,
+  /// `p_value` and `log_ratio`, followed by a row per word.
+  pub fn write_tsv<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "word\tfocus_count\treference_count\tlog_likelihood\tp_value\tlog_ratio")?;
+    for keyword in &self.keywords {
+      writeln!(writer, "{}\t{}\t{}\t{:.4}\t{:e}\t{:.4}",
+               keyword.token, keyword.focus_count, keyword.reference_count,
+               keyword.log_likelihood, keyword.p_value, keyword.log_ratio)?;
+    }
+    Ok(())
+  }
+
+  /// Writes the report as a JSON object, with the sizes of the corpora as
+  /// `focus_tokens` and `reference_tokens`, and the words as an array
+  /// `keywords` of objects with a field for each column of
+  /// [`write_tsv`].
+  ///
+  /// [`write_tsv`]: #method.write_tsv
+  pub fn to_json(&self) -> String {
+    let keywords: Vec<String> = self.keywords.iter()
+      .map(|keyword| {
+        format!("{{\"word\":{},\"focus_count\":{},\"reference_count\":{},\
+                 \"log_likelihood\":{},\"p_value\":{},\"log_ratio\":{}}}",
+                json_string(&keyword.token.to_string()), keyword.focus_count,
+                keyword.reference_count, keyword.log_likelihood, keyword.p_value,
+                keyword.log_ratio)
+      })
+      .collect();
+    format!("{{\"focus_tokens\":{},\"reference_tokens\":{},\"keywords\":[{}]}}",
+            self.focus_tokens, self.reference_tokens, keywords.join(","))
+  }
+}
+
+/// Quotes text as a JSON string.
+fn json_string(text: &str) -> String {
+  let mut quoted = String::with_capacity(text.len() + 2);
+  quoted.push('"');
+  for c in text.chars() {
+    match c {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\t' => quoted.push_str("\\t"),
+      '\r' => quoted.push_str("\\r"),
+      c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+      c => quoted.push(c)
+    }
+  }
+  quoted.push('"');
+  quoted
+}
@@ -0,0 +1,238 @@
+//! Language identification by character n-grams, and the detection of
+//! code-switching within lines.
+//!
+//! A [`LanguageIdentifier`] is trained on sample text of each language,
+//! and scores text by the character n-grams of its words. A
+//! [`CodeSwitchDetector`] uses it to divide a line into spans of one
+//! language each, as in social-media text that alternates between
+//! languages mid-sentence:
+//!
+//! ```rust
+//! let mut identifier = LanguageIdentifier::new(3);
+//! identifier.train("en", &fs::read_to_string("english.txt")?);
+//! identifier.train("es", &fs::read_to_string("spanish.txt")?);
+//!
+//! let document: Document = "I told him que no quería ir to the party".into();
+//! for annotation in CodeSwitchDetector::new(&identifier).detect(&document.tokens().to_vec()) {
+//!   println!("{}\t{}", annotation.span, annotation.label);
+//! }
+//! // 0..3  en
+//! // 3..7  es
+//! // 7..10 en
+//! ```
+//!
+//! [`LanguageIdentifier`]: struct.LanguageIdentifier.html
+//! [`CodeSwitchDetector`]: struct.CodeSwitchDetector.html
+use token::Token;
+use span::Span;
+use language::Language;
+use corpus::AsTokens;
+use subword::char_ngrams;
+use gazetteer::Annotation;
+use viterbi;
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+/// A naive Bayes language identifier over the character n-grams of
+/// words. Words are lowercased and padded with a space at each end, so
+/// that n-grams at the edges of words are distinguished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageIdentifier {
+  /// The length of the longest n-grams counted.
+  n: usize,
+  labels: Vec<String>,
+  /// The count of each n-gram in the samples of each language.
+  profiles: Vec<HashMap<String, usize>>,
+  /// The total count of n-grams in the samples of each language.
+  totals: Vec<usize>,
+  /// Every n-gram observed in any language.
+  grams: HashSet<String>,
+}
+
+impl LanguageIdentifier {
+  /// Creates an identifier of no languages, counting the n-grams of one
+  /// up to `n` characters. Values of `n` below `1` are treated as `1`.
+  pub fn new(n: usize) -> LanguageIdentifier {
+    LanguageIdentifier {
+      n: n.max(1),
+      labels: vec![],
+      profiles: vec![],
+      totals: vec![],
+      grams: HashSet::new(),
+    }
+  }
+
+  /// Counts the n-grams of a sample of text in the language labeled
+  /// `label`, adding the language if it is new.
+  pub fn train(&mut self, label: &str, text: &str) {
+    let l = match self.labels.iter().position(|known| known == label) {
+      Some(l) => l,
+      None => {
+        self.labels.push(label.to_string());
+        self.profiles.push(HashMap::new());
+        self.totals.push(0);
+        self.labels.len() - 1
+      }
+    };
+    for word in text.split_whitespace() {
+      for gram in self.grams(word) {
+        *self.profiles[l].entry(gram.clone()).or_insert(0) += 1;
+        self.totals[l] += 1;
+        self.grams.insert(gram);
+      }
+    }
+  }
+
+  /// Returns the labels of the languages, in the order they were added.
+  pub fn labels(&self) -> &[String] {
+    &self.labels
+  }
+
+  /// Returns the log-probability of the n-grams of a text in each
+  /// language, in the order of [`labels`], with add-one smoothing.
+  ///
+  /// [`labels`]: #method.labels
+  pub fn log_probabilities(&self, text: &str) -> Vec<f64> {
+    let mut scores = vec![0.0; self.labels.len()];
+    for word in text.split_whitespace() {
+      for (score, word) in scores.iter_mut().zip(self.word_scores(word)) {
+        *score += word;
+      }
+    }
+    scores
+  }
+
+  /// Returns the label of the most probable language of a text, or
+  /// `None` if the identifier has no languages or the text has no
+  /// letters.
+  pub fn identify(&self, text: &str) -> Option<&str> {
+    if !text.chars().any(char::is_alphabetic) {
+      return None;
+    }
+    let mut best: Option<(f64, &str)> = None;
+    for (score, label) in self.log_probabilities(text).into_iter().zip(&self.labels) {
+      match best {
+        Some((best, _)) if best >= score => {},
+        _ => best = Some((score, label))
+      }
+    }
+    best.map(|(_, label)| label)
+  }
+
+  /// Returns the log-probability of the n-grams of one word in each
+  /// language.
+  fn word_scores(&self, word: &str) -> Vec<f64> {
+    let grams = self.grams(word);
+    let types = self.grams.len() as f64 + 1.0;
+    self.profiles.iter().zip(&self.totals)
+      .map(|(profile, &total)| {
+        grams.iter()
+          .map(|gram| {
+            let count = profile.get(gram).cloned().unwrap_or(0) as f64;
+            ((count + 1.0) / (total as f64 + types)).ln()
+          })
+          .sum()
+      })
+      .collect()
+  }
+
+  /// Returns the n-grams of a word, lowercased and padded with spaces,
+  /// omitting those of only padding.
+  fn grams(&self, word: &str) -> Vec<String> {
+    let word: String = word.chars().flat_map(char::to_lowercase).collect();
+    (1..=self.n)
+      .flat_map(|n| char_ngrams(&word, n).with_boundaries(' ', ' ').map(Cow::into_owned).collect::<Vec<_>>())
+      .collect()
+  }
+}
+
+/// A detector of code-switching: the alternation between languages
+/// within a line. Every word is scored in every language of a
+/// [`LanguageIdentifier`], along with the words around it, and the line
+/// is divided into spans of one language each by Viterbi decoding, with
+/// a penalty for each switch between languages. Tokens without letters,
+/// such as punctuation and numbers, belong to no language, and join the
+/// span around them.
+///
+/// [`LanguageIdentifier`]: struct.LanguageIdentifier.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodeSwitchDetector<'i> {
+  identifier: &'i LanguageIdentifier,
+  switch_penalty: f64,
+  window: usize,
+}
+
+impl<'i> CodeSwitchDetector<'i> {
+  /// Creates a detector of the languages of `identifier`, with a penalty
+  /// of `5.0` (in natural log units) per switch, scoring each word with
+  /// one word on either side.
+  pub fn new(identifier: &'i LanguageIdentifier) -> CodeSwitchDetector<'i> {
+    CodeSwitchDetector { identifier: identifier, switch_penalty: 5.0, window: 1 }
+  }
+
+  /// Sets the penalty for switching languages between words. Higher
+  /// penalties yield fewer, longer spans.
+  pub fn with_switch_penalty(mut self, penalty: f64) -> CodeSwitchDetector<'i> {
+    self.switch_penalty = penalty;
+    self
+  }
+
+  /// Sets the number of words on either side of each word that are
+  /// scored with it.
+  pub fn with_window(mut self, window: usize) -> CodeSwitchDetector<'i> {
+    self.window = window;
+    self
+  }
+
+  /// Divides a line into spans labeled with their language, in order.
+  /// Tokens without letters at the start or end of the line join the
+  /// nearest span; a line with no letters has no spans.
+  pub fn detect<L: Language>(&self, line: &[Token<L>]) -> Vec<Annotation> {
+    let labels = self.identifier.labels();
+    let scores: Vec<Option<Vec<f64>>> = line.iter()
+      .map(|token| match *token {
+        Token::Word(ref word) if word.chars().any(char::is_alphabetic) =>
+          Some(self.identifier.word_scores(word.as_str())),
+        _ => None
+      })
+      .collect();
+    if scores.iter().all(Option::is_none) {
+      return vec![];
+    }
+    let emission = |position: usize, label: usize| {
+      let start = position.saturating_sub(self.window);
+      let end = (position + self.window + 1).min(scores.len());
+      scores[start..end].iter()
+        .filter_map(|scores| scores.as_ref().map(|scores| scores[label]))
+        .sum()
+    };
+    let transition = |previous: Option<usize>, next: usize| match previous {
+      Some(previous) if previous != next => -self.switch_penalty,
+      _ => 0.0
+    };
+    let path = match viterbi::first_order(labels.len(), line.len(), transition, emission) {
+      Some((path, _)) => path,
+      None => return vec![]
+    };
+
+    let mut annotations: Vec<Annotation> = vec![];
+    for (i, &label) in path.iter().enumerate() {
+      match annotations.last_mut() {
+        Some(last) if last.label == labels[label] => last.span.end = i + 1,
+        _ => annotations.push(Annotation { label: labels[label].clone(), span: Span::new(i, i + 1) })
+      }
+    }
+    annotations
+  }
+
+  /// Divides every line of a document into spans labeled with their
+  /// language.
+  pub fn detect_all<'t, I, L>(&self, lines: I) -> Vec<Vec<Annotation>>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>,
+          L: 't + Language
+  {
+    lines.into_iter().map(|line| self.detect(&line.as_tokens())).collect()
+  }
+}
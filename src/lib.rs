@@ -3,7 +3,7 @@
 //! Linguistics_](http://cs.brown.edu/courses/csci1460/). This toolkit
 //! provides only corpora management utilities.
 //!
-//! This toolkit requires the rust nightly compiler.
+//! This toolkit builds on stable Rust.
 //!
 //! # Using this Library
 //! Add the following to your project's `Cargo.toml` file:
@@ -21,6 +21,22 @@
 //! use nlptk::{Document, Language, Token}
 //! ```
 //!
+//! To cache tokenized documents, vocabularies and n-gram models on disk,
+//! enable the `serde` feature, which implements `Serialize` and
+//! `Deserialize` for them:
+//!
+//! ```
+//! [dependencies.nlptk]
+//! git = "https://gitlab.com/jswrenn/nlptk"
+//! version = "0.1.0"
+//! features = ["serde"]
+//! ```
+//!
+//! To load corpora from asynchronous readers, such as the bodies of HTTP
+//! responses or objects in cloud storage, without blocking a thread,
+//! enable the `async` feature, which adds futures and streams over the
+//! `AsyncRead` and `AsyncBufRead` traits of the `futures` crate.
+//!
 //! # Language Annotations for Corpora and Tokens
 //! This toolkit uses [phantom types][spooky] to tag corpora and tokens with
 //! what language they are in. It is easy to accidentally reverse the order
@@ -73,12 +89,23 @@
 //! cargo run --example unigram -- examples/data/penn-tree-bank-train.txt | more
 //! ```
 //!
+//! To sample from an n-gram model of a higher order, give the order after
+//! the path; `3` samples from a trigram model.
+//!
 //! [unigram]: https://gitlab.com/jswrenn/nlptk/blob/master/examples/unigram.rs
 
-#![feature(conservative_impl_trait)]
-#![feature(try_from)]
 #![allow(non_snake_case)]
 extern crate itertools;
+extern crate rand;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "async")]
+extern crate futures_core;
+#[cfg(feature = "async")]
+extern crate futures_io;
 
 #[macro_use]
 mod language;
@@ -89,3 +116,188 @@ pub use token::*;
 
 mod corpus;
 pub use corpus::*;
+
+mod error;
+pub use error::*;
+
+mod stream;
+pub use stream::*;
+
+#[cfg(feature = "async")]
+mod nonblocking;
+#[cfg(feature = "async")]
+pub use nonblocking::*;
+
+mod process;
+pub use process::*;
+
+mod serving;
+pub use serving::*;
+
+mod parallel;
+pub use parallel::*;
+
+mod noise;
+pub use noise::*;
+
+mod subword;
+pub use subword::*;
+
+mod boilerplate;
+pub use boilerplate::*;
+
+mod gutenberg;
+pub use gutenberg::*;
+
+mod wikipedia;
+pub use wikipedia::*;
+
+mod overlap;
+pub use overlap::*;
+
+mod compound;
+pub use compound::*;
+
+mod mwe;
+pub use mwe::*;
+
+mod gazetteer;
+pub use gazetteer::*;
+
+mod cascade;
+pub use cascade::*;
+
+mod terms;
+pub use terms::*;
+
+mod readability;
+pub use readability::*;
+
+mod vocabulary;
+pub use vocabulary::*;
+
+mod termdoc;
+pub use termdoc::*;
+
+mod collection;
+pub use collection::*;
+
+mod tfidf;
+pub use tfidf::*;
+
+mod intern;
+pub use intern::*;
+
+mod frequency;
+pub use frequency::*;
+
+mod dispersion;
+pub use dispersion::*;
+
+mod keyness;
+pub use keyness::*;
+
+mod sampling;
+pub use sampling::*;
+
+mod matching;
+pub use matching::*;
+
+mod cloze;
+pub use cloze::*;
+
+mod tree;
+pub use tree::*;
+
+mod dependency;
+pub use dependency::*;
+
+mod heads;
+pub use heads::*;
+
+mod grammar;
+pub use grammar::*;
+
+mod forest;
+pub use forest::*;
+
+mod earley;
+pub use earley::*;
+
+mod shift_reduce;
+pub use shift_reduce::*;
+
+mod ccg;
+pub use ccg::*;
+
+mod span;
+pub use span::*;
+
+mod conll;
+
+mod analyzed;
+pub use analyzed::*;
+
+mod dialogue;
+pub use dialogue::*;
+
+mod srl;
+pub use srl::*;
+
+mod coref;
+pub use coref::*;
+
+mod hobbs;
+pub use hobbs::*;
+
+mod quotes;
+pub use quotes::*;
+
+mod poetry;
+pub use poetry::*;
+
+pub mod smoothing;
+
+pub mod good_turing;
+
+pub mod viterbi;
+
+pub mod hmm;
+
+pub mod segment;
+
+pub mod perceptron;
+
+pub mod tag;
+
+pub mod classify;
+
+pub mod active;
+
+pub mod ngram;
+
+pub mod rerank;
+
+pub mod confusion;
+
+pub mod eval;
+
+pub mod morphology;
+
+pub mod realize;
+
+pub mod translation;
+
+pub mod langid;
+
+pub mod data;
+
+pub mod diachronic;
+
+pub mod collocations;
+
+pub mod lsa;
+
+pub mod cocluster;
+
+pub mod similarity;
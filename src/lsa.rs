@@ -0,0 +1,298 @@
+//! Latent semantic analysis: vectors of documents and terms in a space
+//! of few dimensions, from the truncated singular value decomposition of
+//! a term-document matrix (Deerwester et al., 1990).
+//!
+//! The decomposition `A ≈ U Σ Vᵀ` keeps the `k` largest singular values
+//! of the weighted [`TermDocumentMatrix`] `A`. Documents are represented
+//! by the rows of `V Σ` and terms by the rows of `U Σ`, so that documents
+//! or terms that share contexts lie close together even when they share
+//! no terms. A new document is folded in as its column's projection
+//! `qᵀ U`, which for a document of the matrix is its own row of `V Σ`:
+//!
+//! ```rust
+//! let matrix = TermDocumentMatrix::new(documents.iter().map(|document| document.tokens()))
+//!   .with_weighting(Weighting::TfIdf);
+//! let lsa = Lsa::new(&matrix, 100);
+//!
+//! let query: Document<English> = "latent semantic indexing".into();
+//! let vector = lsa.fold_in(&matrix.vector(&query.tokens()));
+//! for (d, similarity) in lsa.most_similar(&vector, 10) {
+//!   println!("{}\t{:.3}", d, similarity);
+//! }
+//! ```
+//!
+//! [`TermDocumentMatrix`]: ../struct.TermDocumentMatrix.html
+use language::Language;
+use termdoc::TermDocumentMatrix;
+
+/// The largest number of rounds of subspace iteration.
+const MAX_ITERATIONS: usize = 300;
+
+/// The change of the subspace below which iteration stops.
+const TOLERANCE: f64 = 1e-10;
+
+/// The vectors of the documents and terms of a term-document matrix in
+/// the space of its largest singular values.
+///
+/// The decomposition is computed by subspace iteration on `AᵀA`, from a
+/// fixed starting subspace, so that it is the same on every run,
+/// followed by a Rayleigh-Ritz projection. Only products of the sparse
+/// matrix with dense vectors are computed, so its cost grows with the
+/// number of nonzero entries rather than the size of the matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lsa {
+  singular_values: Vec<f64>,
+  /// The rows of `U Σ`, one for each term.
+  terms: Vec<Vec<f64>>,
+  /// The rows of `V Σ`, one for each document.
+  documents: Vec<Vec<f64>>,
+}
+
+impl Lsa {
+  /// Decomposes a matrix, weighted as it is, keeping `dimensions`
+  /// singular values, or as many as it has terms or documents if fewer.
+  pub fn new<'t, L: Language>(matrix: &TermDocumentMatrix<'t, L>, dimensions: usize) -> Lsa {
+    let columns: Vec<Vec<(usize, f64)>> = (0..matrix.documents()).map(|d| matrix.column(d)).collect();
+    let Svd { singular_values, left, right } = svd(&columns, matrix.terms(), dimensions);
+    let scale = |vectors: Vec<Vec<f64>>| -> Vec<Vec<f64>> {
+      vectors.into_iter()
+        .map(|vector| vector.iter().zip(&singular_values).map(|(x, sigma)| x * sigma).collect())
+        .collect()
+    };
+    Lsa {
+      terms: scale(left),
+      documents: scale(right),
+      singular_values: singular_values,
+    }
+  }
+
+  /// Returns the number of dimensions of the space.
+  pub fn dimensions(&self) -> usize {
+    self.singular_values.len()
+  }
+
+  /// Returns the singular values kept, from largest to smallest.
+  pub fn singular_values(&self) -> &[f64] {
+    &self.singular_values
+  }
+
+  /// Returns the vector of a document of the matrix, by its column.
+  pub fn document(&self, document: usize) -> Option<&[f64]> {
+    self.documents.get(document).map(|vector| &vector[..])
+  }
+
+  /// Returns the vector of a term of the matrix, by its row.
+  pub fn term(&self, term: usize) -> Option<&[f64]> {
+    self.terms.get(term).map(|vector| &vector[..])
+  }
+
+  /// Projects a column of weighted terms, such as a new document weighed
+  /// by [`TermDocumentMatrix::vector`], into the space of the documents.
+  /// Terms outside the matrix are ignored.
+  ///
+  /// [`TermDocumentMatrix::vector`]: ../struct.TermDocumentMatrix.html#method.vector
+  pub fn fold_in(&self, column: &[(usize, f64)]) -> Vec<f64> {
+    let mut vector = vec![0.0; self.dimensions()];
+    for &(t, weight) in column {
+      if let Some(term) = self.terms.get(t) {
+        for ((x, &y), &sigma) in vector.iter_mut().zip(term).zip(&self.singular_values) {
+          if sigma > 0.0 {
+            *x += weight * y / sigma;
+          }
+        }
+      }
+    }
+    vector
+  }
+
+  /// Returns the `n` documents most similar to a vector by cosine
+  /// similarity, with their similarities, most similar first.
+  pub fn most_similar(&self, vector: &[f64], n: usize) -> Vec<(usize, f64)> {
+    let mut similarities: Vec<(usize, f64)> = self.documents.iter()
+      .map(|document| cosine(vector, document))
+      .enumerate()
+      .collect();
+    similarities.sort_by(|a, b| {
+      b.1.partial_cmp(&a.1).unwrap_or(::std::cmp::Ordering::Equal).then(a.0.cmp(&b.0))
+    });
+    similarities.truncate(n);
+    similarities
+  }
+}
+
+/// A truncated singular value decomposition `A ≈ U Σ Vᵀ`.
+pub(crate) struct Svd {
+  /// The singular values, from largest to smallest.
+  pub singular_values: Vec<f64>,
+  /// The rows of `U`.
+  pub left: Vec<Vec<f64>>,
+  /// The rows of `V`.
+  pub right: Vec<Vec<f64>>,
+}
+
+/// Decomposes a sparse matrix of `rows` rows, given by its columns,
+/// keeping `dimensions` singular values, or as many as it has rows or
+/// columns if fewer, by subspace iteration on `AᵀA` from a fixed starting
+/// subspace, followed by a Rayleigh-Ritz projection. The left singular
+/// vectors of zero singular values are left zero.
+pub(crate) fn svd(columns: &[Vec<(usize, f64)>], rows: usize, dimensions: usize) -> Svd {
+  let k = dimensions.min(rows).min(columns.len());
+
+  // `A v`, for a vector over documents, and `Aᵀ u`, for one over terms.
+  let multiply = |v: &[f64]| -> Vec<f64> {
+    let mut product = vec![0.0; rows];
+    for (column, &x) in columns.iter().zip(v) {
+      for &(t, weight) in column {
+        product[t] += weight * x;
+      }
+    }
+    product
+  };
+  let transpose_multiply = |u: &[f64]| -> Vec<f64> {
+    columns.iter()
+      .map(|column| column.iter().map(|&(t, weight)| weight * u[t]).sum())
+      .collect()
+  };
+
+  // A fixed, pseudorandom starting subspace.
+  let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+  let mut basis: Vec<Vec<f64>> = (0..k)
+    .map(|_| {
+      (0..columns.len())
+        .map(|_| {
+          state ^= state << 13;
+          state ^= state >> 7;
+          state ^= state << 17;
+          (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+        })
+        .collect()
+    })
+    .collect();
+  orthonormalize(&mut basis);
+  for _ in 0..MAX_ITERATIONS {
+    let mut next: Vec<Vec<f64>> = basis.iter().map(|v| transpose_multiply(&multiply(v))).collect();
+    orthonormalize(&mut next);
+    let change = basis.iter().zip(&next)
+      .map(|(v, w)| 1.0 - dot(v, w).abs())
+      .fold(0.0, f64::max);
+    basis = next;
+    if change < TOLERANCE {
+      break;
+    }
+  }
+
+  // The Rayleigh-Ritz projection: rotating the basis by the
+  // eigenvectors of `(A V)ᵀ (A V)` separates the singular vectors within
+  // the subspace.
+  let images: Vec<Vec<f64>> = basis.iter().map(|v| multiply(v)).collect();
+  let gram: Vec<Vec<f64>> = images.iter()
+    .map(|a| images.iter().map(|b| dot(a, b)).collect())
+    .collect();
+  let (eigenvalues, eigenvectors) = symmetric_eigen(gram);
+  let mut order: Vec<usize> = (0..k).collect();
+  order.sort_by(|&a, &b| eigenvalues[b].partial_cmp(&eigenvalues[a]).unwrap_or(::std::cmp::Ordering::Equal));
+
+  let singular_values: Vec<f64> = order.iter().map(|&i| eigenvalues[i].max(0.0).sqrt()).collect();
+  let mut left = vec![vec![0.0; k]; rows];
+  let mut right = vec![vec![0.0; k]; columns.len()];
+  for (j, &i) in order.iter().enumerate() {
+    // The right singular vector `v` and its image `A v = σ u`.
+    let mut v = vec![0.0; columns.len()];
+    let mut image = vec![0.0; rows];
+    for (r, weight) in eigenvectors.iter().map(|row| row[i]).enumerate() {
+      for (x, y) in v.iter_mut().zip(&basis[r]) {
+        *x += weight * y;
+      }
+      for (x, y) in image.iter_mut().zip(&images[r]) {
+        *x += weight * y;
+      }
+    }
+    for (d, x) in v.into_iter().enumerate() {
+      right[d][j] = x;
+    }
+    if singular_values[j] > 0.0 {
+      for (t, x) in image.into_iter().enumerate() {
+        left[t][j] = x / singular_values[j];
+      }
+    }
+  }
+
+  Svd {
+    singular_values: singular_values,
+    left: left,
+    right: right,
+  }
+}
+
+/// Returns the cosine of the angle between two vectors, or zero if
+/// either is zero.
+pub fn cosine(a: &[f64], b: &[f64]) -> f64 {
+  let norms = dot(a, a).sqrt() * dot(b, b).sqrt();
+  if norms == 0.0 { 0.0 } else { dot(a, b) / norms }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+  a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Makes vectors orthonormal in order by the modified Gram-Schmidt
+/// process. A vector dependent on those before it is left zero.
+fn orthonormalize(vectors: &mut [Vec<f64>]) {
+  for i in 0..vectors.len() {
+    let (before, rest) = vectors.split_at_mut(i);
+    let vector = &mut rest[0];
+    for other in before.iter() {
+      let projection = dot(vector, other);
+      for (x, y) in vector.iter_mut().zip(other) {
+        *x -= projection * y;
+      }
+    }
+    let norm = dot(vector, vector).sqrt();
+    for x in vector.iter_mut() {
+      *x = if norm > 1e-12 { *x / norm } else { 0.0 };
+    }
+  }
+}
+
+/// Returns the eigenvalues of a symmetric matrix and its eigenvectors, as
+/// columns, by the cyclic Jacobi method.
+fn symmetric_eigen(mut matrix: Vec<Vec<f64>>) -> (Vec<f64>, Vec<Vec<f64>>) {
+  let n = matrix.len();
+  let mut vectors: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+  for _ in 0..100 {
+    let off: f64 = (0..n).flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+      .map(|(i, j)| matrix[i][j] * matrix[i][j])
+      .sum();
+    if off < 1e-22 {
+      break;
+    }
+    for p in 0..n {
+      for q in p + 1..n {
+        if matrix[p][q].abs() < 1e-300 {
+          continue;
+        }
+        let theta = (matrix[q][q] - matrix[p][p]) / (2.0 * matrix[p][q]);
+        let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+        let c = 1.0 / (t * t + 1.0).sqrt();
+        let s = t * c;
+        for row in matrix.iter_mut() {
+          let (a, b) = (row[p], row[q]);
+          row[p] = c * a - s * b;
+          row[q] = s * a + c * b;
+        }
+        let (first, second) = matrix.split_at_mut(q);
+        for (x, y) in first[p].iter_mut().zip(second[0].iter_mut()) {
+          let (a, b) = (*x, *y);
+          *x = c * a - s * b;
+          *y = s * a + c * b;
+        }
+        for row in vectors.iter_mut() {
+          let (a, b) = (row[p], row[q]);
+          row[p] = c * a - s * b;
+          row[q] = s * a + c * b;
+        }
+      }
+    }
+  }
+  ((0..n).map(|i| matrix[i][i]).collect(), vectors)
+}
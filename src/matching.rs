@@ -0,0 +1,148 @@
+use token::Token;
+use language::Language;
+use frequency::FreqDist;
+
+use std::collections::HashMap;
+
+/// The count added to every word of the target distribution, and to the
+/// words outside it together, when comparing a selection to the target,
+/// so that the divergence is finite while words are missing.
+const SMOOTHING: f64 = 0.5;
+
+/// A subset of the parts of a corpus, such as its documents or lines,
+/// whose distribution of words approximately matches a target
+/// distribution: for building a control corpus for an experiment, or
+/// choosing stimuli of controlled frequency.
+///
+/// Parts are selected greedily: each step adds the part that brings the
+/// Kullback-Leibler divergence of the selection from the target lowest,
+/// until the selection holds enough tokens. The divergence is
+/// `Σ P(w) ln(P(w) / Q(w))`, where `P` is the target distribution and `Q`
+/// the distribution of the selection, with `0.5` added to the count of
+/// each word of the target and to the count of all other words together.
+/// Words outside the target thus only raise the divergence, by taking a
+/// share of the selection from the words within it. `Null` tokens are not
+/// counted.
+///
+/// ```rust
+/// let target: FreqDist<Token<English>> = stimuli.tokens().iter().collect();
+/// let control = FrequencyMatch::select(corpus.lines(), &target, 10_000);
+/// println!("divergence: {:.4}", control.divergence());
+/// for &i in control.selected() {
+///   println!("{}", corpus.lines().get(i).unwrap().iter().join(" "));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyMatch {
+  selected: Vec<usize>,
+  tokens: usize,
+  divergence: f64,
+}
+
+impl FrequencyMatch {
+  /// Selects parts, each a sequence of tokens, until the selection holds
+  /// at least `tokens` tokens or every part with tokens is selected.
+  /// Nothing is selected if the target distribution is empty.
+  pub fn select<'t, L, I, P>(parts: I, target: &FreqDist<Token<'t, L>>, tokens: usize)
+      -> FrequencyMatch
+    where L: Language,
+          I: IntoIterator<Item=P>,
+          P: IntoIterator<Item=Token<'t, L>>
+  {
+    if target.is_empty() {
+      return FrequencyMatch { selected: vec![], tokens: 0, divergence: 0.0 };
+    }
+    let total = target.total() as f64;
+    let words: Vec<(&Token<'t, L>, f64)> = target.iter()
+      .map(|(token, count)| (token, count as f64 / total))
+      .collect();
+    let index: HashMap<&Token<'t, L>, usize> = words.iter().enumerate()
+      .map(|(w, &(token, _))| (token, w))
+      .collect();
+    // The smoothed size of a selection of no tokens: the target's words
+    // and one more for every other word.
+    let bins = SMOOTHING * (words.len() as f64 + 1.0);
+
+    // The size of each part, and its count of each word of the target.
+    let candidates: Vec<(usize, Vec<(usize, usize)>)> = parts.into_iter()
+      .map(|part| {
+        let mut size = 0;
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for token in part {
+          if token == Token::Null {
+            continue;
+          }
+          size += 1;
+          if let Some(&w) = index.get(&token) {
+            *counts.entry(w).or_insert(0) += 1;
+          }
+        }
+        let mut counts: Vec<(usize, usize)> = counts.into_iter().collect();
+        counts.sort();
+        (size, counts)
+      })
+      .collect();
+
+    // The divergence is `Σ P(w) ln P(w) - Σ P(w) ln(c(w) + 0.5) +
+    // ln(n + bins)`, for counts `c` of a selection of `n` tokens; only
+    // the middle sum changes with the counts of the target's words.
+    let entropy: f64 = words.iter().map(|&(_, p)| p * p.ln()).sum();
+    let mut counts = vec![0; words.len()];
+    let mut fit: f64 = words.iter().map(|&(_, p)| p * SMOOTHING.ln()).sum();
+    let mut size = 0;
+    let mut available: Vec<bool> = candidates.iter().map(|&(size, _)| size > 0).collect();
+    let mut selected = vec![];
+    while size < tokens {
+      let mut best: Option<(usize, f64, f64)> = None;
+      for (c, &(part, ref part_counts)) in candidates.iter().enumerate() {
+        if !available[c] {
+          continue;
+        }
+        let gain: f64 = part_counts.iter()
+          .map(|&(w, count)| {
+            let before = counts[w] as f64 + SMOOTHING;
+            words[w].1 * ((before + count as f64) / before).ln()
+          })
+          .sum();
+        let divergence = entropy - (fit + gain) + ((size + part) as f64 + bins).ln();
+        if best.is_none_or(|(_, _, lowest)| divergence < lowest) {
+          best = Some((c, gain, divergence));
+        }
+      }
+      let (c, gain, _) = match best {
+        Some(best) => best,
+        None => break
+      };
+      available[c] = false;
+      selected.push(c);
+      fit += gain;
+      size += candidates[c].0;
+      for &(w, count) in &candidates[c].1 {
+        counts[w] += count;
+      }
+    }
+
+    FrequencyMatch {
+      selected: selected,
+      tokens: size,
+      divergence: entropy - fit + (size as f64 + bins).ln(),
+    }
+  }
+
+  /// Returns the indices of the selected parts, in the order they were
+  /// selected.
+  pub fn selected(&self) -> &[usize] {
+    &self.selected
+  }
+
+  /// Returns the number of tokens of the selected parts.
+  pub fn tokens(&self) -> usize {
+    self.tokens
+  }
+
+  /// Returns the smoothed Kullback-Leibler divergence of the selection
+  /// from the target distribution, in nats.
+  pub fn divergence(&self) -> f64 {
+    self.divergence
+  }
+}
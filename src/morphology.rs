@@ -0,0 +1,402 @@
+//! Morphological features, as annotated in the `FEATS` column of the
+//! Universal Dependencies treebanks.
+//!
+//! Each universal feature is an enum of its values, and a [`Features`]
+//! bundle holds any number of them compactly, alongside any
+//! language-specific features it does not recognize:
+//!
+//! ```rust
+//! use nlptk::morphology::{Features, Case, Number, Person};
+//!
+//! let features: Features = "Case=Acc|Number=Plur|Person=3".parse()?;
+//! assert!(features.has(Case::Acc));
+//! assert_eq!(features.get::<Number>(), Some(Number::Plur));
+//! assert_eq!(features.get::<Person>(), Some(Person::Third));
+//! assert_eq!(features.to_string(), "Case=Acc|Number=Plur|Person=3");
+//! ```
+//!
+//! An [`Inflector`] generates the surface form of a lemma bearing given
+//! features, inverting lemmatization:
+//!
+//! ```rust
+//! let treebank: AnalyzedDocument<English> =
+//!   read_conllu(&fs::read_to_string("en_ewt-ud-train.conllu")?)?;
+//! let inflector = Inflector::train(&treebank);
+//! let past = "Mood=Ind|Tense=Past|VerbForm=Fin".parse()?;
+//! assert_eq!(inflector.inflect("hope", &past), Some("hoped".to_string()));
+//! ```
+//!
+//! [`Features`]: struct.Features.html
+//! [`Inflector`]: struct.Inflector.html
+use token::Token;
+use language::Language;
+use analyzed::AnalyzedDocument;
+use error::ParseError;
+
+use std::fmt;
+use std::str::FromStr;
+use std::marker::PhantomData;
+use std::collections::HashMap;
+
+/// A universal feature, whose values are the variants of the
+/// implementing enum.
+pub trait Feature: Copy + Eq + 'static {
+  /// The name of the feature, as in `Case`.
+  const NAME: &'static str;
+
+  /// Returns every value of the feature.
+  fn values() -> &'static [Self];
+
+  /// Returns the value as written in `FEATS`, as in `Acc`.
+  fn as_str(self) -> &'static str;
+
+  /// Parses a value as written in `FEATS`.
+  fn parse(value: &str) -> Option<Self> {
+    Self::values().iter().cloned().find(|v| v.as_str() == value)
+  }
+}
+
+macro_rules! feature_text {
+  ($value:ident) => (stringify!($value));
+  ($value:ident, $text:expr) => ($text);
+}
+
+macro_rules! features {
+  ($( $(#[$attr:meta])* $feature:ident [$($value:ident $(($text:expr))*),*] )*) => {
+    $(
+      $(#[$attr])*
+      #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+      pub enum $feature { $($value),* }
+
+      impl Feature for $feature {
+        const NAME: &'static str = stringify!($feature);
+
+        fn values() -> &'static [$feature] {
+          &[$($feature::$value),*]
+        }
+
+        fn as_str(self) -> &'static str {
+          match self { $($feature::$value => feature_text!($value $(, $text)*)),* }
+        }
+      }
+    )*
+
+    /// The name and values of every universal feature, indexed by kind.
+    const KINDS: &[(&str, &[&str])] = &[
+      $((stringify!($feature), &[$(feature_text!($value $(, $text)*)),*])),*
+    ];
+  }
+}
+
+features! {
+  /// The type of a pronoun, determiner, or similar word.
+  PronType [Art, Dem, Emp, Exc, Ind, Int, Neg, Prs, Rcp, Rel, Tot]
+  /// The type of a numeral.
+  NumType [Card, Dist, Frac, Mult, Ord, Range, Sets]
+  /// Whether a word is possessive.
+  Poss [Yes]
+  /// Whether a word is reflexive.
+  Reflex [Yes]
+  /// Whether a word is foreign.
+  Foreign [Yes]
+  /// Whether a word is an abbreviation.
+  Abbr [Yes]
+  /// Whether a word is misspelled.
+  Typo [Yes]
+  Gender [Com, Fem, Masc, Neut]
+  Animacy [Anim, Hum, Inan, Nhum]
+  Number [Coll, Count, Dual, Grpa, Grpl, Inv, Pauc, Plur, Ptan, Sing, Tri]
+  Case [Abs, Acc, Erg, Nom, Abe, Ben, Cau, Cmp, Cns, Com, Dat, Dis, Equ, Gen, Ins,
+        Par, Tem, Tra, Voc, Abl, Add, Ade, All, Del, Ela, Ess, Ill, Ine, Lat, Loc,
+        Per, Sbe, Sbl, Spl, Sub, Sup, Ter]
+  Definite [Com, Cons, Def, Ind, Spec]
+  Degree [Abs, Aug, Cmp, Dim, Equ, Pos, Sup]
+  VerbForm [Conv, Fin, Gdv, Ger, Inf, Part, Sup, Vnoun]
+  Mood [Adm, Cnd, Des, Imp, Ind, Int, Irr, Jus, Nec, Opt, Pot, Prp, Qot, Sub]
+  Tense [Fut, Imp, Past, Pqp, Pres]
+  Aspect [Hab, Imp, Iter, Perf, Prog, Prosp]
+  Voice [Act, Antip, Bfoc, Cau, Dir, Inv, Lfoc, Mid, Pass, Rcp]
+  Evident [Fh, Nfh]
+  Polarity [Neg, Pos]
+  Person [Zero("0"), First("1"), Second("2"), Third("3"), Fourth("4")]
+  Polite [Elev, Form, Humb, Infm]
+  Clusivity [Ex, In]
+}
+
+/// Returns the kind and index of a universal feature value.
+fn known(name: &str, value: &str) -> Option<(u8, u8)> {
+  let kind = KINDS.iter().position(|&(n, _)| n == name)?;
+  let index = KINDS[kind].1.iter().position(|&v| v == value)?;
+  Some((kind as u8, index as u8))
+}
+
+/// Returns the kind and index of a value of feature `F`.
+fn encode<F: Feature>(value: F) -> (u8, u8) {
+  known(F::NAME, value.as_str()).expect("universal feature")
+}
+
+/// A bundle of morphological features. Universal features are stored as
+/// a pair of bytes each; others, such as language-specific features and
+/// layered features like `Number[psor]`, are kept as written.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Features {
+  /// The kind and value index of each universal feature, sorted.
+  known: Vec<(u8, u8)>,
+  /// The name and value of every other feature, sorted by name.
+  other: Vec<(String, String)>,
+}
+
+impl Features {
+  /// Creates an empty bundle.
+  pub fn new() -> Features {
+    Features::default()
+  }
+
+  /// Returns `true` if the bundle has no features.
+  pub fn is_empty(&self) -> bool {
+    self.known.is_empty() && self.other.is_empty()
+  }
+
+  /// Returns `true` if the bundle has the given value, as in
+  /// `features.has(Case::Acc)`.
+  pub fn has<F: Feature>(&self, value: F) -> bool {
+    self.known.binary_search(&encode(value)).is_ok()
+  }
+
+  /// Returns the value of feature `F`, or the first of its values if it
+  /// has several, as in `features.get::<Case>()`.
+  pub fn get<F: Feature>(&self) -> Option<F> {
+    self.values::<F>().into_iter().next()
+  }
+
+  /// Returns every value of feature `F`.
+  pub fn values<F: Feature>(&self) -> Vec<F> {
+    F::values().iter().cloned().filter(|&value| self.has(value)).collect()
+  }
+
+  /// Adds a value of a universal feature.
+  pub fn insert<F: Feature>(&mut self, value: F) {
+    let pair = encode(value);
+    if let Err(i) = self.known.binary_search(&pair) {
+      self.known.insert(i, pair);
+    }
+  }
+
+  /// Removes every value of feature `F`.
+  pub fn remove<F: Feature>(&mut self) {
+    let kind = encode(F::values()[0]).0;
+    self.known.retain(|&(k, _)| k != kind);
+  }
+
+  /// Returns the value of a feature that is not universal, as written,
+  /// such as `features.other("Number[psor]")`.
+  pub fn other(&self, name: &str) -> Option<&str> {
+    self.other.iter().find(|(n, _)| n == name).map(|(_, v)| &v[..])
+  }
+
+  /// Adds one value of a feature given by name, as a universal feature if
+  /// it is one.
+  fn add(&mut self, name: &str, value: &str) {
+    match known(name, value) {
+      Some(pair) => if let Err(i) = self.known.binary_search(&pair) {
+        self.known.insert(i, pair);
+      },
+      None => match self.other.iter_mut().find(|(n, _)| n == name) {
+        Some(other) => {
+          other.1.push(',');
+          other.1.push_str(value);
+        },
+        None => {
+          let i = self.other.iter().position(|(n, _)| &n[..] > name).unwrap_or(self.other.len());
+          self.other.insert(i, (name.to_string(), value.to_string()));
+        },
+      }
+    }
+  }
+}
+
+impl FromStr for Features {
+  type Err = ParseError;
+
+  /// Parses features written as in `FEATS`: `Name=Value` pairs separated
+  /// by `|`, with several values of one feature separated by commas, or
+  /// `_` for none.
+  fn from_str(text: &str) -> Result<Features, ParseError> {
+    let mut features = Features::new();
+    if text == "_" || text.is_empty() {
+      return Ok(features);
+    }
+    let mut offset = 0;
+    for pair in text.split('|') {
+      let error = |message| ParseError { offset: offset, message: message };
+      let equals = pair.find('=').ok_or_else(|| error("expected `=`"))?;
+      let (name, values) = (&pair[..equals], &pair[equals + 1..]);
+      if name.is_empty() {
+        return Err(error("empty feature name"));
+      }
+      for value in values.split(',') {
+        if value.is_empty() {
+          return Err(error("empty feature value"));
+        }
+        features.add(name, value);
+      }
+      offset += pair.len() + 1;
+    }
+    Ok(features)
+  }
+}
+
+impl fmt::Display for Features {
+  /// Writes the features as in `FEATS`, sorted by name, or `_` if there
+  /// are none.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let mut pairs: Vec<(&str, String)> = self.other.iter()
+      .map(|(name, value)| (&name[..], value.clone()))
+      .collect();
+    for &(kind, _) in &self.known {
+      let (name, values) = KINDS[kind as usize];
+      if pairs.iter().any(|&(n, _)| n == name) {
+        continue;
+      }
+      let mut written: Vec<&str> = self.known.iter()
+        .filter(|&&(k, _)| k == kind)
+        .map(|&(_, index)| values[index as usize])
+        .collect();
+      written.sort();
+      pairs.push((name, written.join(",")));
+    }
+    if pairs.is_empty() {
+      return write!(f, "_");
+    }
+    pairs.sort_by_key(|&(name, _)| name.to_lowercase());
+    let written: Vec<String> = pairs.into_iter()
+      .map(|(name, value)| format!("{}={}", name, value))
+      .collect();
+    write!(f, "{}", written.join("|"))
+  }
+}
+
+/// The number of characters of a lemma, beyond those an inflection
+/// rewrites, that condition the rule.
+const CONTEXT: usize = 2;
+
+/// A rewrite of the end of a lemma into the end of an inflected form.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+struct Rule {
+  /// The ending a lemma must have for the rule to apply, which includes
+  /// `strip`.
+  suffix: String,
+  /// The ending removed from the lemma.
+  strip: String,
+  /// The ending added in its place.
+  append: String,
+}
+
+/// A morphological generator for language `L`, producing the surface
+/// form of a lemma bearing a bundle of [`Features`].
+///
+/// Forms are looked up in a table of known inflections first. Otherwise,
+/// the lemma is rewritten by a suffix rule for the bundle: of the rules
+/// whose conditioning ending the lemma has, the one with the longest
+/// ending is applied, with ties going to the rule seen most often. Both
+/// the table and the rules are learned from analyzed text with
+/// [`train`], and may be supplemented by hand.
+///
+/// [`Features`]: struct.Features.html
+/// [`train`]: #method.train
+#[derive(Debug, Clone)]
+pub struct Inflector<L: Language> {
+  forms: HashMap<(String, Features), String>,
+  rules: HashMap<Features, HashMap<Rule, usize>>,
+  language: PhantomData<L>,
+}
+
+impl<L: Language> Inflector<L> {
+  /// Creates an inflector with no forms or rules, which inflects nothing.
+  pub fn new() -> Inflector<L> {
+    Inflector { forms: HashMap::new(), rules: HashMap::new(), language: PhantomData }
+  }
+
+  /// Learns the forms and rules exhibited by every token of a document,
+  /// pairing its lemma and features with its surface form.
+  pub fn train(document: &AnalyzedDocument<L>) -> Inflector<L> {
+    let mut inflector = Inflector::new();
+    let text = |token: &Token<L>| match *token {
+      Token::Word(ref word) => Some(String::from_utf8_lossy(word.as_bytes()).into_owned()),
+      _ => None
+    };
+    for (i, (lemma, form)) in document.lemmas().iter().zip(document.surface()).enumerate() {
+      if let (Some(lemma), Some(form)) = (text(&lemma), text(&form)) {
+        inflector.learn(&lemma, document.features(i), &form);
+      }
+    }
+    inflector
+  }
+
+  /// Records one inflection, as both a known form and an instance of the
+  /// rules it exhibits.
+  pub fn learn(&mut self, lemma: &str, features: &Features, form: &str) {
+    self.add_form(lemma, features, form);
+    let prefix = lemma.chars().zip(form.chars())
+      .take_while(|(a, b)| a == b)
+      .map(|(a, _)| a.len_utf8())
+      .sum::<usize>();
+    let (strip, append) = (&lemma[prefix..], &form[prefix..]);
+    let rules = self.rules.entry(features.clone()).or_default();
+    let mut start = prefix;
+    for _ in 0..=CONTEXT {
+      let rule = Rule {
+        suffix: lemma[start..].to_string(),
+        strip: strip.to_string(),
+        append: append.to_string(),
+      };
+      *rules.entry(rule).or_insert(0) += 1;
+      match lemma[..start].chars().next_back() {
+        Some(c) => start -= c.len_utf8(),
+        None => break
+      }
+    }
+  }
+
+  /// Adds a known form, such as an irregular inflection, which takes
+  /// precedence over every rule.
+  pub fn add_form(&mut self, lemma: &str, features: &Features, form: &str) {
+    self.forms.insert((lemma.to_string(), features.clone()), form.to_string());
+  }
+
+  /// Adds a rule replacing the ending `strip` of lemmas with `append`,
+  /// as for `add_rule(&past, "y", "ied")`. The rule applies to every
+  /// lemma ending in `strip`, and outranks learned rules conditioned on
+  /// endings no longer than `strip`.
+  pub fn add_rule(&mut self, features: &Features, strip: &str, append: &str) {
+    let rule = Rule {
+      suffix: strip.to_string(),
+      strip: strip.to_string(),
+      append: append.to_string(),
+    };
+    self.rules.entry(features.clone()).or_default().insert(rule, usize::MAX);
+  }
+
+  /// Returns the surface form of `lemma` bearing `features`, or `None`
+  /// if no known form or rule covers them.
+  pub fn inflect(&self, lemma: &str, features: &Features) -> Option<String> {
+    if let Some(form) = self.forms.get(&(lemma.to_string(), features.clone())) {
+      return Some(form.clone());
+    }
+    let rules = self.rules.get(features)?;
+    rules.iter()
+      .filter(|&(rule, _)| lemma.ends_with(&rule.suffix[..]))
+      .max_by(|&(a, m), &(b, n)| {
+        a.suffix.len().cmp(&b.suffix.len())
+          .then(m.cmp(n))
+          .then(b.append.cmp(&a.append))
+      })
+      .map(|(rule, _)| format!("{}{}", &lemma[..lemma.len() - rule.strip.len()], rule.append))
+  }
+}
+
+impl<L: Language> Default for Inflector<L> {
+  fn default() -> Inflector<L> {
+    Inflector::new()
+  }
+}
@@ -0,0 +1,161 @@
+use token::Token;
+use language::Language;
+use corpus::Document;
+
+use std::collections::HashSet;
+
+/// Merges multi-word expressions, such as `New York` or `kick the
+/// bucket`, into single tokens, and splits them apart again. The words
+/// of a merged expression are joined by a configurable joiner:
+///
+/// ```rust
+/// let mut merger = MweMerger::new("_");
+/// merger.insert(&["New", "York"]);
+/// merger.insert(&["New", "York", "City"]);
+///
+/// let merged: Document = merger.merge(&document);
+/// // "I love New York City" becomes "I love New_York_City"
+/// ```
+///
+/// When expressions overlap, the longest expression starting at the
+/// leftmost position wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MweMerger {
+  /// Each expression, with its words joined by the joiner.
+  expressions: HashSet<Vec<u8>>,
+  /// The number of words in the longest expression.
+  longest: usize,
+  joiner: Vec<u8>,
+}
+
+impl MweMerger {
+  /// Creates a merger with no expressions, which joins the words of
+  /// merged expressions with `joiner`.
+  pub fn new<J: Into<Vec<u8>>>(joiner: J) -> MweMerger {
+    MweMerger {
+      expressions: HashSet::new(),
+      longest: 0,
+      joiner: joiner.into(),
+    }
+  }
+
+  /// Adds an expression of two or more words. Shorter expressions are
+  /// ignored.
+  pub fn insert<S: AsRef<[u8]>>(&mut self, words: &[S]) {
+    if words.len() < 2 {
+      return;
+    }
+    self.longest = self.longest.max(words.len());
+    let joined = words.iter()
+      .map(|word| word.as_ref())
+      .collect::<Vec<_>>()
+      .join(&self.joiner[..]);
+    self.expressions.insert(joined);
+  }
+
+  /// Adds each sequence of tokens as an expression, for instance, the
+  /// bigrams of a mined list of collocations. Sequences containing
+  /// `Null` or `Unknown` tokens are ignored.
+  pub fn extend<'t, I, S, L>(&mut self, expressions: I)
+    where L: Language + 't,
+          I: IntoIterator<Item=S>,
+          S: IntoIterator<Item=Token<'t, L>>
+  {
+    for expression in expressions {
+      let words: Option<Vec<&[u8]>> = expression.into_iter()
+        .map(|token| match token {
+          Token::Word(word) => Some(word.as_bytes()),
+          _ => None
+        })
+        .collect();
+      if let Some(words) = words {
+        self.insert(&words);
+      }
+    }
+  }
+
+  /// Produces a copy of `document` in which every occurrence of an
+  /// expression is merged into a single token.
+  pub fn merge<L, M>(&self, document: &Document<L>) -> Document<M>
+    where L: Language
+  {
+    rebuild(document, |line| {
+      let words = line.iter().map(bytes).collect::<Vec<_>>();
+      let mut merged = vec![];
+      let mut start = 0;
+      while start < words.len() {
+        let longest = self.longest.min(words.len() - start);
+        let expression = (2..longest + 1).rev()
+          .map(|n| (n, words[start..start + n].join(&self.joiner[..])))
+          .find(|(_, joined)| self.expressions.contains(joined));
+        match expression {
+          Some((length, joined)) => {
+            merged.push(joined);
+            start += length;
+          },
+          None => {
+            merged.push(words[start].to_vec());
+            start += 1;
+          }
+        }
+      }
+      merged
+    })
+  }
+
+  /// Produces a copy of `document` in which every merged expression is
+  /// split back into its words. Tokens containing the joiner that are
+  /// not known expressions are left intact.
+  pub fn split<L, M>(&self, document: &Document<L>) -> Document<M>
+    where L: Language
+  {
+    rebuild(document, |line| {
+      line.iter().map(bytes)
+        .flat_map(|word|
+          if self.expressions.contains(word) { split_on(word, &self.joiner) }
+          else { vec![word] })
+        .map(|word| word.to_vec())
+        .collect()
+    })
+  }
+}
+
+/// The bytes of a token as they appear in a document.
+fn bytes<'t, L>(token: &Token<'t, L>) -> &'t [u8] {
+  match *token {
+    Token::Word(ref word) => word.as_bytes(),
+    _ => b""
+  }
+}
+
+/// Splits `bytes` on every occurrence of a non-empty `separator`.
+fn split_on<'b>(bytes: &'b [u8], separator: &[u8]) -> Vec<&'b [u8]> {
+  if separator.is_empty() {
+    return vec![bytes];
+  }
+  let mut parts = vec![];
+  let mut start = 0;
+  let mut i = 0;
+  while i + separator.len() <= bytes.len() {
+    if &bytes[i..i + separator.len()] == separator {
+      parts.push(&bytes[start..i]);
+      i += separator.len();
+      start = i;
+    } else {
+      i += 1;
+    }
+  }
+  parts.push(&bytes[start..]);
+  parts
+}
+
+/// Builds a new document by rewriting each line of `document` into a
+/// new sequence of words.
+fn rebuild<L, M, F>(document: &Document<L>, mut rewrite: F) -> Document<M>
+  where F: FnMut(&[Token<L>]) -> Vec<Vec<u8>>
+{
+  let lines = document.lines().iter()
+    .map(|line| rewrite(&line.to_vec()).join(&b' '))
+    .collect::<Vec<_>>();
+  lines.join(&b'\n').into()
+}
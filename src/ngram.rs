@@ -0,0 +1,1170 @@
+//! n-gram language models over the tokens of a document, and their
+//! evaluation.
+//!
+//! A [`Model`] estimates the probability of each token given the tokens
+//! before it on its line, smoothed by one of several methods of
+//! [`Smoothing`]. Lines are padded with [`Token::Null`] at both
+//! ends, so that the model also predicts which tokens begin lines, and
+//! where lines end. A [`Mixture`] interpolates models trained on
+//! different corpora.
+//!
+//! ```rust
+//! let training: Document<English> = Document::from_reader(File::open("train.txt")?)?;
+//! let testing: Document<English> = Document::from_reader(File::open("test.txt")?)?;
+//!
+//! let model = Model::train(&training, 3);
+//! println!("perplexity: {}", model.perplexity(&testing));
+//! ```
+//!
+//! [`Model`]: struct.Model.html
+//! [`Smoothing`]: enum.Smoothing.html
+//! [`Mixture`]: struct.Mixture.html
+//! [`Token::Null`]: ../enum.Token.html#variant.Null
+use token::Token;
+use language::Language;
+use corpus::AsTokens;
+use intern::TokenId;
+use good_turing::SimpleGoodTuring;
+use eval;
+
+use std::fmt;
+use std::io;
+use std::iter::once;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::collections::{HashMap, HashSet};
+use rand::Rng;
+
+/// What a [`Model`] counts: a token, or the id of a token interned by an
+/// [`Interner`], so that a model of ids never hashes the characters of
+/// words.
+///
+/// [`Model`]: struct.Model.html
+/// [`Interner`]: ../struct.Interner.html
+pub trait Key: Copy + Hash + Ord {
+  /// The key that pads both ends of every line.
+  const NULL: Self;
+}
+
+impl<'t, L: Language> Key for Token<'t, L> {
+  const NULL: Token<'t, L> = Token::Null;
+}
+
+impl Key for TokenId {
+  const NULL: TokenId = TokenId::NULL;
+}
+
+/// The keys observed after a context.
+struct Followers<K> {
+  /// The total count of the context.
+  total: usize,
+  counts: HashMap<K, usize>,
+  /// The number of tokens observed once, twice, and three or more times.
+  buckets: [usize; 3],
+  /// The count taken from the tokens by Good-Turing discounting, set
+  /// only while the model is smoothed by `Smoothing::GoodTuring`.
+  reserved: f64,
+}
+
+impl<K: Key> Followers<K> {
+  fn new() -> Followers<K> {
+    Followers { total: 0, counts: HashMap::new(), buckets: [0; 3], reserved: 0.0 }
+  }
+
+  /// Counts an observation of `token`, returning its new count.
+  fn add(&mut self, token: K) -> usize {
+    self.total += 1;
+    let count = self.counts.entry(token).or_insert(0);
+    *count += 1;
+    shift(&mut self.buckets, *count);
+    *count
+  }
+}
+
+/// Moves an item whose count has just risen to `count` into its bucket of
+/// a count-of-counts table, whose last bucket holds every greater count.
+fn shift(buckets: &mut [usize], count: usize) {
+  if count <= buckets.len() {
+    buckets[count - 1] += 1;
+    if count > 1 {
+      buckets[count - 2] -= 1;
+    }
+  }
+}
+
+/// Returns the simple Good-Turing adjusted count of an n-gram observed
+/// `count` times, renormalized so that the n-grams of its length keep
+/// all but the share of their count that is of n-grams observed once,
+/// and never more than the count itself, so that no context gives a
+/// negative count to the shorter one.
+fn good_turing_count(estimator: &SimpleGoodTuring, count: usize) -> f64 {
+  if count == 0 {
+    return 0.0;
+  }
+  (estimator.probability(count) * estimator.total() as f64).min(count as f64)
+}
+
+/// Returns the discounts of modified Kneser-Ney smoothing for n-grams
+/// observed once, twice, and three or more times, estimated from the
+/// number of n-grams observed once, twice, three and four times.
+fn discounts(n: &[usize; 5]) -> [f64; 3] {
+  let n: Vec<f64> = n.iter().map(|&n| n as f64).collect();
+  let y = if n[0] > 0.0 { n[0] / (n[0] + 2.0 * n[1]) } else { 0.0 };
+  let mut discounts = [0.0; 3];
+  for (k, discount) in discounts.iter_mut().enumerate() {
+    if n[k] > 0.0 {
+      let count = k as f64 + 1.0;
+      *discount = (count - (count + 1.0) * y * n[k + 1] / n[k]).max(0.0).min(count);
+    }
+  }
+  discounts
+}
+
+/// How a [`Model`] estimates probabilities from its counts. The
+/// vocabulary of every method includes one unseen token.
+///
+/// [`Model`]: struct.Model.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Smoothing {
+  /// Interpolated Witten-Bell smoothing: the probability of a token after
+  /// a context of `n - 1` tokens is interpolated with its probability
+  /// after the last `n - 2` tokens, and so on down to the empty context,
+  /// which is interpolated with a uniform distribution over the
+  /// vocabulary. The weight given to the shorter context is proportional
+  /// to the number of distinct tokens observed after the longer one.
+  WittenBell,
+  /// The maximum-likelihood estimate: the number of times the token
+  /// followed the context, divided by the number of times the context
+  /// occurred. Tokens never seen after the context have probability
+  /// zero, and so perplexity on unseen text is usually infinite.
+  MaximumLikelihood,
+  /// Additive smoothing, which adds `k` to the count of every token of
+  /// the vocabulary after every context: Laplace smoothing is
+  /// `AddK(1.0)`.
+  AddK(f64),
+  /// Interpolated modified Kneser-Ney smoothing, after Chen and Goodman:
+  /// counts are discounted by one of three amounts, for tokens seen once,
+  /// twice, and more often after the context, estimated from the counts
+  /// of counts of the model. The discounted mass is given to the shorter
+  /// context, whose counts are the number of distinct tokens observed
+  /// before each n-gram rather than its number of occurrences. Contexts
+  /// beginning a line, which have no tokens before them, keep their
+  /// counts.
+  KneserNey,
+  /// Interpolated Good-Turing smoothing: the count of a token after a
+  /// context is discounted to its [simple Good-Turing] adjusted count,
+  /// estimated from the counts of every n-gram of the same length, and
+  /// the count taken from the tokens observed after the context is given
+  /// to the shorter context, down to a uniform distribution over the
+  /// vocabulary. Unseen tokens have probability zero if no token was
+  /// observed exactly once. The estimates are refitted whenever the
+  /// counts change, at a cost proportional to the size of the model.
+  ///
+  /// [simple Good-Turing]: ../good_turing/index.html
+  GoodTuring,
+}
+
+/// An n-gram language model. By default, its probabilities are smoothed
+/// by interpolated Witten-Bell smoothing; see [`Smoothing`] for others.
+///
+/// A model counts the tokens of language `L`, or, trained by
+/// [`train_ids`], the ids of tokens interned by an [`Interner<L>`]:
+///
+/// ```rust
+/// let mut interner: Interner<English> = Interner::new();
+/// let training = training.intern(&mut interner);
+/// let testing = testing.intern(&mut interner);
+/// let model: Model<English, TokenId> = Model::train_ids(training.lines(), 3);
+/// let total: f64 = testing.lines().map(|line| model.log_probability(line)).sum();
+/// ```
+///
+/// [`Smoothing`]: enum.Smoothing.html
+/// [`train_ids`]: #method.train_ids
+/// [`Interner<L>`]: ../struct.Interner.html
+pub struct Model<'t, L: Language, K = Token<'t, L>> {
+  order: usize,
+  smoothing: Smoothing,
+  /// The followers of every context of fewer than `order` tokens.
+  contexts: HashMap<Vec<K>, Followers<K>>,
+  /// Every token observed, including the `NULL` boundary key.
+  vocabulary: HashSet<K>,
+  /// The followers of every context of fewer than `order - 1` tokens,
+  /// counting each n-gram once per distinct token observed before it.
+  continuations: HashMap<Vec<K>, Followers<K>>,
+  /// The number of n-grams of each length observed once, twice, three,
+  /// four, and more times.
+  count_of_counts: Vec<[usize; 5]>,
+  /// The number of n-grams of each length with a continuation count of
+  /// one, two, three, four, and more.
+  continuation_count_of_counts: Vec<[usize; 5]>,
+  /// The simple Good-Turing estimates for n-grams of each length, fitted
+  /// only while the model is smoothed by `Smoothing::GoodTuring`.
+  good_turing: Vec<SimpleGoodTuring>,
+  language: PhantomData<Token<'t, L>>,
+}
+
+impl<'t, L: 't + Language> Model<'t, L> {
+  /// Trains a model of the given order (`2` for a bigram model, `3` for a
+  /// trigram model, and so on) on lines of tokens, such as those of a
+  /// [`Document`]. Orders below `1` are treated as `1`.
+  ///
+  /// [`Document`]: ../struct.Document.html
+  pub fn train<I>(lines: I, order: usize) -> Model<'t, L>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let mut model = Model::empty(order.max(1), Smoothing::WittenBell);
+    model.update(lines);
+    model
+  }
+
+  /// Folds the counts of further lines of tokens into the model, for
+  /// adapting it to new text without retraining. The smoothed estimates
+  /// are computed from the counts when queried, so they reflect the new
+  /// counts immediately. As the model borrows the tokens it counts, the
+  /// new lines must live as long as the model.
+  ///
+  /// ```rust
+  /// let mut model = Model::train(&news, 3);
+  /// model.update(&transcripts);
+  /// ```
+  pub fn update<I>(&mut self, lines: I)
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    self.update_with(lines, Repetition::Keep);
+  }
+
+  /// Trains a model as [`train`] does, but counting repeated lines only
+  /// as often as `repetition` allows, and reports how much text was
+  /// skipped.
+  ///
+  /// ```rust
+  /// let (model, report) = Model::train_with(&crawl, 3, Repetition::Cap(5));
+  /// println!("skipped {:.1}% of tokens", 100.0 * report.skipped_share());
+  /// ```
+  ///
+  /// [`train`]: #method.train
+  pub fn train_with<I>(lines: I, order: usize, repetition: Repetition)
+      -> (Model<'t, L>, RepetitionReport)
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let mut model = Model::empty(order.max(1), Smoothing::WittenBell);
+    let report = model.update_with(lines, repetition);
+    (model, report)
+  }
+
+  /// Folds the counts of further lines into the model as [`update`]
+  /// does, but counting repeated lines only as often as `repetition`
+  /// allows, and reports how much text was skipped. Repetitions are
+  /// counted among the given lines only, not against earlier updates.
+  ///
+  /// [`update`]: #method.update
+  pub fn update_with<I>(&mut self, lines: I, repetition: Repetition) -> RepetitionReport
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let cap = match repetition {
+      Repetition::Keep => None,
+      Repetition::Dedupe => Some(1),
+      Repetition::Cap(cap) => Some(cap),
+    };
+    let mut report = RepetitionReport::default();
+    let mut seen: HashMap<Vec<Token<'t, L>>, usize> = HashMap::new();
+    for line in lines {
+      let line = line.as_tokens();
+      report.lines += 1;
+      report.tokens += line.len();
+      if let Some(cap) = cap {
+        let occurrences = seen.entry(line.to_vec()).or_insert(0);
+        *occurrences += 1;
+        if *occurrences == 1 {
+          report.distinct += 1;
+        }
+        if *occurrences > cap {
+          report.skipped_lines += 1;
+          report.skipped_tokens += line.len();
+          continue;
+        }
+      }
+      self.count(&line);
+    }
+    self.fit_good_turing();
+    report
+  }
+}
+
+impl<'t, L: 't + Language, K: Key> Model<'t, L, K> {
+  /// Creates a model of the given order with no counts.
+  fn empty(order: usize, smoothing: Smoothing) -> Model<'t, L, K> {
+    Model {
+      order: order,
+      smoothing: smoothing,
+      contexts: HashMap::new(),
+      vocabulary: HashSet::new(),
+      continuations: HashMap::new(),
+      count_of_counts: vec![[0; 5]; order + 1],
+      continuation_count_of_counts: vec![[0; 5]; order + 1],
+      good_turing: vec![],
+      language: PhantomData,
+    }
+  }
+
+  /// Adds the n-grams of a line to the counts of the model.
+  fn count(&mut self, line: &[K]) {
+    let padded: Vec<K> =
+      once(K::NULL).chain(line.iter().cloned()).chain(once(K::NULL)).collect();
+    self.vocabulary.extend(padded.iter().cloned());
+    for i in 1..padded.len() {
+      for start in i.saturating_sub(self.order - 1)..=i {
+        let count = self.contexts.entry(padded[start..i].to_vec())
+          .or_insert_with(Followers::new)
+          .add(padded[i]);
+        shift(&mut self.count_of_counts[i - start + 1], count);
+        if count == 1 && start < i {
+          let count = self.continuations.entry(padded[start + 1..i].to_vec())
+            .or_insert_with(Followers::new)
+            .add(padded[i]);
+          shift(&mut self.continuation_count_of_counts[i - start], count);
+        }
+      }
+    }
+  }
+
+  /// Sets how the model estimates probabilities from its counts.
+  ///
+  /// ```rust
+  /// let laplace = Model::train(&training, 2).with_smoothing(Smoothing::AddK(1.0));
+  /// println!("perplexity: {}", laplace.perplexity(&testing));
+  /// ```
+  pub fn with_smoothing(mut self, smoothing: Smoothing) -> Model<'t, L, K> {
+    self.smoothing = smoothing;
+    self.fit_good_turing();
+    self
+  }
+
+  /// Fits the simple Good-Turing estimates for n-grams of each length to
+  /// the counts of the model, and the count each context gives to the
+  /// shorter one, if the model is smoothed by `Smoothing::GoodTuring`;
+  /// otherwise, discards them.
+  fn fit_good_turing(&mut self) {
+    if self.smoothing != Smoothing::GoodTuring {
+      self.good_turing.clear();
+      return;
+    }
+    let mut count_of_counts: Vec<HashMap<usize, usize>> = vec![HashMap::new(); self.order + 1];
+    for (context, followers) in &self.contexts {
+      for &count in followers.counts.values() {
+        *count_of_counts[context.len() + 1].entry(count).or_insert(0) += 1;
+      }
+    }
+    self.good_turing = count_of_counts.into_iter().map(SimpleGoodTuring::new).collect();
+    for (context, followers) in &mut self.contexts {
+      let estimator = &self.good_turing[context.len() + 1];
+      followers.reserved = followers.counts.values()
+        .map(|&count| count as f64 - good_turing_count(estimator, count))
+        .sum();
+    }
+  }
+
+  /// Returns how the model estimates probabilities from its counts.
+  pub fn smoothing(&self) -> Smoothing {
+    self.smoothing
+  }
+
+  /// Returns the order of the model.
+  pub fn order(&self) -> usize {
+    self.order
+  }
+
+  /// Returns `true` if the token was observed in training.
+  pub fn contains(&self, token: &K) -> bool {
+    self.vocabulary.contains(token)
+  }
+
+  /// Returns the probability of `token` following `context`. Only the
+  /// last `order - 1` tokens of the context are considered; to condition
+  /// on the beginning of a line, begin the context with `Token::Null`, or
+  /// `TokenId::NULL` in a model of ids.
+  pub fn probability(&self, context: &[K], token: &K) -> f64 {
+    self.estimate(context, token).0
+  }
+
+  /// Returns the probability of `token` following `context`, with the
+  /// length of the longest n-gram ending in `token` observed in training,
+  /// or `0` if the token itself was never observed.
+  fn estimate(&self, context: &[K], token: &K) -> (f64, usize) {
+    let k = match self.smoothing {
+      Smoothing::WittenBell => return self.interpolate(context, token),
+      Smoothing::KneserNey => return self.kneser_ney(context, token),
+      Smoothing::GoodTuring => return self.good_turing(context, token),
+      Smoothing::MaximumLikelihood => 0.0,
+      Smoothing::AddK(k) => k,
+    };
+    let context = &context[context.len().saturating_sub(self.order - 1)..];
+    let (count, total) = match self.contexts.get(context) {
+      Some(followers) => (followers.counts.get(token).cloned().unwrap_or(0), followers.total),
+      None => (0, 0)
+    };
+    let types = self.vocabulary.len() as f64 + 1.0;
+    let probability = if total == 0 && k == 0.0 { 0.0 }
+      else { (count as f64 + k) / (total as f64 + k * types) };
+    (probability, if count > 0 { context.len() + 1 } else { 0 })
+  }
+
+  /// Estimates the probability of `token` following `context` by
+  /// interpolated Witten-Bell smoothing, as `estimate` does.
+  fn interpolate(&self, context: &[K], token: &K) -> (f64, usize) {
+    let mut probability = 1.0 / (self.vocabulary.len() as f64 + 1.0);
+    let mut order = 0;
+    let longest = context.len().saturating_sub(self.order - 1);
+    for start in (longest..=context.len()).rev() {
+      let followers = match self.contexts.get(&context[start..]) {
+        Some(followers) => followers,
+        None => break
+      };
+      let count = followers.counts.get(token).cloned().unwrap_or(0);
+      if count > 0 {
+        order = context.len() - start + 1;
+      }
+      let types = followers.counts.len() as f64;
+      probability = (count as f64 + types * probability) / (followers.total as f64 + types);
+    }
+    (probability, order)
+  }
+
+  /// Estimates the probability of `token` following `context` by
+  /// interpolated modified Kneser-Ney smoothing, as `estimate` does.
+  fn kneser_ney(&self, context: &[K], token: &K) -> (f64, usize) {
+    let mut probability = 1.0 / (self.vocabulary.len() as f64 + 1.0);
+    let mut order = 0;
+    let longest = context.len().saturating_sub(self.order - 1);
+    for start in (longest..=context.len()).rev() {
+      let context = &context[start..];
+      let raw = context.len() == self.order - 1 || context.first() == Some(&K::NULL);
+      let (contexts, count_of_counts) = if raw {
+        (&self.contexts, &self.count_of_counts)
+      } else {
+        (&self.continuations, &self.continuation_count_of_counts)
+      };
+      let followers = match contexts.get(context) {
+        Some(followers) => followers,
+        None => break
+      };
+      let discounts = discounts(&count_of_counts[context.len() + 1]);
+      let count = followers.counts.get(token).cloned().unwrap_or(0);
+      let discounted = match count {
+        0 => 0.0,
+        count => count as f64 - discounts[count.min(3) - 1]
+      };
+      if count > 0 {
+        order = context.len() + 1;
+      }
+      let total = followers.total as f64;
+      let backoff: f64 = discounts.iter().zip(&followers.buckets)
+        .map(|(discount, &types)| discount * types as f64)
+        .sum();
+      probability = (discounted + backoff * probability) / total;
+    }
+    (probability, order)
+  }
+
+  /// Estimates the probability of `token` following `context` by
+  /// interpolated Good-Turing smoothing, as `estimate` does.
+  fn good_turing(&self, context: &[K], token: &K) -> (f64, usize) {
+    let mut probability = 1.0 / (self.vocabulary.len() as f64 + 1.0);
+    let mut order = 0;
+    let longest = context.len().saturating_sub(self.order - 1);
+    for start in (longest..=context.len()).rev() {
+      let context = &context[start..];
+      let followers = match self.contexts.get(context) {
+        Some(followers) => followers,
+        None => break
+      };
+      let count = followers.counts.get(token).cloned().unwrap_or(0);
+      if count > 0 {
+        order = context.len() + 1;
+      }
+      let adjusted = good_turing_count(&self.good_turing[context.len() + 1], count);
+      probability = (adjusted + followers.reserved * probability) / followers.total as f64;
+    }
+    (probability, order)
+  }
+
+  /// Returns the log-probability of each token of a line, followed by
+  /// the log-probability of the line ending.
+  fn token_scores(&self, line: &[K]) -> Vec<f64> {
+    let padded: Vec<K> =
+      once(K::NULL).chain(line.iter().cloned()).chain(once(K::NULL)).collect();
+    (1..padded.len())
+      .map(|i| {
+        let start = i.saturating_sub(self.order - 1);
+        self.probability(&padded[start..i], &padded[i]).ln()
+      })
+      .collect()
+  }
+
+  /// Returns the natural logarithm of the probability of a line: the sum
+  /// of the log-probabilities of its tokens, each after the tokens before
+  /// it, and of the line ending where it does.
+  pub fn log_probability(&self, line: &[K]) -> f64 {
+    self.token_scores(line).iter().sum()
+  }
+
+  /// Returns the `k` most probable tokens to follow `context`, with their
+  /// probabilities, most probable first. `Token::Null`, or `TokenId::NULL`
+  /// in a model of ids, stands for the end of the line. Every token of the vocabulary is considered, so this
+  /// takes time proportional to its size.
+  pub fn continuations(&self, context: &[K], k: usize) -> Vec<(K, f64)> {
+    let mut continuations: Vec<(K, f64)> = self.vocabulary.iter()
+      .map(|token| (*token, self.probability(context, token)))
+      .collect();
+    continuations.sort_by(|a, b| {
+      b.1.partial_cmp(&a.1).unwrap_or(::std::cmp::Ordering::Equal).then(a.0.cmp(&b.0))
+    });
+    continuations.truncate(k);
+    continuations
+  }
+}
+
+impl<'t, L: 't + Language> Model<'t, L, TokenId> {
+  /// Trains a model of the given order on lines of ids of an
+  /// [`Interner`], such as those of an [`IdDocument`], as [`train`] does
+  /// on lines of tokens.
+  ///
+  /// [`Interner`]: ../struct.Interner.html
+  /// [`IdDocument`]: ../type.IdDocument.html
+  /// [`train`]: #method.train
+  pub fn train_ids<'d, I>(lines: I, order: usize) -> Model<'t, L, TokenId>
+    where I: IntoIterator<Item=&'d [TokenId]>
+  {
+    let mut model = Model::empty(order.max(1), Smoothing::WittenBell);
+    model.update_ids(lines);
+    model
+  }
+
+  /// Folds the counts of further lines of ids into the model, as
+  /// [`update`] does with lines of tokens. The ids must be of the same
+  /// interner as those the model was trained on.
+  ///
+  /// [`update`]: #method.update
+  pub fn update_ids<'d, I>(&mut self, lines: I)
+    where I: IntoIterator<Item=&'d [TokenId]>
+  {
+    for line in lines {
+      self.count(line);
+    }
+    self.fit_good_turing();
+  }
+}
+
+impl<'t, L: 't + Language> Model<'t, L> {
+  /// Reports how the model scores each token of a line, and the line
+  /// ending, for finding out why a line scores poorly.
+  ///
+  /// ```rust
+  /// let diagnosis = model.diagnose(line);
+  /// println!("{}", diagnosis);
+  /// for token in diagnosis.tokens.iter().filter(|token| token.oov) {
+  ///   println!("out of vocabulary: {}", token.token);
+  /// }
+  /// ```
+  pub fn diagnose(&self, line: &[Token<'t, L>]) -> Diagnosis<'t, L> {
+    let padded: Vec<Token<'t, L>> =
+      once(Token::Null).chain(line.iter().cloned()).chain(once(Token::Null)).collect();
+    let tokens = (1..padded.len())
+      .map(|i| {
+        let start = i.saturating_sub(self.order - 1);
+        let (probability, order) = self.estimate(&padded[start..i], &padded[i]);
+        TokenDiagnosis {
+          token: padded[i],
+          log_probability: probability.ln(),
+          order: order,
+          oov: !self.vocabulary.contains(&padded[i]),
+        }
+      })
+      .collect();
+    Diagnosis { tokens: tokens }
+  }
+
+  /// Returns the perplexity of the model on lines of tokens: the
+  /// exponential of the negated mean log-probability of their tokens and
+  /// line endings, as measured by [`eval::cross_entropy`].
+  ///
+  /// [`eval::cross_entropy`]: ../eval/fn.cross_entropy.html
+  pub fn perplexity<I>(&self, lines: I) -> f64
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    eval::cross_entropy(self, lines).perplexity()
+  }
+
+  /// Ranks candidate lines, such as paraphrases or OCR hypotheses, by
+  /// fluency: their log-probability divided by the length penalty
+  /// `((5 + n) / 6)^alpha` of Wu et al. (2016), where `n` is the number
+  /// of tokens scored (including the line ending). An `alpha` of `0`
+  /// ranks by raw log-probability, which favours short candidates, and
+  /// an `alpha` of `1` roughly by mean log-probability per token.
+  ///
+  /// Returns the index and normalized score of each candidate, most
+  /// fluent first; ties keep the order of the candidates.
+  ///
+  /// ```rust
+  /// let candidates: Document<English> = "the cat sat on the mat\n\
+  ///                                      cat the on sat mat the".into();
+  /// let ranking = model.rank(candidates.lines(), 0.6);
+  /// assert_eq!(ranking[0].0, 0);
+  /// ```
+  pub fn rank<I>(&self, candidates: I, alpha: f64) -> Vec<(usize, f64)>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let mut ranking: Vec<(usize, f64)> = candidates.into_iter().enumerate()
+      .map(|(i, candidate)| {
+        let scores = self.token_scores(&candidate.as_tokens());
+        let penalty = ((5.0 + scores.len() as f64) / 6.0).powf(alpha);
+        (i, scores.iter().sum::<f64>() / penalty)
+      })
+      .collect();
+    ranking.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(::std::cmp::Ordering::Equal));
+    ranking
+  }
+
+  /// Samples a line from the model, drawing each token in proportion to
+  /// its probability after the tokens before it, and returns the first
+  /// line satisfying the constraints, or `None` if none was drawn within
+  /// their number of attempts. The random number generator is taken by
+  /// value; seeding it makes the output reproducible.
+  ///
+  /// Tokens that would violate the constraints as they are drawn, such as
+  /// the end of the line before the least length, are never drawn; lines
+  /// missing a required token are drawn again.
+  ///
+  /// ```rust
+  /// let constraints = Constraints::new()
+  ///   .with_length(5, 12)
+  ///   .with_required(vec![Token::from(&b"dog"[..])]);
+  /// let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+  /// if let Some(line) = model.generate_with(&constraints, &mut rng) {
+  ///   println!("{}", line.iter().join(" "));
+  /// }
+  /// ```
+  pub fn generate_with<R: Rng>(&self, constraints: &Constraints<'t, L>, mut rng: R)
+      -> Option<Vec<Token<'t, L>>>
+  {
+    // The vocabulary is ordered so that seeded generators draw the same
+    // lines on every run.
+    let mut vocabulary: Vec<Token<'t, L>> = self.vocabulary.iter().cloned().collect();
+    vocabulary.sort();
+    'attempts: for _ in 0..constraints.attempts {
+      let mut line = vec![Token::Null];
+      loop {
+        let length = line.len() - 1;
+        let weights: Vec<f64> = vocabulary.iter()
+          .map(|token| {
+            let allowed = match *token {
+              Token::Null => length >= constraints.min_length,
+              Token::Unknown => constraints.unknown && length < constraints.max_length,
+              Token::Word(_) => length < constraints.max_length,
+            };
+            if allowed { self.probability(&line, token) } else { 0.0 }
+          })
+          .collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+          continue 'attempts;
+        }
+        let mut threshold = rng.gen::<f64>() * total;
+        let mut next = Token::Null;
+        for (token, &weight) in vocabulary.iter().zip(&weights) {
+          if weight > 0.0 {
+            next = *token;
+            threshold -= weight;
+            if threshold < 0.0 {
+              break;
+            }
+          }
+        }
+        if next == Token::Null {
+          break;
+        }
+        line.push(next);
+      }
+      line.remove(0);
+      if constraints.required.iter().all(|token| line.contains(token)) {
+        return Some(line);
+      }
+    }
+    None
+  }
+
+  /// Samples a line of one to `max_length` tokens from the model, as
+  /// [`generate_with`] does with no other constraints: each token is
+  /// drawn in proportion to its probability after the tokens before it,
+  /// until the end of the line is drawn. A model of order `1` draws every
+  /// token independently of the tokens before it. Lines are drawn again
+  /// until one ends within `max_length` tokens, up to a hundred times;
+  /// `None` is returned if none does, as may happen under
+  /// `MaximumLikelihood` smoothing.
+  ///
+  /// ```rust
+  /// let model = Model::train(&training, 3);
+  /// let mut rng = rand::thread_rng();
+  /// for _ in 0..10 {
+  ///   if let Some(line) = model.generate(&mut rng, 30) {
+  ///     println!("{}", line.iter().join(" "));
+  ///   }
+  /// }
+  /// ```
+  ///
+  /// [`generate_with`]: #method.generate_with
+  pub fn generate<R: Rng>(&self, rng: R, max_length: usize) -> Option<Vec<Token<'t, L>>> {
+    self.generate_with(&Constraints::new().with_length(1, max_length), rng)
+  }
+
+  /// Writes the `k` most probable continuations of every context observed
+  /// in training, shortest contexts first, one per row of
+  /// tab-separated context, continuation and probability. Line
+  /// boundaries are written as `<s>` in contexts and `</s>` as
+  /// continuations.
+  ///
+  /// ```rust
+  /// model.dump(&mut File::create("continuations.tsv")?, 5)?;
+  /// ```
+  pub fn dump<W: io::Write>(&self, writer: &mut W, k: usize) -> io::Result<()> {
+    let mut contexts: Vec<&Vec<Token<'t, L>>> = self.contexts.keys().collect();
+    contexts.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+    for context in contexts {
+      let history = context.iter()
+        .map(|token| name(token, "<s>"))
+        .collect::<Vec<_>>()
+        .join(" ");
+      for (token, probability) in self.continuations(context, k) {
+        writeln!(writer, "{}\t{}\t{:.6}", history, name(&token, "</s>"), probability)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The serialized form of a model: its order, its smoothing, and the
+/// count of every token after every context. The other tables of the
+/// model are recomputed from the counts when it is read.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Model", bound = "")]
+struct SerializedModel<'t, L> {
+  order: usize,
+  smoothing: Smoothing,
+  #[serde(borrow)]
+  counts: Vec<(Vec<Token<'t, L>>, TokenCounts<'t, L>)>,
+}
+
+#[cfg(feature = "serde")]
+type TokenCounts<'t, L> = Vec<(Token<'t, L>, usize)>;
+
+#[cfg(feature = "serde")]
+impl<'t, L: 't + Language> ::serde::Serialize for Model<'t, L> {
+  fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let model = SerializedModel {
+      order: self.order,
+      smoothing: self.smoothing,
+      counts: self.contexts.iter()
+        .map(|(context, followers)| {
+          (context.clone(), followers.counts.iter().map(|(&token, &count)| (token, count)).collect())
+        })
+        .collect(),
+    };
+    ::serde::Serialize::serialize(&model, serializer)
+  }
+}
+
+/// Tokens are read as by the `Deserialize` implementation of `Token`,
+/// borrowing their words from the input.
+#[cfg(feature = "serde")]
+impl<'de: 't, 't, L: 't + Language> ::serde::Deserialize<'de> for Model<'t, L> {
+  fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Model<'t, L>, D::Error> {
+    use serde::de::Error;
+    let serialized: SerializedModel<'t, L> = ::serde::Deserialize::deserialize(deserializer)?;
+    if serialized.order == 0 {
+      return Err(D::Error::custom("model of order zero"));
+    }
+    let mut model = Model::empty(serialized.order, serialized.smoothing);
+    for (context, counts) in serialized.counts {
+      if context.len() >= model.order {
+        return Err(D::Error::custom("context too long for the order of the model"));
+      }
+      model.vocabulary.extend(context.iter().cloned());
+      for (token, count) in counts {
+        let followers = model.contexts.entry(context.clone()).or_insert_with(Followers::new);
+        if count == 0 || followers.counts.contains_key(&token) {
+          return Err(D::Error::custom("zero or repeated count"));
+        }
+        followers.total += count;
+        followers.counts.insert(token, count);
+        followers.buckets[count.min(3) - 1] += 1;
+        model.count_of_counts[context.len() + 1][count.min(5) - 1] += 1;
+        model.vocabulary.insert(token);
+        if !context.is_empty() {
+          let count = model.continuations.entry(context[1..].to_vec())
+            .or_insert_with(Followers::new)
+            .add(token);
+          shift(&mut model.continuation_count_of_counts[context.len()], count);
+        }
+      }
+    }
+    model.fit_good_turing();
+    Ok(model)
+  }
+}
+
+/// Returns the text of a token, naming the `Null` token `boundary`.
+fn name<L>(token: &Token<L>, boundary: &str) -> String {
+  match *token {
+    Token::Null => String::from(boundary),
+    ref other => other.to_string(),
+  }
+}
+
+/// How often a repeated line is counted in training a [`Model`]. Web
+/// corpora often repeat boilerplate lines thousands of times, which
+/// would otherwise dominate the estimates of the tokens they contain.
+///
+/// [`Model`]: struct.Model.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repetition {
+  /// Count every occurrence of every line.
+  Keep,
+  /// Count each distinct line once.
+  Dedupe,
+  /// Count each distinct line at most the given number of times.
+  Cap(usize),
+}
+
+/// How many lines and tokens a [`Repetition`] policy skipped.
+///
+/// [`Repetition`]: enum.Repetition.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepetitionReport {
+  /// The number of lines given.
+  pub lines: usize,
+  /// The number of distinct lines given, or zero under
+  /// `Repetition::Keep`, which does not compare lines.
+  pub distinct: usize,
+  /// The number of tokens given.
+  pub tokens: usize,
+  /// The number of lines not counted.
+  pub skipped_lines: usize,
+  /// The number of tokens of the lines not counted.
+  pub skipped_tokens: usize,
+}
+
+impl RepetitionReport {
+  /// Returns the fraction of the tokens given that were not counted.
+  pub fn skipped_share(&self) -> f64 {
+    if self.tokens == 0 { 0.0 } else { self.skipped_tokens as f64 / self.tokens as f64 }
+  }
+}
+
+/// Constraints on the lines drawn by [`Model::generate_with`].
+///
+/// [`Model::generate_with`]: struct.Model.html#method.generate_with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraints<'t, L> {
+  min_length: usize,
+  max_length: usize,
+  required: Vec<Token<'t, L>>,
+  unknown: bool,
+  attempts: usize,
+}
+
+impl<'t, L> Constraints<'t, L> {
+  /// Creates constraints allowing lines of one to fifty tokens, with no
+  /// required tokens and no `Token::Unknown`, drawn at most a hundred
+  /// times.
+  pub fn new() -> Constraints<'t, L> {
+    Constraints { min_length: 1, max_length: 50, required: vec![], unknown: false, attempts: 100 }
+  }
+
+  /// Sets the least and greatest number of tokens of a line.
+  pub fn with_length(mut self, min: usize, max: usize) -> Constraints<'t, L> {
+    self.min_length = min;
+    self.max_length = max;
+    self
+  }
+
+  /// Sets tokens that every line must include.
+  pub fn with_required<I>(mut self, tokens: I) -> Constraints<'t, L>
+    where I: IntoIterator<Item=Token<'t, L>>
+  {
+    self.required = tokens.into_iter().collect();
+    self
+  }
+
+  /// Sets whether lines may include `Token::Unknown`.
+  pub fn with_unknown(mut self, unknown: bool) -> Constraints<'t, L> {
+    self.unknown = unknown;
+    self
+  }
+
+  /// Sets how many lines are drawn before giving up.
+  pub fn with_attempts(mut self, attempts: usize) -> Constraints<'t, L> {
+    self.attempts = attempts;
+    self
+  }
+}
+
+impl<'t, L> Default for Constraints<'t, L> {
+  fn default() -> Constraints<'t, L> {
+    Constraints::new()
+  }
+}
+
+/// How a model scored one token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenDiagnosis<'t, L> {
+  /// The token scored, or `Token::Null` for the end of the line.
+  pub token: Token<'t, L>,
+  pub log_probability: f64,
+  /// The length of the longest n-gram ending in the token that was
+  /// observed in training: the model's order if the full context was
+  /// seen with the token, less if it backed off to shorter contexts, and
+  /// `0` if the token was never seen at all.
+  pub order: usize,
+  /// Whether the token is missing from the vocabulary of the model.
+  pub oov: bool,
+}
+
+/// How a model scored each token of a line, followed by the line ending.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnosis<'t, L> {
+  pub tokens: Vec<TokenDiagnosis<'t, L>>,
+}
+
+impl<'t, L> Diagnosis<'t, L> {
+  /// Returns the log-probability of the line.
+  pub fn log_probability(&self) -> f64 {
+    self.tokens.iter().map(|token| token.log_probability).sum()
+  }
+
+  /// Returns the perplexity of the model on the line.
+  pub fn perplexity(&self) -> f64 {
+    if self.tokens.is_empty() { 1.0 }
+    else { (-self.log_probability() / self.tokens.len() as f64).exp() }
+  }
+}
+
+impl<'t, L> fmt::Display for Diagnosis<'t, L> {
+  /// Formats the diagnosis as a table, with a row for each token giving
+  /// its log-probability, the order of the n-gram it was found in, and
+  /// whether it is out of vocabulary, and a final row for the line.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for token in &self.tokens {
+      writeln!(f, "{}\t{:.4}\t{}-gram{}", name(&token.token, "</s>"),
+               token.log_probability, token.order,
+               if token.oov { "\tOOV" } else { "" })?;
+    }
+    write!(f, "total\t{:.4}\tperplexity {:.4}", self.log_probability(), self.perplexity())
+  }
+}
+
+/// A linear interpolation of language models, such as models of several
+/// domains: the probability of a token is the weighted sum of its
+/// probabilities under each model.
+///
+/// ```rust
+/// let news = Model::train(&news, 3);
+/// let fiction = Model::train(&fiction, 3);
+/// let mut mixture = Mixture::new(vec![news, fiction]);
+/// mixture.tune(held_out.lines(), 20);
+/// for (domain, report) in mixture.report(held_out.lines()).iter().enumerate() {
+///   println!("{}: weight {:.3}, share {:.3}, perplexity {:.1}",
+///            domain, report.weight, report.share, report.perplexity);
+/// }
+/// ```
+pub struct Mixture<'t, L: Language> {
+  models: Vec<Model<'t, L>>,
+  weights: Vec<f64>,
+}
+
+/// How one model of a [`Mixture`] contributes to its scores on some
+/// lines.
+///
+/// [`Mixture`]: struct.Mixture.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DomainReport {
+  /// The interpolation weight of the model.
+  pub weight: f64,
+  /// The share of the probability of the lines' tokens due to the model:
+  /// the mean, over tokens, of the model's weighted probability divided
+  /// by the mixture's probability.
+  pub share: f64,
+  /// The perplexity of the model alone on the lines.
+  pub perplexity: f64,
+}
+
+impl<'t, L: 't + Language> Mixture<'t, L> {
+  /// Interpolates models with equal weights.
+  pub fn new(models: Vec<Model<'t, L>>) -> Mixture<'t, L> {
+    let weights = vec![1.0 / models.len() as f64; models.len()];
+    Mixture { models: models, weights: weights }
+  }
+
+  /// Interpolates models with the given weights, which are normalized to
+  /// sum to one. Weights missing for some models are taken to be zero.
+  pub fn with_weights(models: Vec<Model<'t, L>>, weights: &[f64]) -> Mixture<'t, L> {
+    let mut mixture = Mixture::new(models);
+    let total: f64 = weights.iter().take(mixture.models.len()).sum();
+    if total > 0.0 {
+      for (i, weight) in mixture.weights.iter_mut().enumerate() {
+        *weight = weights.get(i).cloned().unwrap_or(0.0) / total;
+      }
+    }
+    mixture
+  }
+
+  /// Trains a model of the given order on each of several corpora, and
+  /// interpolates them with equal weights.
+  pub fn train<I, D>(domains: D, order: usize) -> Mixture<'t, L>
+    where D: IntoIterator<Item=I>,
+          I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    Mixture::new(domains.into_iter().map(|lines| Model::train(lines, order)).collect())
+  }
+
+  /// Returns the models of the mixture.
+  pub fn models(&self) -> &[Model<'t, L>] {
+    &self.models
+  }
+
+  /// Returns the interpolation weight of each model.
+  pub fn weights(&self) -> &[f64] {
+    &self.weights
+  }
+
+  /// Returns the probability of `token` following `context`.
+  pub fn probability(&self, context: &[Token<'t, L>], token: &Token<'t, L>) -> f64 {
+    self.models.iter().zip(&self.weights)
+      .map(|(model, weight)| weight * model.probability(context, token))
+      .sum()
+  }
+
+  /// Returns the probability of each token of each line, and of each line
+  /// ending, under each model.
+  fn component_probabilities<I>(&self, lines: I) -> Vec<Vec<f64>>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let mut probabilities = vec![];
+    for line in lines {
+      let scores: Vec<Vec<f64>> = self.models.iter()
+        .map(|model| model.token_scores(&line.as_tokens()))
+        .collect();
+      let length = scores.first().map_or(0, |s| s.len());
+      for position in 0..length {
+        probabilities.push(scores.iter().map(|s| s[position].exp()).collect());
+      }
+    }
+    probabilities
+  }
+
+  /// Returns the perplexity of the mixture on lines of tokens. Tokens to
+  /// which the mixture gives no probability, as unsmoothed models may,
+  /// are left out, rather than making the perplexity infinite.
+  pub fn perplexity<I>(&self, lines: I) -> f64
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let probabilities = self.component_probabilities(lines);
+    perplexity_of(probabilities.iter().map(|p| self.mix(p)))
+  }
+
+  fn mix(&self, probabilities: &[f64]) -> f64 {
+    probabilities.iter().zip(&self.weights).map(|(p, w)| p * w).sum()
+  }
+
+  /// Tunes the weights to minimize perplexity on held-out lines, with at
+  /// most `iterations` rounds of expectation maximization. The
+  /// probabilities of every held-out token under every model are kept in
+  /// memory while tuning. Tokens to which the mixture gives no
+  /// probability are left out, as no weights could account for them;
+  /// if every token is, the weights are left as they are.
+  pub fn tune<I>(&mut self, lines: I, iterations: usize)
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let probabilities = self.component_probabilities(lines);
+    for _ in 0..iterations {
+      let shares = match self.shares(&probabilities) {
+        Some(shares) => shares,
+        None => return
+      };
+      let change: f64 = shares.iter().zip(&self.weights).map(|(s, w)| (s - w).abs()).sum();
+      self.weights = shares;
+      if change < 1e-9 {
+        break;
+      }
+    }
+  }
+
+  /// Returns the mean share of each model in the probability of each
+  /// token the mixture gives any probability, or `None` if there are no
+  /// such tokens.
+  fn shares(&self, probabilities: &[Vec<f64>]) -> Option<Vec<f64>> {
+    let mut shares = vec![0.0; self.models.len()];
+    let mut count = 0;
+    for p in probabilities {
+      let total = self.mix(p);
+      if total <= 0.0 {
+        continue;
+      }
+      count += 1;
+      for (i, share) in shares.iter_mut().enumerate() {
+        *share += self.weights[i] * p[i] / total;
+      }
+    }
+    if count == 0 {
+      return None;
+    }
+    for share in &mut shares {
+      *share /= count as f64;
+    }
+    Some(shares)
+  }
+
+  /// Reports the contribution of each model to the scores of the mixture
+  /// on lines of tokens. Tokens given no probability are left out of
+  /// the shares and perplexities, as they are by [`tune`] and
+  /// [`perplexity`].
+  ///
+  /// [`tune`]: #method.tune
+  /// [`perplexity`]: #method.perplexity
+  pub fn report<I>(&self, lines: I) -> Vec<DomainReport>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let probabilities = self.component_probabilities(lines);
+    let shares = self.shares(&probabilities).unwrap_or_else(|| vec![0.0; self.models.len()]);
+    (0..self.models.len())
+      .map(|i| {
+        DomainReport {
+          weight: self.weights[i],
+          share: shares[i],
+          perplexity: perplexity_of(probabilities.iter().map(|p| p[i])),
+        }
+      })
+      .collect()
+  }
+}
+
+/// Returns the perplexity of a sequence of probabilities, leaving out
+/// those of zero, or `1` if none are left.
+fn perplexity_of<I: IntoIterator<Item=f64>>(probabilities: I) -> f64 {
+  let (total, count) = probabilities.into_iter()
+    .filter(|&p| p > 0.0)
+    .fold((0.0, 0), |(total, count), p| (total + p.ln(), count + 1));
+  if count == 0 { 1.0 } else { (-total / count as f64).exp() }
+}
@@ -0,0 +1,248 @@
+use token::Token;
+use language::Language;
+use corpus::Document;
+
+use rand::Rng;
+use itertools::Itertools;
+
+/// Consumes an iterator over tokens, and produces an iterator in which
+/// each token is independently dropped with probability `rate`. Word
+/// dropout regularizes language models and embeddings by preventing
+/// them from relying too heavily on any single token of context.
+///
+/// The random number generator is taken by value; seeding it makes the
+/// noise reproducible:
+///
+/// ```rust
+/// let rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+/// let noisy = dropout(training.tokens(), 0.1, rng);
+/// ```
+pub fn dropout<'t, T, L, R>(tokens: T, rate: f64, mut rng: R)
+    -> impl Iterator<Item=Token<'t, L>>
+  where L: Language + 't,
+        T: IntoIterator<Item=Token<'t, L>>,
+        R: Rng
+{
+  IntoIterator::into_iter(tokens)
+    .filter(move |_| rng.gen::<f64>() >= rate)
+}
+
+/// Consumes an iterator over tokens, and produces an iterator in which
+/// each token is independently replaced with [`Token::Unknown`] with
+/// probability `rate`.
+/// [`Token::Unknown`]: enum.Token.html#variant.Unknown
+pub fn blanked<'t, T, L, R>(tokens: T, rate: f64, mut rng: R)
+    -> impl Iterator<Item=Token<'t, L>>
+  where L: Language + 't,
+        T: IntoIterator<Item=Token<'t, L>>,
+        R: Rng
+{
+  IntoIterator::into_iter(tokens)
+    .map(move |token|
+      if rng.gen::<f64>() < rate { Token::Unknown }
+      else { token })
+}
+
+/// Consumes an iterator over tokens, and produces the same tokens in a
+/// locally shuffled order, in which no token moves more than `window`
+/// positions from where it started. Each token at position `i` is
+/// sorted by the key `i + u`, where `u` is drawn uniformly from
+/// `[0, window + 1)`.
+///
+/// Since the whole input is buffered, this adapter is best applied to
+/// one line at a time:
+///
+/// ```rust
+/// let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+/// for line in training.lines() {
+///   let noisy = shuffled(line.iter().cloned(), 3, &mut rng).join(" ");
+///   println!("{}", noisy);
+/// }
+/// ```
+pub fn shuffled<'t, T, L, R>(tokens: T, window: usize, mut rng: R)
+    -> impl Iterator<Item=Token<'t, L>>
+  where L: Language + 't,
+        T: IntoIterator<Item=Token<'t, L>>,
+        R: Rng
+{
+  let mut keyed = IntoIterator::into_iter(tokens)
+    .enumerate()
+    .map(|(i, token)|
+      (i as f64 + rng.gen::<f64>() * (window as f64 + 1.0), token))
+    .collect_vec();
+  keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+  keyed.into_iter().map(|(_, token)| token)
+}
+
+/// Character-level perturbations of the words of a document, for testing
+/// how robust a model is to noisy input: keyboard typos, flipped case,
+/// removed diacritics, and homoglyphs. Each perturbation is applied to
+/// each character independently with its own rate, which is `0` unless
+/// set, so that a perturber makes no changes until configured.
+///
+/// The random number generator is taken by value; seeding it makes the
+/// perturbations reproducible:
+///
+/// ```rust
+/// let perturber = Perturber::new()
+///   .with_typos(0.02)
+///   .with_case_flips(0.01)
+///   .with_homoglyphs(0.01);
+/// let rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+/// let noisy: Document<English> = perturber.perturb(&testing, rng);
+/// println!("perplexity on noisy text: {}", model.perplexity(&noisy));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Perturber {
+  typos: f64,
+  case_flips: f64,
+  diacritics: f64,
+  homoglyphs: f64,
+}
+
+impl Perturber {
+  /// Creates a perturber making no changes.
+  pub fn new() -> Perturber {
+    Perturber::default()
+  }
+
+  /// Replaces each letter, with probability `rate`, by a letter of an
+  /// adjacent key of a QWERTY keyboard, keeping its case.
+  pub fn with_typos(mut self, rate: f64) -> Perturber {
+    self.typos = rate;
+    self
+  }
+
+  /// Changes the case of each letter with probability `rate`.
+  pub fn with_case_flips(mut self, rate: f64) -> Perturber {
+    self.case_flips = rate;
+    self
+  }
+
+  /// Replaces each Latin letter with a diacritic, with probability
+  /// `rate`, by the letter without it: `é` by `e`, and `Ł` by `L`. A rate
+  /// of `1` strips every diacritic.
+  pub fn with_diacritics_removed(mut self, rate: f64) -> Perturber {
+    self.diacritics = rate;
+    self
+  }
+
+  /// Replaces each Latin letter with a Cyrillic lookalike, with
+  /// probability `rate`, such as `a` by `а` (U+0430), which looks the
+  /// same but is a different character.
+  pub fn with_homoglyphs(mut self, rate: f64) -> Perturber {
+    self.homoglyphs = rate;
+    self
+  }
+
+  /// Perturbs the characters of a word. Diacritics are removed first, so
+  /// that the bare letters can be perturbed further.
+  pub fn perturb_word<R: Rng>(&self, word: &str, mut rng: R) -> String {
+    let mut perturbed = String::with_capacity(word.len());
+    for c in word.chars() {
+      let mut c = c;
+      if let Some(bare) = bare(c) {
+        if rng.gen::<f64>() < self.diacritics {
+          c = bare;
+        }
+      }
+      if let Some(neighbours) = neighbours(c.to_ascii_lowercase()) {
+        if rng.gen::<f64>() < self.typos {
+          let typo = neighbours[rng.gen_range(0, neighbours.len())] as char;
+          c = if c.is_ascii_uppercase() { typo.to_ascii_uppercase() } else { typo };
+        }
+      }
+      if let Some(flipped) = flipped(c) {
+        if rng.gen::<f64>() < self.case_flips {
+          c = flipped;
+        }
+      }
+      if let Some(homoglyph) = homoglyph(c) {
+        if rng.gen::<f64>() < self.homoglyphs {
+          c = homoglyph;
+        }
+      }
+      perturbed.push(c);
+    }
+    perturbed
+  }
+
+  /// Perturbs every word of a document, and returns the perturbed words
+  /// as a new document, with the words of each line separated by spaces.
+  pub fn perturb<L, R: Rng>(&self, document: &Document<L>, mut rng: R) -> Document<L> {
+    let lines: Vec<String> = document.lines().iter()
+      .map(|line| {
+        line.iter()
+          .map(|token| self.perturb_word(&token.to_string(), &mut rng))
+          .join(" ")
+      })
+      .collect();
+    lines.join("\n").into()
+  }
+}
+
+/// Returns the letters of the keys adjacent to a lowercase letter on a
+/// QWERTY keyboard.
+fn neighbours(c: char) -> Option<&'static [u8]> {
+  let neighbours: &'static [u8] = match c {
+    'q' => b"wa", 'w' => b"qeas", 'e' => b"wrsd", 'r' => b"etdf", 't' => b"ryfg",
+    'y' => b"tugh", 'u' => b"yihj", 'i' => b"uojk", 'o' => b"ipkl", 'p' => b"ol",
+    'a' => b"qwsz", 's' => b"awedxz", 'd' => b"serfcx", 'f' => b"drtgvc", 'g' => b"ftyhbv",
+    'h' => b"gyujnb", 'j' => b"huikmn", 'k' => b"jiolm", 'l' => b"kop",
+    'z' => b"asx", 'x' => b"zsdc", 'c' => b"xdfv", 'v' => b"cfgb", 'b' => b"vghn",
+    'n' => b"bhjm", 'm' => b"njk",
+    _ => return None
+  };
+  Some(neighbours)
+}
+
+/// Returns a letter in the other case, if it has exactly one.
+fn flipped(c: char) -> Option<char> {
+  let flipped: Vec<char> = if c.is_lowercase() { c.to_uppercase().collect() }
+    else if c.is_uppercase() { c.to_lowercase().collect() }
+    else { return None };
+  match flipped[..] {
+    [flipped] => Some(flipped),
+    _ => None
+  }
+}
+
+/// Returns a Latin letter with a diacritic without it, keeping its case.
+fn bare(c: char) -> Option<char> {
+  let lower = flipped(c).filter(|_| c.is_uppercase()).unwrap_or(c);
+  let bare = match lower {
+    'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+    'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+    'ď' | 'đ' => 'd',
+    'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+    'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+    'ĥ' | 'ħ' => 'h',
+    'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' => 'i',
+    'ĵ' => 'j',
+    'ķ' => 'k',
+    'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+    'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+    'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+    'ŕ' | 'ŗ' | 'ř' => 'r',
+    'ś' | 'ŝ' | 'ş' | 'š' => 's',
+    'ţ' | 'ť' | 'ŧ' => 't',
+    'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+    'ŵ' => 'w',
+    'ý' | 'ÿ' | 'ŷ' => 'y',
+    'ź' | 'ż' | 'ž' => 'z',
+    _ => return None
+  };
+  Some(if c.is_uppercase() { bare.to_ascii_uppercase() } else { bare })
+}
+
+/// Returns the Cyrillic lookalike of a Latin letter.
+fn homoglyph(c: char) -> Option<char> {
+  Some(match c {
+    'a' => 'а', 'c' => 'с', 'e' => 'е', 'i' => 'і', 'j' => 'ј', 'o' => 'о',
+    'p' => 'р', 's' => 'ѕ', 'x' => 'х', 'y' => 'у',
+    'A' => 'А', 'B' => 'В', 'C' => 'С', 'E' => 'Е', 'H' => 'Н', 'I' => 'І',
+    'J' => 'Ј', 'K' => 'К', 'M' => 'М', 'O' => 'О', 'P' => 'Р', 'S' => 'Ѕ',
+    'T' => 'Т', 'X' => 'Х', 'Y' => 'Ү',
+    _ => return None
+  })
+}
@@ -0,0 +1,193 @@
+use language::DefaultLanguage;
+use corpus::{Document, Tokenizer, WhitespaceTokenizer};
+use stream::{OwnedToken, tokenize_line};
+
+use futures_core::{Future, Stream};
+use futures_io::{AsyncRead, AsyncBufRead};
+
+use std::io;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::marker::PhantomData;
+
+/// The number of bytes read from the reader of a [`ReadDocument`] at a
+/// time.
+///
+/// [`ReadDocument`]: struct.ReadDocument.html
+const CHUNK: usize = 8 * 1024;
+
+impl<L> Document<L> {
+  /// Creates a future reading a document from a value implementing the
+  /// [`AsyncRead`] trait, such as the body of an HTTP response, until all
+  /// bytes have been read, as [`from_reader`] does without blocking.
+  ///
+  /// ```rust
+  /// let response = client.get(url).send().await?;
+  /// let body = response.bytes_stream().map_err(to_io_error).into_async_read();
+  /// let document: Document<English> = Document::from_async_reader(body).await?;
+  /// ```
+  ///
+  /// Readers of Tokio implement its own `AsyncRead` trait rather than
+  /// that of `futures`, and may be adapted by the `compat` module of the
+  /// `tokio-util` crate.
+  ///
+  /// [`AsyncRead`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncRead.html
+  /// [`from_reader`]: #method.from_reader
+  pub fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> ReadDocument<R, L> {
+    ReadDocument {
+      reader: reader,
+      bytes: vec![],
+      language: PhantomData,
+    }
+  }
+}
+
+/// A future reading a [`Document`] from an asynchronous reader, created by
+/// [`Document::from_async_reader`].
+///
+/// [`Document`]: struct.Document.html
+/// [`Document::from_async_reader`]: struct.Document.html#method.from_async_reader
+pub struct ReadDocument<R, L=DefaultLanguage> {
+  reader: R,
+  bytes: Vec<u8>,
+  language: PhantomData<L>,
+}
+
+impl<R, L> Unpin for ReadDocument<R, L> where R: Unpin {}
+
+impl<R: AsyncRead + Unpin, L: 'static> Future for ReadDocument<R, L> {
+  type Output = io::Result<Document<L>>;
+
+  fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<Document<L>>> {
+    let this = &mut *self;
+    loop {
+      let length = this.bytes.len();
+      this.bytes.resize(length + CHUNK, 0);
+      let read = Pin::new(&mut this.reader).poll_read(context, &mut this.bytes[length..]);
+      match read {
+        Poll::Ready(Ok(read)) => {
+          this.bytes.truncate(length + read);
+          if read == 0 {
+            return Poll::Ready(Ok(mem::take(&mut this.bytes).into()));
+          }
+        },
+        Poll::Ready(Err(error)) => {
+          this.bytes.truncate(length);
+          if error.kind() != io::ErrorKind::Interrupted {
+            return Poll::Ready(Err(error));
+          }
+        },
+        Poll::Pending => {
+          this.bytes.truncate(length);
+          return Poll::Pending;
+        }
+      }
+    }
+  }
+}
+
+/// A lazy tokenizer of the lines of an asynchronous reader in language
+/// `L`, as a [`TokenStream`] is of a blocking one: a [`Stream`] of the
+/// tokens of each line, as [`OwnedToken`]s.
+///
+/// Lines are read only as the stream is polled, so a consumer slower than
+/// the reader applies backpressure to it: no more than the current line
+/// and the reader's buffer are held in memory.
+///
+/// ```rust
+/// let file = BufReader::new(File::open("crawl.txt").await?.compat());
+/// let mut stream: AsyncTokenStream<_, English> = AsyncTokenStream::new(file);
+/// let mut counts = HashMap::new();
+/// while let Some(line) = stream.next().await {
+///   for token in line? {
+///     *counts.entry(token.to_string()).or_insert(0) += 1;
+///   }
+/// }
+/// ```
+///
+/// [`TokenStream`]: struct.TokenStream.html
+/// [`Stream`]: https://docs.rs/futures/0.3/futures/stream/trait.Stream.html
+/// [`OwnedToken`]: struct.OwnedToken.html
+pub struct AsyncTokenStream<R, L=DefaultLanguage, T=WhitespaceTokenizer> {
+  reader: R,
+  tokenizer: T,
+  buffer: Vec<u8>,
+  language: PhantomData<L>,
+}
+
+impl<R, L, T> Unpin for AsyncTokenStream<R, L, T> where R: Unpin {}
+
+impl<R: AsyncBufRead + Unpin, L> AsyncTokenStream<R, L> {
+  /// Creates a stream splitting the lines of a reader into tokens at
+  /// spaces.
+  pub fn new(reader: R) -> AsyncTokenStream<R, L> {
+    AsyncTokenStream::with_tokenizer(reader, WhitespaceTokenizer)
+  }
+}
+
+impl<R: AsyncBufRead + Unpin, L, T: Tokenizer> AsyncTokenStream<R, L, T> {
+  /// Creates a stream splitting the lines of a reader into tokens with a
+  /// [`Tokenizer`].
+  ///
+  /// [`Tokenizer`]: trait.Tokenizer.html
+  pub fn with_tokenizer(reader: R, tokenizer: T) -> AsyncTokenStream<R, L, T> {
+    AsyncTokenStream {
+      reader: reader,
+      tokenizer: tokenizer,
+      buffer: vec![],
+      language: PhantomData,
+    }
+  }
+
+  /// Consumes the stream, returning its reader. Bytes of a line of which
+  /// the stream has read only part are lost.
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+}
+
+impl<R: AsyncBufRead + Unpin, L, T: Tokenizer> Stream for AsyncTokenStream<R, L, T> {
+  type Item = io::Result<Vec<OwnedToken<L>>>;
+
+  fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<io::Result<Vec<OwnedToken<L>>>>> {
+    let this = &mut *self;
+    // The buffer holds the part of the line read before the reader last
+    // returned `Pending`.
+    loop {
+      let (found, used) = {
+        let available = match Pin::new(&mut this.reader).poll_fill_buf(context) {
+          Poll::Ready(Ok(available)) => available,
+          Poll::Ready(Err(ref error)) if error.kind() == io::ErrorKind::Interrupted => continue,
+          Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(error))),
+          Poll::Pending => return Poll::Pending
+        };
+        if available.is_empty() {
+          if this.buffer.is_empty() {
+            return Poll::Ready(None);
+          }
+          (true, 0)
+        } else {
+          match available.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+              this.buffer.extend_from_slice(&available[..=i]);
+              (true, i + 1)
+            },
+            None => {
+              this.buffer.extend_from_slice(available);
+              (false, available.len())
+            }
+          }
+        }
+      };
+      Pin::new(&mut this.reader).consume(used);
+      if found {
+        let tokens = tokenize_line::<L, T>(&mut this.buffer, &this.tokenizer).into_iter()
+          .map(OwnedToken::from)
+          .collect();
+        this.buffer.clear();
+        return Poll::Ready(Some(Ok(tokens)));
+      }
+    }
+  }
+}
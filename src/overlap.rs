@@ -0,0 +1,228 @@
+use token::Token;
+use span::Span;
+use language::Language;
+
+use std::hash::{Hash, Hasher};
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+
+/// A hashed n-gram selected to represent a document, and the position of
+/// its first token.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fingerprint {
+  pub hash: u64,
+  pub position: usize,
+}
+
+/// A passage shared by two documents: the tokens of `source` in the
+/// first document are the tokens of `target` in the second.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Passage {
+  pub source: Span,
+  pub target: Span,
+}
+
+/// The passages shared by a pair of documents of a collection, given by
+/// their indices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairOverlap {
+  pub source: usize,
+  pub target: usize,
+  /// The share of the fingerprints of either document that both share.
+  pub similarity: f64,
+  pub passages: Vec<Passage>,
+}
+
+/// A detector of passages shared between documents, for screening
+/// collections for duplicated content and plagiarism.
+///
+/// Documents are fingerprinted by winnowing, after Schleimer, Wilkerson
+/// and Aiken: every n-gram of tokens is hashed, and the least hash of
+/// every window of consecutive hashes is kept. Any passage of at least
+/// `n + window - 1` tokens shared by two documents is guaranteed to
+/// share a fingerprint, which is then extended to the whole of the
+/// passage by comparing the tokens of the documents.
+///
+/// ```rust
+/// let detector = OverlapDetector::new(5, 4);
+/// for passage in detector.passages(&essay.tokens().to_vec(), &source.tokens().to_vec()) {
+///   println!("essay {} copies source {}", passage.source, passage.target);
+/// }
+/// ```
+///
+/// Tokens are compared exactly; to detect passages differing in case or
+/// punctuation, normalize the documents first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlapDetector {
+  n: usize,
+  window: usize,
+}
+
+impl OverlapDetector {
+  /// Creates a detector hashing n-grams of `n` tokens and keeping one
+  /// fingerprint per `window` consecutive n-grams. Values below `1` are
+  /// treated as `1`.
+  pub fn new(n: usize, window: usize) -> OverlapDetector {
+    OverlapDetector { n: n.max(1), window: window.max(1) }
+  }
+
+  /// Returns the number of tokens of the shortest passage that is
+  /// guaranteed to be detected.
+  pub fn guarantee(&self) -> usize {
+    self.n + self.window - 1
+  }
+
+  /// Returns the fingerprints of a sequence of tokens, in order of
+  /// position. Where a window has several least hashes, the rightmost is
+  /// kept, and a fingerprint kept by consecutive windows is kept once.
+  pub fn fingerprint<L: Language>(&self, tokens: &[Token<L>]) -> Vec<Fingerprint> {
+    let hashes: Vec<u64> = tokens.windows(self.n)
+      .map(|ngram| {
+        let mut hasher = DefaultHasher::new();
+        ngram.hash(&mut hasher);
+        hasher.finish()
+      })
+      .collect();
+    let mut fingerprints: Vec<Fingerprint> = vec![];
+    if hashes.is_empty() {
+      return fingerprints;
+    }
+    let window = self.window.min(hashes.len());
+    for start in 0..=hashes.len() - window {
+      let mut least = start;
+      for i in start..start + window {
+        if hashes[i] <= hashes[least] {
+          least = i;
+        }
+      }
+      if fingerprints.last().map(|fingerprint| fingerprint.position) != Some(least) {
+        fingerprints.push(Fingerprint { hash: hashes[least], position: least });
+      }
+    }
+    fingerprints
+  }
+
+  /// Returns the share of the distinct fingerprints of either sequence of
+  /// tokens that both share.
+  pub fn similarity<L: Language>(&self, source: &[Token<L>], target: &[Token<L>]) -> f64 {
+    resemblance(&self.fingerprint(source), &self.fingerprint(target))
+  }
+
+  /// Returns the passages shared by two sequences of tokens, such as the
+  /// tokens of two documents, ordered by their position in `source`. Each
+  /// passage is as long as the sequences allow.
+  pub fn passages<L: Language>(&self, source: &[Token<L>], target: &[Token<L>]) -> Vec<Passage> {
+    self.extend(source, target, &self.fingerprint(source), &self.fingerprint(target))
+  }
+
+  /// Compares every pair of documents of a collection, returning the
+  /// pairs sharing at least one passage, ordered by their indices. Only
+  /// documents sharing a fingerprint are compared.
+  pub fn screen<'t, L: 't + Language>(&self, documents: &[&'t [Token<'t, L>]]) -> Vec<PairOverlap> {
+    let fingerprints: Vec<Vec<Fingerprint>> = documents.iter()
+      .map(|tokens| self.fingerprint(tokens))
+      .collect();
+    let mut holders: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (document, fingerprints) in fingerprints.iter().enumerate() {
+      for fingerprint in fingerprints {
+        let holders = holders.entry(fingerprint.hash).or_default();
+        if holders.last() != Some(&document) {
+          holders.push(document);
+        }
+      }
+    }
+    let mut pairs: HashSet<(usize, usize)> = HashSet::new();
+    for holders in holders.values() {
+      for (i, &source) in holders.iter().enumerate() {
+        for &target in &holders[i + 1..] {
+          pairs.insert((source, target));
+        }
+      }
+    }
+    let mut pairs: Vec<(usize, usize)> = pairs.into_iter().collect();
+    pairs.sort();
+
+    pairs.into_iter()
+      .filter_map(|(source, target)| {
+        let passages = self.extend(documents[source], documents[target],
+                                   &fingerprints[source], &fingerprints[target]);
+        if passages.is_empty() {
+          return None;
+        }
+        Some(PairOverlap {
+          source: source,
+          target: target,
+          similarity: resemblance(&fingerprints[source], &fingerprints[target]),
+          passages: passages,
+        })
+      })
+      .collect()
+  }
+
+  /// Extends each pair of matching fingerprints of two sequences of
+  /// tokens to the whole of the passage they share, discarding matches of
+  /// different n-grams with the same hash.
+  fn extend<L: Language>(&self, source: &[Token<L>], target: &[Token<L>],
+                         source_fingerprints: &[Fingerprint],
+                         target_fingerprints: &[Fingerprint]) -> Vec<Passage>
+  {
+    let mut positions: HashMap<u64, Vec<usize>> = HashMap::new();
+    for fingerprint in target_fingerprints {
+      positions.entry(fingerprint.hash).or_default().push(fingerprint.position);
+    }
+    let mut seeds: Vec<(usize, usize)> = vec![];
+    for fingerprint in source_fingerprints {
+      let i = fingerprint.position;
+      for &j in positions.get(&fingerprint.hash).into_iter().flatten() {
+        if source[i..i + self.n] == target[j..j + self.n] {
+          seeds.push((i, j));
+        }
+      }
+    }
+    // Seeds within one passage share the offset between their positions.
+    seeds.sort_by_key(|&(i, j)| (i as isize - j as isize, i));
+
+    let mut passages: Vec<Passage> = vec![];
+    let mut covered: Option<(isize, usize)> = None;
+    for (i, j) in seeds {
+      let offset = i as isize - j as isize;
+      if let Some((covered_offset, end)) = covered {
+        if covered_offset == offset && i < end {
+          continue;
+        }
+      }
+      let before = source[..i].iter().rev()
+        .zip(target[..j].iter().rev())
+        .take_while(|&(a, b)| a == b)
+        .count();
+      let after = source[i..].iter()
+        .zip(&target[j..])
+        .take_while(|&(a, b)| a == b)
+        .count();
+      passages.push(Passage {
+        source: Span::new(i - before, i + after),
+        target: Span::new(j - before, j + after),
+      });
+      covered = Some((offset, i + after));
+    }
+    passages.sort();
+    passages
+  }
+}
+
+impl Default for OverlapDetector {
+  /// Creates a detector of passages of at least eight tokens, hashing
+  /// 5-grams.
+  fn default() -> OverlapDetector {
+    OverlapDetector::new(5, 4)
+  }
+}
+
+/// Returns the share of the distinct hashes of either set of fingerprints
+/// that both share.
+fn resemblance(source: &[Fingerprint], target: &[Fingerprint]) -> f64 {
+  let source: HashSet<u64> = source.iter().map(|fingerprint| fingerprint.hash).collect();
+  let target: HashSet<u64> = target.iter().map(|fingerprint| fingerprint.hash).collect();
+  let union = source.union(&target).count();
+  if union == 0 { 0.0 } else { source.intersection(&target).count() as f64 / union as f64 }
+}
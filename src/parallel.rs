@@ -0,0 +1,344 @@
+use token::Token;
+use language::Language;
+use corpus::{Document, Line};
+use intern::{Interner, TokenId};
+use tree::escape_dot;
+
+/// A word alignment between a pair of parallel lines. Each `(i, j)` pair
+/// links the `i`th token of the source line to the `j`th token of the
+/// target line.
+pub type Alignment = Vec<(usize, usize)>;
+
+/// A `ParallelCorpus` pairs a document with its translation, such that
+/// the `n`th line of the source document is a translation of the `n`th
+/// line of the target document. Both halves keep their own language
+/// parameter, so source and target tokens cannot be confused.
+pub struct ParallelCorpus<L, M>
+  where L: 'static,
+        M: 'static
+{
+  source: Document<L>,
+  target: Document<M>,
+  /// Indices of the line pairs that have not been filtered out.
+  retained: Vec<usize>,
+}
+
+impl<L, M> ParallelCorpus<L, M>
+  where L: Language,
+        M: Language
+{
+  /// Creates a parallel corpus from a source document and its
+  /// line-aligned translation.
+  pub fn new(source: Document<L>, target: Document<M>)
+      -> ParallelCorpus<L, M>
+  {
+    let pairs = source.lines().len().min(target.lines().len());
+    ParallelCorpus {
+      source: source,
+      target: target,
+      retained: (0..pairs).collect(),
+    }
+  }
+
+  /// Returns the number of line pairs in the corpus.
+  pub fn len(&self) -> usize {
+    self.retained.len()
+  }
+
+  /// Returns `true` if the corpus contains no line pairs.
+  pub fn is_empty(&self) -> bool {
+    self.retained.is_empty()
+  }
+
+  /// Returns the source half of the corpus.
+  pub fn source(&self) -> &Document<L> {
+    &self.source
+  }
+
+  /// Returns the target half of the corpus.
+  pub fn target(&self) -> &Document<M> {
+    &self.target
+  }
+
+  /// Produces an iterator over pairs of aligned lines. If one document
+  /// has more lines than the other, the surplus lines are ignored, as
+  /// are line pairs removed by [`clean`].
+  ///
+  /// ```rust
+  /// let bitext = ParallelCorpus::<French, English>::new(
+  ///   "Le chat dort .".into(),
+  ///   "The cat sleeps .".into());
+  ///
+  /// for (french, english) in bitext.lines() {
+  ///   assert_eq!(french.len(), english.len());
+  /// }
+  /// ```
+  ///
+  /// [`clean`]: #method.clean
+  pub fn lines<'t>(&'t self)
+      -> impl Iterator<Item=(Line<'t, L>, Line<'t, M>)>
+  {
+    let source = self.source.lines();
+    let target = self.target.lines();
+    self.retained.iter().filter_map(move |&n| Some((source.get(n)?, target.get(n)?)))
+  }
+
+  /// Removes every line pair rejected by `filter`, and reports which
+  /// pairs were removed and why. Line numbers in the report refer to
+  /// lines of the original documents.
+  pub fn clean(&mut self, filter: &BitextFilter<L, M>) -> FilterReport {
+    let mut report = FilterReport { removed: vec![] };
+    let source = self.source.lines();
+    let target = self.target.lines();
+    self.retained.retain(|&n|
+      match filter.check(&source.get(n).map(|line| line.to_vec()).unwrap_or_default(),
+                         &target.get(n).map(|line| line.to_vec()).unwrap_or_default()) {
+        Some(reason) => { report.removed.push((n, reason)); false },
+        None => true
+      });
+    report
+  }
+
+  /// Consumes an iterator over word alignments, each with the number of
+  /// the line pair it aligns in the original documents, and produces an
+  /// iterator over all aligned `(source, target)` token pairs in the
+  /// corpus. Alignments of line pairs removed by [`clean`] are skipped,
+  /// as are links that point past the end of their line:
+  ///
+  /// ```rust
+  /// // `alignments[n]` aligns the `n`th line pair of the documents.
+  /// bitext.clean(&BitextFilter::new().max_ratio(3.0));
+  /// for (french, english) in bitext.aligned_tokens(alignments.iter().enumerate()) {
+  ///   println!("{}\t{}", french, english);
+  /// }
+  /// ```
+  ///
+  /// [`clean`]: #method.clean
+  pub fn aligned_tokens<'t, A>(&'t self, alignments: A)
+      -> impl 't + Iterator<Item=(Token<'t, L>, Token<'t, M>)>
+    where A: 't + IntoIterator<Item=(usize, &'t Alignment)>
+  {
+    let source = self.source.lines();
+    let target = self.target.lines();
+    alignments.into_iter()
+      .filter(move |&(n, _)| self.retained.binary_search(&n).is_ok())
+      .filter_map(move |(n, alignment)| Some((source.get(n)?, target.get(n)?, alignment)))
+      .flat_map(|(source, target, alignment)|
+        alignment.iter().filter_map(move |&(i, j)|
+          match (source.get(i), target.get(j)) {
+            (Some(s), Some(t)) => Some((s, t)),
+            _ => None
+          }))
+  }
+
+  /// Produces the aligned token pairs of [`aligned_tokens`] as ids of an
+  /// [`Interner`] of each language, for counting them without hashing
+  /// words. Tokens not in their interner have id `TokenId::UNKNOWN`, so
+  /// each half of the corpus should be interned first:
+  ///
+  /// ```rust
+  /// let (mut french, mut english) = (Interner::new(), Interner::new());
+  /// bitext.source().intern(&mut french);
+  /// bitext.target().intern(&mut english);
+  /// let links: FreqDist<(TokenId, TokenId)> =
+  ///   bitext.aligned_ids(alignments.iter().enumerate(), &french, &english).collect();
+  /// ```
+  ///
+  /// [`aligned_tokens`]: #method.aligned_tokens
+  /// [`Interner`]: struct.Interner.html
+  pub fn aligned_ids<'t, A>(&'t self, alignments: A,
+                            source: &'t Interner<L>, target: &'t Interner<M>)
+      -> impl 't + Iterator<Item=(TokenId, TokenId)>
+    where A: 't + IntoIterator<Item=(usize, &'t Alignment)>
+  {
+    self.aligned_tokens(alignments).map(move |(s, t)|
+      (source.get(&s).unwrap_or(TokenId::UNKNOWN), target.get(&t).unwrap_or(TokenId::UNKNOWN)))
+  }
+
+  /// Renders a word alignment of the `n`th line pair of the original
+  /// documents in the Graphviz DOT language, drawing the source line
+  /// above the target line with an edge for each link. Links that point
+  /// past the end of their line are left out. Returns `None` if either
+  /// document has no `n`th line.
+  ///
+  /// ```rust
+  /// // `alignments[n]` aligns the `n`th line pair of the documents.
+  /// let dot = bitext.alignment_to_dot(0, &alignments[0]).unwrap();
+  /// fs::write("alignment.dot", dot)?;
+  /// ```
+  pub fn alignment_to_dot(&self, n: usize, alignment: &Alignment) -> Option<String> {
+    fn row<T: ::std::fmt::Display>(prefix: char, tokens: &[T], out: &mut String) {
+      out.push_str("  { rank=same;\n");
+      for (i, token) in tokens.iter().enumerate() {
+        out.push_str(&format!("    {}{} [label=\"{}\"];\n",
+                              prefix, i, escape_dot(&token.to_string())));
+      }
+      for i in 1..tokens.len() {
+        out.push_str(&format!("    {}{} -> {}{} [style=invis];\n",
+                              prefix, i - 1, prefix, i));
+      }
+      out.push_str("  }\n");
+    }
+
+    let source = self.source.lines().get(n)?.to_vec();
+    let target = self.target.lines().get(n)?.to_vec();
+    let mut out = String::from("digraph alignment {\n  node [shape=plaintext];\n");
+    row('s', &source, &mut out);
+    row('t', &target, &mut out);
+    for &(i, j) in alignment {
+      if i < source.len() && j < target.len() {
+        out.push_str(&format!("  s{} -> t{} [dir=none];\n", i, j));
+      }
+    }
+    out.push_str("}\n");
+    Some(out)
+  }
+}
+
+/// The reason a line pair was removed by a [`BitextFilter`].
+/// [`BitextFilter`]: struct.BitextFilter.html
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Rejection {
+  /// One line is too many times longer than the other.
+  LengthRatio,
+  /// One of the lines exceeds the maximum length.
+  TooLong,
+  /// The target line is an untranslated copy of the source line.
+  Identical,
+  /// One of the lines does not appear to be in its expected language.
+  LanguageMismatch,
+}
+
+/// A report of the line pairs removed by [`ParallelCorpus::clean`].
+/// [`ParallelCorpus::clean`]: struct.ParallelCorpus.html#method.clean
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterReport {
+  /// The line number of every removed pair, with the reason for its
+  /// removal.
+  pub removed: Vec<(usize, Rejection)>,
+}
+
+impl FilterReport {
+  /// Returns the number of removed pairs rejected for `reason`.
+  pub fn count(&self, reason: Rejection) -> usize {
+    self.removed.iter().filter(|&&(_, r)| r == reason).count()
+  }
+}
+
+/// A predicate deciding whether a line is in its expected language.
+type LanguagePredicate<'f, L> = Box<dyn Fn(&[Token<L>]) -> bool + 'f>;
+
+/// A configurable set of sanity checks for cleaning noisy bitext. By
+/// default, no checks are enabled:
+///
+/// ```rust
+/// let filter = BitextFilter::<French, English>::new()
+///   .max_ratio(2.0)
+///   .max_length(80)
+///   .drop_identical();
+///
+/// let report = bitext.clean(&filter);
+/// println!("removed {} pairs", report.removed.len());
+/// ```
+pub struct BitextFilter<'f, L, M> {
+  max_ratio: Option<f64>,
+  max_length: Option<usize>,
+  drop_identical: bool,
+  source_language: Option<LanguagePredicate<'f, L>>,
+  target_language: Option<LanguagePredicate<'f, M>>,
+}
+
+impl<'f, L, M> BitextFilter<'f, L, M>
+  where L: Language,
+        M: Language
+{
+  /// Creates a filter that accepts every line pair.
+  pub fn new() -> BitextFilter<'f, L, M> {
+    BitextFilter {
+      max_ratio: None,
+      max_length: None,
+      drop_identical: false,
+      source_language: None,
+      target_language: None,
+    }
+  }
+
+  /// Rejects pairs in which one line has more than `ratio` times as
+  /// many tokens as the other. Pairs with exactly one empty line are
+  /// always rejected by this check.
+  pub fn max_ratio(mut self, ratio: f64) -> Self {
+    self.max_ratio = Some(ratio);
+    self
+  }
+
+  /// Rejects pairs in which either line has more than `length` tokens.
+  pub fn max_length(mut self, length: usize) -> Self {
+    self.max_length = Some(length);
+    self
+  }
+
+  /// Rejects pairs in which the target line is token-for-token
+  /// identical to the source line.
+  pub fn drop_identical(mut self) -> Self {
+    self.drop_identical = true;
+    self
+  }
+
+  /// Rejects pairs for which either language identification predicate
+  /// returns `false`. Each predicate should return `true` if the given
+  /// line appears to be in its expected language.
+  pub fn language_id<F, G>(mut self, source: F, target: G) -> Self
+    where F: 'f + Fn(&[Token<L>]) -> bool,
+          G: 'f + Fn(&[Token<M>]) -> bool
+  {
+    self.source_language = Some(Box::new(source));
+    self.target_language = Some(Box::new(target));
+    self
+  }
+
+  /// Checks a line pair, producing the reason for its rejection, if
+  /// any.
+  pub fn check(&self, source: &[Token<L>], target: &[Token<M>])
+      -> Option<Rejection>
+  {
+    let (s, t) = (source.len(), target.len());
+
+    if let Some(length) = self.max_length {
+      if s > length || t > length {
+        return Some(Rejection::TooLong);
+      }
+    }
+
+    if let Some(ratio) = self.max_ratio {
+      let (short, long) = (s.min(t) as f64, s.max(t) as f64);
+      if long > 0.0 && (short == 0.0 || long / short > ratio) {
+        return Some(Rejection::LengthRatio);
+      }
+    }
+
+    if self.drop_identical && s == t && s > 0
+        && source.iter().map(|w| w.loan::<M>()).eq(target.iter().cloned())
+    {
+      return Some(Rejection::Identical);
+    }
+
+    if let (Some(f), Some(g))
+        = (self.source_language.as_ref(), self.target_language.as_ref())
+    {
+      if !f(source) || !g(target) {
+        return Some(Rejection::LanguageMismatch);
+      }
+    }
+
+    None
+  }
+}
+
+impl<'f, L, M> Default for BitextFilter<'f, L, M>
+  where L: Language,
+        M: Language
+{
+  fn default() -> Self {
+    BitextFilter::new()
+  }
+}
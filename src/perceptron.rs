@@ -0,0 +1,139 @@
+//! An averaged, multiclass perceptron over string features, for use as
+//! the scoring model of structured predictors.
+//!
+//! Features are arbitrary strings, such as `"w=dog"` or `"s0=NP|q0=VBD"`,
+//! and each feature holds a separate weight for every class it has been
+//! updated with. The model knows nothing of the structure being
+//! predicted: callers score candidate classes, decide which prediction
+//! was wrong, and apply the appropriate updates themselves, which suits
+//! both per-token classification and structured training with early
+//! update.
+//!
+//! ```rust
+//! let mut model = Perceptron::new();
+//! for _ in 0..iterations {
+//!   for &(ref features, ref gold) in &examples {
+//!     let guess = model.predict(features, &classes).unwrap();
+//!     if guess != *gold {
+//!       model.update(features, gold, 1.0);
+//!       model.update(features, &guess, -1.0);
+//!     }
+//!     model.tick();
+//!   }
+//! }
+//! model.average();
+//! ```
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A weight, with the bookkeeping needed to compute its average lazily.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Weight {
+  value: f64,
+  /// The sum of the weight's values at every tick before `stamp`.
+  total: f64,
+  /// The tick at which `value` was last changed.
+  stamp: usize,
+}
+
+/// An averaged perceptron over classes `C`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Perceptron<C>
+  where C: Hash + Eq
+{
+  weights: HashMap<String, HashMap<C, Weight>>,
+  /// The number of training instances seen.
+  time: usize,
+}
+
+impl<C> Perceptron<C>
+  where C: Hash + Eq + Clone
+{
+  /// Creates a perceptron with every weight zero.
+  pub fn new() -> Perceptron<C> {
+    Perceptron { weights: HashMap::new(), time: 0 }
+  }
+
+  /// Returns the score of every class with a non-zero weight for any of
+  /// `features`. Absent classes score zero.
+  pub fn scores<F: AsRef<str>>(&self, features: &[F]) -> HashMap<C, f64> {
+    let mut scores = HashMap::new();
+    for feature in features {
+      if let Some(weights) = self.weights.get(feature.as_ref()) {
+        for (class, weight) in weights {
+          *scores.entry(class.clone()).or_insert(0.0) += weight.value;
+        }
+      }
+    }
+    scores
+  }
+
+  /// Returns the score of `class` given `features`.
+  pub fn score<F: AsRef<str>>(&self, features: &[F], class: &C) -> f64 {
+    features.iter()
+      .filter_map(|feature| self.weights.get(feature.as_ref()))
+      .filter_map(|weights| weights.get(class))
+      .map(|weight| weight.value)
+      .sum()
+  }
+
+  /// Returns the highest scoring of `classes` given `features`, breaking
+  /// ties in favor of the earliest, or `None` if `classes` is empty.
+  pub fn predict<F: AsRef<str>>(&self, features: &[F], classes: &[C]) -> Option<C> {
+    let scores = self.scores(features);
+    let mut best: Option<(&C, f64)> = None;
+    for class in classes {
+      let score = scores.get(class).cloned().unwrap_or(0.0);
+      match best {
+        Some((_, best_score)) if best_score >= score => {},
+        _ => best = Some((class, score))
+      }
+    }
+    best.map(|(class, _)| class.clone())
+  }
+
+  /// Adds `delta` to the weight of each of `features` for `class`.
+  pub fn update<F: AsRef<str>>(&mut self, features: &[F], class: &C, delta: f64) {
+    let time = self.time;
+    for feature in features {
+      let weight = self.weights.entry(feature.as_ref().to_string())
+        .or_default()
+        .entry(class.clone())
+        .or_insert(Weight { value: 0.0, total: 0.0, stamp: time });
+      weight.total += (time - weight.stamp) as f64 * weight.value;
+      weight.stamp = time;
+      weight.value += delta;
+    }
+  }
+
+  /// Marks the end of a training instance. Averaged weights are averaged
+  /// over instances.
+  pub fn tick(&mut self) {
+    self.time += 1;
+  }
+
+  /// Replaces every weight with its average over all training instances
+  /// so far. Averaging reduces overfitting to the last few instances,
+  /// and should be done once, after training.
+  pub fn average(&mut self) {
+    let time = self.time;
+    if time == 0 {
+      return;
+    }
+    for weights in self.weights.values_mut() {
+      for weight in weights.values_mut() {
+        let total = weight.total + (time - weight.stamp) as f64 * weight.value;
+        *weight = Weight { value: total / time as f64, total: 0.0, stamp: 0 };
+      }
+    }
+    self.time = 0;
+  }
+}
+
+impl<C> Default for Perceptron<C>
+  where C: Hash + Eq + Clone
+{
+  fn default() -> Perceptron<C> {
+    Perceptron::new()
+  }
+}
@@ -0,0 +1,533 @@
+use token::Token;
+use span::Span;
+use language::Language;
+use corpus::AsTokens;
+use gazetteer::Annotation;
+use error::LineParseError;
+
+use std::collections::HashMap;
+
+/// A pronouncing dictionary: the pronunciations of words as sequences of
+/// phonemes, in the ARPAbet notation of the CMU Pronouncing Dictionary,
+/// in which vowels end with a digit for their stress: `1` primary, `2`
+/// secondary and `0` none. Words are looked up whatever their case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pronunciations {
+  words: HashMap<String, Vec<Vec<String>>>,
+}
+
+impl Pronunciations {
+  /// Creates an empty dictionary.
+  pub fn new() -> Pronunciations {
+    Pronunciations::default()
+  }
+
+  /// Reads a dictionary in the format of the CMU Pronouncing Dictionary:
+  /// a word and its phonemes on each line, separated by whitespace, with
+  /// further pronunciations of a word numbered in parentheses. Blank
+  /// lines and lines beginning with `;;;` are ignored.
+  ///
+  /// ```text
+  /// EITHER  IY1 DH ER0
+  /// EITHER(1)  AY1 DH ER0
+  /// ```
+  pub fn from_text(text: &str) -> Result<Pronunciations, LineParseError> {
+    let mut pronunciations = Pronunciations::new();
+    for (i, line) in text.lines().enumerate() {
+      let error = |message| LineParseError { line: i + 1, message: message };
+      let line = line.trim();
+      if line.is_empty() || line.starts_with(";;;") {
+        continue;
+      }
+      let mut fields = line.split_whitespace();
+      let mut word = fields.next().ok_or_else(|| error("expected a word"))?;
+      if word.ends_with(')') {
+        let open = word.rfind('(').ok_or_else(|| error("unbalanced `)`"))?;
+        if open == 0 || !word[open + 1..word.len() - 1].bytes().all(|b| b.is_ascii_digit()) {
+          return Err(error("malformed variant number"));
+        }
+        word = &word[..open];
+      }
+      let phonemes: Vec<&str> = fields.collect();
+      if phonemes.is_empty() {
+        return Err(error("expected phonemes"));
+      }
+      pronunciations.insert(word, phonemes);
+    }
+    Ok(pronunciations)
+  }
+
+  /// Adds a pronunciation of a word.
+  pub fn insert<I, S>(&mut self, word: &str, phonemes: I)
+    where I: IntoIterator<Item=S>,
+          S: Into<String>
+  {
+    self.words.entry(word.to_lowercase())
+      .or_default()
+      .push(phonemes.into_iter().map(Into::into).collect());
+  }
+
+  /// Returns the pronunciations of a word, in the order they were added;
+  /// none if the word is not in the dictionary.
+  pub fn get(&self, word: &str) -> &[Vec<String>] {
+    self.words.get(&word.to_lowercase()).map_or(&[], |pronunciations| &pronunciations[..])
+  }
+
+  /// Returns the number of words in the dictionary.
+  pub fn len(&self) -> usize {
+    self.words.len()
+  }
+
+  /// Returns `true` if the dictionary has no words.
+  pub fn is_empty(&self) -> bool {
+    self.words.is_empty()
+  }
+}
+
+/// Two lines whose last words rhyme: their pronunciations agree from the
+/// last stressed vowel on, as in *delight* and *night*, or *mountain* and
+/// *fountain*. Each word is given by its line and its span in the line.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rhyme {
+  pub first: (usize, Span),
+  pub second: (usize, Span),
+  /// The phonemes the words share, separated by spaces.
+  pub sound: String,
+}
+
+/// Finds the sound patterns of verse by the pronunciations of its words:
+/// rhymes between the ends of lines, and alliteration within lines.
+/// Words missing from the [`Pronunciations`] neither rhyme nor
+/// alliterate.
+///
+/// ```rust
+/// let dictionary = Pronunciations::from_text(&fs::read_to_string("cmudict.dict")?)?;
+/// let poem: Document = fs::read_to_string("tyger.txt")?.into();
+/// let analyzer = VerseAnalyzer::new(&dictionary).with_window(2);
+/// for rhyme in analyzer.rhymes(poem.lines()) {
+///   println!("{} {}\t{}", rhyme.first.0, rhyme.second.0, rhyme.sound);
+/// }
+/// for (l, line) in poem.lines().iter().enumerate() {
+///   for alliteration in analyzer.alliterations(&line) {
+///     println!("{}\t{}\t{}", l, alliteration.span, alliteration.label);
+///   }
+/// }
+/// ```
+///
+/// [`Pronunciations`]: struct.Pronunciations.html
+#[derive(Debug, Clone)]
+pub struct VerseAnalyzer<'p> {
+  pronunciations: &'p Pronunciations,
+  window: usize,
+  gap: usize,
+}
+
+impl<'p> VerseAnalyzer<'p> {
+  /// Creates an analyzer that pairs rhymes up to four lines apart, as in
+  /// the alternating rhymes of a quatrain and beyond, and lets a word
+  /// interrupt alliteration.
+  pub fn new(pronunciations: &'p Pronunciations) -> VerseAnalyzer<'p> {
+    VerseAnalyzer {
+      pronunciations: pronunciations,
+      window: 4,
+      gap: 1,
+    }
+  }
+
+  /// Pairs rhymes of lines at most `window` lines apart.
+  pub fn with_window(mut self, window: usize) -> VerseAnalyzer<'p> {
+    self.window = window;
+    self
+  }
+
+  /// Lets at most `gap` words without the sound, such as articles and
+  /// prepositions, fall between alliterating words, as *a* does in
+  /// *picked a peck*.
+  pub fn with_gap(mut self, gap: usize) -> VerseAnalyzer<'p> {
+    self.gap = gap;
+    self
+  }
+
+  /// Returns every pair of lines, at most the window apart, whose last
+  /// words rhyme, in order of their first line and then their second.
+  /// The last word of a line is its last token with a letter, so that
+  /// closing punctuation is passed over; a word never rhymes with
+  /// itself.
+  pub fn rhymes<'t, I, L>(&self, lines: I) -> Vec<Rhyme>
+    where L: Language + 't,
+          I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let ends: Vec<Option<LineEnd>> = lines.into_iter()
+      .map(|line| {
+        let line = line.as_tokens();
+        line.iter().enumerate().rev()
+          .filter_map(|(i, token)| match *token {
+            Token::Word(ref word) if word.chars().any(char::is_alphabetic) => Some((i, word.as_str())),
+            _ => None
+          })
+          .next()
+          .map(|(i, word)| {
+            let sounds = self.pronunciations.get(word).iter()
+              .filter_map(|phonemes| rhyme(phonemes))
+              .collect();
+            (word.to_lowercase(), i, sounds)
+          })
+      })
+      .collect();
+
+    let mut rhymes = vec![];
+    for (a, first) in ends.iter().enumerate() {
+      let (word, i, sounds) = match *first {
+        Some((ref word, i, ref sounds)) => (word, i, sounds),
+        None => continue
+      };
+      for (b, second) in ends.iter().enumerate().skip(a + 1).take(self.window) {
+        let (other, j, others) = match *second {
+          Some((ref other, j, ref others)) => (other, j, others),
+          None => continue
+        };
+        if word == other {
+          continue;
+        }
+        if let Some(sound) = sounds.iter().find(|sound| others.contains(sound)) {
+          rhymes.push(Rhyme {
+            first: (a, Span::new(i, i + 1)),
+            second: (b, Span::new(j, j + 1)),
+            sound: sound.join(" "),
+          });
+        }
+      }
+    }
+    rhymes
+  }
+
+  /// Returns the runs of words of a line that begin with the same
+  /// consonant, each labeled with the consonant, in order. A run holds at
+  /// least two such words, and spans from the first to the last of them.
+  /// Words of several pronunciations are taken by their first.
+  pub fn alliterations<'t, T, L>(&self, line: &T) -> Vec<Annotation>
+    where L: Language + 't,
+          T: AsTokens<'t, L> + ?Sized
+  {
+    let line = line.as_tokens();
+    let mut alliterations = vec![];
+    // The onset of the current run, its first and last words, and its
+    // number of words.
+    let mut run: Option<(&str, usize, usize, usize)> = None;
+    for (i, token) in line.iter().enumerate() {
+      let onset = match *token {
+        Token::Word(ref word) => self.pronunciations.get(word.as_str()).first()
+          .and_then(|phonemes| phonemes.first())
+          .filter(|phoneme| !is_vowel(phoneme)),
+        _ => None
+      };
+      let onset = match onset {
+        Some(onset) => onset.as_str(),
+        None => continue
+      };
+      match run {
+        Some((sound, _, ref mut last, ref mut count)) if sound == onset && i - *last <= self.gap + 1 => {
+          *last = i;
+          *count += 1;
+          continue;
+        },
+        _ => {}
+      }
+      if let Some((sound, first, last, count)) = run {
+        if count >= 2 {
+          alliterations.push(Annotation { label: sound.to_string(), span: Span::new(first, last + 1) });
+        }
+      }
+      run = Some((onset, i, i, 1));
+    }
+    if let Some((sound, first, last, count)) = run {
+      if count >= 2 {
+        alliterations.push(Annotation { label: sound.to_string(), span: Span::new(first, last + 1) });
+      }
+    }
+    alliterations
+  }
+}
+
+/// The last word of a line, lowercased, its position, and its rhyming
+/// sounds.
+type LineEnd<'p> = (String, usize, Vec<&'p [String]>);
+
+/// Returns `true` if an ARPAbet phoneme is a vowel, which bears a stress
+/// digit.
+fn is_vowel(phoneme: &str) -> bool {
+  phoneme.ends_with(|c: char| c.is_ascii_digit())
+}
+
+/// Returns the rhyming part of a pronunciation: the phonemes from its
+/// last stressed vowel on, or from its last vowel if none is stressed.
+fn rhyme(phonemes: &[String]) -> Option<&[String]> {
+  phonemes.iter().rposition(|phoneme| phoneme.ends_with('1') || phoneme.ends_with('2'))
+    .or_else(|| phonemes.iter().rposition(|phoneme| is_vowel(phoneme)))
+    .map(|start| &phonemes[start..])
+}
+
+/// The stress of a syllable in verse.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stress {
+  Stressed,
+  Unstressed,
+  /// A syllable that can take either stress as the meter requires, such
+  /// as that of a word of one syllable.
+  Either,
+}
+
+/// A meter: the pattern of stressed and unstressed syllables a line of
+/// verse is expected to follow.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Meter {
+  name: String,
+  /// Whether each syllable of the pattern is stressed.
+  pattern: Vec<bool>,
+}
+
+impl Meter {
+  /// Creates the meter named `name` of a pattern written with `/` for a
+  /// stressed syllable and `x` for an unstressed one, as in `x/x/x/x/x/`
+  /// for iambic pentameter. Other characters are ignored, so feet may be
+  /// separated by spaces or bars.
+  pub fn new<S: Into<String>>(name: S, pattern: &str) -> Meter {
+    Meter {
+      name: name.into(),
+      pattern: pattern.chars()
+        .filter_map(|c| match c {
+          '/' => Some(true),
+          'x' => Some(false),
+          _ => None
+        })
+        .collect(),
+    }
+  }
+
+  /// Returns the common meters of English verse: iambic, trochaic,
+  /// anapestic and dactylic, of three to six feet.
+  pub fn common() -> Vec<Meter> {
+    let feet = [("iambic", "x/"), ("trochaic", "/x"), ("anapestic", "xx/"), ("dactylic", "/xx")];
+    let lengths = [("trimeter", 3), ("tetrameter", 4), ("pentameter", 5), ("hexameter", 6)];
+    let mut meters = vec![];
+    for &(foot, pattern) in &feet {
+      for &(length, n) in &lengths {
+        meters.push(Meter::new(format!("{} {}", foot, length), &pattern.repeat(n)));
+      }
+    }
+    meters
+  }
+
+  /// Returns the name of the meter.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Returns the number of syllables of the meter.
+  pub fn len(&self) -> usize {
+    self.pattern.len()
+  }
+
+  /// Returns `true` if the meter has no syllables.
+  pub fn is_empty(&self) -> bool {
+    self.pattern.is_empty()
+  }
+}
+
+/// A departure of a line from its meter.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Deviation {
+  /// A syllable of the opposite stress to the meter's, of the word at
+  /// position `word` of the line.
+  Stress { syllable: usize, word: usize, expected: Stress },
+  /// A line of more or fewer syllables than the meter, as one of a
+  /// feminine ending has.
+  Length { expected: usize, found: usize },
+}
+
+/// The scansion of a line of verse: the estimated stress of each of its
+/// syllables, the meter they follow best, and where they depart from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scansion {
+  /// The stress of each syllable, in order.
+  pub stresses: Vec<Stress>,
+  /// The position in the line of the word of each syllable.
+  pub words: Vec<usize>,
+  /// The name of the meter the line follows best.
+  pub meter: String,
+  pub deviations: Vec<Deviation>,
+}
+
+impl Scansion {
+  /// Returns the stresses of the line written as a meter is, with `/`
+  /// for a stressed syllable, `x` for an unstressed one, and `?` for one
+  /// that may be either.
+  pub fn pattern(&self) -> String {
+    self.stresses.iter()
+      .map(|stress| match *stress {
+        Stress::Stressed => '/',
+        Stress::Unstressed => 'x',
+        Stress::Either => '?'
+      })
+      .collect()
+  }
+}
+
+/// Scans lines of verse: estimates the stress of each syllable and
+/// matches the line against meters.
+///
+/// The stresses of a word are taken from the [`Pronunciations`] of its
+/// first pronunciation: syllables of primary stress are stressed, of no
+/// stress unstressed, and of secondary stress either. A word of one
+/// syllable may be either, as its stress in verse depends on its role.
+/// The syllables of a word missing from the dictionary are estimated
+/// from the groups of vowels in its spelling, with a silent final `e`,
+/// and the first of several is stressed. Tokens without letters have no
+/// syllables.
+///
+/// A line follows the meter with the fewest deviations from it: each
+/// syllable of the opposite stress to the meter counts one, and each
+/// syllable more or fewer than the meter has counts one, except for an
+/// extra unstressed syllable at the end, a feminine ending, or a missing
+/// unstressed syllable at the end, a catalectic line. These are still
+/// reported as deviations.
+///
+/// ```rust
+/// let scanner = Scanner::new(&dictionary);
+/// for line in sonnet.lines() {
+///   if let Some(scansion) = scanner.scan(&line) {
+///     println!("{}\t{}\t{}", scansion.pattern(), scansion.meter, scansion.deviations.len());
+///   }
+/// }
+/// // x/x/x/x/?/  iambic pentameter  0
+/// ```
+///
+/// [`Pronunciations`]: struct.Pronunciations.html
+#[derive(Debug, Clone)]
+pub struct Scanner<'p> {
+  pronunciations: &'p Pronunciations,
+  meters: Vec<Meter>,
+}
+
+impl<'p> Scanner<'p> {
+  /// Creates a scanner matching lines against the [`common`] meters.
+  ///
+  /// [`common`]: struct.Meter.html#method.common
+  pub fn new(pronunciations: &'p Pronunciations) -> Scanner<'p> {
+    Scanner {
+      pronunciations: pronunciations,
+      meters: Meter::common(),
+    }
+  }
+
+  /// Matches lines against the given meters instead, preferring earlier
+  /// meters of as few deviations.
+  pub fn with_meters<I>(mut self, meters: I) -> Scanner<'p>
+    where I: IntoIterator<Item=Meter>
+  {
+    self.meters = meters.into_iter().collect();
+    self
+  }
+
+  /// Returns the estimated stress of each syllable of a line, and the
+  /// position in the line of the word of each.
+  pub fn stresses<'t, T, L>(&self, line: &T) -> Vec<(usize, Stress)>
+    where L: Language + 't,
+          T: AsTokens<'t, L> + ?Sized
+  {
+    let line = line.as_tokens();
+    let mut stresses = vec![];
+    for (i, token) in line.iter().enumerate() {
+      let word = match *token {
+        Token::Word(ref word) if word.chars().any(char::is_alphabetic) => word.as_str(),
+        _ => continue
+      };
+      let mut syllables: Vec<Stress> = match self.pronunciations.get(word).first() {
+        Some(phonemes) => phonemes.iter()
+          .filter(|phoneme| is_vowel(phoneme))
+          .map(|phoneme| match phoneme.chars().last() {
+            Some('1') => Stress::Stressed,
+            Some('2') => Stress::Either,
+            _ => Stress::Unstressed
+          })
+          .collect(),
+        None => {
+          let count = syllables(word);
+          (0..count).map(|s| if s == 0 { Stress::Stressed } else { Stress::Unstressed }).collect()
+        }
+      };
+      if syllables.len() == 1 {
+        syllables[0] = Stress::Either;
+      }
+      stresses.extend(syllables.into_iter().map(|stress| (i, stress)));
+    }
+    stresses
+  }
+
+  /// Scans a line against each meter, and returns its scansion by the
+  /// meter it follows best, or `None` if the scanner has no meters.
+  pub fn scan<'t, T, L>(&self, line: &T) -> Option<Scansion>
+    where L: Language + 't,
+          T: AsTokens<'t, L> + ?Sized
+  {
+    let (words, stresses): (Vec<usize>, Vec<Stress>) = self.stresses(line).into_iter().unzip();
+    let mut best: Option<(&Meter, Vec<Deviation>, usize)> = None;
+    for meter in &self.meters {
+      let mut deviations: Vec<Deviation> = meter.pattern.iter().zip(&stresses).enumerate()
+        .filter_map(|(s, (&stressed, &stress))| {
+          let expected = if stressed { Stress::Stressed } else { Stress::Unstressed };
+          match (expected, stress) {
+            (Stress::Stressed, Stress::Unstressed) | (Stress::Unstressed, Stress::Stressed) =>
+              Some(Deviation::Stress { syllable: s, word: words[s], expected: expected }),
+            _ => None
+          }
+        })
+        .collect();
+      let mut cost = deviations.len();
+      if meter.len() != stresses.len() {
+        deviations.push(Deviation::Length { expected: meter.len(), found: stresses.len() });
+        cost += (meter.len() as isize - stresses.len() as isize).unsigned_abs();
+        // A feminine ending or a catalectic line is conventional.
+        let feminine = stresses.len() == meter.len() + 1 && stresses.last() == Some(&Stress::Unstressed);
+        let catalectic = meter.len() == stresses.len() + 1 && meter.pattern.last() == Some(&false);
+        if feminine || catalectic {
+          cost -= 1;
+        }
+      }
+      if best.as_ref().is_none_or(|&(_, _, lowest)| cost < lowest) {
+        best = Some((meter, deviations, cost));
+      }
+    }
+    best.map(|(meter, deviations, _)| Scansion {
+      stresses: stresses,
+      words: words,
+      meter: meter.name.clone(),
+      deviations: deviations,
+    })
+  }
+}
+
+/// Estimates the number of syllables of a word by its spelling: the
+/// groups of vowels, not counting a final `e` after a consonant unless
+/// it ends `le`, and at least one.
+fn syllables(word: &str) -> usize {
+  let word = word.to_lowercase();
+  let chars: Vec<char> = word.chars().collect();
+  let vowel = |c: char| "aeiouy".contains(c);
+  let mut count = 0;
+  for (i, &c) in chars.iter().enumerate() {
+    if vowel(c) && (i == 0 || !vowel(chars[i - 1])) {
+      count += 1;
+    }
+  }
+  let n = chars.len();
+  if count > 1 && n >= 3 {
+    let silent = chars[n - 1] == 'e' && !vowel(chars[n - 2]);
+    let syllabic = chars[n - 2] == 'l' && !vowel(chars[n - 3]);
+    if silent && !syllabic {
+      count -= 1;
+    }
+  }
+  count.max(1)
+}
@@ -0,0 +1,81 @@
+use token::Token;
+use language::Language;
+use corpus::Lines;
+
+use std::thread;
+
+/// A line that could not be processed, with the error it produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError<E> {
+  /// The index of the line in the document.
+  pub line: usize,
+  pub error: E,
+}
+
+/// The results of processing every line of a document with
+/// [`process_lines`].
+///
+/// [`process_lines`]: fn.process_lines.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Processed<T, E> {
+  /// The index and result of each line processed successfully, in order.
+  pub successes: Vec<(usize, T)>,
+  /// The index and error of each line that failed, in order.
+  pub failures: Vec<LineError<E>>,
+}
+
+impl<T, E> Processed<T, E> {
+  /// Returns `true` if no line failed.
+  pub fn is_ok(&self) -> bool {
+    self.failures.is_empty()
+  }
+
+  /// Returns the results of the lines processed successfully, without
+  /// their indices.
+  pub fn into_values(self) -> Vec<T> {
+    self.successes.into_iter().map(|(_, value)| value).collect()
+  }
+}
+
+/// Applies a fallible function to every line of a document, spreading
+/// the lines across threads. Rather than stopping at the first failure,
+/// every line is processed, and failures are collected with the index of
+/// the line that caused them:
+///
+/// ```rust
+/// let processed = process_lines(&treebank, |line| parser.parse(line));
+/// for failure in &processed.failures {
+///   eprintln!("line {}: {}", failure.line + 1, failure.error);
+/// }
+/// let trees = processed.into_values();
+/// ```
+pub fn process_lines<'t, I, L, F, T, E>(lines: I, f: F) -> Processed<T, E>
+  where I: Into<Lines<'t, L>>,
+        L: 't + Language,
+        F: Sync + Fn(&[Token<'t, L>]) -> Result<T, E>,
+        T: Send,
+        E: Send
+{
+  let lines = lines.into();
+  let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+  let chunk = lines.len().div_ceil(threads).max(1);
+  let f = &f;
+  let results: Vec<Vec<Result<T, E>>> = thread::scope(|scope| {
+    let workers: Vec<_> = (0..lines.len()).step_by(chunk)
+      .map(|start| lines.slice(start..(start + chunk).min(lines.len())))
+      .map(|lines| scope.spawn(move || lines.iter().map(|line| f(&line.to_vec())).collect()))
+      .collect();
+    workers.into_iter()
+      .map(|worker| worker.join().unwrap_or_else(|panic| ::std::panic::resume_unwind(panic)))
+      .collect()
+  });
+
+  let mut processed = Processed { successes: vec![], failures: vec![] };
+  for (line, result) in results.into_iter().flatten().enumerate() {
+    match result {
+      Ok(value) => processed.successes.push((line, value)),
+      Err(error) => processed.failures.push(LineError { line: line, error: error }),
+    }
+  }
+  processed
+}
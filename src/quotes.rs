@@ -0,0 +1,291 @@
+use span::Span;
+use coref::Mention;
+
+use std::collections::HashSet;
+
+/// The verbs that introduce reported speech, by default.
+const SPEECH_VERBS: &[&str] = &[
+  "say", "says", "said", "ask", "asks", "asked", "reply", "replies", "replied",
+  "tell", "tells", "told", "add", "adds", "added", "answer", "answers", "answered",
+  "cry", "cries", "cried", "shout", "shouts", "shouted", "whisper", "whispers",
+  "whispered", "explain", "explains", "explained", "insist", "insists", "insisted",
+  "continue", "continues", "continued", "remark", "remarks", "remarked", "exclaimed",
+  "muttered", "murmured", "note", "notes", "noted", "declare", "declares", "declared",
+  "state", "states", "stated", "announce", "announces", "announced", "admit",
+  "admits", "admitted", "argue", "argues", "argued", "claim", "claims", "claimed",
+  "write", "writes", "wrote", "respond", "responds", "responded", "yelled",
+];
+
+/// Words that may stand alone as the subject of a speech verb.
+const PRONOUNS: &[&str] = &["i", "you", "he", "she", "it", "we", "they", "who"];
+
+/// Words that may begin a common-noun subject, as in `the minister said`.
+const DETERMINERS: &[&str] = &[
+  "the", "a", "an", "this", "that", "his", "her", "its", "our", "their", "my", "your",
+];
+
+/// The role of a token as a quotation mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark { Open, Close, Either }
+
+fn mark(word: &str) -> Option<Mark> {
+  match word {
+    "“" | "``" | "«" | "„" => Some(Mark::Open),
+    "”" | "''" | "»" => Some(Mark::Close),
+    "\"" => Some(Mark::Either),
+    _ => None
+  }
+}
+
+/// A quotation: the text between a pair of quotation marks, possibly
+/// spanning several lines, and the mention of its speaker, if one was
+/// found.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Quote {
+  /// The line and position of the opening quotation mark.
+  pub open: (usize, usize),
+  /// The line and position of the closing quotation mark.
+  pub close: (usize, usize),
+  pub speaker: Option<Mention>,
+}
+
+impl Quote {
+  /// Returns the quoted words as one span of each line of `lines`, the
+  /// document the quotation was found in, excluding the quotation marks.
+  /// Lines that contribute no words are omitted.
+  pub fn spans<V, S>(&self, lines: &[V]) -> Vec<Mention>
+    where V: AsRef<[S]>
+  {
+    let (first, start) = self.open;
+    let (last, end) = self.close;
+    (first..=last)
+      .map(|line| {
+        let from = if line == first { start + 1 } else { 0 };
+        let to = if line == last { end } else { lines[line].as_ref().len() };
+        Mention::new(line, Span::new(from, to.max(from)))
+      })
+      .filter(|mention| !mention.span.is_empty())
+      .collect()
+  }
+}
+
+/// A rule-based detector of quotations and their speakers.
+///
+/// Opening and closing marks (`“ ”`, ``` `` '' ```, `« »`) are paired in
+/// document order, across line boundaries; a straight `"` closes the open
+/// quotation if there is one, and opens one otherwise. A closing mark
+/// without an open quotation is ignored, and an opening mark within an
+/// open quotation restarts it, as does a quotation open for more lines
+/// than the detector permits.
+///
+/// The speaker of a quotation is the subject of the nearest speech verb
+/// (`said`, `asked`, …) outside it: first in the rest of the line after
+/// the closing mark, then in the part of the line before the opening mark,
+/// then, if the quotation opens its line, at the end of the line before.
+/// The subject is a pronoun, a run of capitalized words, or a determiner
+/// and the words following it, immediately before the verb, or, failing
+/// that, immediately after it, as in `said the minister`.
+///
+/// ```rust
+/// let lines = vec![
+///   vec!["“", "We", "will", "win", ".", "”"],
+///   vec!["“", "Not", "this", "year", ",", "”", "Jane", "Smith", "replied", "."],
+/// ];
+/// let quotes = QuoteDetector::new().detect(&lines);
+/// assert_eq!(quotes[1].speaker, Some(Mention::new(1, Span::new(6, 8))));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteDetector {
+  verbs: HashSet<String>,
+  max_lines: usize,
+}
+
+impl QuoteDetector {
+  /// Creates a detector with a list of common English speech verbs, which
+  /// closes no quotation more than ten lines after opening it.
+  pub fn new() -> QuoteDetector {
+    QuoteDetector {
+      verbs: SPEECH_VERBS.iter().map(|verb| verb.to_string()).collect(),
+      max_lines: 10,
+    }
+  }
+
+  /// Replaces the speech verbs, which are matched without regard to case.
+  pub fn with_verbs<I, S>(mut self, verbs: I) -> QuoteDetector
+    where I: IntoIterator<Item=S>,
+          S: AsRef<str>
+  {
+    self.verbs = verbs.into_iter().map(|verb| verb.as_ref().to_lowercase()).collect();
+    self
+  }
+
+  /// Sets the greatest number of lines a quotation may span.
+  pub fn with_max_lines(mut self, lines: usize) -> QuoteDetector {
+    self.max_lines = lines.max(1);
+    self
+  }
+
+  /// Detects the quotations of a document, given as the words of each of
+  /// its lines, in document order.
+  pub fn detect<V, S>(&self, lines: &[V]) -> Vec<Quote>
+    where V: AsRef<[S]>,
+          S: AsRef<str>
+  {
+    let mut quotes = vec![];
+    let mut open: Option<(usize, usize)> = None;
+    for (l, line) in lines.iter().enumerate() {
+      if let Some((first, _)) = open {
+        if l - first >= self.max_lines {
+          open = None;
+        }
+      }
+      for (i, word) in line.as_ref().iter().enumerate() {
+        match (mark(word.as_ref()), open) {
+          (Some(Mark::Close), Some(start)) | (Some(Mark::Either), Some(start)) => {
+            quotes.push(Quote { open: start, close: (l, i), speaker: None });
+            open = None;
+          },
+          (Some(Mark::Open), _) | (Some(Mark::Either), None) => open = Some((l, i)),
+          _ => {}
+        }
+      }
+    }
+
+    for q in 0..quotes.len() {
+      quotes[q].speaker = self.attribute(lines, &quotes, q);
+    }
+    quotes
+  }
+
+  /// Finds the speaker of quotation `q`.
+  fn attribute<V, S>(&self, lines: &[V], quotes: &[Quote], q: usize) -> Option<Mention>
+    where V: AsRef<[S]>,
+          S: AsRef<str>
+  {
+    let quote = quotes[q];
+    let words = |line: usize| -> Vec<&str> {
+      lines[line].as_ref().iter().map(|word| word.as_ref()).collect()
+    };
+
+    // After the closing mark, up to the next quotation on its line.
+    let (line, close) = quote.close;
+    let end = match quotes.get(q + 1) {
+      Some(next) if next.open.0 == line => next.open.1,
+      _ => lines[line].as_ref().len()
+    };
+    let after = words(line);
+    let cue = (close + 1..end).find(|&v| self.is_verb(after[v]));
+    if let Some(speaker) = cue.and_then(|v| subject(&after, close + 1, end, v)) {
+      return Some(Mention::new(line, speaker));
+    }
+
+    // Before the opening mark, back to the previous quotation on its line.
+    let (line, open) = quote.open;
+    let start = match q.checked_sub(1).map(|p| quotes[p]) {
+      Some(previous) if previous.close.0 == line => previous.close.1 + 1,
+      _ => 0
+    };
+    let before = words(line);
+    let cue = (start..open).rev().find(|&v| self.is_verb(before[v]));
+    if let Some(speaker) = cue.and_then(|v| subject(&before, start, open, v)) {
+      return Some(Mention::new(line, speaker));
+    }
+
+    // At the end of the preceding line, as in `He said:`.
+    if start == open && line > 0 {
+      let previous = words(line - 1);
+      let start = match q.checked_sub(1).map(|p| quotes[p]) {
+        Some(earlier) if earlier.close.0 == line - 1 => earlier.close.1 + 1,
+        _ => 0
+      };
+      let end = previous.len();
+      let cue = (start..end).rev().find(|&v| self.is_verb(previous[v]));
+      if let Some(speaker) = cue.and_then(|v| subject(&previous, start, end, v)) {
+        return Some(Mention::new(line - 1, speaker));
+      }
+    }
+    None
+  }
+
+  fn is_verb(&self, word: &str) -> bool {
+    self.verbs.contains(&word.to_lowercase())
+  }
+}
+
+impl Default for QuoteDetector {
+  fn default() -> QuoteDetector {
+    QuoteDetector::new()
+  }
+}
+
+fn is_pronoun(word: &str) -> bool {
+  PRONOUNS.contains(&&word.to_lowercase()[..])
+}
+
+fn is_determiner(word: &str) -> bool {
+  DETERMINERS.contains(&&word.to_lowercase()[..])
+}
+
+fn is_capitalized(word: &str) -> bool {
+  match word.chars().next() {
+    Some(c) => c.is_uppercase(),
+    None => false
+  }
+}
+
+fn is_lowercase_word(word: &str) -> bool {
+  !word.is_empty() && word.chars().all(|c| c.is_alphabetic() && c.is_lowercase())
+}
+
+/// Finds the subject of the speech verb at position `v` of `words`, within
+/// positions `start..end`.
+fn subject(words: &[&str], start: usize, end: usize, v: usize) -> Option<Span> {
+  // Before the verb: a pronoun, a name, or a determiner and up to two
+  // words, as in `he said`, `Jane Smith said` or `the new minister said`.
+  if v > start {
+    let previous = words[v - 1];
+    if is_pronoun(previous) {
+      return Some(Span::new(v - 1, v));
+    }
+    if is_capitalized(previous) && mark(previous).is_none() {
+      let mut first = v - 1;
+      while first > start && is_capitalized(words[first - 1])
+          && !is_determiner(words[first - 1]) {
+        first -= 1;
+      }
+      if first > start && is_determiner(words[first - 1]) {
+        first -= 1;
+      }
+      return Some(Span::new(first, v));
+    }
+    for first in (start.max(v.saturating_sub(3))..v - 1).rev() {
+      if is_determiner(words[first]) {
+        return Some(Span::new(first, v));
+      }
+      if !is_lowercase_word(words[first]) {
+        break;
+      }
+    }
+  }
+
+  // After the verb, as in `said Jane Smith` or `said the minister`.
+  let next = *words.get(v + 1).filter(|_| v + 1 < end)?;
+  if is_pronoun(next) {
+    return Some(Span::new(v + 1, v + 2));
+  }
+  if is_determiner(next) {
+    let mut last = v + 2;
+    while last < end && last < v + 4 && is_lowercase_word(words[last]) {
+      last += 1;
+    }
+    return if last > v + 2 { Some(Span::new(v + 1, last)) } else { None };
+  }
+  if is_capitalized(next) && mark(next).is_none() {
+    let mut last = v + 2;
+    while last < end && is_capitalized(words[last]) {
+      last += 1;
+    }
+    return Some(Span::new(v + 1, last));
+  }
+  None
+}
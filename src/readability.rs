@@ -0,0 +1,326 @@
+use token::Token;
+use language::Language;
+use corpus::{Document, Tokens};
+use dependency::DepGraph;
+
+use std::collections::{HashMap, HashSet};
+
+/// Measures of the readability and complexity of one document, as
+/// produced by a [`ReadabilityAnalyzer`]. Sentences are the lines of the
+/// document, and words are its tokens containing a letter or digit.
+///
+/// [`ReadabilityAnalyzer`]: struct.ReadabilityAnalyzer.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadabilityReport {
+  pub sentences: usize,
+  pub words: usize,
+  /// The number of syllables of the words, estimated from their spelling
+  /// as English.
+  pub syllables: usize,
+  /// The Flesch reading ease: higher is easier, and `60` to `70` is
+  /// plain English.
+  pub flesch_reading_ease: f64,
+  /// The Flesch-Kincaid grade level: the years of US schooling needed to
+  /// understand the text.
+  pub flesch_kincaid_grade: f64,
+  /// The number of distinct words divided by the number of words, with
+  /// case folded.
+  pub type_token_ratio: f64,
+  /// The measure of textual lexical diversity (McCarthy and Jarvis,
+  /// 2010): the mean length of the stretches of words over which the
+  /// type-token ratio stays above `0.72`. Unlike the type-token ratio,
+  /// it does not fall as documents grow longer.
+  pub mtld: f64,
+  /// The mean distance between each token and its head, if the document
+  /// was analyzed with dependency parses.
+  pub mean_dependency_length: Option<f64>,
+  /// For each of the analyzer's [`FrequencyBands`], as the number of
+  /// most frequent reference words it holds, the share of the document's
+  /// words within it. Empty without frequency bands.
+  ///
+  /// [`FrequencyBands`]: struct.FrequencyBands.html
+  pub band_coverage: Vec<(usize, f64)>,
+}
+
+impl ReadabilityReport {
+  /// Writes the report as a JSON object, with a field for each measure.
+  /// A missing mean dependency length is written as `null`, and band
+  /// coverage as an object from band sizes to shares.
+  pub fn to_json(&self) -> String {
+    let number = |value: f64| if value.is_finite() { value.to_string() } else { "null".to_string() };
+    let bands: Vec<String> = self.band_coverage.iter()
+      .map(|&(band, share)| format!("\"{}\":{}", band, number(share)))
+      .collect();
+    format!("{{\"sentences\":{},\"words\":{},\"syllables\":{},\
+             \"flesch_reading_ease\":{},\"flesch_kincaid_grade\":{},\
+             \"type_token_ratio\":{},\"mtld\":{},\
+             \"mean_dependency_length\":{},\"band_coverage\":{{{}}}}}",
+            self.sentences, self.words, self.syllables,
+            number(self.flesch_reading_ease), number(self.flesch_kincaid_grade),
+            number(self.type_token_ratio), number(self.mtld),
+            self.mean_dependency_length.map_or("null".to_string(), number),
+            bands.join(","))
+  }
+}
+
+/// Produces a [`ReadabilityReport`] for each document of a collection,
+/// for content-quality dashboards.
+///
+/// ```rust
+/// let analyzer = ReadabilityAnalyzer::new()
+///   .with_bands(FrequencyBands::new(&reference, vec![1000, 2000, 5000]));
+/// for (article, parses) in articles.iter().zip(&parses) {
+///   println!("{}", analyzer.report(article, Some(parses)).to_json());
+/// }
+/// ```
+///
+/// [`ReadabilityReport`]: struct.ReadabilityReport.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadabilityAnalyzer {
+  bands: Option<FrequencyBands>,
+}
+
+impl ReadabilityAnalyzer {
+  /// Creates an analyzer with no frequency bands.
+  pub fn new() -> ReadabilityAnalyzer {
+    ReadabilityAnalyzer { bands: None }
+  }
+
+  /// Sets the frequency bands whose coverage is reported.
+  pub fn with_bands(mut self, bands: FrequencyBands) -> ReadabilityAnalyzer {
+    self.bands = Some(bands);
+    self
+  }
+
+  /// Reports on a document, with a dependency parse of each of its lines
+  /// if available.
+  pub fn report<L: Language>(&self, document: &Document<L>, parses: Option<&[DepGraph]>)
+      -> ReadabilityReport
+  {
+    let words = words(document.tokens());
+    let sentences = document.lines().iter().filter(|line| !line.is_empty()).count();
+    let syllables: usize = words.iter().map(|word| syllables(word)).sum();
+    let (per_sentence, per_word) = if words.is_empty() { (0.0, 0.0) } else {
+      (words.len() as f64 / sentences as f64, syllables as f64 / words.len() as f64)
+    };
+    let types: HashSet<&[u8]> = words.iter().map(|word| &word[..]).collect();
+
+    let mean_dependency_length = parses.map(|parses| {
+      let (total, arcs) = parses.iter()
+        .flat_map(|parse| parse.arcs())
+        .fold((0, 0), |(total, arcs), (head, dependent)| {
+          (total + head.abs_diff(dependent), arcs + 1)
+        });
+      if arcs == 0 { 0.0 } else { total as f64 / arcs as f64 }
+    });
+
+    let band_coverage = match self.bands {
+      Some(ref bands) => bands.count(&words).cumulative(),
+      None => vec![]
+    };
+
+    ReadabilityReport {
+      sentences: sentences,
+      words: words.len(),
+      syllables: syllables,
+      flesch_reading_ease: 206.835 - 1.015 * per_sentence - 84.6 * per_word,
+      flesch_kincaid_grade: 0.39 * per_sentence + 11.8 * per_word - 15.59,
+      type_token_ratio: if words.is_empty() { 0.0 } else { types.len() as f64 / words.len() as f64 },
+      mtld: (mtld(words.iter()) + mtld(words.iter().rev())) / 2.0,
+      mean_dependency_length: mean_dependency_length,
+      band_coverage: band_coverage,
+    }
+  }
+}
+
+/// Frequency bands of a reference corpus, such as its 1,000, 2,000 and
+/// 5,000 most frequent words, for measuring how much of a text's
+/// vocabulary falls within each, as in vocabulary profiling for language
+/// teaching. Words are compared with ASCII case folded.
+///
+/// ```rust
+/// let bands = FrequencyBands::new(&reference, vec![1000, 2000, 5000]);
+/// let coverage = bands.coverage(&reader);
+/// for (band, share) in coverage.cumulative() {
+///   println!("top {}: {:.1}%", band, 100.0 * share);
+/// }
+/// println!("off-list: {:.1}%", 100.0 * coverage.off_list_share());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrequencyBands {
+  bands: Vec<usize>,
+  /// The frequency rank of each reference word, from zero.
+  ranks: HashMap<Vec<u8>, usize>,
+}
+
+impl FrequencyBands {
+  /// Ranks the words of a reference document by frequency, with ties in
+  /// order of first occurrence, and divides them into bands of the
+  /// given cumulative sizes.
+  pub fn new<L: Language>(reference: &Document<L>, bands: Vec<usize>) -> FrequencyBands {
+    let mut counts: HashMap<Vec<u8>, (usize, usize)> = HashMap::new();
+    for (word, position) in words(reference.tokens()).into_iter().zip(0..) {
+      counts.entry(word).or_insert((0, position)).0 += 1;
+    }
+    let mut words: Vec<(Vec<u8>, (usize, usize))> = counts.into_iter().collect();
+    words.sort_by(|&(_, (a, i)), &(_, (b, j))| b.cmp(&a).then(i.cmp(&j)));
+    FrequencyBands::from_ranked(words.into_iter().map(|(word, _)| word), bands)
+  }
+
+  /// Divides a list of words, from most to least frequent, into bands of
+  /// the given cumulative sizes. Only the first occurrence of a word in
+  /// the list counts.
+  pub fn from_ranked<I, S>(words: I, mut bands: Vec<usize>) -> FrequencyBands
+    where I: IntoIterator<Item=S>,
+          S: AsRef<[u8]>
+  {
+    bands.sort();
+    bands.dedup();
+    let mut ranks = HashMap::new();
+    for word in words {
+      let rank = ranks.len();
+      ranks.entry(word.as_ref().to_ascii_lowercase()).or_insert(rank);
+    }
+    FrequencyBands { bands: bands, ranks: ranks }
+  }
+
+  /// Returns the cumulative size of each band, in increasing order.
+  pub fn bands(&self) -> &[usize] {
+    &self.bands
+  }
+
+  /// Returns the index of the first band containing a word, or `None` if
+  /// the word is in no band.
+  pub fn band(&self, word: &[u8]) -> Option<usize> {
+    self.folded_band(&word.to_ascii_lowercase())
+  }
+
+  /// Returns the index of the first band containing a case-folded word.
+  fn folded_band(&self, word: &[u8]) -> Option<usize> {
+    let rank = *self.ranks.get(word)?;
+    self.bands.iter().position(|&band| rank < band)
+  }
+
+  /// Measures how many of the words of a document fall within each band.
+  /// Words are its tokens containing a letter or digit.
+  pub fn coverage<L>(&self, document: &Document<L>) -> Coverage {
+    self.count(&words(document.tokens()))
+  }
+
+  /// Measures how many of the given case-folded words fall within each
+  /// band.
+  fn count(&self, words: &[Vec<u8>]) -> Coverage {
+    let mut counts = vec![0; self.bands.len()];
+    for word in words {
+      if let Some(band) = self.folded_band(word) {
+        counts[band] += 1;
+      }
+    }
+    Coverage { bands: self.bands.clone(), counts: counts, words: words.len() }
+  }
+}
+
+/// How many of the words of a document fall within each of a set of
+/// [`FrequencyBands`].
+///
+/// [`FrequencyBands`]: struct.FrequencyBands.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+  /// The cumulative size of each band.
+  pub bands: Vec<usize>,
+  /// The number of words in each band and no smaller one.
+  pub counts: Vec<usize>,
+  /// The number of words of the document.
+  pub words: usize,
+}
+
+impl Coverage {
+  /// Returns the share of the words of the document in each band and no
+  /// smaller one, paired with the size of the band.
+  pub fn shares(&self) -> Vec<(usize, f64)> {
+    self.bands.iter().zip(&self.counts)
+      .map(|(&band, &count)| (band, self.share(count)))
+      .collect()
+  }
+
+  /// Returns the share of the words of the document within each band,
+  /// paired with the size of the band.
+  pub fn cumulative(&self) -> Vec<(usize, f64)> {
+    let mut covered = 0;
+    self.bands.iter().zip(&self.counts)
+      .map(|(&band, &count)| {
+        covered += count;
+        (band, self.share(covered))
+      })
+      .collect()
+  }
+
+  /// Returns the share of the words of the document in no band.
+  pub fn off_list_share(&self) -> f64 {
+    self.share(self.words - self.counts.iter().sum::<usize>())
+  }
+
+  fn share(&self, count: usize) -> f64 {
+    if self.words == 0 { 0.0 } else { count as f64 / self.words as f64 }
+  }
+}
+
+/// Returns the tokens containing a letter or digit, with ASCII case
+/// folded.
+fn words<L>(tokens: Tokens<L>) -> Vec<Vec<u8>> {
+  tokens.iter()
+    .filter_map(|token| match token {
+      Token::Word(ref word) => Some(word.as_bytes()),
+      _ => None
+    })
+    .filter(|word| word.iter().any(|b| b.is_ascii_alphanumeric() || *b >= 0x80))
+    .map(|word| word.to_ascii_lowercase())
+    .collect()
+}
+
+/// Estimates the syllables of an English word as its groups of vowels,
+/// not counting a silent final `e`, and at least one.
+fn syllables(word: &[u8]) -> usize {
+  let vowel = |b: &u8| b"aeiouy".contains(b);
+  let mut groups = 0;
+  let mut previous = false;
+  for b in word {
+    let current = vowel(b);
+    if current && !previous {
+      groups += 1;
+    }
+    previous = current;
+  }
+  if groups > 1 && word.ends_with(b"e") && !word.ends_with(b"le") {
+    groups -= 1;
+  }
+  groups.max(1)
+}
+
+/// Computes one pass of the measure of textual lexical diversity: the
+/// number of words divided by the number of factors, stretches over
+/// which the type-token ratio falls to `0.72`, counting a final partial
+/// factor in proportion to how far its ratio has fallen.
+fn mtld<'w, I>(words: I) -> f64
+  where I: Iterator<Item=&'w Vec<u8>>
+{
+  const THRESHOLD: f64 = 0.72;
+  let mut factors = 0.0;
+  let mut total = 0;
+  let mut types: HashSet<&[u8]> = HashSet::new();
+  let mut length = 0;
+  for word in words {
+    total += 1;
+    length += 1;
+    types.insert(word);
+    if (types.len() as f64 / length as f64) <= THRESHOLD {
+      factors += 1.0;
+      types.clear();
+      length = 0;
+    }
+  }
+  if length > 0 {
+    factors += (1.0 - types.len() as f64 / length as f64) / (1.0 - THRESHOLD);
+  }
+  if factors == 0.0 { total as f64 } else { total as f64 / factors }
+}
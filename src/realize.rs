@@ -0,0 +1,337 @@
+//! Surface realization of sentences from templates.
+//!
+//! A [`Template`] is text with slots in braces, filled from named
+//! [`Value`]s by a [`Realizer`]. Numbers are written and lists conjoined
+//! according to the [`Conventions`] of the language, and a word may be
+//! inflected to agree in number with a slot:
+//!
+//! ```rust
+//! let template: Template = "{name} found {count} {file:count} in {dirs}.".parse()?;
+//! let mut inflector = Inflector::new();
+//! inflector.add_rule(&"Number=Plur".parse()?, "", "s");
+//! let realizer: Realizer<English> = Realizer::new(Conventions::english(), inflector);
+//!
+//! let mut values = HashMap::new();
+//! values.insert("name", Value::Text("grep".to_string()));
+//! values.insert("count", Value::Count(1204));
+//! values.insert("dirs", Value::List(vec!["src".to_string(), "doc".to_string(), "tests".to_string()]));
+//! assert_eq!(realizer.realize(&template, &values)?,
+//!            "grep found 1,204 files in src, doc, and tests.");
+//! ```
+//!
+//! [`Template`]: struct.Template.html
+//! [`Value`]: enum.Value.html
+//! [`Realizer`]: struct.Realizer.html
+//! [`Conventions`]: struct.Conventions.html
+use language::Language;
+use morphology::{Features, Number, Inflector};
+use error::ParseError;
+
+use std::fmt;
+use std::error::Error;
+use std::str::FromStr;
+use std::collections::HashMap;
+
+/// The conventions of a language for writing numbers and lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conventions {
+  /// The word joining the last two items of a list, as in `and`.
+  pub conjunction: String,
+  /// Whether a comma precedes the conjunction in lists of three or more.
+  pub serial_comma: bool,
+  /// The mark separating the integer and fractional parts of a number.
+  pub decimal_separator: char,
+  /// The mark separating groups of three digits in the integer part of a
+  /// number.
+  pub group_separator: char,
+}
+
+impl Conventions {
+  /// The conventions of English: `1,204.5` and `a, b, and c`.
+  pub fn english() -> Conventions {
+    Conventions {
+      conjunction: "and".to_string(),
+      serial_comma: true,
+      decimal_separator: '.',
+      group_separator: ',',
+    }
+  }
+
+  /// The conventions of French: `1 204,5` and `a, b et c`, grouping
+  /// digits with a no-break space.
+  pub fn french() -> Conventions {
+    Conventions {
+      conjunction: "et".to_string(),
+      serial_comma: false,
+      decimal_separator: ',',
+      group_separator: '\u{a0}',
+    }
+  }
+
+  /// The conventions of German: `1.204,5` and `a, b und c`.
+  pub fn german() -> Conventions {
+    Conventions {
+      conjunction: "und".to_string(),
+      serial_comma: false,
+      decimal_separator: ',',
+      group_separator: '.',
+    }
+  }
+
+  /// Joins items into a list: `a`, `a and b`, or `a, b, and c`.
+  pub fn conjoin<S: AsRef<str>>(&self, items: &[S]) -> String {
+    match items.len() {
+      0 => String::new(),
+      1 => items[0].as_ref().to_string(),
+      2 => format!("{} {} {}", items[0].as_ref(), self.conjunction, items[1].as_ref()),
+      n => {
+        let init: Vec<&str> = items[..n - 1].iter().map(|item| item.as_ref()).collect();
+        let comma = if self.serial_comma { "," } else { "" };
+        format!("{}{} {} {}", init.join(", "), comma, self.conjunction, items[n - 1].as_ref())
+      }
+    }
+  }
+
+  /// Writes a number with `precision` digits after the decimal separator,
+  /// grouping the digits of its integer part.
+  pub fn format_number(&self, number: f64, precision: usize) -> String {
+    let written = format!("{:.*}", precision, number.abs());
+    let (integer, fraction) = match written.find('.') {
+      Some(point) => (&written[..point], &written[point + 1..]),
+      None => (&written[..], "")
+    };
+    let mut formatted = String::new();
+    if number < 0.0 && written.bytes().any(|b| b != b'0' && b != b'.') {
+      formatted.push('-');
+    }
+    for (i, digit) in integer.chars().enumerate() {
+      if i > 0 && (integer.len() - i) % 3 == 0 {
+        formatted.push(self.group_separator);
+      }
+      formatted.push(digit);
+    }
+    if !fraction.is_empty() {
+      formatted.push(self.decimal_separator);
+      formatted.push_str(fraction);
+    }
+    formatted
+  }
+}
+
+impl Default for Conventions {
+  fn default() -> Conventions {
+    Conventions::english()
+  }
+}
+
+/// A value filling a slot of a template.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  /// Text, written as is. Words agreeing with it are singular.
+  Text(String),
+  /// A whole number, written with grouped digits. Words agreeing with it
+  /// are singular if it is one or minus one, and plural otherwise.
+  Count(i64),
+  /// A number written with the given number of digits after the
+  /// decimal separator. Words agreeing with it are plural.
+  Decimal(f64, usize),
+  /// Items, conjoined into a list. Words agreeing with it are singular
+  /// if it has one item, and plural otherwise.
+  List(Vec<String>),
+}
+
+impl Value {
+  /// Returns the grammatical number of words agreeing with the value.
+  pub fn number(&self) -> Number {
+    match *self {
+      Value::Text(_) => Number::Sing,
+      Value::Count(n) => if n == 1 || n == -1 { Number::Sing } else { Number::Plur },
+      Value::Decimal(..) => Number::Plur,
+      Value::List(ref items) => if items.len() == 1 { Number::Sing } else { Number::Plur },
+    }
+  }
+}
+
+/// A piece of a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+  /// Literal text.
+  Text(String),
+  /// The value of a slot.
+  Slot(String),
+  /// A lemma, inflected with the given features and the number of a
+  /// slot.
+  Agree(String, String, Features),
+}
+
+/// Text with slots to be filled by a [`Realizer`].
+///
+/// A slot `{name}` is replaced by the value named `name`. A slot
+/// `{lemma:name}` is replaced by `lemma` inflected to agree in number
+/// with the value named `name`, and `{lemma:name:features}` also gives
+/// it the other features, written as in `FEATS`; for example,
+/// `{be:count:Mood=Ind|Person=3|Tense=Pres|VerbForm=Fin}`. Literal braces
+/// are written `{{` and `}}`.
+///
+/// [`Realizer`]: struct.Realizer.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+  pieces: Vec<Piece>,
+}
+
+impl Template {
+  /// Returns the name of every slot the template refers to, in order of
+  /// first reference.
+  pub fn slots(&self) -> Vec<&str> {
+    let mut slots: Vec<&str> = vec![];
+    for piece in &self.pieces {
+      let name = match *piece {
+        Piece::Text(_) => continue,
+        Piece::Slot(ref name) | Piece::Agree(_, ref name, _) => &name[..],
+      };
+      if !slots.contains(&name) {
+        slots.push(name);
+      }
+    }
+    slots
+  }
+}
+
+impl FromStr for Template {
+  type Err = ParseError;
+
+  fn from_str(text: &str) -> Result<Template, ParseError> {
+    let mut pieces = vec![];
+    let mut literal = String::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+      match c {
+        '{' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+          chars.next();
+          literal.push('{');
+        },
+        '}' if chars.peek().map(|&(_, c)| c) == Some('}') => {
+          chars.next();
+          literal.push('}');
+        },
+        '}' => return Err(ParseError { offset: offset, message: "unmatched `}`" }),
+        '{' => {
+          let end = text[offset..].find('}')
+            .ok_or(ParseError { offset: offset, message: "unclosed slot" })? + offset;
+          let error = |message| ParseError { offset: offset + 1, message: message };
+          let slot = &text[offset + 1..end];
+          let parts: Vec<&str> = slot.splitn(3, ':').collect();
+          if parts.iter().any(|part| part.is_empty() || part.contains('{')) {
+            return Err(error("malformed slot"));
+          }
+          if !literal.is_empty() {
+            pieces.push(Piece::Text(::std::mem::take(&mut literal)));
+          }
+          pieces.push(match parts.len() {
+            1 => Piece::Slot(slot.to_string()),
+            2 => Piece::Agree(parts[0].to_string(), parts[1].to_string(), Features::new()),
+            _ => {
+              let features = parts[2].parse().map_err(|_| error("malformed features"))?;
+              Piece::Agree(parts[0].to_string(), parts[1].to_string(), features)
+            }
+          });
+          while let Some(&(i, _)) = chars.peek() {
+            if i >= end {
+              break;
+            }
+            chars.next();
+          }
+          chars.next();
+        },
+        c => literal.push(c)
+      }
+    }
+    if !literal.is_empty() {
+      pieces.push(Piece::Text(literal));
+    }
+    Ok(Template { pieces: pieces })
+  }
+}
+
+/// An error arising when a template refers to a slot with no value.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct UnboundSlot {
+  /// The name of the slot.
+  pub name: String,
+}
+
+impl fmt::Display for UnboundSlot {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "no value for slot `{}`", self.name)
+  }
+}
+
+impl Error for UnboundSlot {
+  fn description(&self) -> &str {
+    "no value for slot"
+  }
+}
+
+/// Fills templates in language `L`, writing values by the conventions of
+/// the language and inflecting agreeing words with its [`Inflector`].
+///
+/// [`Inflector`]: ../morphology/struct.Inflector.html
+#[derive(Debug, Clone)]
+pub struct Realizer<L: Language> {
+  conventions: Conventions,
+  inflector: Inflector<L>,
+}
+
+impl<L: Language> Realizer<L> {
+  /// Creates a realizer with the given conventions and inflector.
+  pub fn new(conventions: Conventions, inflector: Inflector<L>) -> Realizer<L> {
+    Realizer { conventions: conventions, inflector: inflector }
+  }
+
+  /// Returns the conventions of the language.
+  pub fn conventions(&self) -> &Conventions {
+    &self.conventions
+  }
+
+  /// Returns the inflector of the language.
+  pub fn inflector(&self) -> &Inflector<L> {
+    &self.inflector
+  }
+
+  /// Writes a value by the conventions of the language.
+  pub fn write(&self, value: &Value) -> String {
+    match *value {
+      Value::Text(ref text) => text.clone(),
+      Value::Count(n) => self.conventions.format_number(n as f64, 0),
+      Value::Decimal(n, precision) => self.conventions.format_number(n, precision),
+      Value::List(ref items) => self.conventions.conjoin(items),
+    }
+  }
+
+  /// Fills the slots of a template. An agreeing word the inflector
+  /// cannot inflect is written as its lemma.
+  pub fn realize(&self, template: &Template, values: &HashMap<&str, Value>)
+      -> Result<String, UnboundSlot>
+  {
+    let value = |name: &str| {
+      values.get(name).ok_or_else(|| UnboundSlot { name: name.to_string() })
+    };
+    let mut realized = String::new();
+    for piece in &template.pieces {
+      match *piece {
+        Piece::Text(ref text) => realized.push_str(text),
+        Piece::Slot(ref name) => realized.push_str(&self.write(value(name)?)),
+        Piece::Agree(ref lemma, ref name, ref features) => {
+          let mut features = features.clone();
+          features.remove::<Number>();
+          features.insert(value(name)?.number());
+          match self.inflector.inflect(lemma, &features) {
+            Some(form) => realized.push_str(&form),
+            None => realized.push_str(lemma)
+          }
+        }
+      }
+    }
+    Ok(realized)
+  }
+}
@@ -0,0 +1,253 @@
+//! Reranking of n-best lists by a weighted combination of feature
+//! scores.
+//!
+//! Systems that produce several hypotheses per input—translations,
+//! transcriptions, parses—can often be improved by rescoring their
+//! n-best lists with features the system did not use, such as the score
+//! of a language model, or the length of the output. Each [`Hypothesis`]
+//! carries its feature scores, and a [`Reranker`] combines them linearly.
+//! The weights can be tuned on held-out lists with known gains, either by
+//! an exhaustive [`grid`] search or by the line search of [`mert`].
+//!
+//! ```rust
+//! let lists: Vec<Vec<Hypothesis<Line>>> = outputs.iter().map(|list| {
+//!   list.iter().map(|&(line, system_score)| Hypothesis {
+//!     output: line,
+//!     features: vec![system_score, model.log_probability(line), line.len() as f64],
+//!     gain: sentence_bleu(line, reference),
+//!   }).collect()
+//! }).collect();
+//!
+//! let reranker = Reranker::mert(&lists, vec![1.0, 0.0, 0.0], 10);
+//! let best = reranker.best(&lists[0]);
+//! ```
+//!
+//! [`Hypothesis`]: struct.Hypothesis.html
+//! [`Reranker`]: struct.Reranker.html
+//! [`grid`]: struct.Reranker.html#method.grid
+//! [`mert`]: struct.Reranker.html#method.mert
+use std::cmp::Ordering;
+use std::f64;
+
+/// A hypothesis of an n-best list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hypothesis<T> {
+  pub output: T,
+  /// The feature scores of the hypothesis, in the same order for every
+  /// hypothesis.
+  pub features: Vec<f64>,
+  /// The quality of the hypothesis against its reference, such as its
+  /// sentence-level BLEU score or negated word error rate. It is used only
+  /// for tuning, and may be zero otherwise.
+  pub gain: f64,
+}
+
+/// A linear combination of feature scores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reranker {
+  weights: Vec<f64>,
+}
+
+impl Reranker {
+  /// Creates a reranker with the given feature weights.
+  pub fn new(weights: Vec<f64>) -> Reranker {
+    Reranker { weights: weights }
+  }
+
+  /// Returns the weight of each feature.
+  pub fn weights(&self) -> &[f64] {
+    &self.weights
+  }
+
+  /// Returns the weighted sum of the feature scores. Features without a
+  /// weight are ignored.
+  pub fn score(&self, features: &[f64]) -> f64 {
+    dot(&self.weights, features)
+  }
+
+  /// Returns the highest scoring hypothesis of a list, or `None` if the
+  /// list is empty. Ties go to the earlier hypothesis.
+  pub fn best<'h, T>(&self, list: &'h [Hypothesis<T>]) -> Option<&'h Hypothesis<T>> {
+    argmax(list, &self.weights).map(|i| &list[i])
+  }
+
+  /// Returns the indices of the hypotheses of a list, from the highest
+  /// scoring to the lowest.
+  pub fn rerank<T>(&self, list: &[Hypothesis<T>]) -> Vec<usize> {
+    let scores: Vec<f64> = list.iter().map(|h| self.score(&h.features)).collect();
+    let mut order: Vec<usize> = (0..list.len()).collect();
+    order.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap_or(Ordering::Equal));
+    order
+  }
+
+  /// Returns the total gain of the hypotheses the reranker selects from
+  /// each list.
+  pub fn gain<T>(&self, lists: &[Vec<Hypothesis<T>>]) -> f64 {
+    total_gain(lists, &self.weights)
+  }
+
+  /// Tunes weights by trying every combination of the given values for
+  /// each of `features` weights, keeping the combination that selects
+  /// hypotheses of the greatest total gain. The search is exhaustive, so
+  /// it is practical only for a handful of features.
+  pub fn grid<T>(lists: &[Vec<Hypothesis<T>>], features: usize, values: &[f64]) -> Reranker {
+    let mut best = (f64::NEG_INFINITY, vec![0.0; features]);
+    if values.is_empty() {
+      return Reranker::new(best.1);
+    }
+    let mut indices = vec![0; features];
+    loop {
+      let weights: Vec<f64> = indices.iter().map(|&i| values[i]).collect();
+      let gain = total_gain(lists, &weights);
+      if gain > best.0 {
+        best = (gain, weights);
+      }
+      // Advance the indices like the digits of an odometer.
+      let mut digit = 0;
+      while digit < features && indices[digit] + 1 == values.len() {
+        indices[digit] = 0;
+        digit += 1;
+      }
+      if digit == features {
+        break;
+      }
+      indices[digit] += 1;
+    }
+    Reranker::new(best.1)
+  }
+
+  /// Tunes weights with the line search of minimum error rate training
+  /// (Och, 2003), starting from `initial`: each weight in turn is set
+  /// to the value that selects hypotheses of the greatest total gain,
+  /// holding the others fixed, which is found exactly from the upper
+  /// envelope of each list's scores as a function of that weight. Passes
+  /// over the weights repeat until none improves the gain, or for at most
+  /// `iterations` passes.
+  pub fn mert<T>(lists: &[Vec<Hypothesis<T>>], initial: Vec<f64>,
+                 iterations: usize) -> Reranker {
+    let mut weights = initial;
+    let mut gain = total_gain(lists, &weights);
+    for _ in 0..iterations {
+      let mut improved = false;
+      for feature in 0..weights.len() {
+        if let Some((value, new_gain)) = line_search(lists, &weights, feature) {
+          if new_gain > gain + 1e-12 {
+            weights[feature] = value;
+            gain = new_gain;
+            improved = true;
+          }
+        }
+      }
+      if !improved {
+        break;
+      }
+    }
+    Reranker::new(weights)
+  }
+}
+
+fn dot(weights: &[f64], features: &[f64]) -> f64 {
+  weights.iter().zip(features).map(|(w, f)| w * f).sum()
+}
+
+/// Returns the index of the highest scoring hypothesis of a list.
+fn argmax<T>(list: &[Hypothesis<T>], weights: &[f64]) -> Option<usize> {
+  let mut best: Option<(usize, f64)> = None;
+  for (i, hypothesis) in list.iter().enumerate() {
+    let score = dot(weights, &hypothesis.features);
+    match best {
+      Some((_, top)) if top >= score => {},
+      _ => best = Some((i, score))
+    }
+  }
+  best.map(|(i, _)| i)
+}
+
+fn total_gain<T>(lists: &[Vec<Hypothesis<T>>], weights: &[f64]) -> f64 {
+  lists.iter()
+    .filter_map(|list| argmax(list, weights).map(|i| list[i].gain))
+    .sum()
+}
+
+/// Finds the value of weight `feature` that maximizes the total gain,
+/// holding the other weights fixed, returning it with that gain.
+fn line_search<T>(lists: &[Vec<Hypothesis<T>>], weights: &[f64],
+                  feature: usize) -> Option<(f64, f64)> {
+  // As the weight varies over `x`, the score of each hypothesis is the
+  // line `intercept + x * slope`.
+  let mut base = 0.0;
+  let mut changes: Vec<(f64, f64)> = vec![];
+  for list in lists {
+    let lines: Vec<(f64, f64, f64)> = list.iter()
+      .map(|h| {
+        let slope = h.features.get(feature).cloned().unwrap_or(0.0);
+        (dot(weights, &h.features) - weights[feature] * slope, slope, h.gain)
+      })
+      .collect();
+    let envelope = upper_envelope(&lines);
+    if let Some(&(_, first)) = envelope.first() {
+      base += lines[first].2;
+    }
+    for pair in envelope.windows(2) {
+      let (x, next) = pair[1];
+      changes.push((x, lines[next].2 - lines[pair[0].1].2));
+    }
+  }
+  if lists.iter().all(|list| list.is_empty()) {
+    return None;
+  }
+
+  // Sweep the breakpoints, choosing the midpoint of the best interval.
+  changes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+  let mut best = (match changes.first() { Some(&(x, _)) => x - 1.0, None => 0.0 }, base);
+  let mut gain = base;
+  for (i, &(x, delta)) in changes.iter().enumerate() {
+    gain += delta;
+    let end = changes.get(i + 1).map(|&(next, _)| next);
+    if end == Some(x) {
+      continue;
+    }
+    if gain > best.1 {
+      best = (end.map_or(x + 1.0, |end| (x + end) / 2.0), gain);
+    }
+  }
+  Some(best)
+}
+
+/// Computes the upper envelope of lines `(intercept, slope, _)`: the
+/// lines that are highest for some `x`, from left to right, each with the
+/// `x` at which it becomes highest (negative infinity for the first).
+/// Of identical lines, the first is kept, as by `argmax`.
+fn upper_envelope(lines: &[(f64, f64, f64)]) -> Vec<(f64, usize)> {
+  let mut order: Vec<usize> = (0..lines.len()).collect();
+  order.sort_by(|&a, &b| {
+    (lines[a].1, lines[a].0).partial_cmp(&(lines[b].1, lines[b].0))
+      .unwrap_or(Ordering::Equal)
+      .then(b.cmp(&a))
+  });
+  let mut envelope: Vec<(f64, usize)> = vec![];
+  for l in order {
+    let (intercept, slope, _) = lines[l];
+    loop {
+      let (x, top) = match envelope.last() {
+        Some(&last) => last,
+        None => {
+          envelope.push((f64::NEG_INFINITY, l));
+          break;
+        }
+      };
+      if lines[top].1 == slope {
+        envelope.pop();
+        continue;
+      }
+      let crossing = (lines[top].0 - intercept) / (slope - lines[top].1);
+      if crossing <= x {
+        envelope.pop();
+        continue;
+      }
+      envelope.push((crossing, l));
+      break;
+    }
+  }
+  envelope
+}
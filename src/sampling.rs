@@ -0,0 +1,143 @@
+use rand::Rng;
+
+use std::hash::Hash;
+use std::collections::HashMap;
+
+/// How a [`StratifiedSampler`] divides a sample among strata.
+///
+/// [`StratifiedSampler`]: struct.StratifiedSampler.html
+#[derive(Debug, Clone, PartialEq)]
+enum Shares<K: Hash + Eq> {
+  /// In proportion to the number of items of each stratum.
+  Population,
+  /// Equally among strata.
+  Equal,
+  /// In the given proportions.
+  Given(HashMap<K, f64>),
+}
+
+/// Draws random samples of items, such as documents, that hold each
+/// stratum of some metadata, such as their genre, source or label, in
+/// chosen proportions: by default those of the population, so that
+/// the sample is representative of it; equal, for balanced evaluation
+/// sets; or any other.
+///
+/// Each stratum's share of the sample is rounded to a whole number of
+/// items by the largest remainder method, and a stratum with fewer items
+/// than its share contributes all of them, leaving the sample smaller
+/// than asked for rather than skewing its proportions.
+///
+/// ```rust
+/// let articles: Vec<(Genre, Document<English>)> = load_articles()?;
+/// let sampler = StratifiedSampler::new()
+///   .with_proportions(vec![(Genre::News, 0.5), (Genre::Fiction, 0.3), (Genre::Academic, 0.2)]);
+/// let rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+/// let evaluation: Vec<&Document<English>> = sampler
+///   .sample(&articles, |&(genre, _)| genre, 1000, rng)
+///   .into_iter()
+///   .map(|i| &articles[i].1)
+///   .collect();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StratifiedSampler<K: Hash + Eq> {
+  shares: Shares<K>,
+}
+
+impl<K: Hash + Eq + Clone> StratifiedSampler<K> {
+  /// Creates a sampler that divides samples among strata in proportion
+  /// to the number of items of each.
+  pub fn new() -> StratifiedSampler<K> {
+    StratifiedSampler { shares: Shares::Population }
+  }
+
+  /// Divides samples equally among the strata of the population.
+  pub fn with_equal_proportions(mut self) -> StratifiedSampler<K> {
+    self.shares = Shares::Equal;
+    self
+  }
+
+  /// Divides samples among strata in the given proportions, which need
+  /// not sum to `1`: they are rescaled to the strata of the population.
+  /// Strata without a proportion are left out of samples.
+  pub fn with_proportions<I>(mut self, proportions: I) -> StratifiedSampler<K>
+    where I: IntoIterator<Item=(K, f64)>
+  {
+    self.shares = Shares::Given(proportions.into_iter().collect());
+    self
+  }
+
+  /// Draws a sample of up to `size` items without replacement, in which
+  /// the stratum of each item is given by `stratum`, and returns the
+  /// indices of the items drawn, in ascending order. The random number
+  /// generator is taken by value; seeding it makes the sample
+  /// reproducible.
+  pub fn sample<T, F, R>(&self, items: &[T], mut stratum: F, size: usize, mut rng: R) -> Vec<usize>
+    where F: FnMut(&T) -> K,
+          R: Rng
+  {
+    // The strata in order of their first item, so that seeded generators
+    // draw the same sample on every run.
+    let mut strata: Vec<(K, Vec<usize>)> = vec![];
+    let mut index: HashMap<K, usize> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+      let key = stratum(item);
+      let next = strata.len();
+      let s = *index.entry(key.clone()).or_insert(next);
+      if s == next {
+        strata.push((key, vec![]));
+      }
+      strata[s].1.push(i);
+    }
+
+    let weights: Vec<f64> = strata.iter()
+      .map(|(key, members)| match self.shares {
+        Shares::Population => members.len() as f64,
+        Shares::Equal => 1.0,
+        Shares::Given(ref proportions) => proportions.get(key).cloned().unwrap_or(0.0).max(0.0),
+      })
+      .collect();
+    let quotas = quotas(&weights, size);
+
+    let mut sample = vec![];
+    for ((_, mut members), quota) in strata.into_iter().zip(quotas) {
+      let quota = quota.min(members.len());
+      // A partial Fisher-Yates shuffle moves a uniform sample of the
+      // members to the front.
+      for i in 0..quota {
+        let j = rng.gen_range(i, members.len());
+        members.swap(i, j);
+      }
+      sample.extend_from_slice(&members[..quota]);
+    }
+    sample.sort();
+    sample
+  }
+}
+
+impl<K: Hash + Eq + Clone> Default for StratifiedSampler<K> {
+  fn default() -> StratifiedSampler<K> {
+    StratifiedSampler::new()
+  }
+}
+
+/// Divides `size` items among strata in proportion to their weights,
+/// rounding by the largest remainder method, with ties going to the
+/// earlier stratum.
+pub(crate) fn quotas(weights: &[f64], size: usize) -> Vec<usize> {
+  let total: f64 = weights.iter().sum();
+  if total <= 0.0 {
+    return vec![0; weights.len()];
+  }
+  let exact: Vec<f64> = weights.iter().map(|&weight| weight / total * size as f64).collect();
+  let mut quotas: Vec<usize> = exact.iter().map(|&exact| exact.floor() as usize).collect();
+  let mut order: Vec<usize> = (0..weights.len()).collect();
+  order.sort_by(|&a, &b| {
+    let remainder = |s: usize| exact[s] - quotas[s] as f64;
+    remainder(b).partial_cmp(&remainder(a)).unwrap_or(::std::cmp::Ordering::Equal).then(a.cmp(&b))
+  });
+  let left = size.saturating_sub(quotas.iter().sum());
+  for &s in order.iter().take(left) {
+    quotas[s] += 1;
+  }
+  quotas
+}
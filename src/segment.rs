@@ -0,0 +1,328 @@
+//! Word segmentation for scripts written without spaces between words.
+//!
+//! A [`Lexicon`] assigns a cost to each known word, and segments text
+//! either greedily, by maximum matching, or optimally, by finding the
+//! segmentation with the least total cost. Segmented text is turned into
+//! an ordinary [`Document`]:
+//!
+//! ```rust
+//! let lexicon = Lexicon::from_counts(vec![("研究", 30), ("研究生", 5),
+//!                                        ("生命", 20), ("命", 2),
+//!                                        ("起源", 15)]);
+//!
+//! let document: Document<Chinese> =
+//!   lexicon.document("研究生命起源", Strategy::Viterbi);
+//! ```
+//!
+//! Where no dictionary exists, a [`BranchingEntropy`] model can instead
+//! learn likely word boundaries from raw, unsegmented text.
+//!
+//! [`Lexicon`]: struct.Lexicon.html
+//! [`BranchingEntropy`]: struct.BranchingEntropy.html
+//! [`Document`]: ../struct.Document.html
+use corpus::Document;
+
+use std::collections::HashMap;
+
+/// The method used to choose between competing segmentations.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Strategy {
+  /// Repeatedly take the longest known word at the start of the
+  /// remaining text.
+  MaximumMatching,
+  /// Choose the segmentation with the least total word cost.
+  Viterbi,
+}
+
+/// A vocabulary of words, each with a cost. Costs are typically negative
+/// log-probabilities, so that the cheapest segmentation of a string is
+/// the most probable under a unigram model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lexicon {
+  costs: HashMap<String, f64>,
+  /// The length, in characters, of the longest word.
+  longest: usize,
+  /// The cost of a single unknown character, if set explicitly.
+  unknown: Option<f64>,
+}
+
+impl Lexicon {
+  /// Creates an empty lexicon.
+  pub fn new() -> Lexicon {
+    Lexicon {
+      costs: HashMap::new(),
+      longest: 0,
+      unknown: None,
+    }
+  }
+
+  /// Creates a lexicon from word frequencies, assigning each word the
+  /// negative log of its relative frequency as its cost.
+  pub fn from_counts<I, S>(counts: I) -> Lexicon
+    where I: IntoIterator<Item=(S, usize)>,
+          S: Into<String>
+  {
+    let counts: Vec<(String, usize)> = counts.into_iter()
+      .filter(|&(_, count)| count > 0)
+      .map(|(word, count)| (word.into(), count))
+      .collect();
+    let total = counts.iter().map(|&(_, count)| count).sum::<usize>() as f64;
+    let mut lexicon = Lexicon::new();
+    for (word, count) in counts {
+      lexicon.insert(word, -(count as f64 / total).ln());
+    }
+    lexicon
+  }
+
+  /// Adds a word to the lexicon with the given cost, replacing any
+  /// previous cost for that word.
+  pub fn insert<S: Into<String>>(&mut self, word: S, cost: f64) {
+    let word = word.into();
+    self.longest = self.longest.max(word.chars().count());
+    self.costs.insert(word, cost);
+  }
+
+  /// Returns the cost of `word`, if it is in the lexicon.
+  pub fn cost(&self, word: &str) -> Option<f64> {
+    self.costs.get(word).cloned()
+  }
+
+  /// Sets the cost of segmenting a single character that does not begin
+  /// any known word. By default, this is one more than the cost of the
+  /// costliest word in the lexicon.
+  pub fn with_unknown_cost(mut self, cost: f64) -> Lexicon {
+    self.unknown = Some(cost);
+    self
+  }
+
+  fn unknown_cost(&self) -> f64 {
+    self.unknown.unwrap_or_else(||
+      self.costs.values().cloned().fold(0.0, f64::max) + 1.0)
+  }
+
+  /// Segments `text` into words. Whitespace in `text` is treated as a
+  /// word boundary, and is not included in any word.
+  pub fn segment<'s>(&self, text: &'s str, strategy: Strategy) -> Vec<&'s str> {
+    text.split_whitespace()
+      .flat_map(|chunk| match strategy {
+        Strategy::MaximumMatching => self.maximum_matching(chunk),
+        Strategy::Viterbi => self.viterbi(chunk),
+      })
+      .collect()
+  }
+
+  /// Segments each line of `text`, producing a document in which words
+  /// are separated by spaces.
+  pub fn document<L>(&self, text: &str, strategy: Strategy) -> Document<L> {
+    text.lines()
+      .map(|line| self.segment(line, strategy).join(" "))
+      .collect::<Vec<_>>()
+      .join("\n")
+      .into()
+  }
+
+  /// Greedily segments a chunk of text containing no whitespace.
+  fn maximum_matching<'s>(&self, chunk: &'s str) -> Vec<&'s str> {
+    let bounds = boundaries(chunk);
+    let mut words = vec![];
+    let mut start = 0;
+    while start + 1 < bounds.len() {
+      let longest = (start + self.longest).min(bounds.len() - 1);
+      let end = (start + 1..longest + 1).rev()
+        .find(|&end| self.costs.contains_key(&chunk[bounds[start]..bounds[end]]))
+        .unwrap_or(start + 1);
+      words.push(&chunk[bounds[start]..bounds[end]]);
+      start = end;
+    }
+    words
+  }
+
+  /// Finds the least costly segmentation of a chunk of text containing
+  /// no whitespace.
+  fn viterbi<'s>(&self, chunk: &'s str) -> Vec<&'s str> {
+    let bounds = boundaries(chunk);
+    let length = bounds.len() - 1;
+    let unknown = self.unknown_cost();
+
+    // `best[i]` is the cost of the cheapest segmentation of the first `i`
+    // characters, and `back[i]` is where its final word begins.
+    let mut best = vec![f64::INFINITY; length + 1];
+    let mut back = vec![0; length + 1];
+    best[0] = 0.0;
+
+    for end in 1..length + 1 {
+      let first = end.saturating_sub(self.longest.max(1));
+      for start in first..end {
+        let word = &chunk[bounds[start]..bounds[end]];
+        let cost = match self.costs.get(word) {
+          Some(&cost) => cost,
+          None if end - start == 1 => unknown,
+          None => continue
+        };
+        if best[start] + cost < best[end] {
+          best[end] = best[start] + cost;
+          back[end] = start;
+        }
+      }
+    }
+
+    let mut words = vec![];
+    let mut end = length;
+    while end > 0 {
+      let start = back[end];
+      words.push(&chunk[bounds[start]..bounds[end]]);
+      end = start;
+    }
+    words.reverse();
+    words
+  }
+}
+
+impl Default for Lexicon {
+  fn default() -> Lexicon {
+    Lexicon::new()
+  }
+}
+
+/// An unsupervised word segmenter based on branching entropy. Within a
+/// word, the next character is fairly predictable from the preceding
+/// ones; at a word boundary, it is not. The uncertainty of the character
+/// following (and preceding) each substring is learned from raw text,
+/// and boundaries are placed where that uncertainty peaks.
+///
+/// ```rust
+/// let raw = fs::read_to_string("unsegmented.txt")?;
+/// let model = BranchingEntropy::train(&raw, 4);
+/// let document: Document<Thai> = model.document(&raw);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchingEntropy {
+  order: usize,
+  threshold: Option<f64>,
+  /// Counts of the characters following each substring.
+  forward: HashMap<String, HashMap<char, usize>>,
+  /// Counts of the characters preceding each substring.
+  backward: HashMap<String, HashMap<char, usize>>,
+}
+
+impl BranchingEntropy {
+  /// Learns branching entropies from each line of `text`, considering
+  /// substrings of up to `order` characters.
+  pub fn train(text: &str, order: usize) -> BranchingEntropy {
+    let mut forward: HashMap<String, HashMap<char, usize>> = HashMap::new();
+    let mut backward: HashMap<String, HashMap<char, usize>> = HashMap::new();
+
+    for chunk in text.split_whitespace() {
+      let chars: Vec<char> = chunk.chars().collect();
+      for i in 0..chars.len() {
+        for k in 1..order + 1 {
+          if i + k < chars.len() {
+            let context: String = chars[i..i + k].iter().collect();
+            *forward.entry(context).or_default()
+              .entry(chars[i + k]).or_insert(0) += 1;
+          }
+          if i + 1 + k <= chars.len() {
+            let context: String = chars[i + 1..i + 1 + k].iter().collect();
+            *backward.entry(context).or_default()
+              .entry(chars[i]).or_insert(0) += 1;
+          }
+        }
+      }
+    }
+
+    BranchingEntropy {
+      order: order,
+      threshold: None,
+      forward: forward,
+      backward: backward,
+    }
+  }
+
+  /// Places a boundary wherever the boundary score exceeds `threshold`.
+  /// By default, boundaries are instead placed wherever the score is a
+  /// local maximum.
+  pub fn with_threshold(mut self, threshold: f64) -> BranchingEntropy {
+    self.threshold = Some(threshold);
+    self
+  }
+
+  /// Returns the boundary score before each character of `chars`: the
+  /// mean branching entropy of the substrings ending and beginning at
+  /// that position.
+  fn scores(&self, chars: &[char]) -> Vec<f64> {
+    (0..chars.len() + 1)
+      .map(|i| {
+        let (mut total, mut terms) = (0.0, 0);
+        for k in 1..self.order + 1 {
+          if k <= i {
+            let context: String = chars[i - k..i].iter().collect();
+            total += entropy(self.forward.get(&context));
+            terms += 1;
+          }
+          if i + k <= chars.len() {
+            let context: String = chars[i..i + k].iter().collect();
+            total += entropy(self.backward.get(&context));
+            terms += 1;
+          }
+        }
+        if terms == 0 { 0.0 } else { total / terms as f64 }
+      })
+      .collect()
+  }
+
+  /// Segments `text` into words. Whitespace in `text` is treated as a
+  /// word boundary, and is not included in any word.
+  pub fn segment<'s>(&self, text: &'s str) -> Vec<&'s str> {
+    let mut words = vec![];
+    for chunk in text.split_whitespace() {
+      let bounds = boundaries(chunk);
+      let chars: Vec<char> = chunk.chars().collect();
+      let scores = self.scores(&chars);
+      let mut start = 0;
+      for i in 1..chars.len() {
+        let boundary = match self.threshold {
+          Some(threshold) => scores[i] > threshold,
+          None => scores[i] > scores[i - 1] && scores[i] >= scores[i + 1]
+        };
+        if boundary {
+          words.push(&chunk[bounds[start]..bounds[i]]);
+          start = i;
+        }
+      }
+      words.push(&chunk[bounds[start]..]);
+    }
+    words
+  }
+
+  /// Segments each line of `text`, producing a document in which words
+  /// are separated by spaces.
+  pub fn document<L>(&self, text: &str) -> Document<L> {
+    text.lines()
+      .map(|line| self.segment(line).join(" "))
+      .collect::<Vec<_>>()
+      .join("\n")
+      .into()
+  }
+}
+
+/// The entropy, in bits, of a distribution given by counts.
+fn entropy(counts: Option<&HashMap<char, usize>>) -> f64 {
+  let counts = match counts {
+    Some(counts) => counts,
+    None => return 0.0
+  };
+  let total = counts.values().sum::<usize>() as f64;
+  counts.values()
+    .map(|&count| count as f64 / total)
+    .map(|p| -p * p.log2())
+    .sum()
+}
+
+/// Returns the byte offset of every character boundary in `text`,
+/// including the end of the text.
+fn boundaries(text: &str) -> Vec<usize> {
+  text.char_indices()
+    .map(|(i, _)| i)
+    .chain(Some(text.len()))
+    .collect()
+}
@@ -0,0 +1,179 @@
+use std::ops::Deref;
+use std::sync::Arc;
+use std::hash::Hash;
+use std::collections::{BTreeMap, HashMap};
+
+/// A trained model, such as a language model, tagger or classifier,
+/// shared among threads for scoring, as inside a web service.
+///
+/// Scoring only reads a trained model, so threads share it without locks:
+/// a `Serving` holds the model behind a reference count, and each clone
+/// is a handle to the same model, to be moved to another thread. Only
+/// models that may be read from several threads at once, which are
+/// `Sync`, can be served, so that a model with unsynchronized interior
+/// mutability is rejected when compiled rather than raced on.
+///
+/// Each thread may also keep a [`ServingCache`] of recent scores, which
+/// needs no locks either, as it belongs to that thread alone:
+///
+/// ```rust
+/// let serving = Serving::new(Model::train(corpus.lines(), 3));
+/// thread::scope(|scope| {
+///   for requests in batches {
+///     let serving = serving.clone();
+///     scope.spawn(move || {
+///       let mut cache = serving.cache(10_000);
+///       for line in requests {
+///         let score = cache.get_or_score(line.to_vec(), |model, line| model.log_probability(line));
+///         respond(score);
+///       }
+///     });
+///   }
+/// });
+/// ```
+///
+/// [`ServingCache`]: struct.ServingCache.html
+#[derive(Debug)]
+pub struct Serving<M> {
+  model: Arc<M>,
+}
+
+impl<M> Serving<M>
+  where M: Send + Sync
+{
+  /// Serves a model.
+  pub fn new(model: M) -> Serving<M> {
+    Serving { model: Arc::new(model) }
+  }
+
+  /// Returns the model served.
+  pub fn model(&self) -> &M {
+    &self.model
+  }
+
+  /// Returns a handle to the model with a cache of the scores of at most
+  /// `capacity` keys, for the thread that holds it. When the cache is
+  /// full, the key used least recently is dropped to make room.
+  pub fn cache<K, V>(&self, capacity: usize) -> ServingCache<M, K, V>
+    where K: Hash + Eq + Clone,
+          V: Clone
+  {
+    ServingCache {
+      model: self.model.clone(),
+      capacity: capacity,
+      entries: HashMap::new(),
+      recency: BTreeMap::new(),
+      clock: 0,
+      hits: 0,
+      misses: 0,
+    }
+  }
+}
+
+impl<M> Clone for Serving<M> {
+  fn clone(&self) -> Serving<M> {
+    Serving { model: self.model.clone() }
+  }
+}
+
+impl<M> Deref for Serving<M> {
+  type Target = M;
+
+  fn deref(&self) -> &M {
+    &self.model
+  }
+}
+
+impl<M: Send + Sync> From<Arc<M>> for Serving<M> {
+  /// Serves a model already shared by reference counting.
+  fn from(model: Arc<M>) -> Serving<M> {
+    Serving { model: model }
+  }
+}
+
+/// A handle to a [`Serving`] model with a cache of scores by key, created
+/// by [`Serving::cache`], for the repeated requests of a single thread.
+/// It may be moved between threads, but not shared by them.
+///
+/// [`Serving`]: struct.Serving.html
+/// [`Serving::cache`]: struct.Serving.html#method.cache
+#[derive(Debug)]
+pub struct ServingCache<M, K, V> {
+  model: Arc<M>,
+  capacity: usize,
+  /// The score of each key, and when it was last used.
+  entries: HashMap<K, (V, u64)>,
+  /// The key last used at each time.
+  recency: BTreeMap<u64, K>,
+  clock: u64,
+  hits: usize,
+  misses: usize,
+}
+
+impl<M, K, V> ServingCache<M, K, V>
+  where K: Hash + Eq + Clone,
+        V: Clone
+{
+  /// Returns the model served.
+  pub fn model(&self) -> &M {
+    &self.model
+  }
+
+  /// Returns the cached score of a key, or scores it with the model by
+  /// `score` and caches the score.
+  pub fn get_or_score<F>(&mut self, key: K, score: F) -> V
+    where F: FnOnce(&M, &K) -> V
+  {
+    self.clock += 1;
+    let clock = self.clock;
+    if let Some(&mut (ref value, ref mut used)) = self.entries.get_mut(&key) {
+      self.hits += 1;
+      self.recency.remove(used);
+      self.recency.insert(clock, key);
+      *used = clock;
+      return value.clone();
+    }
+    self.misses += 1;
+    let value = score(&self.model, &key);
+    if self.capacity == 0 {
+      return value;
+    }
+    if self.entries.len() >= self.capacity {
+      let oldest = self.recency.keys().next().cloned();
+      if let Some(key) = oldest.and_then(|oldest| self.recency.remove(&oldest)) {
+        self.entries.remove(&key);
+      }
+    }
+    self.recency.insert(clock, key.clone());
+    self.entries.insert(key, (value.clone(), clock));
+    value
+  }
+
+  /// Returns the number of keys cached.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns `true` if no keys are cached.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Returns the number of requests answered from the cache.
+  pub fn hits(&self) -> usize {
+    self.hits
+  }
+
+  /// Returns the number of requests scored by the model.
+  pub fn misses(&self) -> usize {
+    self.misses
+  }
+
+  /// Empties the cache, and resets its counts of hits and misses.
+  pub fn clear(&mut self) {
+    self.entries.clear();
+    self.recency.clear();
+    self.hits = 0;
+    self.misses = 0;
+  }
+}
@@ -0,0 +1,463 @@
+use tree::Tree;
+use grammar::{GrammarInducer, restore};
+use perceptron::Perceptron;
+
+use std::fmt;
+use std::rc::Rc;
+use std::collections::BTreeSet;
+
+/// The longest chain of unary reductions the parser will build.
+const MAX_UNARY_CHAIN: usize = 3;
+
+/// A transition of a [`ShiftReduceParser`].
+/// [`ShiftReduceParser`]: struct.ShiftReduceParser.html
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Transition {
+  /// Moves the next word, with its tag, onto the stack.
+  Shift,
+  /// Replaces the top of the stack with a constituent of the given label
+  /// over it.
+  Unary(String),
+  /// Replaces the top two items of the stack with a constituent of the
+  /// given label over them.
+  Binary(String),
+  /// Accepts the single constituent on the stack as the parse.
+  Finish,
+}
+
+/// A partial parse on the stack.
+#[derive(Debug)]
+struct Item {
+  label: String,
+  start: usize,
+  end: usize,
+  children: Vec<Rc<Item>>,
+  /// The number of unary reductions directly beneath this item.
+  unary: usize,
+}
+
+/// A persistent stack of partial parses.
+#[derive(Debug)]
+struct Stack {
+  top: Rc<Item>,
+  below: Option<Rc<Stack>>,
+}
+
+/// A persistent history of the actions taken to reach a state, with the
+/// features each was scored by.
+#[derive(Debug)]
+struct History {
+  action: Transition,
+  features: Rc<Vec<String>>,
+  previous: Option<Rc<History>>,
+}
+
+/// A parser state in the beam.
+#[derive(Debug, Clone)]
+struct State {
+  stack: Option<Rc<Stack>>,
+  /// The position of the next word to shift.
+  next: usize,
+  finished: bool,
+  score: f64,
+  history: Option<Rc<History>>,
+  /// Whether every action so far agrees with the gold derivation.
+  gold: bool,
+}
+
+impl State {
+  fn initial() -> State {
+    State {
+      stack: None,
+      next: 0,
+      finished: false,
+      score: 0.0,
+      history: None,
+      gold: true,
+    }
+  }
+
+  fn top(&self) -> Option<&Rc<Item>> {
+    self.stack.as_ref().map(|stack| &stack.top)
+  }
+
+  fn second(&self) -> Option<&Rc<Item>> {
+    self.stack.as_ref()
+      .and_then(|stack| stack.below.as_ref())
+      .map(|below| &below.top)
+  }
+
+  fn depth(&self) -> usize {
+    let mut depth = 0;
+    let mut stack = self.stack.as_ref();
+    while let Some(s) = stack {
+      depth += 1;
+      stack = s.below.as_ref();
+    }
+    depth
+  }
+
+  fn last_action(&self) -> Option<&Transition> {
+    self.history.as_ref().map(|history| &history.action)
+  }
+
+  /// Applies `action`, which must be valid in this state.
+  fn apply(&self, action: &Transition, tags: &[String], features: Rc<Vec<String>>,
+           score: f64) -> State {
+    let stack = self.stack.clone();
+    let (stack, next, finished) = match *action {
+      Transition::Shift => {
+        let item = Item {
+          label: tags[self.next].clone(),
+          start: self.next,
+          end: self.next + 1,
+          children: vec![],
+          unary: 0,
+        };
+        (push(stack, item), self.next + 1, false)
+      },
+      Transition::Unary(ref label) => {
+        let stack = stack.unwrap();
+        let item = Item {
+          label: label.clone(),
+          start: stack.top.start,
+          end: stack.top.end,
+          unary: stack.top.unary + 1,
+          children: vec![stack.top.clone()],
+        };
+        (push(stack.below.clone(), item), self.next, false)
+      },
+      Transition::Binary(ref label) => {
+        let stack = stack.unwrap();
+        let below = stack.below.clone().unwrap();
+        let item = Item {
+          label: label.clone(),
+          start: below.top.start,
+          end: stack.top.end,
+          unary: 0,
+          children: vec![below.top.clone(), stack.top.clone()],
+        };
+        (push(below.below.clone(), item), self.next, false)
+      },
+      Transition::Finish => (stack, self.next, true),
+    };
+    State {
+      stack: stack,
+      next: next,
+      finished: finished,
+      score: self.score + score,
+      history: Some(Rc::new(History {
+        action: action.clone(),
+        features: features,
+        previous: self.history.clone(),
+      })),
+      gold: false,
+    }
+  }
+
+  /// Returns every `(features, action)` pair taken to reach this state.
+  fn steps(&self) -> Vec<(Rc<Vec<String>>, Transition)> {
+    let mut steps = vec![];
+    let mut history = self.history.as_ref();
+    while let Some(h) = history {
+      steps.push((h.features.clone(), h.action.clone()));
+      history = h.previous.as_ref();
+    }
+    steps.reverse();
+    steps
+  }
+}
+
+fn push(below: Option<Rc<Stack>>, item: Item) -> Option<Rc<Stack>> {
+  Some(Rc::new(Stack { top: Rc::new(item), below: below }))
+}
+
+/// A transition-based constituency parser, following Zhang and Clark
+/// (2009): a shift-reduce parser over binarized trees, scored by an
+/// averaged perceptron and searched with a beam. It parses in time
+/// linear in the length of the sentence, making it much faster than
+/// chart parsing for long sentences.
+///
+/// The parser expects part-of-speech tagged input, and produces trees
+/// whose preterminals are the given tags:
+///
+/// ```rust
+/// let trees = read_trees(&fs::read_to_string("train.mrg")?)?;
+/// let parser = ShiftReduceParser::train(&trees, 16, 10);
+///
+/// let tagged = [("the", "DT"), ("dog", "NN"), ("barked", "VBD")];
+/// println!("{}", parser.parse(&tagged).unwrap());
+/// ```
+///
+/// Training trees are binarized as by a [`GrammarInducer`] with
+/// horizontal markovization of order one, and the binarization undone
+/// in the output.
+///
+/// [`GrammarInducer`]: struct.GrammarInducer.html
+#[derive(Debug, Clone)]
+pub struct ShiftReduceParser {
+  model: Perceptron<Transition>,
+  unary: Vec<String>,
+  binary: Vec<String>,
+  beam: usize,
+}
+
+impl ShiftReduceParser {
+  /// Trains a parser on a treebank, for `iterations` passes over the
+  /// trees with a beam of width `beam`, with early update (Collins and
+  /// Roark, 2004): when the gold derivation falls off the beam, the model
+  /// is updated towards its prefix and away from the best state.
+  pub fn train<'a, I, T>(trees: I, beam: usize, iterations: usize) -> ShiftReduceParser
+    where I: IntoIterator<Item=&'a Tree<T>>,
+          T: 'a + fmt::Display
+  {
+    let transform = GrammarInducer::new().horizontal(1);
+    let examples: Vec<(Vec<String>, Vec<String>, Vec<Transition>)> = trees.into_iter()
+      .map(|tree| transform.transform(tree))
+      .filter_map(|tree| {
+        let mut words = vec![];
+        let mut tags = vec![];
+        let mut actions = vec![];
+        oracle(&tree, &mut words, &mut tags, &mut actions)?;
+        actions.push(Transition::Finish);
+        Some((words, tags, actions))
+      })
+      .collect();
+
+    let mut unary = BTreeSet::new();
+    let mut binary = BTreeSet::new();
+    for (_, _, actions) in &examples {
+      for action in actions {
+        match *action {
+          Transition::Unary(ref label) => { unary.insert(label.clone()); },
+          Transition::Binary(ref label) => { binary.insert(label.clone()); },
+          _ => {}
+        }
+      }
+    }
+
+    let mut parser = ShiftReduceParser {
+      model: Perceptron::new(),
+      unary: unary.into_iter().collect(),
+      binary: binary.into_iter().collect(),
+      beam: beam.max(1),
+    };
+    for _ in 0..iterations {
+      for (words, tags, gold) in &examples {
+        parser.train_one(words, tags, gold);
+        parser.model.tick();
+      }
+    }
+    parser.model.average();
+    parser
+  }
+
+  /// Sets the width of the beam used when parsing.
+  pub fn with_beam(mut self, width: usize) -> ShiftReduceParser {
+    self.beam = width.max(1);
+    self
+  }
+
+  fn train_one(&mut self, words: &[String], tags: &[String], gold: &[Transition]) {
+    let mut beam = vec![State::initial()];
+    for step in 0.. {
+      if beam.iter().all(|state| state.finished) {
+        break;
+      }
+      beam = self.advance(&beam, words, tags, Some(gold.get(step)));
+      let best = &beam[0];
+      let gold_state = beam.iter().find(|state| state.gold);
+      let lost = gold_state.is_none();
+      if lost || beam.iter().all(|state| state.finished) {
+        if lost || !best.gold {
+          self.early_update(words, tags, &gold[..(step + 1).min(gold.len())], best);
+        }
+        return;
+      }
+    }
+  }
+
+  /// Updates the model towards the gold action sequence `gold`, and away
+  /// from the actions taken to reach `predicted`.
+  fn early_update(&mut self, words: &[String], tags: &[String], gold: &[Transition],
+                  predicted: &State) {
+    let mut state = State::initial();
+    for action in gold {
+      let features = Rc::new(features(&state, words, tags));
+      self.model.update(&features, action, 1.0);
+      state = state.apply(action, tags, features, 0.0);
+    }
+    for (features, action) in predicted.steps() {
+      self.model.update(&features, &action, -1.0);
+    }
+  }
+
+  /// Expands every state in the beam by every valid action, and keeps the
+  /// best. When training, `gold` is the next gold action, if any.
+  fn advance(&self, beam: &[State], words: &[String], tags: &[String],
+             gold: Option<Option<&Transition>>) -> Vec<State> {
+    let mut next = vec![];
+    for state in beam {
+      if state.finished {
+        let mut idle = state.clone();
+        idle.gold = state.gold && gold == Some(None);
+        next.push(idle);
+        continue;
+      }
+      let features = Rc::new(features(state, words, tags));
+      let scores = self.model.scores(&features);
+      for action in self.actions(state, words.len()) {
+        let score = scores.get(&action).cloned().unwrap_or(0.0);
+        let mut successor = state.apply(&action, tags, features.clone(), score);
+        successor.gold = state.gold && gold == Some(Some(&action));
+        next.push(successor);
+      }
+    }
+    next.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    next.truncate(self.beam);
+    next
+  }
+
+  /// Returns the actions valid in `state`, for a sentence of `length`
+  /// words.
+  fn actions(&self, state: &State, length: usize) -> Vec<Transition> {
+    let mut actions = vec![];
+    let depth = state.depth();
+    if state.next < length {
+      actions.push(Transition::Shift);
+    }
+    if let Some(top) = state.top() {
+      // Intermediate constituents may only be completed by a binary
+      // reduction, and never extended by a unary one.
+      if top.unary < MAX_UNARY_CHAIN && !top.label.starts_with('@') {
+        for label in &self.unary {
+          if !label.starts_with('@') {
+            actions.push(Transition::Unary(label.clone()));
+          }
+        }
+      }
+      if depth == 1 && state.next == length && !top.label.starts_with('@') {
+        actions.push(Transition::Finish);
+      }
+    }
+    if depth >= 2 {
+      let second_intermediate = match state.second() {
+        Some(second) => second.label.starts_with('@'),
+        None => false
+      };
+      for label in &self.binary {
+        // A right-factored intermediate node never has an intermediate
+        // node as its left child.
+        if !second_intermediate {
+          actions.push(Transition::Binary(label.clone()));
+        }
+      }
+    }
+    actions
+  }
+
+  /// Parses a sentence of `(word, tag)` pairs, returning `None` if the
+  /// sentence is empty or no complete parse survives the beam.
+  pub fn parse<W, G>(&self, tagged: &[(W, G)]) -> Option<Tree>
+    where W: AsRef<str>,
+          G: AsRef<str>
+  {
+    let words: Vec<String> = tagged.iter().map(|(w, _)| w.as_ref().to_string()).collect();
+    let tags: Vec<String> = tagged.iter().map(|(_, g)| g.as_ref().to_string()).collect();
+    if words.is_empty() {
+      return None;
+    }
+
+    let mut beam = vec![State::initial()];
+    while !beam.iter().all(|state| state.finished) {
+      beam = self.advance(&beam, &words, &tags, None);
+      if beam.is_empty() {
+        return None;
+      }
+    }
+    let top = beam[0].top()?;
+    Some(restore(build(top, &words)))
+  }
+}
+
+/// Appends the words, tags and gold actions of a binarized tree, or
+/// returns `None` if the tree cannot be derived by the parser.
+fn oracle(tree: &Tree, words: &mut Vec<String>, tags: &mut Vec<String>,
+          actions: &mut Vec<Transition>) -> Option<()> {
+  match *tree {
+    Tree::Node(ref tag, ref children) if tree.is_preterminal() => {
+      if let Tree::Leaf(ref word) = children[0] {
+        words.push(word.clone());
+      }
+      tags.push(tag.clone());
+      actions.push(Transition::Shift);
+    },
+    Tree::Node(ref label, ref children) => {
+      for child in children {
+        oracle(child, words, tags, actions)?;
+      }
+      match children.len() {
+        1 => actions.push(Transition::Unary(label.clone())),
+        2 => actions.push(Transition::Binary(label.clone())),
+        _ => return None
+      }
+    },
+    Tree::Leaf(_) => return None,
+  }
+  Some(())
+}
+
+/// Converts a partial parse to a tree.
+fn build(item: &Item, words: &[String]) -> Tree {
+  if item.children.is_empty() {
+    Tree::Node(item.label.clone(), vec![Tree::Leaf(words[item.start].clone())])
+  } else {
+    Tree::Node(item.label.clone(),
+               item.children.iter().map(|child| build(child, words)).collect())
+  }
+}
+
+/// Extracts the features of a state.
+fn features(state: &State, words: &[String], tags: &[String]) -> Vec<String> {
+  let word = |i: usize| words.get(i).map_or("</s>", |w| &w[..]);
+  let tag = |i: usize| tags.get(i).map_or("</s>", |t| &t[..]);
+  let none = "<none>";
+
+  let s0 = state.top();
+  let s1 = state.second();
+  let s0l = s0.map_or(none, |item| &item.label[..]);
+  let s1l = s1.map_or(none, |item| &item.label[..]);
+  let s0w = s0.map_or(none, |item| word(item.end - 1));
+  let s0f = s0.map_or(none, |item| word(item.start));
+  let s1w = s1.map_or(none, |item| word(item.end - 1));
+  let q0w = word(state.next);
+  let q0t = tag(state.next);
+  let q1w = word(state.next + 1);
+  let q1t = tag(state.next + 1);
+  let last = match state.last_action() {
+    Some(&Transition::Shift) => "shift",
+    Some(&Transition::Unary(_)) => "unary",
+    Some(&Transition::Binary(_)) => "binary",
+    Some(&Transition::Finish) => "finish",
+    None => none,
+  };
+
+  vec![
+    String::from("bias"),
+    format!("s0l={}", s0l),
+    format!("s0w={}", s0w),
+    format!("s0f={}", s0f),
+    format!("s0l,s0w={},{}", s0l, s0w),
+    format!("s1l={}", s1l),
+    format!("s1l,s1w={},{}", s1l, s1w),
+    format!("q0w={}", q0w),
+    format!("q0t={}", q0t),
+    format!("q1w={}", q1w),
+    format!("q1t={}", q1t),
+    format!("s0l,s1l={},{}", s0l, s1l),
+    format!("s0l,q0t={},{}", s0l, q0t),
+    format!("s1l,s0l,q0t={},{},{}", s1l, s0l, q0t),
+    format!("s0l,q0t,q1t={},{},{}", s0l, q0t, q1t),
+    format!("last,s0l={},{}", last, s0l),
+  ]
+}
@@ -0,0 +1,131 @@
+//! Similarities between documents, from their sparse vectors, for finding
+//! near-duplicates and clustering a corpus.
+//!
+//! The vectors are those of a [`TfIdf`] transformer, or any other sparse
+//! vectors of `(term, weight)` entries in order of term. The similarity
+//! of every pair of documents of a corpus is its [`similarity_matrix`],
+//! which may be computed across threads for large corpora:
+//!
+//! ```rust
+//! let matrix = similarity::par_similarity_matrix(&corpus);
+//! for (a, b) in similarity::duplicates(&matrix, 0.9) {
+//!   println!("{}\t{}\t{:.3}", a, b, matrix[a][b]);
+//! }
+//! ```
+//!
+//! [`TfIdf`]: ../struct.TfIdf.html
+//! [`similarity_matrix`]: fn.similarity_matrix.html
+use language::Language;
+use collection::Corpus;
+use tfidf::TfIdf;
+
+use std::thread;
+use std::cmp::Ordering;
+
+/// Returns the cosine of the angle between two sparse vectors, given as
+/// `(term, weight)` entries in order of term, or zero if either is zero.
+pub fn cosine(a: &[(usize, f64)], b: &[(usize, f64)]) -> f64 {
+  let norms = norm(a) * norm(b);
+  if norms == 0.0 { 0.0 } else { dot(a, b) / norms }
+}
+
+fn norm(a: &[(usize, f64)]) -> f64 {
+  a.iter().map(|&(_, weight)| weight * weight).sum::<f64>().sqrt()
+}
+
+fn dot(a: &[(usize, f64)], b: &[(usize, f64)]) -> f64 {
+  let (mut i, mut j) = (0, 0);
+  let mut sum = 0.0;
+  while i < a.len() && j < b.len() {
+    match a[i].0.cmp(&b[j].0) {
+      Ordering::Less => i += 1,
+      Ordering::Greater => j += 1,
+      Ordering::Equal => {
+        sum += a[i].1 * b[j].1;
+        i += 1;
+        j += 1;
+      }
+    }
+  }
+  sum
+}
+
+/// Returns the cosine similarity of every pair of documents of a corpus,
+/// by their TF-IDF vectors with a transformer of default settings fit to
+/// the corpus. Row `i` holds the similarities of document `i`; a document
+/// with no terms is similar to none, not even itself.
+pub fn similarity_matrix<L: Language>(corpus: &Corpus<L>) -> Vec<Vec<f64>> {
+  cosine_matrix(&TfIdf::new().fit(corpus).transform_all(corpus))
+}
+
+/// Returns the same similarities as [`similarity_matrix`], spreading the
+/// documents across threads.
+///
+/// [`similarity_matrix`]: fn.similarity_matrix.html
+pub fn par_similarity_matrix<L: Language>(corpus: &Corpus<L>) -> Vec<Vec<f64>> {
+  par_cosine_matrix(&TfIdf::new().fit(corpus).transform_all(corpus))
+}
+
+/// Returns the cosine similarity of every pair of sparse vectors.
+pub fn cosine_matrix(vectors: &[Vec<(usize, f64)>]) -> Vec<Vec<f64>> {
+  let rows: Vec<(usize, Vec<f64>)> = (0..vectors.len())
+    .map(|i| (i, row(vectors, i)))
+    .collect();
+  symmetric(vectors.len(), rows)
+}
+
+/// Returns the same similarities as [`cosine_matrix`], spreading the
+/// vectors across threads.
+///
+/// [`cosine_matrix`]: fn.cosine_matrix.html
+pub fn par_cosine_matrix(vectors: &[Vec<(usize, f64)>]) -> Vec<Vec<f64>> {
+  let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+  // Each row is compared only with those after it, so rows are dealt out
+  // in turn rather than in runs, to give each thread a like share.
+  let rows: Vec<(usize, Vec<f64>)> = thread::scope(|scope| {
+    let workers: Vec<_> = (0..threads.min(vectors.len()))
+      .map(|first| scope.spawn(move || {
+        (first..vectors.len()).step_by(threads)
+          .map(|i| (i, row(vectors, i)))
+          .collect::<Vec<_>>()
+      }))
+      .collect();
+    workers.into_iter()
+      .flat_map(|worker| worker.join().unwrap_or_else(|panic| ::std::panic::resume_unwind(panic)))
+      .collect()
+  });
+  symmetric(vectors.len(), rows)
+}
+
+/// Returns the similarities of vector `i` to itself and each vector after
+/// it.
+fn row(vectors: &[Vec<(usize, f64)>], i: usize) -> Vec<f64> {
+  vectors[i..].iter().map(|vector| cosine(&vectors[i], vector)).collect()
+}
+
+/// Fills a symmetric matrix from the rows of its upper triangle.
+fn symmetric(size: usize, rows: Vec<(usize, Vec<f64>)>) -> Vec<Vec<f64>> {
+  let mut matrix = vec![vec![0.0; size]; size];
+  for (i, row) in rows {
+    for (offset, similarity) in row.into_iter().enumerate() {
+      matrix[i][i + offset] = similarity;
+      matrix[i + offset][i] = similarity;
+    }
+  }
+  matrix
+}
+
+/// Returns the pairs of distinct documents whose similarity is at least
+/// `threshold`, such as `0.9` for near-duplicates, each as `(a, b)` with
+/// `a < b`, in order.
+pub fn duplicates(matrix: &[Vec<f64>], threshold: f64) -> Vec<(usize, usize)> {
+  let mut pairs = vec![];
+  for (a, row) in matrix.iter().enumerate() {
+    for (b, &similarity) in row.iter().enumerate().skip(a + 1) {
+      if similarity >= threshold {
+        pairs.push((a, b));
+      }
+    }
+  }
+  pairs
+}
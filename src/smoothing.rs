@@ -0,0 +1,352 @@
+//! Smoothed conditional probability estimators over arbitrary
+//! `(context, event)` counts.
+//!
+//! Nothing in this module is specific to tokens: the same estimators
+//! can smooth word bigrams, part-of-speech transitions, or character
+//! models. Each estimator is constructed from an iterator over
+//! `((context, event), count)` pairs, such as a `HashMap<(C, E), usize>`.
+//!
+//! ```rust
+//! let mut transitions = HashMap::new();
+//! for (previous, next) in tags.iter().tuple_windows() {
+//!   *transitions.entry((previous, next)).or_insert(0) += 1;
+//! }
+//!
+//! let estimator = WittenBell::new(transitions);
+//! let p = estimator.probability(&"DT", &"NN");
+//! ```
+//!
+//! Every estimator reserves a share of probability in every context for
+//! events never observed in it, so that unseen events are assigned
+//! non-zero probability.
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A conditional probability distribution over events, given a context.
+pub trait Estimator<C, E> {
+  /// Returns the smoothed probability of `event` occurring in `context`.
+  fn probability(&self, context: &C, event: &E) -> f64;
+}
+
+/// Count tables shared by all estimators.
+struct Table<C, E>
+  where C: Hash + Eq,
+        E: Hash + Eq
+{
+  /// Counts of each event, by context.
+  joint: HashMap<C, HashMap<E, usize>>,
+  /// Total count of each context.
+  contexts: HashMap<C, usize>,
+  /// Total count of each event, regardless of context.
+  events: HashMap<E, usize>,
+  /// Number of distinct contexts each event has been observed in.
+  continuations: HashMap<E, usize>,
+  /// Number of distinct `(context, event)` pairs with each count.
+  count_of_counts: HashMap<usize, usize>,
+  /// Total count of all observations.
+  total: usize,
+  /// Number of distinct `(context, event)` pairs.
+  pairs: usize,
+}
+
+impl<C, E> Table<C, E>
+  where C: Hash + Eq + Clone,
+        E: Hash + Eq + Clone
+{
+  fn new<I>(counts: I) -> Table<C, E>
+    where I: IntoIterator<Item=((C, E), usize)>
+  {
+    let mut joint: HashMap<C, HashMap<E, usize>> = HashMap::new();
+    for ((context, event), count) in counts {
+      if count > 0 {
+        *joint.entry(context).or_default()
+          .entry(event).or_insert(0) += count;
+      }
+    }
+
+    let mut contexts = HashMap::new();
+    let mut events = HashMap::new();
+    let mut continuations = HashMap::new();
+    let mut count_of_counts = HashMap::new();
+    let mut total = 0;
+    let mut pairs = 0;
+
+    for (context, followers) in &joint {
+      let mut context_total = 0;
+      for (event, &count) in followers {
+        context_total += count;
+        *events.entry(event.clone()).or_insert(0) += count;
+        *continuations.entry(event.clone()).or_insert(0) += 1;
+        *count_of_counts.entry(count).or_insert(0) += 1;
+        pairs += 1;
+      }
+      total += context_total;
+      contexts.insert(context.clone(), context_total);
+    }
+
+    Table {
+      joint: joint,
+      contexts: contexts,
+      events: events,
+      continuations: continuations,
+      count_of_counts: count_of_counts,
+      total: total,
+      pairs: pairs,
+    }
+  }
+
+  /// The number of times `event` was observed in `context`.
+  fn count(&self, context: &C, event: &E) -> usize {
+    self.joint.get(context)
+      .and_then(|followers| followers.get(event))
+      .cloned()
+      .unwrap_or(0)
+  }
+
+  /// The number of distinct events observed in `context`.
+  fn types(&self, context: &C) -> usize {
+    self.joint.get(context).map(HashMap::len).unwrap_or(0)
+  }
+
+  /// The number of distinct `(context, event)` pairs observed exactly
+  /// `r` times.
+  fn n(&self, r: usize) -> usize {
+    self.count_of_counts.get(&r).cloned().unwrap_or(0)
+  }
+
+  /// The uniform probability of any one event, with one extra outcome
+  /// reserved for unseen events.
+  fn uniform(&self) -> f64 {
+    1.0 / (self.events.len() as f64 + 1.0)
+  }
+}
+
+/// Witten-Bell smoothing. The probability of backing off to a
+/// lower-order distribution in a context is proportional to the number
+/// of distinct events observed in that context. The lower-order
+/// distribution is the marginal distribution of events, itself
+/// Witten-Bell smoothed against a uniform distribution.
+pub struct WittenBell<C, E>
+  where C: Hash + Eq,
+        E: Hash + Eq
+{
+  table: Table<C, E>,
+}
+
+impl<C, E> WittenBell<C, E>
+  where C: Hash + Eq + Clone,
+        E: Hash + Eq + Clone
+{
+  /// Creates a Witten-Bell estimator from `((context, event), count)`
+  /// pairs.
+  pub fn new<I>(counts: I) -> WittenBell<C, E>
+    where I: IntoIterator<Item=((C, E), usize)>
+  {
+    WittenBell { table: Table::new(counts) }
+  }
+
+  /// The smoothed marginal probability of `event`.
+  fn lower(&self, event: &E) -> f64 {
+    let table = &self.table;
+    let seen = table.events.get(event).cloned().unwrap_or(0) as f64;
+    let types = table.events.len() as f64;
+    if table.total == 0 {
+      return table.uniform();
+    }
+    (seen + types * table.uniform()) / (table.total as f64 + types)
+  }
+}
+
+impl<C, E> Estimator<C, E> for WittenBell<C, E>
+  where C: Hash + Eq + Clone,
+        E: Hash + Eq + Clone
+{
+  fn probability(&self, context: &C, event: &E) -> f64 {
+    let table = &self.table;
+    let total = match table.contexts.get(context) {
+      Some(&total) => total as f64,
+      None => return self.lower(event)
+    };
+    let types = table.types(context) as f64;
+    let count = table.count(context, event) as f64;
+    (count + types * self.lower(event)) / (total + types)
+  }
+}
+
+/// Interpolated Kneser-Ney smoothing. A fixed discount is subtracted
+/// from every observed count, and the freed probability mass is
+/// distributed according to the continuation probability of each
+/// event: the proportion of distinct contexts in which it occurs.
+pub struct KneserNey<C, E>
+  where C: Hash + Eq,
+        E: Hash + Eq
+{
+  table: Table<C, E>,
+  discount: f64,
+}
+
+impl<C, E> KneserNey<C, E>
+  where C: Hash + Eq + Clone,
+        E: Hash + Eq + Clone
+{
+  /// Creates a Kneser-Ney estimator from `((context, event), count)`
+  /// pairs, estimating the discount as `n1 / (n1 + 2 n2)`, where `nr`
+  /// is the number of pairs observed exactly `r` times.
+  pub fn new<I>(counts: I) -> KneserNey<C, E>
+    where I: IntoIterator<Item=((C, E), usize)>
+  {
+    let table = Table::new(counts);
+    let (n1, n2) = (table.n(1) as f64, table.n(2) as f64);
+    let discount =
+      if n1 + n2 > 0.0 { n1 / (n1 + 2.0 * n2) }
+      else { 0.5 };
+    KneserNey { table: table, discount: discount }
+  }
+
+  /// Creates a Kneser-Ney estimator with the given discount, which
+  /// should lie between `0` and `1`.
+  pub fn with_discount<I>(counts: I, discount: f64) -> KneserNey<C, E>
+    where I: IntoIterator<Item=((C, E), usize)>
+  {
+    KneserNey { table: Table::new(counts), discount: discount }
+  }
+
+  /// Returns the discount subtracted from each observed count.
+  pub fn discount(&self) -> f64 {
+    self.discount
+  }
+
+  /// The continuation probability of `event`, interpolated with a
+  /// uniform distribution.
+  fn continuation(&self, event: &E) -> f64 {
+    let table = &self.table;
+    if table.pairs == 0 {
+      return table.uniform();
+    }
+    let pairs = table.pairs as f64;
+    let contexts = table.continuations.get(event).cloned().unwrap_or(0);
+    let types = table.continuations.len() as f64;
+    (contexts as f64 - self.discount).max(0.0) / pairs
+      + self.discount * types / pairs * table.uniform()
+  }
+}
+
+impl<C, E> Estimator<C, E> for KneserNey<C, E>
+  where C: Hash + Eq + Clone,
+        E: Hash + Eq + Clone
+{
+  fn probability(&self, context: &C, event: &E) -> f64 {
+    let table = &self.table;
+    let total = match table.contexts.get(context) {
+      Some(&total) => total as f64,
+      None => return self.continuation(event)
+    };
+    let types = table.types(context) as f64;
+    let count = table.count(context, event) as f64;
+    (count - self.discount).max(0.0) / total
+      + self.discount * types / total * self.continuation(event)
+  }
+}
+
+/// Good-Turing smoothing with Katz back-off. Counts of `r` up to a
+/// threshold are discounted to `(r + 1) n(r + 1) / n(r)`, where `n(r)`
+/// is the number of pairs observed exactly `r` times. The mass freed
+/// in each context is distributed over unseen events in proportion to
+/// their add-one smoothed marginal probability. A context in which the
+/// discounts free less than `n(1) / (N + 1)`, where `N` is the total of
+/// all counts, such as one whose counts are all above the threshold,
+/// reserves that much instead, by scaling down its seen events.
+pub struct GoodTuring<C, E>
+  where C: Hash + Eq,
+        E: Hash + Eq
+{
+  table: Table<C, E>,
+  threshold: usize,
+  /// The back-off weight, the total lower-order probability of seen
+  /// events, and the factor by which the discounted probabilities of
+  /// seen events are scaled to leave the back-off weight, for each
+  /// context.
+  backoff: HashMap<C, (f64, f64, f64)>,
+}
+
+impl<C, E> GoodTuring<C, E>
+  where C: Hash + Eq + Clone,
+        E: Hash + Eq + Clone
+{
+  /// Creates a Good-Turing estimator from `((context, event), count)`
+  /// pairs, discounting counts up to five.
+  pub fn new<I>(counts: I) -> GoodTuring<C, E>
+    where I: IntoIterator<Item=((C, E), usize)>
+  {
+    GoodTuring::with_threshold(counts, 5)
+  }
+
+  /// Creates a Good-Turing estimator from `((context, event), count)`
+  /// pairs, discounting counts up to `threshold`. Counts above the
+  /// threshold are considered reliable, and are left undiscounted.
+  pub fn with_threshold<I>(counts: I, threshold: usize) -> GoodTuring<C, E>
+    where I: IntoIterator<Item=((C, E), usize)>
+  {
+    let mut estimator = GoodTuring {
+      table: Table::new(counts),
+      threshold: threshold,
+      backoff: HashMap::new(),
+    };
+
+    // The least mass reserved for unseen events in any context: the
+    // global Good-Turing estimate of the probability of an unseen pair,
+    // counting at least one pair as seen once so that it is never zero.
+    let floor = estimator.table.n(1).max(1) as f64 / (estimator.table.total as f64 + 1.0);
+    let backoff = estimator.table.joint.iter()
+      .map(|(context, followers)| {
+        let total = estimator.table.contexts[context] as f64;
+        let (seen, lower) = followers.iter()
+          .fold((0.0, 0.0), |(seen, lower), (event, &count)|
+            (seen + estimator.discounted(count) / total,
+             lower + estimator.lower(event)));
+        let weight = (1.0 - seen).max(floor);
+        (context.clone(), (weight, lower, (1.0 - weight) / seen))
+      })
+      .collect();
+    estimator.backoff = backoff;
+    estimator
+  }
+
+  /// The Good-Turing discounted value of the count `r`.
+  fn discounted(&self, r: usize) -> f64 {
+    let (nr, next) = (self.table.n(r), self.table.n(r + 1));
+    if r > self.threshold || nr == 0 || next == 0 {
+      return r as f64;
+    }
+    // Sparse count-of-counts can yield a discounted count larger than
+    // the original; such counts are left undiscounted.
+    (((r + 1) * next) as f64 / nr as f64).min(r as f64)
+  }
+
+  /// The add-one smoothed marginal probability of `event`.
+  fn lower(&self, event: &E) -> f64 {
+    let table = &self.table;
+    let seen = table.events.get(event).cloned().unwrap_or(0) as f64;
+    (seen + 1.0) / (table.total as f64 + table.events.len() as f64 + 1.0)
+  }
+}
+
+impl<C, E> Estimator<C, E> for GoodTuring<C, E>
+  where C: Hash + Eq + Clone,
+        E: Hash + Eq + Clone
+{
+  fn probability(&self, context: &C, event: &E) -> f64 {
+    let table = &self.table;
+    let total = match table.contexts.get(context) {
+      Some(&total) => total as f64,
+      None => return self.lower(event)
+    };
+    match table.count(context, event) {
+      0 => {
+        let (weight, seen, _) = self.backoff[context];
+        weight * self.lower(event) / (1.0 - seen)
+      },
+      r => self.backoff[context].2 * self.discounted(r) / total
+    }
+  }
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+
+/// A contiguous run of tokens within a [`Line`], from position `start` up
+/// to (but not including) position `end`.
+///
+/// Annotations refer to tokens by `Span`s rather than borrowing them, so
+/// they can be stored, compared and read from annotated files
+/// independently of any [`Document`].
+///
+/// [`Line`]: type.Line.html
+/// [`Document`]: struct.Document.html
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+  pub start: usize,
+  pub end: usize,
+}
+
+impl Span {
+  /// Creates the span from `start` up to `end`.
+  pub fn new(start: usize, end: usize) -> Span {
+    Span { start: start, end: end }
+  }
+
+  /// Returns the number of tokens in the span.
+  pub fn len(&self) -> usize {
+    self.end.saturating_sub(self.start)
+  }
+
+  /// Returns `true` if the span contains no tokens.
+  pub fn is_empty(&self) -> bool {
+    self.end <= self.start
+  }
+
+  /// Returns `true` if position `i` is within the span.
+  pub fn contains(&self, i: usize) -> bool {
+    self.start <= i && i < self.end
+  }
+
+  /// Returns `true` if every position of `other` is within this span.
+  pub fn covers(&self, other: &Span) -> bool {
+    self.start <= other.start && other.end <= self.end
+  }
+
+  /// Returns `true` if the spans share at least one position.
+  pub fn overlaps(&self, other: &Span) -> bool {
+    self.start < other.end && other.start < self.end
+  }
+
+  /// Returns the tokens of `line` within the span.
+  pub fn of<'l, T>(&self, line: &'l [T]) -> &'l [T] {
+    &line[self.start..self.end]
+  }
+}
+
+impl fmt::Display for Span {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}..{}", self.start, self.end)
+  }
+}
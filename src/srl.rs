@@ -0,0 +1,273 @@
+use span::Span;
+use conll;
+use error::LineParseError;
+use perceptron::Perceptron;
+
+/// A semantic argument of a predicate: a span of the sentence, labeled
+/// with its role, such as `ARG0` or `ARGM-TMP`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Argument {
+  pub role: String,
+  pub span: Span,
+}
+
+/// A predicate and its arguments.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Frame {
+  /// The position of the predicate in the sentence.
+  pub predicate: usize,
+  /// The sense of the predicate, like `give.01`, if known.
+  pub sense: Option<String>,
+  pub arguments: Vec<Argument>,
+}
+
+/// A sentence annotated with predicate–argument structure. The positions
+/// of its frames index into `words`, and equally into a [`Line`] of the
+/// same words.
+///
+/// [`Line`]: type.Line.html
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct SrlSentence {
+  pub words: Vec<String>,
+  /// The part-of-speech tag of each word.
+  pub tags: Vec<String>,
+  pub frames: Vec<Frame>,
+}
+
+/// Reads the predicate–argument annotations of a file in the format of
+/// the CoNLL-2009 shared task, in which each argument is marked on its
+/// head word. The span of each argument is therefore its head alone.
+pub fn read_conll2009(text: &str) -> Result<Vec<SrlSentence>, LineParseError> {
+  conll::blocks(text).into_iter().map(|block| {
+    let words = block.column(1)?;
+    let tags = block.column(4)?;
+    let fill = block.column(12)?;
+    let senses = block.column(13)?;
+
+    let predicates: Vec<usize> = (0..fill.len()).filter(|&i| fill[i] == "Y").collect();
+    let mut frames = vec![];
+    for (j, &predicate) in predicates.iter().enumerate() {
+      let roles = block.column(14 + j)?;
+      frames.push(Frame {
+        predicate: predicate,
+        sense: Some(senses[predicate].to_string()).filter(|s| s != "_"),
+        arguments: roles.iter().enumerate()
+          .filter(|&(_, role)| *role != "_")
+          .map(|(i, role)| Argument { role: role.to_string(), span: Span::new(i, i + 1) })
+          .collect(),
+      });
+    }
+
+    Ok(SrlSentence {
+      words: words.iter().map(|w| w.to_string()).collect(),
+      tags: tags.iter().map(|t| t.to_string()).collect(),
+      frames: frames,
+    })
+  }).collect()
+}
+
+/// Reads the predicate–argument annotations of a file in the format of
+/// the CoNLL-2012 shared task (OntoNotes), in which arguments are spans
+/// marked by brackets, with one column per predicate.
+pub fn read_conll2012(text: &str) -> Result<Vec<SrlSentence>, LineParseError> {
+  conll::blocks(text).into_iter().map(|block| {
+    let words = block.column(3)?;
+    let tags = block.column(4)?;
+    let lemmas = block.column(6)?;
+    let framesets = block.column(7)?;
+    let width = block.rows[0].len();
+    if width < 12 || block.rows.iter().any(|row| row.len() != width) {
+      return Err(block.error(0, "inconsistent number of columns"));
+    }
+
+    let mut frames = vec![];
+    for column in 11..width - 1 {
+      let cells = block.column(column)?;
+      let spans = conll::bracket_spans(&cells)
+        .map_err(|row| block.error(row, "unbalanced brackets"))?;
+      let predicate = match spans.iter().find(|&(role, _)| role == "V") {
+        Some(&(_, span)) => span.start,
+        None => return Err(block.error(0, "argument column without a predicate"))
+      };
+      let sense = match (lemmas[predicate], framesets[predicate]) {
+        ("-", _) | (_, "-") => None,
+        (lemma, frameset) => Some(format!("{}.{}", lemma, frameset))
+      };
+      frames.push(Frame {
+        predicate: predicate,
+        sense: sense,
+        arguments: spans.into_iter()
+          .filter(|(role, _)| role != "V")
+          .map(|(role, span)| Argument { role: role, span: span })
+          .collect(),
+      });
+    }
+
+    Ok(SrlSentence {
+      words: words.iter().map(|w| w.to_string()).collect(),
+      tags: tags.iter().map(|t| t.to_string()).collect(),
+      frames: frames,
+    })
+  }).collect()
+}
+
+/// A baseline argument labeler: given a predicate, it tags each word of
+/// the sentence as beginning (`B-ARG0`), inside (`I-ARG0`) or outside
+/// (`O`) an argument, greedily from left to right, with an averaged
+/// perceptron over features of the word, the predicate, and their
+/// relative position.
+///
+/// ```rust
+/// let training = read_conll2012(&fs::read_to_string("train.gold_conll")?)?;
+/// let labeler = ArgumentLabeler::train(&training, 10);
+///
+/// let tagged = [("John", "NNP"), ("gave", "VBD"), ("Mary", "NNP"), ("flowers", "NNS")];
+/// for argument in labeler.label(&tagged, 1) {
+///   println!("{} {:?}", argument.role, argument.span.of(&tagged));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArgumentLabeler {
+  model: Perceptron<String>,
+  /// Every `B-` and `I-` tag seen in training, and `O`.
+  tags: Vec<String>,
+}
+
+impl ArgumentLabeler {
+  /// Trains a labeler on the frames of `sentences`, for `iterations`
+  /// passes.
+  pub fn train(sentences: &[SrlSentence], iterations: usize) -> ArgumentLabeler {
+    let mut examples = vec![];
+    let mut tags = vec![String::from("O")];
+    for sentence in sentences {
+      for frame in &sentence.frames {
+        let gold = bio(sentence.words.len(), &frame.arguments);
+        for tag in &gold {
+          if !tags.contains(tag) {
+            tags.push(tag.clone());
+          }
+        }
+        examples.push((sentence, frame.predicate, gold));
+      }
+    }
+
+    let mut labeler = ArgumentLabeler { model: Perceptron::new(), tags: tags };
+    for _ in 0..iterations {
+      for &(sentence, predicate, ref gold) in &examples {
+        let mut history: Vec<String> = vec![];
+        for (i, tag) in gold.iter().enumerate() {
+          let features = features(&sentence.words, &sentence.tags, predicate, i, &history);
+          let guess = labeler.predict(&features, history.last());
+          if guess != *tag {
+            labeler.model.update(&features, tag, 1.0);
+            labeler.model.update(&features, &guess, -1.0);
+          }
+          labeler.model.tick();
+          history.push(tag.clone());
+        }
+      }
+    }
+    labeler.model.average();
+    labeler
+  }
+
+  /// Predicts the tag of a word, permitting an `I-` tag only after a tag
+  /// of the same role.
+  fn predict(&self, features: &[String], previous: Option<&String>) -> String {
+    let role = previous.map(|tag| &tag[tag.find('-').map_or(tag.len(), |i| i + 1)..]);
+    let candidates: Vec<String> = self.tags.iter()
+      .filter(|tag| !tag.starts_with("I-") || Some(&tag[2..]) == role)
+      .cloned()
+      .collect();
+    self.model.predict(features, &candidates).unwrap_or_else(|| String::from("O"))
+  }
+
+  /// Labels the arguments of the predicate at position `predicate` in a
+  /// sentence of `(word, tag)` pairs.
+  pub fn label<W, G>(&self, tagged: &[(W, G)], predicate: usize) -> Vec<Argument>
+    where W: AsRef<str>,
+          G: AsRef<str>
+  {
+    let words: Vec<&str> = tagged.iter().map(|(w, _)| w.as_ref()).collect();
+    let tags: Vec<&str> = tagged.iter().map(|(_, g)| g.as_ref()).collect();
+    let mut history: Vec<String> = vec![];
+    for i in 0..words.len() {
+      let features = features(&words, &tags, predicate, i, &history);
+      let guess = if i == predicate { String::from("O") }
+        else { self.predict(&features, history.last()) };
+      history.push(guess);
+    }
+    arguments(&history)
+  }
+}
+
+/// Encodes arguments as one BIO tag per word.
+fn bio(length: usize, arguments: &[Argument]) -> Vec<String> {
+  let mut tags = vec![String::from("O"); length];
+  for argument in arguments {
+    let end = argument.span.end.min(length);
+    if argument.span.start >= end {
+      continue;
+    }
+    for (offset, tag) in tags[argument.span.start..end].iter_mut().enumerate() {
+      let prefix = if offset == 0 { "B-" } else { "I-" };
+      *tag = format!("{}{}", prefix, argument.role);
+    }
+  }
+  tags
+}
+
+/// Decodes BIO tags into arguments.
+fn arguments(tags: &[String]) -> Vec<Argument> {
+  let mut arguments: Vec<Argument> = vec![];
+  for (i, tag) in tags.iter().enumerate() {
+    if let Some(role) = tag.strip_prefix("B-") {
+      arguments.push(Argument { role: role.to_string(), span: Span::new(i, i + 1) });
+    } else if let Some(role) = tag.strip_prefix("I-") {
+      if let Some(last) = arguments.last_mut() {
+        if last.span.end == i && last.role == role {
+          last.span.end = i + 1;
+        }
+      }
+    }
+  }
+  arguments
+}
+
+/// Extracts the features of the word at position `i`, given the tags
+/// assigned to the words before it.
+fn features<W, G>(words: &[W], tags: &[G], predicate: usize, i: usize,
+                  history: &[String]) -> Vec<String>
+  where W: AsRef<str>,
+        G: AsRef<str>
+{
+  let word = |j: Option<usize>| j.and_then(|j| words.get(j)).map_or("<none>", |w| w.as_ref());
+  let tag = |j: Option<usize>| j.and_then(|j| tags.get(j)).map_or("<none>", |t| t.as_ref());
+  let (w, t) = (word(Some(i)), tag(Some(i)));
+  let (pw, pt) = (word(Some(predicate)), tag(Some(predicate)));
+  let position =
+    if i < predicate { "before" } else if i > predicate { "after" } else { "at" };
+  let distance = match (i as isize - predicate as isize).abs() {
+    0 => "0", 1 => "1", 2 => "2", 3..=5 => "3-5", _ => "6+"
+  };
+  let previous = history.last().map_or("<s>", |tag| &tag[..]);
+
+  vec![
+    String::from("bias"),
+    format!("w={}", w),
+    format!("t={}", t),
+    format!("pw={}", pw),
+    format!("pt={}", pt),
+    format!("position={}", position),
+    format!("distance,position={},{}", distance, position),
+    format!("t,position={},{}", t, position),
+    format!("t,pt,position={},{},{}", t, pt, position),
+    format!("w,pw={},{}", w, pw),
+    format!("t-1={}", tag(i.checked_sub(1))),
+    format!("t+1={}", tag(Some(i + 1))),
+    format!("w-1={}", word(i.checked_sub(1))),
+    format!("w+1={}", word(Some(i + 1))),
+    format!("previous={}", previous),
+    format!("previous,t={},{}", previous, t),
+  ]
+}
@@ -0,0 +1,164 @@
+use token::Token;
+use language::DefaultLanguage;
+use corpus::{Tokenizer, WhitespaceTokenizer};
+
+use std::io::{self, BufRead};
+use std::fmt;
+use std::str;
+use std::marker::PhantomData;
+
+/// A token that owns its characters, for tokens that must outlive the
+/// text they were read from, such as those yielded by a [`TokenStream`].
+/// Owned tokens are read and compared through the borrowed [`Token`]
+/// returned by [`as_token`].
+///
+/// [`TokenStream`]: struct.TokenStream.html
+/// [`Token`]: enum.Token.html
+/// [`as_token`]: #method.as_token
+#[derive(Clone, Hash, PartialEq, PartialOrd, Eq, Ord)]
+pub struct OwnedToken<L=DefaultLanguage> {
+  token: Owned,
+  language: PhantomData<L>,
+}
+
+#[derive(Clone, Hash, PartialEq, PartialOrd, Eq, Ord)]
+enum Owned {
+  Word(Box<str>),
+  Null,
+  Unknown,
+}
+
+impl<L> OwnedToken<L> {
+  /// Returns the token, borrowing its characters.
+  pub fn as_token<'t>(&'t self) -> Token<'t, L> {
+    match self.token {
+      Owned::Word(ref word) => Token::from(word.as_bytes()),
+      Owned::Null => Token::Null,
+      Owned::Unknown => Token::Unknown,
+    }
+  }
+}
+
+impl<'t, L> From<Token<'t, L>> for OwnedToken<L> {
+  fn from(token: Token<'t, L>) -> OwnedToken<L> {
+    let token = match token {
+      Token::Word(word) => Owned::Word(word.as_str().into()),
+      Token::Null => Owned::Null,
+      Token::Unknown => Owned::Unknown,
+    };
+    OwnedToken { token: token, language: PhantomData }
+  }
+}
+
+impl<L> fmt::Debug for OwnedToken<L> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Debug::fmt(&self.as_token(), f)
+  }
+}
+
+impl<L> fmt::Display for OwnedToken<L> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.as_token(), f)
+  }
+}
+
+/// A lazy tokenizer of the lines of a reader in language `L`, for
+/// corpora too large to hold as a [`Document`], whose bytes, tokens and
+/// lines are all held in memory at once. Only the current line is held.
+///
+/// As an iterator, a stream yields the tokens of each line as
+/// [`OwnedToken`]s. To avoid allocating every token, [`next_line`]
+/// instead lends the tokens of each line from a buffer reused for every
+/// line:
+///
+/// ```rust
+/// let file = BufReader::new(File::open("crawl.txt")?);
+/// let mut stream: TokenStream<_, English> = TokenStream::new(file);
+/// let mut counts = HashMap::new();
+/// while let Some(line) = stream.next_line() {
+///   for token in line? {
+///     *counts.entry(token.to_string()).or_insert(0) += 1;
+///   }
+/// }
+/// ```
+///
+/// Lines are decoded as UTF-8, replacing invalid sequences with `U+FFFD`,
+/// and split into tokens by a [`Tokenizer`], as documents are; lines
+/// yield the same tokens as the lines of a document of the same text.
+///
+/// [`Document`]: struct.Document.html
+/// [`OwnedToken`]: struct.OwnedToken.html
+/// [`next_line`]: #method.next_line
+/// [`Tokenizer`]: trait.Tokenizer.html
+pub struct TokenStream<R, L=DefaultLanguage, T=WhitespaceTokenizer> {
+  reader: R,
+  tokenizer: T,
+  buffer: Vec<u8>,
+  language: PhantomData<L>,
+}
+
+impl<R: BufRead, L> TokenStream<R, L> {
+  /// Creates a stream splitting the lines of a reader into tokens at
+  /// spaces.
+  pub fn new(reader: R) -> TokenStream<R, L> {
+    TokenStream::with_tokenizer(reader, WhitespaceTokenizer)
+  }
+}
+
+impl<R: BufRead, L, T: Tokenizer> TokenStream<R, L, T> {
+  /// Creates a stream splitting the lines of a reader into tokens with a
+  /// [`Tokenizer`].
+  ///
+  /// [`Tokenizer`]: trait.Tokenizer.html
+  pub fn with_tokenizer(reader: R, tokenizer: T) -> TokenStream<R, L, T> {
+    TokenStream {
+      reader: reader,
+      tokenizer: tokenizer,
+      buffer: vec![],
+      language: PhantomData,
+    }
+  }
+
+  /// Reads and tokenizes the next line, returning `None` at the end of
+  /// the reader. The tokens borrow the stream's buffer, so must be
+  /// dropped before the following line is read.
+  pub fn next_line<'t>(&'t mut self) -> Option<io::Result<Vec<Token<'t, L>>>> {
+    self.buffer.clear();
+    match self.reader.read_until(b'\n', &mut self.buffer) {
+      Ok(0) => return None,
+      Ok(_) => {},
+      Err(error) => return Some(Err(error))
+    }
+    Some(Ok(tokenize_line(&mut self.buffer, &self.tokenizer)))
+  }
+
+  /// Consumes the stream, returning its reader.
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+}
+
+impl<R: BufRead, L, T: Tokenizer> Iterator for TokenStream<R, L, T> {
+  type Item = io::Result<Vec<OwnedToken<L>>>;
+
+  fn next(&mut self) -> Option<io::Result<Vec<OwnedToken<L>>>> {
+    self.next_line()
+      .map(|line| line.map(|tokens| tokens.into_iter().map(OwnedToken::from).collect()))
+  }
+}
+
+/// Splits a line read into a buffer into tokens, as the lines of a
+/// document are: without its newline, and with invalid UTF-8 replaced.
+pub(crate) fn tokenize_line<'t, L, T>(buffer: &'t mut Vec<u8>, tokenizer: &T) -> Vec<Token<'t, L>>
+  where T: Tokenizer + ?Sized
+{
+  if buffer.last() == Some(&b'\n') {
+    buffer.pop();
+  }
+  if str::from_utf8(buffer).is_err() {
+    *buffer = String::from_utf8_lossy(buffer).into_owned().into_bytes();
+  }
+  let (spans, _) = tokenizer.tokenize(buffer);
+  let buffer = &*buffer;
+  spans.into_iter().map(|span| Token::from(&buffer[span])).collect()
+}
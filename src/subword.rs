@@ -0,0 +1,141 @@
+use token::Token;
+use corpus::{Document, TokenIter};
+
+use std::borrow::Cow;
+use std::iter;
+
+/// Produces an iterator over the n-grams of `n` characters of a word, in
+/// order, for language identification and subword models. An n-gram is a
+/// slice of the word; words shorter than `n` characters, and every word
+/// when `n` is `0`, have none.
+///
+/// ```rust
+/// let grams: Vec<Cow<str>> = char_ngrams("where", 3).collect();
+/// assert_eq!(grams, vec!["whe", "her", "ere"]);
+///
+/// // Marking the boundaries of the word distinguishes prefixes and
+/// // suffixes from the n-grams within it.
+/// let grams: Vec<Cow<str>> = char_ngrams("where", 3).with_boundaries('<', '>').collect();
+/// assert_eq!(grams, vec!["<wh", "whe", "her", "ere", "re>"]);
+/// ```
+pub fn char_ngrams<'w>(word: &'w str, n: usize) -> CharNgrams<'w, iter::Once<&'w str>> {
+  CharNgrams::new(iter::once(word), n)
+}
+
+impl<L> Document<L> {
+  /// Returns an iterator over the n-grams of `n` characters of each word
+  /// of the document in turn, as [`char_ngrams`] produces them. `Null`
+  /// and `Unknown` tokens have none.
+  ///
+  /// ```rust
+  /// let profile: FreqDist<Cow<str>> = document.char_ngrams(3).with_boundaries('^', '$').collect();
+  /// ```
+  ///
+  /// [`char_ngrams`]: fn.char_ngrams.html
+  pub fn char_ngrams<'t>(&'t self, n: usize) -> CharNgrams<'t, DocumentWords<'t, L>> {
+    fn word<'t, L>(token: Token<'t, L>) -> Option<&'t str> {
+      match token {
+        Token::Word(word) => Some(word.as_str()),
+        _ => None
+      }
+    }
+    CharNgrams::new(self.tokens().iter().filter_map(word), n)
+  }
+}
+
+/// The words of a document, as string slices.
+pub type DocumentWords<'t, L> = iter::FilterMap<TokenIter<'t, L>, fn(Token<'t, L>) -> Option<&'t str>>;
+
+/// An iterator over the character n-grams of a sequence of words,
+/// produced by [`char_ngrams`] and [`Document::char_ngrams`].
+///
+/// [`char_ngrams`]: fn.char_ngrams.html
+/// [`Document::char_ngrams`]: struct.Document.html#method.char_ngrams
+#[derive(Debug, Clone)]
+pub struct CharNgrams<'w, I> {
+  words: I,
+  n: usize,
+  /// The characters marking the start and end of each word, if any.
+  boundaries: Option<(char, char)>,
+  /// The current word.
+  word: &'w str,
+  /// The byte offset of each character of the current word, and of its
+  /// end.
+  offsets: Vec<usize>,
+  /// The position of the next n-gram of the current word, counting the
+  /// start marker, if any, as the first.
+  next: usize,
+}
+
+impl<'w, I> CharNgrams<'w, I>
+  where I: Iterator<Item=&'w str>
+{
+  fn new(words: I, n: usize) -> CharNgrams<'w, I> {
+    CharNgrams {
+      words: words,
+      n: n,
+      boundaries: None,
+      word: "",
+      offsets: vec![0],
+      next: 0,
+    }
+  }
+
+  /// Pads each word with `start` and `end`, so that the n-grams at its
+  /// edges are distinct from the same characters within words. N-grams
+  /// of only these markers are omitted. A marked n-gram is allocated;
+  /// every other is a slice of its word.
+  pub fn with_boundaries(mut self, start: char, end: char) -> CharNgrams<'w, I> {
+    self.boundaries = Some((start, end));
+    self
+  }
+}
+
+impl<'w, I> Iterator for CharNgrams<'w, I>
+  where I: Iterator<Item=&'w str>
+{
+  type Item = Cow<'w, str>;
+
+  fn next(&mut self) -> Option<Cow<'w, str>> {
+    if self.n == 0 {
+      return None;
+    }
+    let marked = self.boundaries.is_some() as usize;
+    loop {
+      let chars = self.offsets.len() - 1;
+      while self.next + self.n <= chars + 2 * marked {
+        let i = self.next;
+        self.next += 1;
+        // The n-gram spans the characters `first..last` of the word, and
+        // the markers of the edges it reaches.
+        let first = i.saturating_sub(marked);
+        let last = (i + self.n - marked).min(chars);
+        if first >= last {
+          continue;
+        }
+        let slice = &self.word[self.offsets[first]..self.offsets[last]];
+        let lead = marked == 1 && i == 0;
+        let trail = marked == 1 && i + self.n == chars + 2;
+        return Some(match self.boundaries {
+          Some((start, end)) if lead || trail => {
+            let mut gram = String::with_capacity(slice.len() + start.len_utf8() + end.len_utf8());
+            if lead {
+              gram.push(start);
+            }
+            gram.push_str(slice);
+            if trail {
+              gram.push(end);
+            }
+            Cow::Owned(gram)
+          },
+          _ => Cow::Borrowed(slice)
+        });
+      }
+      self.word = self.words.next()?;
+      self.offsets.clear();
+      self.offsets.extend(self.word.char_indices().map(|(i, _)| i));
+      self.offsets.push(self.word.len());
+      self.next = 0;
+    }
+  }
+}
@@ -0,0 +1,412 @@
+//! Part-of-speech tagging with a hidden Markov model.
+//!
+//! Tagged text is read in the `word/TAG` format of the Penn Treebank's
+//! tagged files and of most course assignments, one sentence per line,
+//! into sentences of [`Tagged`] pairs. An [`HmmTagger`] is trained on
+//! such sentences and tags new ones by Viterbi decoding:
+//!
+//! ```rust
+//! let training = fs::read_to_string("train.pos")?;
+//! let tagger = HmmTagger::train(&tag::read_tagged(&training)?);
+//!
+//! let tags = tagger.tag(&["the", "old", "man", "the", "boats"]);
+//! assert_eq!(tags, vec!["DT", "NN", "VBP", "DT", "NNS"]);
+//!
+//! // Token accuracy on held-out sentences.
+//! let testing = fs::read_to_string("test.pos")?;
+//! let mut accuracy = Accuracy::new();
+//! for sentence in tag::read_tagged(&testing)? {
+//!   let words: Vec<&str> = sentence.iter().map(|tagged| tagged.token).collect();
+//!   let gold: Vec<&str> = sentence.iter().map(|tagged| tagged.tag).collect();
+//!   accuracy.add(&tagger.tag(&words), &gold);
+//! }
+//! ```
+//!
+//! A [`TaggedDocument`] holds tagged text read in this format, or in
+//! CoNLL-style columns, as a document of tokens with a tag each.
+//!
+//! [`Tagged`]: struct.Tagged.html
+//! [`HmmTagger`]: struct.HmmTagger.html
+//! [`TaggedDocument`]: struct.TaggedDocument.html
+use token::Token;
+use language::{Language, DefaultLanguage};
+use corpus::{Document, Tokens, Lines};
+use conll;
+use error::LineParseError;
+use frequency::ConditionalFreqDist;
+use viterbi;
+use hmm;
+
+use std::io;
+use std::fmt;
+use std::hash::Hash;
+use std::collections::HashMap;
+
+/// A token paired with its tag, such as a word with its part of speech.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tagged<T, G> {
+  pub token: T,
+  pub tag: G,
+}
+
+impl<T, G> Tagged<T, G> {
+  /// Pairs a token with its tag.
+  pub fn new(token: T, tag: G) -> Tagged<T, G> {
+    Tagged { token: token, tag: tag }
+  }
+}
+
+/// Tagged tokens are displayed in the `word/TAG` format.
+impl<T: fmt::Display, G: fmt::Display> fmt::Display for Tagged<T, G> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}/{}", self.token, self.tag)
+  }
+}
+
+/// Reads sentences of tagged words, one sentence per line, with the
+/// words separated by whitespace and each word separated from its tag by
+/// its last `/`, so that words such as `1/2/CD` may contain slashes.
+/// Blank lines are skipped.
+///
+/// ```text
+/// The/DT old/JJ man/NN ./.
+/// ```
+pub fn read_tagged(text: &str) -> Result<Vec<Vec<Tagged<&str, &str>>>, LineParseError> {
+  let mut sentences = vec![];
+  for (i, line) in text.lines().enumerate() {
+    let error = |message| LineParseError { line: i + 1, message: message };
+    let mut sentence = vec![];
+    for field in line.split_whitespace() {
+      let slash = field.rfind('/').ok_or_else(|| error("expected `/` between word and tag"))?;
+      let (word, tag) = (&field[..slash], &field[slash + 1..]);
+      if word.is_empty() {
+        return Err(error("expected a word before `/`"));
+      }
+      if tag.is_empty() {
+        return Err(error("expected a tag after `/`"));
+      }
+      sentence.push(Tagged::new(word, tag));
+    }
+    if !sentence.is_empty() {
+      sentences.push(sentence);
+    }
+  }
+  Ok(sentences)
+}
+
+/// The formats of tagged text read by [`TaggedDocument::from_reader`].
+///
+/// [`TaggedDocument::from_reader`]: struct.TaggedDocument.html#method.from_reader
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum TaggedFormat {
+  /// Sentences of `word/TAG` pairs, one per line, as [`read_tagged`]
+  /// reads them.
+  ///
+  /// [`read_tagged`]: fn.read_tagged.html
+  Slash,
+  /// CoNLL-style columns, one word per row and sentences separated by
+  /// blank lines, with the word and the tag in the given columns,
+  /// counting from zero.
+  Conll { word: usize, tag: usize },
+}
+
+/// A document whose tokens each carry a tag, such as a part of speech,
+/// one line per sentence. Tags are interned: each is stored as an index
+/// into the document's tagset.
+///
+/// ```rust
+/// let training: TaggedDocument<English> =
+///   TaggedDocument::from_reader(File::open("train.pos")?, TaggedFormat::Slash)?;
+/// let tagger = HmmTagger::train(training.sentences());
+/// ```
+pub struct TaggedDocument<L=DefaultLanguage>
+  where L: 'static
+{
+  words: Document<L>,
+  tags: Vec<usize>,
+  tagset: Vec<String>,
+}
+
+impl<L: Language> TaggedDocument<L> {
+  /// Creates a document from lines of tagged words. Words are stored as
+  /// tokens of a [`Document`], and so should not contain whitespace.
+  ///
+  /// [`Document`]: ../struct.Document.html
+  pub fn new<S, G>(lines: &[Vec<Tagged<S, G>>]) -> TaggedDocument<L>
+    where S: AsRef<str>,
+          G: AsRef<str>
+  {
+    let mut tags = vec![];
+    let mut tagset: Vec<String> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut text = vec![];
+    for line in lines {
+      let mut words = vec![];
+      for tagged in line {
+        words.push(tagged.token.as_ref().as_bytes());
+        let tag = tagged.tag.as_ref();
+        let id = *index.entry(tag.to_string()).or_insert_with(|| {
+          tagset.push(tag.to_string());
+          tagset.len() - 1
+        });
+        tags.push(id);
+      }
+      text.push(words.join(&b' '));
+    }
+    TaggedDocument {
+      words: text.join(&b'\n').into(),
+      tags: tags,
+      tagset: tagset,
+    }
+  }
+
+  /// Reads sentences of `word/TAG` pairs, one per line, as
+  /// [`read_tagged`] does.
+  ///
+  /// [`read_tagged`]: fn.read_tagged.html
+  pub fn from_slash(text: &str) -> Result<TaggedDocument<L>, LineParseError> {
+    Ok(TaggedDocument::new(&read_tagged(text)?))
+  }
+
+  /// Reads a file of CoNLL-style columns, taking the word of each row
+  /// from column `word` and its tag from column `tag`, counting from
+  /// zero, as `0` and `1` for the part-of-speech tags of CoNLL-2000
+  /// chunking data. Sentences are separated by blank lines; lines
+  /// beginning with `#` are skipped. Every row is a word, so CoNLL-U
+  /// files, whose multiword tokens take rows of their own, are better
+  /// read by [`read_conllu`].
+  ///
+  /// [`read_conllu`]: ../fn.read_conllu.html
+  pub fn from_conll(text: &str, word: usize, tag: usize) -> Result<TaggedDocument<L>, LineParseError> {
+    let mut lines = vec![];
+    for block in conll::blocks(text) {
+      let words = block.column(word)?;
+      let tags = block.column(tag)?;
+      lines.push(words.into_iter().zip(tags).map(|(word, tag)| Tagged::new(word, tag)).collect());
+    }
+    Ok(TaggedDocument::new(&lines))
+  }
+
+  /// Creates a document from a value implementing the [`Read`] trait by
+  /// reading bytes until all bytes have been read, and reading them as
+  /// tagged text in the given format. Malformed text is reported as an
+  /// error of kind `InvalidData`.
+  ///
+  /// ```rust
+  /// let chunking: TaggedDocument<English> = TaggedDocument::from_reader(
+  ///   File::open("train.txt")?, TaggedFormat::Conll { word: 0, tag: 1 })?;
+  /// ```
+  ///
+  /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+  pub fn from_reader<R: io::Read>(mut reader: R, format: TaggedFormat) -> io::Result<TaggedDocument<L>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let document = match format {
+      TaggedFormat::Slash => TaggedDocument::from_slash(&text),
+      TaggedFormat::Conll { word, tag } => TaggedDocument::from_conll(&text, word, tag),
+    };
+    document.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+  }
+
+  /// Returns the number of tokens in the document.
+  pub fn len(&self) -> usize {
+    self.tags.len()
+  }
+
+  /// Returns `true` if the document has no tokens.
+  pub fn is_empty(&self) -> bool {
+    self.tags.is_empty()
+  }
+
+  /// Returns the words of the document.
+  pub fn words<'t>(&'t self) -> Tokens<'t, L> {
+    self.words.tokens()
+  }
+
+  /// Returns the words of the document, one line per sentence.
+  pub fn lines<'t>(&'t self) -> Lines<'t, L> {
+    self.words.lines()
+  }
+
+  /// Returns the tag of token `i`.
+  pub fn tag(&self, i: usize) -> &str {
+    &self.tagset[self.tags[i]]
+  }
+
+  /// Returns the index of the tag of every token in the tagset.
+  pub fn tag_ids(&self) -> &[usize] {
+    &self.tags
+  }
+
+  /// Returns each distinct tag, in order of first occurrence.
+  pub fn tagset(&self) -> &[String] {
+    &self.tagset
+  }
+
+  /// Returns the sentences of the document as tagged words, such as for
+  /// training an [`HmmTagger`].
+  ///
+  /// [`HmmTagger`]: struct.HmmTagger.html
+  pub fn sentences<'t>(&'t self) -> Vec<Vec<Tagged<Token<'t, L>, &'t str>>> {
+    let mut i = 0;
+    self.words.lines().iter()
+      .map(|line| {
+        line.iter()
+          .map(|word| {
+            i += 1;
+            Tagged::new(word, self.tag(i - 1))
+          })
+          .collect()
+      })
+      .collect()
+  }
+}
+
+/// A bigram hidden Markov model tagger over words of type `W`, such as
+/// string slices or the tokens of a document.
+///
+/// Each tag is conditioned on the tag before it, with the start and the
+/// end of a sentence as tags of their own, and each word on its tag.
+/// Transition probabilities are estimated with add-one smoothing.
+/// Emission probabilities are estimated by relative frequency, reserving
+/// for words never seen in training a share of each tag's probability
+/// proportional to the number of words seen only once with it, plus one:
+/// tags such as nouns, which take many new words, take unknown words
+/// most readily. Known words keep only the tags they were seen with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HmmTagger<W: Hash + Eq> {
+  tags: Vec<String>,
+  /// `transitions[p][n]`: the log-probability of tag `n` after tag `p`,
+  /// where the tag after the last is the start before a sentence, and
+  /// the end after one.
+  transitions: Vec<Vec<f64>>,
+  /// The log-probability of each word given each tag.
+  emissions: HashMap<W, Vec<f64>>,
+  /// The log-probability of a word never seen in training given each tag.
+  unknown: Vec<f64>,
+}
+
+impl<W: Hash + Eq + Clone> HmmTagger<W> {
+  /// Trains a tagger on sentences of tagged words.
+  pub fn train<I, S, G>(sentences: I) -> HmmTagger<W>
+    where I: IntoIterator<Item=S>,
+          S: AsRef<[Tagged<W, G>]>,
+          G: AsRef<str>
+  {
+    let mut tags: Vec<String> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut bigrams: ConditionalFreqDist<usize, usize> = ConditionalFreqDist::new();
+    let mut words: ConditionalFreqDist<usize, W> = ConditionalFreqDist::new();
+    for sentence in sentences {
+      // The start and end of the sentence are numbered once every tag is
+      // known; until then they are `usize::MAX`.
+      let mut previous = usize::MAX;
+      for tagged in sentence.as_ref() {
+        let tag = tagged.tag.as_ref();
+        let next = tags.len();
+        let t = *index.entry(tag.to_string()).or_insert(next);
+        if t == next {
+          tags.push(tag.to_string());
+        }
+        bigrams.add(previous, t);
+        words.add(t, tagged.token.clone());
+        previous = t;
+      }
+      if previous != usize::MAX {
+        bigrams.add(previous, usize::MAX);
+      }
+    }
+
+    let boundary = tags.len();
+    let states = boundary + 1;
+    let mut counts = vec![vec![0; states]; states];
+    for (&previous, following) in bigrams.iter() {
+      for (&next, count) in following.iter() {
+        counts[previous.min(boundary)][next.min(boundary)] = count;
+      }
+    }
+    let transitions = counts.iter()
+      .map(|row| {
+        let total: usize = row.iter().sum();
+        row.iter()
+          .map(|&count| ((count + 1) as f64 / (total + states) as f64).ln())
+          .collect()
+      })
+      .collect();
+
+    let mut totals = vec![0; boundary];
+    let mut hapaxes = vec![0; boundary];
+    for (&t, tagged) in words.iter() {
+      totals[t] = tagged.total();
+      hapaxes[t] = tagged.hapaxes().len();
+    }
+    let denominator = |t: usize| (totals[t] + hapaxes[t] + 1) as f64;
+    let mut emissions: HashMap<W, Vec<f64>> = HashMap::new();
+    for (&t, tagged) in words.iter() {
+      for (word, count) in tagged.iter() {
+        emissions.entry(word.clone())
+          .or_insert_with(|| vec![f64::NEG_INFINITY; boundary])[t] = (count as f64 / denominator(t)).ln();
+      }
+    }
+    let unknown = (0..boundary)
+      .map(|t| ((hapaxes[t] + 1) as f64 / denominator(t)).ln())
+      .collect();
+
+    HmmTagger {
+      tags: tags,
+      transitions: transitions,
+      emissions: emissions,
+      unknown: unknown,
+    }
+  }
+
+  /// Returns the tags, in the order they were first seen in training.
+  pub fn tags(&self) -> &[String] {
+    &self.tags
+  }
+
+  /// Returns `true` if a word was seen in training.
+  pub fn knows(&self, word: &W) -> bool {
+    self.emissions.contains_key(word)
+  }
+
+  /// Returns the most probable tags of a sentence, one per word, and the
+  /// log-probability of the sentence and those tags together.
+  pub fn tag_with_score<'s>(&'s self, words: &[W]) -> (Vec<&'s str>, f64) {
+    let boundary = self.tags.len();
+    let emissions: Vec<&[f64]> = words.iter()
+      .map(|word| self.emissions.get(word).map_or(&self.unknown[..], |scores| &scores[..]))
+      .collect();
+    let last = words.len().saturating_sub(1);
+    let (path, score) = viterbi::first_order(boundary, words.len(),
+      |previous, next| self.transitions[previous.unwrap_or(boundary)][next],
+      |i, t| emissions[i][t] + if i == last { self.transitions[t][boundary] } else { 0.0 })
+      .unwrap_or((vec![], f64::NEG_INFINITY));
+    let score = if words.is_empty() { self.transitions[boundary][boundary] } else { score };
+    (path.into_iter().map(|t| &self.tags[t][..]).collect(), score)
+  }
+
+  /// Returns the probability of each tag of each word of a sentence,
+  /// given the whole sentence, in the order of [`tags`], by the
+  /// forward-backward algorithm. A tagger trained on no sentences gives
+  /// no probabilities.
+  ///
+  /// [`tags`]: #method.tags
+  pub fn posteriors(&self, words: &[W]) -> Vec<Vec<f64>> {
+    let boundary = self.tags.len();
+    let emissions: Vec<&[f64]> = words.iter()
+      .map(|word| self.emissions.get(word).map_or(&self.unknown[..], |scores| &scores[..]))
+      .collect();
+    let last = words.len().saturating_sub(1);
+    hmm::forward_backward(boundary, words.len(),
+      |previous, next| self.transitions[previous.unwrap_or(boundary)][next],
+      |i, t| emissions[i][t] + if i == last { self.transitions[t][boundary] } else { 0.0 })
+      .map_or(vec![], |posteriors| posteriors.states)
+  }
+
+  /// Returns the most probable tags of a sentence, one per word. A
+  /// tagger trained on no sentences tags no words.
+  pub fn tag<'s>(&'s self, words: &[W]) -> Vec<&'s str> {
+    self.tag_with_score(words).0
+  }
+}
@@ -0,0 +1,252 @@
+use token::Token;
+use language::Language;
+use corpus::AsTokens;
+
+use std::collections::HashMap;
+
+/// How the entries of a [`TermDocumentMatrix`] are weighted.
+///
+/// [`TermDocumentMatrix`]: struct.TermDocumentMatrix.html
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Weighting {
+  /// The number of occurrences of the term in the document, as topic
+  /// models such as LDA expect.
+  Counts,
+  /// The count times the inverse document frequency of the term,
+  /// `ln(D / df)` for `D` documents of which `df` hold the term, so that
+  /// terms of every document weigh nothing.
+  TfIdf,
+  /// Positive pointwise mutual information between the term and the
+  /// document: `log₂(c N / (t d))` for a count `c`, of `N` counts in all,
+  /// `t` of the term and `d` of the document, or zero if negative.
+  Ppmi,
+}
+
+/// A sparse matrix of the counts of terms in documents, with a row for
+/// each term and a column for each document, weighted on reading by a
+/// [`Weighting`]. It is the shared input of latent semantic analysis,
+/// matrix factorization and topic models, and of retrieval by the
+/// similarity of columns.
+///
+/// Terms are numbered in the order they were first counted. `Null`
+/// tokens are not counted.
+///
+/// ```rust
+/// let documents: Vec<Document<English>> = load_articles()?;
+/// let mut matrix = TermDocumentMatrix::new(documents.iter().map(|document| document.tokens()))
+///   .with_weighting(Weighting::TfIdf);
+/// // Drop terms of fewer than five documents, or of more than half.
+/// matrix.prune(5, 0.5);
+/// for (term, weight) in matrix.column(0) {
+///   println!("{}\t{:.3}", matrix.term(term).unwrap(), weight);
+/// }
+/// ```
+///
+/// [`Weighting`]: enum.Weighting.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermDocumentMatrix<'t, L: Language> {
+  terms: Vec<Token<'t, L>>,
+  ids: HashMap<Token<'t, L>, usize>,
+  /// The terms of each document and their counts, in order of term.
+  columns: Vec<Vec<(usize, usize)>>,
+  /// The number of documents holding each term.
+  document_frequencies: Vec<usize>,
+  /// The count of each term over every document.
+  term_totals: Vec<usize>,
+  total: usize,
+  weighting: Weighting,
+}
+
+impl<'t, L: Language> TermDocumentMatrix<'t, L> {
+  /// Counts the terms of each document, with entries weighted as
+  /// [`Weighting::Counts`].
+  ///
+  /// [`Weighting::Counts`]: enum.Weighting.html#variant.Counts
+  pub fn new<I>(documents: I) -> TermDocumentMatrix<'t, L>
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    let mut matrix = TermDocumentMatrix {
+      terms: vec![],
+      ids: HashMap::new(),
+      columns: vec![],
+      document_frequencies: vec![],
+      term_totals: vec![],
+      total: 0,
+      weighting: Weighting::Counts,
+    };
+    for document in documents {
+      let mut counts: HashMap<usize, usize> = HashMap::new();
+      for &token in document.as_tokens().iter() {
+        if token == Token::Null {
+          continue;
+        }
+        let next = matrix.terms.len();
+        let id = *matrix.ids.entry(token).or_insert(next);
+        if id == next {
+          matrix.terms.push(token);
+          matrix.document_frequencies.push(0);
+          matrix.term_totals.push(0);
+        }
+        *counts.entry(id).or_insert(0) += 1;
+      }
+      let mut column: Vec<(usize, usize)> = counts.into_iter().collect();
+      column.sort();
+      for &(id, count) in &column {
+        matrix.document_frequencies[id] += 1;
+        matrix.term_totals[id] += count;
+        matrix.total += count;
+      }
+      matrix.columns.push(column);
+    }
+    matrix
+  }
+
+  /// Weights entries by `weighting` when they are read. The counts are
+  /// kept, so the weighting may be changed at any time.
+  pub fn with_weighting(mut self, weighting: Weighting) -> TermDocumentMatrix<'t, L> {
+    self.weighting = weighting;
+    self
+  }
+
+  /// Returns the weighting of the entries.
+  pub fn weighting(&self) -> Weighting {
+    self.weighting
+  }
+
+  /// Removes every term held by fewer than `min_documents` documents, or
+  /// by more than `max_share` of them, such as `0.5`, and renumbers the
+  /// rest in their previous order. Returns the new id of each previous
+  /// id, or `None` for removed terms.
+  pub fn prune(&mut self, min_documents: usize, max_share: f64) -> Vec<Option<usize>> {
+    let max_documents = max_share * self.columns.len() as f64;
+    let mut mapping = vec![None; self.terms.len()];
+    let mut kept = 0;
+    for (id, new) in mapping.iter_mut().enumerate() {
+      let frequency = self.document_frequencies[id];
+      if frequency >= min_documents && frequency as f64 <= max_documents {
+        *new = Some(kept);
+        self.terms[kept] = self.terms[id];
+        self.term_totals[kept] = self.term_totals[id];
+        self.document_frequencies[kept] = frequency;
+        kept += 1;
+      }
+    }
+    self.terms.truncate(kept);
+    self.term_totals.truncate(kept);
+    self.document_frequencies.truncate(kept);
+    self.ids = self.terms.iter().enumerate().map(|(id, &token)| (token, id)).collect();
+    for column in &mut self.columns {
+      column.retain(|&(id, _)| mapping[id].is_some());
+      for entry in column.iter_mut() {
+        entry.0 = mapping[entry.0].unwrap();
+      }
+    }
+    self.total = self.term_totals.iter().sum();
+    mapping
+  }
+
+  /// Returns the number of terms: the rows of the matrix.
+  pub fn terms(&self) -> usize {
+    self.terms.len()
+  }
+
+  /// Returns the number of documents: the columns of the matrix.
+  pub fn documents(&self) -> usize {
+    self.columns.len()
+  }
+
+  /// Returns the id of a term: its row.
+  pub fn id(&self, token: &Token<'t, L>) -> Option<usize> {
+    self.ids.get(token).cloned()
+  }
+
+  /// Returns the term with the given id.
+  pub fn term(&self, id: usize) -> Option<Token<'t, L>> {
+    self.terms.get(id).cloned()
+  }
+
+  /// Returns the number of occurrences of a term in a document.
+  pub fn count(&self, term: usize, document: usize) -> usize {
+    self.columns.get(document)
+      .and_then(|column| column.binary_search_by_key(&term, |&(id, _)| id).ok().map(|i| column[i].1))
+      .unwrap_or(0)
+  }
+
+  /// Returns the number of documents holding a term.
+  pub fn document_frequency(&self, term: usize) -> usize {
+    self.document_frequencies.get(term).cloned().unwrap_or(0)
+  }
+
+  /// Returns the weighted entry of a term in a document.
+  pub fn get(&self, term: usize, document: usize) -> f64 {
+    let length = self.columns.get(document).map_or(0, |column| column.iter().map(|&(_, count)| count).sum());
+    self.weight(term, self.count(term, document), length)
+  }
+
+  /// Returns the terms of a document with nonzero weighted entries, and
+  /// their entries, in order of term.
+  pub fn column(&self, document: usize) -> Vec<(usize, f64)> {
+    match self.columns.get(document) {
+      Some(column) => self.weigh(column),
+      None => vec![]
+    }
+  }
+
+  /// Weighs the tokens of a document outside the matrix as a column of
+  /// it, by the document frequencies and totals of the matrix, for
+  /// comparing new documents with those of the matrix. Terms outside the
+  /// matrix are ignored.
+  pub fn vector<T>(&self, document: &T) -> Vec<(usize, f64)>
+    where T: AsTokens<'t, L> + ?Sized
+  {
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for token in document.as_tokens().iter() {
+      if let Some(&id) = self.ids.get(token) {
+        *counts.entry(id).or_insert(0) += 1;
+      }
+    }
+    let mut column: Vec<(usize, usize)> = counts.into_iter().collect();
+    column.sort();
+    self.weigh(&column)
+  }
+
+  /// Returns the weighted entries of the matrix as dense rows, one for
+  /// each term with an entry for each document.
+  pub fn to_dense(&self) -> Vec<Vec<f64>> {
+    let mut rows = vec![vec![0.0; self.columns.len()]; self.terms.len()];
+    for (d, column) in self.columns.iter().enumerate() {
+      for (term, weight) in self.weigh(column) {
+        rows[term][d] = weight;
+      }
+    }
+    rows
+  }
+
+  /// Weighs the counts of a column, dropping zero entries.
+  fn weigh(&self, column: &[(usize, usize)]) -> Vec<(usize, f64)> {
+    let length = column.iter().map(|&(_, count)| count).sum();
+    column.iter()
+      .map(|&(term, count)| (term, self.weight(term, count, length)))
+      .filter(|&(_, weight)| weight != 0.0)
+      .collect()
+  }
+
+  /// Weighs the count of a term in a document of `length` counts.
+  fn weight(&self, term: usize, count: usize, length: usize) -> f64 {
+    if count == 0 {
+      return 0.0;
+    }
+    match self.weighting {
+      Weighting::Counts => count as f64,
+      Weighting::TfIdf => {
+        let frequency = self.document_frequencies[term].max(1) as f64;
+        count as f64 * (self.columns.len() as f64 / frequency).ln()
+      },
+      Weighting::Ppmi => {
+        let expected = self.term_totals[term] as f64 * length as f64 / self.total as f64;
+        (count as f64 / expected).log2().max(0.0)
+      }
+    }
+  }
+}
@@ -0,0 +1,204 @@
+use token::Token;
+use language::Language;
+use corpus::Document;
+
+use std::collections::{HashMap, HashSet};
+
+/// A candidate term mined by a [`TermExtractor`].
+/// [`TermExtractor`]: struct.TermExtractor.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term<'t, L> {
+  /// The words of the term.
+  pub words: Vec<Token<'t, L>>,
+  /// The number of times the term occurs in the domain document.
+  pub frequency: usize,
+  /// The C-value of the term, which rewards long, frequent terms that
+  /// do not occur only as part of longer terms.
+  pub c_value: f64,
+  /// The ratio of the term's relative frequency in the domain document
+  /// to its (add-one smoothed) relative frequency in the reference
+  /// document. Without a reference document, this is `1`.
+  pub contrast: f64,
+  /// The overall score by which terms are ranked:
+  /// `c_value * log2(1 + contrast)`.
+  pub score: f64,
+}
+
+/// Extracts a ranked list of domain terms from a document, combining
+/// the C-value measure of termhood (Frantzi et al., 2000) with the
+/// contrast between term frequencies in the domain and in a general
+/// reference corpus.
+///
+/// Candidates are n-grams of word-like tokens (tokens containing at
+/// least one alphanumeric character) that neither begin nor end with a
+/// stopword, approximating noun phrases without a tagger:
+///
+/// ```rust
+/// let terms = TermExtractor::new()
+///   .with_stopwords(vec!["the", "of", "a", "in", "and"])
+///   .extract(&medical, Some(&news));
+///
+/// for term in terms.iter().take(20) {
+///   println!("{}\t{:.2}", term.words.iter().join(" "), term.score);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermExtractor {
+  min_length: usize,
+  max_length: usize,
+  min_frequency: usize,
+  stopwords: HashSet<Vec<u8>>,
+}
+
+impl TermExtractor {
+  /// Creates an extractor for terms of two to four words occurring at
+  /// least twice, with no stopwords.
+  pub fn new() -> TermExtractor {
+    TermExtractor {
+      min_length: 2,
+      max_length: 4,
+      min_frequency: 2,
+      stopwords: HashSet::new(),
+    }
+  }
+
+  /// Sets the minimum and maximum number of words in a term. The C-value
+  /// of single-word terms is always zero, so the minimum should usually
+  /// be at least two.
+  pub fn with_lengths(mut self, min: usize, max: usize) -> TermExtractor {
+    self.min_length = min.max(1);
+    self.max_length = max.max(self.min_length);
+    self
+  }
+
+  /// Sets the minimum number of occurrences of a term.
+  pub fn with_min_frequency(mut self, frequency: usize) -> TermExtractor {
+    self.min_frequency = frequency;
+    self
+  }
+
+  /// Sets the words which may not begin or end a term.
+  pub fn with_stopwords<I, S>(mut self, stopwords: I) -> TermExtractor
+    where I: IntoIterator<Item=S>,
+          S: Into<Vec<u8>>
+  {
+    self.stopwords = stopwords.into_iter().map(Into::into).collect();
+    self
+  }
+
+  fn is_stopword<L>(&self, token: &Token<L>) -> bool {
+    match *token {
+      Token::Word(ref word) => self.stopwords.contains(word.as_bytes()),
+      _ => true
+    }
+  }
+
+  fn is_wordlike<L>(token: &Token<L>) -> bool {
+    match *token {
+      Token::Word(ref word) =>
+        word.as_bytes().iter().any(|b| b.is_ascii_alphanumeric() || *b >= 0x80),
+      _ => false
+    }
+  }
+
+  /// Counts every candidate term in `document`.
+  fn candidates<'t, L>(&self, document: &'t Document<L>)
+      -> HashMap<Vec<Token<'t, L>>, usize>
+    where L: Language
+  {
+    let mut counts = HashMap::new();
+    for line in document.lines() {
+      let line = line.to_vec();
+      for start in 0..line.len() {
+        for length in self.min_length..self.max_length + 1 {
+          if start + length > line.len() {
+            break;
+          }
+          let words = &line[start..start + length];
+          if !words.iter().all(TermExtractor::is_wordlike) {
+            break;
+          }
+          if self.is_stopword(&words[0]) || self.is_stopword(&words[length - 1]) {
+            continue;
+          }
+          *counts.entry(words.to_vec()).or_insert(0) += 1;
+        }
+      }
+    }
+    counts
+  }
+
+  /// Extracts terms from `domain`, ranked from highest to lowest score.
+  /// If a `reference` document is given, terms that are proportionally
+  /// as frequent in the reference as in the domain are penalized.
+  pub fn extract<'t, L>(&self, domain: &'t Document<L>,
+                        reference: Option<&'t Document<L>>)
+      -> Vec<Term<'t, L>>
+    where L: Language
+  {
+    let counts = self.candidates(domain);
+
+    // For each candidate, the total frequency of, and number of, the
+    // longer candidates in which it is nested.
+    let mut nested: HashMap<&[Token<L>], (usize, usize)> = HashMap::new();
+    for (longer, &frequency) in &counts {
+      if frequency < self.min_frequency {
+        continue;
+      }
+      let mut seen = HashSet::new();
+      for length in self.min_length..longer.len() {
+        for shorter in longer.windows(length) {
+          if counts.contains_key(shorter) && seen.insert(shorter) {
+            let entry = nested.entry(shorter).or_insert((0, 0));
+            entry.0 += frequency;
+            entry.1 += 1;
+          }
+        }
+      }
+    }
+
+    let domain_total = domain.tokens().len().max(1) as f64;
+    let (reference_counts, reference_total) = match reference {
+      Some(reference) =>
+        (self.candidates(reference), reference.tokens().len() as f64),
+      None => (HashMap::new(), 0.0)
+    };
+
+    let mut terms: Vec<Term<L>> = counts.iter()
+      .filter(|&(_, &frequency)| frequency >= self.min_frequency)
+      .map(|(words, &frequency)| {
+        let length = (words.len() as f64).log2();
+        let c_value = match nested.get(&words[..]) {
+          Some(&(total, count)) =>
+            length * (frequency as f64 - total as f64 / count as f64),
+          None => length * frequency as f64
+        };
+        let contrast = match reference {
+          Some(_) => {
+            let background =
+              reference_counts.get(words).cloned().unwrap_or(0) as f64;
+            (frequency as f64 / domain_total)
+              / ((background + 1.0) / (reference_total + 1.0))
+          },
+          None => 1.0
+        };
+        Term {
+          words: words.clone(),
+          frequency: frequency,
+          c_value: c_value,
+          contrast: contrast,
+          score: c_value * (1.0 + contrast).log2(),
+        }
+      })
+      .collect();
+
+    terms.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    terms
+  }
+}
+
+impl Default for TermExtractor {
+  fn default() -> TermExtractor {
+    TermExtractor::new()
+  }
+}
@@ -0,0 +1,225 @@
+use token::Token;
+use language::Language;
+use corpus::AsTokens;
+use collection::Corpus;
+
+use std::collections::HashMap;
+
+/// A transformer of documents into sparse TF-IDF vectors, with a
+/// vocabulary and inverse document frequencies fit on a [`Corpus`], as
+/// the input of retrieval and classification.
+///
+/// The weight of a term in a document is its term frequency, the number
+/// of its occurrences or, if sublinear, `1 + ln(count)`, times its
+/// inverse document frequency. Unsmoothed, the inverse document frequency
+/// is `ln(N / df)` for `N` documents of which `df` hold the term, so that
+/// terms of every document weigh nothing; smoothed, it is
+/// `ln((1 + N) / (1 + df)) + 1`, as if one more document held every
+/// term, so that no term of the vocabulary weighs nothing. Vectors are
+/// normalized to unit length, so that their dot product is their cosine
+/// similarity, unless normalization is turned off.
+///
+/// Unlike a [`TermDocumentMatrix`], which weighs the documents it was
+/// built from, a fitted transformer keeps only its vocabulary and
+/// weights, to transform documents outside the corpus alike:
+///
+/// ```rust
+/// let tfidf = TfIdf::new()
+///   .with_sublinear_tf(true)
+///   .with_min_documents(2)
+///   .with_max_share(0.5)
+///   .fit(&corpus);
+/// let vectors = tfidf.transform_all(&corpus);
+/// let query: Document<English> = "latent semantic indexing".into();
+/// let query = tfidf.transform(&query.tokens());
+/// ```
+///
+/// [`Corpus`]: struct.Corpus.html
+/// [`TermDocumentMatrix`]: struct.TermDocumentMatrix.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct TfIdf<'t, L: Language> {
+  terms: Vec<Token<'t, L>>,
+  ids: HashMap<Token<'t, L>, usize>,
+  idf: Vec<f64>,
+  sublinear_tf: bool,
+  smooth_idf: bool,
+  normalize: bool,
+  min_documents: usize,
+  max_share: f64,
+  max_terms: Option<usize>,
+}
+
+impl<'t, L: Language> TfIdf<'t, L> {
+  /// Creates a transformer, to be fit to a corpus, of raw term
+  /// frequencies, smoothed inverse document frequencies and normalized
+  /// vectors, keeping every term in its vocabulary.
+  pub fn new() -> TfIdf<'t, L> {
+    TfIdf {
+      terms: vec![],
+      ids: HashMap::new(),
+      idf: vec![],
+      sublinear_tf: false,
+      smooth_idf: true,
+      normalize: true,
+      min_documents: 1,
+      max_share: 1.0,
+      max_terms: None,
+    }
+  }
+
+  /// Weighs term frequencies sublinearly, as `1 + ln(count)`, so that
+  /// repetitions of a term within a document count less than the first.
+  pub fn with_sublinear_tf(mut self, sublinear: bool) -> TfIdf<'t, L> {
+    self.sublinear_tf = sublinear;
+    self
+  }
+
+  /// Smooths inverse document frequencies as if one more document held
+  /// every term.
+  pub fn with_smooth_idf(mut self, smooth: bool) -> TfIdf<'t, L> {
+    self.smooth_idf = smooth;
+    self
+  }
+
+  /// Normalizes vectors to unit length.
+  pub fn with_normalization(mut self, normalize: bool) -> TfIdf<'t, L> {
+    self.normalize = normalize;
+    self
+  }
+
+  /// Leaves out of the vocabulary terms held by fewer than `count`
+  /// documents, which are often misspellings and names.
+  pub fn with_min_documents(mut self, count: usize) -> TfIdf<'t, L> {
+    self.min_documents = count;
+    self
+  }
+
+  /// Leaves out of the vocabulary terms held by more than `share` of the
+  /// documents, such as `0.5`, which are often function words.
+  pub fn with_max_share(mut self, share: f64) -> TfIdf<'t, L> {
+    self.max_share = share;
+    self
+  }
+
+  /// Keeps in the vocabulary only the `count` terms held by the most
+  /// documents, after the other filters, breaking ties by the order in
+  /// which the terms were first seen.
+  pub fn with_max_terms(mut self, count: usize) -> TfIdf<'t, L> {
+    self.max_terms = Some(count);
+    self
+  }
+
+  /// Fits the vocabulary and inverse document frequencies to a corpus,
+  /// replacing any fit before. Terms are numbered in the order they were
+  /// first seen. `Null` tokens are not counted.
+  pub fn fit(mut self, corpus: &'t Corpus<L>) -> TfIdf<'t, L> {
+    let mut seen: Vec<Token<'t, L>> = vec![];
+    let mut frequencies: HashMap<Token<'t, L>, usize> = HashMap::new();
+    for document in corpus {
+      let mut tokens: Vec<Token<'t, L>> = document.tokens().iter()
+        .filter(|&token| token != Token::Null)
+        .collect();
+      for &token in &tokens {
+        frequencies.entry(token).or_insert_with(|| {
+          seen.push(token);
+          0
+        });
+      }
+      tokens.sort();
+      tokens.dedup();
+      for token in tokens {
+        *frequencies.get_mut(&token).unwrap() += 1;
+      }
+    }
+
+    let documents = corpus.len() as f64;
+    let mut kept: Vec<(Token<'t, L>, usize)> = seen.into_iter()
+      .map(|token| (token, frequencies[&token]))
+      .filter(|&(_, frequency)| frequency >= self.min_documents && frequency as f64 <= self.max_share * documents)
+      .collect();
+    if let Some(max_terms) = self.max_terms {
+      let mut ranked: Vec<usize> = (0..kept.len()).collect();
+      ranked.sort_by(|&a, &b| kept[b].1.cmp(&kept[a].1).then(a.cmp(&b)));
+      ranked.truncate(max_terms);
+      ranked.sort();
+      kept = ranked.into_iter().map(|i| kept[i]).collect();
+    }
+
+    self.terms = kept.iter().map(|&(token, _)| token).collect();
+    self.ids = self.terms.iter().enumerate().map(|(id, &token)| (token, id)).collect();
+    let smooth = self.smooth_idf;
+    self.idf = kept.iter()
+      .map(|&(_, frequency)| {
+        if smooth {
+          ((1.0 + documents) / (1.0 + frequency as f64)).ln() + 1.0
+        } else {
+          (documents / frequency as f64).ln()
+        }
+      })
+      .collect();
+    self
+  }
+
+  /// Returns the number of terms in the vocabulary: the dimensions of the
+  /// vectors.
+  pub fn terms(&self) -> usize {
+    self.terms.len()
+  }
+
+  /// Returns the id of a term of the vocabulary.
+  pub fn id(&self, token: &Token<'t, L>) -> Option<usize> {
+    self.ids.get(token).cloned()
+  }
+
+  /// Returns the term with the given id.
+  pub fn term(&self, id: usize) -> Option<Token<'t, L>> {
+    self.terms.get(id).cloned()
+  }
+
+  /// Returns the inverse document frequency of a term.
+  pub fn idf(&self, id: usize) -> Option<f64> {
+    self.idf.get(id).cloned()
+  }
+
+  /// Returns the sparse vector of the tokens of a document: the terms of
+  /// the vocabulary it holds with nonzero weights, and their weights, in
+  /// order of term. Terms outside the vocabulary are ignored.
+  pub fn transform<T>(&self, document: &T) -> Vec<(usize, f64)>
+    where T: AsTokens<'t, L> + ?Sized
+  {
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for token in document.as_tokens().iter() {
+      if let Some(&id) = self.ids.get(token) {
+        *counts.entry(id).or_insert(0) += 1;
+      }
+    }
+    let mut vector: Vec<(usize, f64)> = counts.into_iter()
+      .map(|(id, count)| {
+        let tf = if self.sublinear_tf { 1.0 + (count as f64).ln() } else { count as f64 };
+        (id, tf * self.idf[id])
+      })
+      .filter(|&(_, weight)| weight != 0.0)
+      .collect();
+    vector.sort_by_key(|&(id, _)| id);
+    if self.normalize {
+      let norm = vector.iter().map(|&(_, weight)| weight * weight).sum::<f64>().sqrt();
+      if norm > 0.0 {
+        for entry in &mut vector {
+          entry.1 /= norm;
+        }
+      }
+    }
+    vector
+  }
+
+  /// Returns the sparse vector of each document of a corpus, in order.
+  pub fn transform_all(&self, corpus: &'t Corpus<L>) -> Vec<Vec<(usize, f64)>> {
+    corpus.iter().map(|document| self.transform(&document.tokens())).collect()
+  }
+}
+
+impl<'t, L: Language> Default for TfIdf<'t, L> {
+  fn default() -> TfIdf<'t, L> {
+    TfIdf::new()
+  }
+}
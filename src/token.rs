@@ -1,4 +1,5 @@
 use std::fmt;
+use std::str;
 use std::marker::PhantomData;
 use language::{Language, DefaultLanguage};
 
@@ -15,6 +16,29 @@ pub struct Word<'t, L>{
   language: PhantomData<L>
 }
 
+impl<'t, L> Word<'t, L> {
+  /// Returns the characters of this word, as a slice of bytes in the
+  /// document it belongs to.
+  pub fn as_bytes(&self) -> &'t [u8] {
+    self.chars
+  }
+
+  /// Returns the characters of this word as a string slice. The words of
+  /// a `Document` are always valid UTF-8; a word created from other bytes
+  /// is cut short at its first invalid sequence.
+  pub fn as_str(&self) -> &'t str {
+    match str::from_utf8(self.chars) {
+      Ok(word) => word,
+      Err(error) => str::from_utf8(&self.chars[..error.valid_up_to()]).unwrap_or_default()
+    }
+  }
+
+  /// Returns an iterator over the characters of this word.
+  pub fn chars(&self) -> str::Chars<'t> {
+    self.as_str().chars()
+  }
+}
+
 impl<'t, L> From<&'t[u8]> for Word<'t, L> {
   fn from(chars: &'t[u8]) -> Word<'t, L> {
     Word {
@@ -60,15 +84,13 @@ impl<'l, L:Language> Token<'l, L>
 
 impl<'t,L> fmt::Debug for Word<'t,L> {
   fn fmt(&self, f: &mut fmt::Formatter) -> ::std::fmt::Result {
-    use std::iter::FromIterator;
-    write!(f, "{}", String::from_iter(self.chars.iter().map(|&c| c as char)))
+    write!(f, "{}", String::from_utf8_lossy(self.chars))
   }
 }
 
 impl<'t,L> fmt::Display for Word<'t,L> {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    use std::iter::FromIterator;
-    write!(f, "{}", String::from_iter(self.chars.iter().map(|&c| c as char)))
+    write!(f, "{}", String::from_utf8_lossy(self.chars))
   }
 }
 
@@ -91,3 +113,57 @@ impl<'t,L> fmt::Display for Token<'t,L> {
     }
   }
 }
+
+/// The serialized form of a token. Words are written as strings, and are
+/// borrowed from the input when read.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Token")]
+enum SerializedToken<'t> {
+  Word(&'t str),
+  Null,
+  Unknown,
+}
+
+#[cfg(feature = "serde")]
+impl<'t, L> ::serde::Serialize for Word<'t, L> {
+  fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.as_str())
+  }
+}
+
+/// Words borrow their characters from the input, so they can be read only
+/// from formats that can lend strings, such as strings without escape
+/// sequences in JSON.
+#[cfg(feature = "serde")]
+impl<'de: 't, 't, L> ::serde::Deserialize<'de> for Word<'t, L> {
+  fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Word<'t, L>, D::Error> {
+    let word: &'de str = ::serde::Deserialize::deserialize(deserializer)?;
+    Ok(word.as_bytes().into())
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'t, L> ::serde::Serialize for Token<'t, L> {
+  fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let token = match *self {
+      Token::Word(ref word) => SerializedToken::Word(word.as_str()),
+      Token::Null => SerializedToken::Null,
+      Token::Unknown => SerializedToken::Unknown,
+    };
+    ::serde::Serialize::serialize(&token, serializer)
+  }
+}
+
+/// Tokens are read as their words are; see the `Deserialize`
+/// implementation of `Word`.
+#[cfg(feature = "serde")]
+impl<'de: 't, 't, L> ::serde::Deserialize<'de> for Token<'t, L> {
+  fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Token<'t, L>, D::Error> {
+    Ok(match ::serde::Deserialize::deserialize(deserializer)? {
+      SerializedToken::Word(word) => Token::Word(word.as_bytes().into()),
+      SerializedToken::Null => Token::Null,
+      SerializedToken::Unknown => Token::Unknown,
+    })
+  }
+}
@@ -0,0 +1,259 @@
+//! Phrase-based translation models, typed by their source and target
+//! languages.
+//!
+//! A [`TranslationModel<L, M>`] bundles the word lexicon, phrase table
+//! and lexicalized reordering statistics of a model translating from
+//! language `L` into language `M`. Its lookups take tokens of `L` and
+//! yield words of `M`, so that a model cannot be applied to text in the
+//! wrong language:
+//!
+//! ```rust
+//! let mut model: TranslationModel<French, English> = TranslationModel::new();
+//! model.read_phrase_table(&fs::read_to_string("phrase-table.fr-en")?)?;
+//! model.read_reordering_table(&fs::read_to_string("reordering-table.fr-en")?)?;
+//!
+//! let french: Document<French> = Document::from_reader(File::open("input.fr")?)?;
+//! let line = french.lines().get(0).unwrap().to_vec();
+//! for option in model.options(&line) {
+//!   println!("{}..{}\t{}", option.start, option.end, option.pair.target.join(" "));
+//! }
+//!
+//! let german: Document<German> = Document::from_reader(File::open("input.de")?)?;
+//! model.options(&german.tokens().to_vec()); // error: expected `Token<French>`
+//! ```
+//!
+//! [`TranslationModel<L, M>`]: struct.TranslationModel.html
+use token::Token;
+use language::Language;
+use error::LineParseError;
+
+use std::marker::PhantomData;
+use std::collections::HashMap;
+
+/// The probabilities of the orientations of a phrase pair relative to
+/// the previously translated phrase, as in lexicalized reordering
+/// models.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reordering {
+  /// The previous phrase immediately precedes this one in the source.
+  pub monotone: f64,
+  /// The previous phrase immediately follows this one in the source.
+  pub swap: f64,
+  /// The previous phrase is elsewhere in the source.
+  pub discontinuous: f64,
+}
+
+/// A translation of a source phrase.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhrasePair {
+  /// The words of the translation.
+  pub target: Vec<String>,
+  /// The probability of the translation given the source phrase.
+  pub probability: f64,
+  /// The reordering statistics of the pair, if known.
+  pub reordering: Option<Reordering>,
+}
+
+/// A phrase pair whose source phrase covers tokens `start..end` of a
+/// line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhraseOption<'m> {
+  pub start: usize,
+  pub end: usize,
+  pub pair: &'m PhrasePair,
+}
+
+/// A translation model from language `L` into language `M`.
+#[derive(Debug, Clone)]
+pub struct TranslationModel<L, M> {
+  /// The probability of each target word given each source word.
+  lexicon: HashMap<(Vec<u8>, Vec<u8>), f64>,
+  /// The translations of each source phrase, whose words are joined by
+  /// spaces.
+  phrases: HashMap<Vec<u8>, Vec<PhrasePair>>,
+  /// The number of words in the longest source phrase.
+  max_phrase_length: usize,
+  languages: PhantomData<(L, M)>,
+}
+
+impl<L: Language, M: Language> TranslationModel<L, M> {
+  /// Creates an empty model.
+  pub fn new() -> TranslationModel<L, M> {
+    TranslationModel {
+      lexicon: HashMap::new(),
+      phrases: HashMap::new(),
+      max_phrase_length: 0,
+      languages: PhantomData,
+    }
+  }
+
+  /// Sets the probability of the target word `target` given the source
+  /// word `source`.
+  pub fn add_word(&mut self, source: &str, target: &str, probability: f64) {
+    self.lexicon.insert((source.as_bytes().to_vec(), target.as_bytes().to_vec()), probability);
+  }
+
+  /// Adds a translation of a source phrase, with the words of each
+  /// phrase separated by whitespace, replacing any previous translation
+  /// of the phrase into the same words.
+  pub fn add_phrase(&mut self, source: &str, target: &str, probability: f64) {
+    let words: Vec<&str> = source.split_whitespace().collect();
+    self.max_phrase_length = self.max_phrase_length.max(words.len());
+    let target: Vec<String> = target.split_whitespace().map(String::from).collect();
+    let pairs = self.phrases.entry(words.join(" ").into_bytes()).or_default();
+    match pairs.iter_mut().find(|pair| pair.target == target) {
+      Some(pair) => pair.probability = probability,
+      None => pairs.push(PhrasePair { target: target, probability: probability, reordering: None })
+    }
+  }
+
+  /// Sets the reordering statistics of a phrase pair. Returns `false`,
+  /// leaving the model unchanged, if the model has no such pair.
+  pub fn set_reordering(&mut self, source: &str, target: &str, reordering: Reordering) -> bool {
+    let source = source.split_whitespace().collect::<Vec<_>>().join(" ").into_bytes();
+    let target: Vec<&str> = target.split_whitespace().collect();
+    let pair = self.phrases.get_mut(&source)
+      .and_then(|pairs| pairs.iter_mut().find(|pair| pair.target == target));
+    match pair {
+      Some(pair) => {
+        pair.reordering = Some(reordering);
+        true
+      },
+      None => false
+    }
+  }
+
+  /// Returns the probability of the target word given the source word,
+  /// or zero if the lexicon has no such entry.
+  pub fn lexical_probability(&self, source: &Token<L>, target: &Token<M>) -> f64 {
+    match (*source, *target) {
+      (Token::Word(ref s), Token::Word(ref t)) => {
+        let key = (s.as_bytes().to_vec(), t.as_bytes().to_vec());
+        self.lexicon.get(&key).cloned().unwrap_or(0.0)
+      },
+      _ => 0.0
+    }
+  }
+
+  /// Returns the translations of a source phrase.
+  pub fn translations(&self, source: &[Token<L>]) -> &[PhrasePair] {
+    match key(source).and_then(|key| self.phrases.get(&key)) {
+      Some(pairs) => pairs,
+      None => &[]
+    }
+  }
+
+  /// Returns every translation of every phrase of a source line, ordered
+  /// by the start and then the end of the phrase.
+  pub fn options<'m>(&'m self, line: &[Token<L>]) -> Vec<PhraseOption<'m>> {
+    let mut options = vec![];
+    for start in 0..line.len() {
+      for end in start + 1..=line.len().min(start + self.max_phrase_length) {
+        for pair in self.translations(&line[start..end]) {
+          options.push(PhraseOption { start: start, end: end, pair: pair });
+        }
+      }
+    }
+    options
+  }
+
+  /// Reads a word lexicon, with one entry per line: a source word, a
+  /// target word, and the probability of the target word given the
+  /// source word, separated by whitespace.
+  pub fn read_lexicon(&mut self, text: &str) -> Result<(), LineParseError> {
+    for (i, line) in text.lines().enumerate() {
+      let error = |message| LineParseError { line: i + 1, message: message };
+      let fields: Vec<&str> = line.split_whitespace().collect();
+      match fields.len() {
+        0 => continue,
+        3 => {
+          let probability = fields[2].parse().map_err(|_| error("malformed probability"))?;
+          self.add_word(fields[0], fields[1], probability);
+        },
+        _ => return Err(error("expected three fields"))
+      }
+    }
+    Ok(())
+  }
+
+  /// Reads a phrase table in the format of Moses, with one phrase pair
+  /// per line: the source phrase, the target phrase, and scores,
+  /// separated by `|||`. The first score is taken as the probability of
+  /// the target phrase given the source phrase; further scores and
+  /// fields are ignored.
+  pub fn read_phrase_table(&mut self, text: &str) -> Result<(), LineParseError> {
+    for (i, line) in text.lines().enumerate() {
+      if line.trim().is_empty() {
+        continue;
+      }
+      let (source, target, scores) = fields(line, i)?;
+      let error = |message| LineParseError { line: i + 1, message: message };
+      let probability = scores.first()
+        .ok_or_else(|| error("missing score"))?
+        .parse().map_err(|_| error("malformed score"))?;
+      self.add_phrase(source, target, probability);
+    }
+    Ok(())
+  }
+
+  /// Reads a lexicalized reordering table in the format of Moses, with
+  /// one phrase pair per line: the source phrase, the target phrase, and
+  /// the probabilities of monotone, swap and discontinuous orientation,
+  /// separated by `|||`. Pairs missing from the phrase table are ignored.
+  pub fn read_reordering_table(&mut self, text: &str) -> Result<(), LineParseError> {
+    for (i, line) in text.lines().enumerate() {
+      if line.trim().is_empty() {
+        continue;
+      }
+      let (source, target, scores) = fields(line, i)?;
+      let error = |message| LineParseError { line: i + 1, message: message };
+      if scores.len() < 3 {
+        return Err(error("expected three orientation probabilities"));
+      }
+      let mut probabilities = [0.0; 3];
+      for (probability, score) in probabilities.iter_mut().zip(scores) {
+        *probability = score.parse().map_err(|_| error("malformed score"))?;
+      }
+      self.set_reordering(source, target, Reordering {
+        monotone: probabilities[0],
+        swap: probabilities[1],
+        discontinuous: probabilities[2],
+      });
+    }
+    Ok(())
+  }
+}
+
+impl<L: Language, M: Language> Default for TranslationModel<L, M> {
+  fn default() -> TranslationModel<L, M> {
+    TranslationModel::new()
+  }
+}
+
+/// Returns the words of a phrase joined by spaces, or `None` if it
+/// contains a token that is not a word.
+fn key<L>(phrase: &[Token<L>]) -> Option<Vec<u8>> {
+  let mut key = vec![];
+  for (i, token) in phrase.iter().enumerate() {
+    match *token {
+      Token::Word(ref word) => {
+        if i > 0 {
+          key.push(b' ');
+        }
+        key.extend_from_slice(word.as_bytes());
+      },
+      _ => return None
+    }
+  }
+  Some(key)
+}
+
+/// Splits line `i` of a Moses table into its source phrase, target
+/// phrase and scores.
+fn fields(line: &str, i: usize) -> Result<(&str, &str, Vec<&str>), LineParseError> {
+  let fields: Vec<&str> = line.split("|||").collect();
+  if fields.len() < 3 {
+    return Err(LineParseError { line: i + 1, message: "expected `|||`-separated fields" });
+  }
+  Ok((fields[0], fields[1], fields[2].split_whitespace().collect()))
+}
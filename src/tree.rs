@@ -0,0 +1,397 @@
+use error::ParseError;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A `Tree` is a labeled, ordered tree, such as a constituency parse. Its
+/// leaves carry values of type `T`, which are usually words.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Tree<T=String> {
+  /// A leaf of the tree.
+  Leaf(T),
+  /// An interior node, with its label and children.
+  Node(String, Vec<Tree<T>>),
+}
+
+impl<T> Tree<T> {
+  /// Returns the label of this node, or `None` if this is a leaf.
+  pub fn label(&self) -> Option<&str> {
+    match *self {
+      Tree::Node(ref label, _) => Some(label),
+      Tree::Leaf(_) => None
+    }
+  }
+
+  /// Returns the children of this node. Leaves have no children.
+  pub fn children(&self) -> &[Tree<T>] {
+    match *self {
+      Tree::Node(_, ref children) => children,
+      Tree::Leaf(_) => &[]
+    }
+  }
+
+  /// Returns `true` if this is a leaf.
+  pub fn is_leaf(&self) -> bool {
+    match *self {
+      Tree::Leaf(_) => true,
+      Tree::Node(..) => false
+    }
+  }
+
+  /// Returns `true` if this node's only child is a leaf, as is the case
+  /// for the part-of-speech nodes of a constituency parse.
+  pub fn is_preterminal(&self) -> bool {
+    match *self {
+      Tree::Node(_, ref children) => children.len() == 1 && children[0].is_leaf(),
+      Tree::Leaf(_) => false
+    }
+  }
+
+  /// Returns the leaves of this tree, from left to right.
+  pub fn leaves(&self) -> Vec<&T> {
+    let mut leaves = vec![];
+    self.collect_leaves(&mut leaves);
+    leaves
+  }
+
+  fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a T>) {
+    match *self {
+      Tree::Leaf(ref leaf) => leaves.push(leaf),
+      Tree::Node(_, ref children) =>
+        for child in children { child.collect_leaves(leaves) }
+    }
+  }
+
+  /// Returns the `(label, leaf)` pairs of the preterminal nodes of this
+  /// tree, from left to right: for a constituency parse, its tagging.
+  pub fn preterminals(&self) -> Vec<(&str, &T)> {
+    let mut pairs = vec![];
+    self.collect_preterminals(&mut pairs);
+    pairs
+  }
+
+  fn collect_preterminals<'a>(&'a self, pairs: &mut Vec<(&'a str, &'a T)>) {
+    match *self {
+      Tree::Node(ref label, ref children) => {
+        if let (1, Some(Tree::Leaf(leaf))) = (children.len(), children.first()) {
+          pairs.push((label, leaf));
+        } else {
+          for child in children { child.collect_preterminals(pairs) }
+        }
+      },
+      Tree::Leaf(_) => {}
+    }
+  }
+
+  /// Consumes this tree, and produces a tree of the same shape in which
+  /// every leaf has been transformed by `f`.
+  pub fn map_leaves<U, F>(self, f: &mut F) -> Tree<U>
+    where F: FnMut(T) -> U
+  {
+    match self {
+      Tree::Leaf(leaf) => Tree::Leaf(f(leaf)),
+      Tree::Node(label, children) =>
+        Tree::Node(label,
+          children.into_iter().map(|child| child.map_leaves(f)).collect())
+    }
+  }
+}
+
+impl<T: fmt::Display> Tree<T> {
+  /// Renders this tree in the bracketed format of the Penn Treebank, on
+  /// a single line. This is also the tree's `Display` format:
+  ///
+  /// ```text
+  /// (S (NP (DT the) (NN dog)) (VP (VBD barked)))
+  /// ```
+  pub fn to_bracketed(&self) -> String {
+    self.to_string()
+  }
+
+  /// Renders this tree for the LaTeX `qtree` package:
+  ///
+  /// ```text
+  /// \Tree [.S [.NP [.DT the ] [.NN dog ] ] [.VP [.VBD barked ] ] ]
+  /// ```
+  pub fn to_qtree(&self) -> String {
+    fn render<T: fmt::Display>(tree: &Tree<T>, out: &mut String) {
+      match *tree {
+        Tree::Leaf(ref leaf) => out.push_str(&escape_latex(&leaf.to_string())),
+        Tree::Node(ref label, ref children) => {
+          out.push_str("[.");
+          out.push_str(&escape_latex(label));
+          for child in children {
+            out.push(' ');
+            render(child, out);
+          }
+          out.push_str(" ]");
+        }
+      }
+    }
+    let mut out = String::from("\\Tree ");
+    render(self, &mut out);
+    out
+  }
+
+  /// Renders this tree as an indented diagram, one node per line, using
+  /// either ASCII or Unicode box-drawing characters:
+  ///
+  /// ```text
+  /// S
+  /// ├── NP
+  /// │   ├── DT
+  /// │   │   └── the
+  /// │   └── NN
+  /// │       └── dog
+  /// └── VP
+  ///     └── VBD
+  ///         └── barked
+  /// ```
+  pub fn to_diagram(&self, style: DiagramStyle) -> String {
+    let (branch, last, pipe, space) = match style {
+      DiagramStyle::Ascii => ("|-- ", "`-- ", "|   ", "    "),
+      DiagramStyle::Unicode => ("├── ", "└── ", "│   ", "    "),
+    };
+
+    fn render<T: fmt::Display>(tree: &Tree<T>, prefix: &str, glyphs: (&str, &str, &str, &str),
+                               out: &mut String) {
+      for (i, child) in tree.children().iter().enumerate() {
+        let final_child = i + 1 == tree.children().len();
+        out.push_str(prefix);
+        out.push_str(if final_child { glyphs.1 } else { glyphs.0 });
+        out.push_str(&node_text(child));
+        out.push('\n');
+        let prefix = format!("{}{}", prefix, if final_child { glyphs.3 } else { glyphs.2 });
+        render(child, &prefix, glyphs, out);
+      }
+    }
+
+    let mut out = node_text(self);
+    out.push('\n');
+    render(self, "", (branch, last, pipe, space), &mut out);
+    out
+  }
+}
+
+impl<T: fmt::Display> Tree<T> {
+  /// Renders this tree in the Graphviz DOT language, for visualization
+  /// with tools like `dot`. Leaves are drawn without a border, and in
+  /// order from left to right.
+  ///
+  /// ```bash
+  /// dot -Tpng tree.dot > tree.png
+  /// ```
+  pub fn to_dot(&self) -> String {
+    fn render<T: fmt::Display>(tree: &Tree<T>, next: &mut usize,
+                               leaves: &mut Vec<usize>, out: &mut String)
+        -> usize
+    {
+      let id = *next;
+      *next += 1;
+      match *tree {
+        Tree::Leaf(ref leaf) => {
+          out.push_str(&format!("  n{} [label=\"{}\", shape=plaintext];\n",
+                                id, escape_dot(&leaf.to_string())));
+          leaves.push(id);
+        },
+        Tree::Node(ref label, ref children) => {
+          out.push_str(&format!("  n{} [label=\"{}\"];\n", id, escape_dot(label)));
+          for child in children {
+            let child = render(child, next, leaves, out);
+            out.push_str(&format!("  n{} -> n{};\n", id, child));
+          }
+        }
+      }
+      id
+    }
+
+    let mut out = String::from("digraph tree {\n");
+    let mut leaves = vec![];
+    render(self, &mut 0, &mut leaves, &mut out);
+    if leaves.len() > 1 {
+      let ids: Vec<String> = leaves.iter().map(|id| format!("n{}", id)).collect();
+      out.push_str(&format!("  {{ rank=same; {} [style=invis]; }}\n",
+                            ids.join(" -> ")));
+    }
+    out.push_str("}\n");
+    out
+  }
+}
+
+/// Escapes text for use inside a double-quoted DOT string.
+pub(crate) fn escape_dot(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The text of a single node: its label, or its leaf value.
+fn node_text<T: fmt::Display>(tree: &Tree<T>) -> String {
+  match *tree {
+    Tree::Leaf(ref leaf) => leaf.to_string(),
+    Tree::Node(ref label, _) => label.clone()
+  }
+}
+
+fn escape_latex(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '$' | '%' | '&' | '#' | '_' | '{' | '}' => { escaped.push('\\'); escaped.push(c) },
+      '\\' => escaped.push_str("\\textbackslash{}"),
+      '[' | ']' => { escaped.push('{'); escaped.push(c); escaped.push('}') },
+      _ => escaped.push(c)
+    }
+  }
+  escaped
+}
+
+/// The characters used to draw a [`Tree::to_diagram`].
+/// [`Tree::to_diagram`]: enum.Tree.html#method.to_diagram
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum DiagramStyle {
+  /// Draw branches with `|`, `` ` `` and `-`.
+  Ascii,
+  /// Draw branches with Unicode box-drawing characters.
+  Unicode,
+}
+
+impl<T: fmt::Display> fmt::Display for Tree<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Tree::Leaf(ref leaf) => write!(f, "{}", leaf),
+      Tree::Node(ref label, ref children) => {
+        write!(f, "({}", label)?;
+        for child in children {
+          write!(f, " {}", child)?;
+        }
+        write!(f, ")")
+      }
+    }
+  }
+}
+
+impl FromStr for Tree<String> {
+  type Err = ParseError;
+
+  /// Reads a tree in the bracketed format of the Penn Treebank. The
+  /// unlabeled outermost brackets used by the treebank's files, as in
+  /// `( (S ...) )`, are removed.
+  ///
+  /// ```rust
+  /// let tree: Tree = "(S (NP (DT the) (NN dog)) (VP (VBD barked)))".parse()?;
+  /// assert_eq!(tree.leaves(), vec!["the", "dog", "barked"]);
+  /// ```
+  fn from_str(s: &str) -> Result<Tree<String>, ParseError> {
+    let mut parser = BracketParser { text: s, offset: 0 };
+    let tree = parser.tree()?;
+    parser.skip_whitespace();
+    if parser.offset != s.len() {
+      return Err(parser.error("unexpected text after tree"));
+    }
+    Ok(unwrap_root(tree))
+  }
+}
+
+/// Removes the unlabeled outermost brackets of a treebank tree, as in
+/// `( (S ...) )`.
+fn unwrap_root(tree: Tree<String>) -> Tree<String> {
+  match tree {
+    Tree::Node(label, mut children) if label.is_empty() && children.len() == 1 =>
+      children.pop().unwrap(),
+    tree => tree
+  }
+}
+
+/// A reader for bracketed trees. Reads one tree at a time; see
+/// [`read_trees`] for reading a whole treebank file.
+/// [`read_trees`]: fn.read_trees.html
+struct BracketParser<'s> {
+  text: &'s str,
+  offset: usize,
+}
+
+impl<'s> BracketParser<'s> {
+  fn error(&self, message: &'static str) -> ParseError {
+    ParseError { offset: self.offset, message: message }
+  }
+
+  fn rest(&self) -> &'s str {
+    &self.text[self.offset..]
+  }
+
+  fn skip_whitespace(&mut self) {
+    let rest = self.rest();
+    self.offset += rest.len() - rest.trim_start().len();
+  }
+
+  fn atom(&mut self) -> &'s str {
+    let rest = self.rest();
+    let end = rest.find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+      .unwrap_or(rest.len());
+    self.offset += end;
+    &rest[..end]
+  }
+
+  fn tree(&mut self) -> Result<Tree<String>, ParseError> {
+    self.skip_whitespace();
+    if !self.rest().starts_with('(') {
+      return Err(self.error("expected `(`"));
+    }
+    self.offset += 1;
+    self.skip_whitespace();
+    let label = self.atom().to_string();
+    let mut children = vec![];
+    loop {
+      self.skip_whitespace();
+      let rest = self.rest();
+      if rest.starts_with(')') {
+        self.offset += 1;
+        return Ok(Tree::Node(label, children));
+      } else if rest.starts_with('(') {
+        children.push(self.tree()?);
+      } else if rest.is_empty() {
+        return Err(self.error("unclosed `(`"));
+      } else {
+        children.push(Tree::Leaf(self.atom().to_string()));
+      }
+    }
+  }
+}
+
+/// Reads every bracketed tree in `text`, such as the contents of a Penn
+/// Treebank `.mrg` file.
+pub fn read_trees(text: &str) -> Result<Vec<Tree>, ParseError> {
+  let mut parser = BracketParser { text: text, offset: 0 };
+  let mut trees = vec![];
+  loop {
+    parser.skip_whitespace();
+    if parser.rest().is_empty() {
+      return Ok(trees);
+    }
+    trees.push(unwrap_root(parser.tree()?));
+  }
+}
+
+/// Renders a tagged sentence as two aligned rows of words and tags:
+///
+/// ```text
+/// The dog barked .
+/// DT  NN  VBD    .
+/// ```
+pub fn tagged_columns<'a, I, T, G>(tagged: I) -> String
+  where I: IntoIterator<Item=&'a (T, G)>,
+        T: 'a + fmt::Display,
+        G: 'a + fmt::Display
+{
+  let (mut words, mut tags) = (String::new(), String::new());
+  for (word, tag) in tagged {
+    let (word, tag) = (word.to_string(), tag.to_string());
+    let width = word.chars().count().max(tag.chars().count());
+    if !words.is_empty() {
+      words.push(' ');
+      tags.push(' ');
+    }
+    words.push_str(&format!("{:1$}", word, width));
+    tags.push_str(&format!("{:1$}", tag, width));
+  }
+  format!("{}\n{}", words.trim_end(), tags.trim_end())
+}
@@ -0,0 +1,151 @@
+//! Viterbi decoding, generic over the scoring function.
+//!
+//! The decoders in this module know nothing about tokens, tags or
+//! probabilities: states are identified by index, positions are
+//! identified by index, and scores are supplied by closures. Scores are
+//! summed along a path, so they should typically be log-probabilities
+//! (or any other additive score, such as the weights of a linear
+//! model). Higher scores are better. To sum over paths rather than
+//! choose the best, see the [`hmm`] module.
+//!
+//! ```rust
+//! let (path, score) = viterbi::first_order(tags.len(), words.len(),
+//!   |previous, next| transitions.log_probability(previous, next),
+//!   |position, tag| emissions.log_probability(tag, words[position]));
+//! ```
+//!
+//! [`hmm`]: ../hmm/index.html
+
+/// Finds the highest scoring sequence of states, one per position, for
+/// a sequence of `length` positions over `states` possible states.
+///
+/// `transition(previous, next)` scores moving from state `previous` to
+/// state `next`; `previous` is `None` at the first position.
+/// `emission(position, state)` scores assigning `state` to `position`.
+///
+/// Returns the best path and its total score, or `None` if there are no
+/// states to choose from. An empty sequence has an empty best path,
+/// with a score of zero.
+pub fn first_order<T, E>(states: usize, length: usize,
+                         transition: T, emission: E)
+    -> Option<(Vec<usize>, f64)>
+  where T: Fn(Option<usize>, usize) -> f64,
+        E: Fn(usize, usize) -> f64
+{
+  if length == 0 {
+    return Some((vec![], 0.0));
+  }
+  if states == 0 {
+    return None;
+  }
+
+  // `best[i][s]` is the score of the best path ending in state `s` at
+  // position `i`, and `back[i][s]` is the previous state on that path.
+  let mut best = vec![vec![0.0; states]; length];
+  let mut back = vec![vec![0; states]; length];
+
+  for (s, score) in best[0].iter_mut().enumerate() {
+    *score = transition(None, s) + emission(0, s);
+  }
+
+  for i in 1..length {
+    for s in 0..states {
+      let (previous, score) = (0..states)
+        .map(|p| (p, best[i - 1][p] + transition(Some(p), s)))
+        .max_score();
+      best[i][s] = score + emission(i, s);
+      back[i][s] = previous;
+    }
+  }
+
+  let (last, score) = best[length - 1].iter().cloned().enumerate()
+    .max_score();
+
+  let mut path = vec![last; length];
+  for i in (1..length).rev() {
+    path[i - 1] = back[i][path[i]];
+  }
+
+  Some((path, score))
+}
+
+/// Finds the highest scoring sequence of states under a second-order
+/// model, in which each transition is conditioned on the two preceding
+/// states.
+///
+/// `transition(before, previous, next)` scores moving to state `next`
+/// after states `before` and `previous`; at the first position both are
+/// `None`, and at the second position `before` is `None`.
+/// `emission(position, state)` scores assigning `state` to `position`.
+///
+/// Returns the best path and its total score, or `None` if there are no
+/// states to choose from. Decoding takes time proportional to the cube
+/// of the number of states.
+pub fn second_order<T, E>(states: usize, length: usize,
+                          transition: T, emission: E)
+    -> Option<(Vec<usize>, f64)>
+  where T: Fn(Option<usize>, Option<usize>, usize) -> f64,
+        E: Fn(usize, usize) -> f64
+{
+  if length == 0 {
+    return Some((vec![], 0.0));
+  }
+  if states == 0 {
+    return None;
+  }
+  if length == 1 {
+    return first_order(states, length,
+      |_, next| transition(None, None, next), emission);
+  }
+
+  // `best[i][p * states + s]` is the score of the best path ending in
+  // states `p` and `s` at positions `i - 1` and `i`.
+  let pairs = states * states;
+  let mut best = vec![vec![f64::NEG_INFINITY; pairs]; length];
+  let mut back = vec![vec![0; pairs]; length];
+
+  for p in 0..states {
+    let first = transition(None, None, p) + emission(0, p);
+    for s in 0..states {
+      best[1][p * states + s] =
+        first + transition(None, Some(p), s) + emission(1, s);
+    }
+  }
+
+  for i in 2..length {
+    for p in 0..states {
+      for s in 0..states {
+        let (before, score) = (0..states)
+          .map(|b| (b, best[i - 1][b * states + p]
+                       + transition(Some(b), Some(p), s)))
+          .max_score();
+        best[i][p * states + s] = score + emission(i, s);
+        back[i][p * states + s] = before;
+      }
+    }
+  }
+
+  let (pair, score) = best[length - 1].iter().cloned().enumerate()
+    .max_score();
+
+  let mut path = vec![0; length];
+  path[length - 2] = pair / states;
+  path[length - 1] = pair % states;
+  for i in (2..length).rev() {
+    path[i - 2] = back[i][path[i - 1] * states + path[i]];
+  }
+
+  Some((path, score))
+}
+
+trait MaxScore {
+  /// Returns the `(state, score)` pair with the greatest score.
+  fn max_score(self) -> (usize, f64);
+}
+
+impl<I: Iterator<Item=(usize, f64)>> MaxScore for I {
+  fn max_score(self) -> (usize, f64) {
+    self.fold((0, f64::NEG_INFINITY),
+      |best, next| if next.1 > best.1 { next } else { best })
+  }
+}
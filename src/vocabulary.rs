@@ -0,0 +1,295 @@
+use token::Token;
+use language::Language;
+use corpus::{AsTokens, Document, TokenSet};
+
+use std::collections::HashMap;
+
+/// The id of `Token::Unknown` in every [`Vocabulary`], which stands for
+/// every token outside the vocabulary.
+///
+/// [`Vocabulary`]: struct.Vocabulary.html
+pub const UNKNOWN_ID: usize = 0;
+
+/// A numbering of tokens, with the number of times each was counted.
+/// Id [`UNKNOWN_ID`] is always `Token::Unknown`; other tokens are
+/// numbered in the order they were first counted.
+///
+/// ```rust
+/// let training: Document<English> = Document::from_reader(File::open("train.txt")?)?;
+/// let mut vocabulary = Vocabulary::from_tokens(training.tokens());
+/// let mut encoded = vocabulary.encode(training.lines());
+///
+/// // Keep the 10,000 most frequent tokens, without re-encoding the text.
+/// let mapping = vocabulary.truncate(10_000);
+/// encoded.remap(&mapping);
+/// ```
+///
+/// [`UNKNOWN_ID`]: constant.UNKNOWN_ID.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vocabulary<'t, L: Language> {
+  tokens: Vec<Token<'t, L>>,
+  counts: Vec<usize>,
+  ids: HashMap<Token<'t, L>, usize>,
+}
+
+impl<'t, L: Language> Vocabulary<'t, L> {
+  /// Creates a vocabulary of only `Token::Unknown`.
+  pub fn new() -> Vocabulary<'t, L> {
+    let mut ids = HashMap::new();
+    ids.insert(Token::Unknown, UNKNOWN_ID);
+    Vocabulary { tokens: vec![Token::Unknown], counts: vec![0], ids: ids }
+  }
+
+  /// Creates a vocabulary of the given tokens, counting each occurrence.
+  pub fn from_tokens<I>(tokens: I) -> Vocabulary<'t, L>
+    where I: IntoIterator<Item=Token<'t, L>>
+  {
+    let mut vocabulary = Vocabulary::new();
+    for token in tokens {
+      vocabulary.add(token);
+    }
+    vocabulary
+  }
+
+  /// Creates a vocabulary of the tokens of a document, counting each
+  /// occurrence.
+  pub fn from_document(document: &'t Document<L>) -> Vocabulary<'t, L> {
+    Vocabulary::from_tokens(document.tokens())
+  }
+
+  /// Counts an occurrence of a token, adding it to the vocabulary if
+  /// necessary, and returns its id.
+  pub fn add(&mut self, token: Token<'t, L>) -> usize {
+    let next = self.tokens.len();
+    let id = *self.ids.entry(token).or_insert(next);
+    if id == next {
+      self.tokens.push(token);
+      self.counts.push(0);
+    }
+    self.counts[id] += 1;
+    id
+  }
+
+  /// Returns the number of tokens in the vocabulary, including
+  /// `Token::Unknown`.
+  pub fn len(&self) -> usize {
+    self.tokens.len()
+  }
+
+  /// Returns `true` if the vocabulary holds only `Token::Unknown`.
+  pub fn is_empty(&self) -> bool {
+    self.tokens.len() == 1
+  }
+
+  /// Returns `true` if the token is in the vocabulary.
+  pub fn contains(&self, token: &Token<'t, L>) -> bool {
+    self.ids.contains_key(token)
+  }
+
+  /// Returns the id of a token, or [`UNKNOWN_ID`] if the token is not in
+  /// the vocabulary.
+  ///
+  /// [`UNKNOWN_ID`]: constant.UNKNOWN_ID.html
+  pub fn id(&self, token: &Token<'t, L>) -> usize {
+    self.ids.get(token).cloned().unwrap_or(UNKNOWN_ID)
+  }
+
+  /// Returns the token with the given id.
+  pub fn token(&self, id: usize) -> Option<Token<'t, L>> {
+    self.tokens.get(id).cloned()
+  }
+
+  /// Returns the number of times a token was counted. The count of
+  /// `Token::Unknown` includes the counts of tokens removed by
+  /// [`truncate`].
+  ///
+  /// [`truncate`]: #method.truncate
+  pub fn count(&self, token: &Token<'t, L>) -> usize {
+    self.ids.get(token).map_or(0, |&id| self.counts[id])
+  }
+
+  /// Encodes lines of tokens as ids. Tokens outside the vocabulary are
+  /// encoded as [`UNKNOWN_ID`].
+  ///
+  /// [`UNKNOWN_ID`]: constant.UNKNOWN_ID.html
+  pub fn encode<I>(&self, lines: I) -> EncodedDocument
+    where I: IntoIterator,
+          I::Item: AsTokens<'t, L>
+  {
+    EncodedDocument::from_lines(lines.into_iter()
+      .map(|line| line.as_tokens().iter().map(|token| self.id(token)).collect::<Vec<_>>()))
+  }
+
+  /// Decodes ids into tokens. Ids outside the vocabulary are decoded as
+  /// `Token::Unknown`.
+  pub fn decode(&self, ids: &[usize]) -> Vec<Token<'t, L>> {
+    ids.iter().map(|&id| self.token(id).unwrap_or(Token::Unknown)).collect()
+  }
+
+  /// Keeps only the `max_size` most frequent tokens, counting
+  /// `Token::Unknown` among them, and renumbers them from most to least
+  /// frequent, with ties in the order of their previous ids. The counts
+  /// of the tokens removed are added to the count of `Token::Unknown`.
+  ///
+  /// Returns the new id of each previous id, with removed tokens mapped
+  /// to [`UNKNOWN_ID`], for updating encoded documents with
+  /// [`EncodedDocument::remap`].
+  ///
+  /// [`UNKNOWN_ID`]: constant.UNKNOWN_ID.html
+  /// [`EncodedDocument::remap`]: struct.EncodedDocument.html#method.remap
+  pub fn truncate(&mut self, max_size: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (1..self.tokens.len()).collect();
+    order.sort_by(|&a, &b| self.counts[b].cmp(&self.counts[a]).then(a.cmp(&b)));
+    let (kept, removed) = order.split_at(max_size.saturating_sub(1).min(order.len()));
+
+    let mut mapping = vec![UNKNOWN_ID; self.tokens.len()];
+    let mut tokens = vec![Token::Unknown];
+    let mut counts = vec![self.counts[UNKNOWN_ID]
+      + removed.iter().map(|&id| self.counts[id]).sum::<usize>()];
+    for &id in kept {
+      mapping[id] = tokens.len();
+      tokens.push(self.tokens[id]);
+      counts.push(self.counts[id]);
+    }
+
+    self.ids = tokens.iter().enumerate().map(|(id, &token)| (token, id)).collect();
+    self.tokens = tokens;
+    self.counts = counts;
+    mapping
+  }
+
+  /// Removes every token counted fewer than `min_count` times, keeping
+  /// `Token::Unknown`, and renumbers the rest as [`truncate`] does.
+  /// Returns the new id of each previous id, as [`truncate`] does.
+  ///
+  /// ```rust
+  /// let mut vocabulary = Vocabulary::from_document(&training);
+  /// vocabulary.prune(3);
+  /// let testing = unk(testing.tokens(), &vocabulary);
+  /// ```
+  ///
+  /// [`truncate`]: #method.truncate
+  pub fn prune(&mut self, min_count: usize) -> Vec<usize> {
+    let kept = self.counts[1..].iter().filter(|&&count| count >= min_count).count();
+    self.truncate(kept + 1)
+  }
+}
+
+impl<'t, L: Language> TokenSet<'t, L> for Vocabulary<'t, L> {
+  fn contains(&self, token: &Token<'t, L>) -> bool {
+    Vocabulary::contains(self, token)
+  }
+}
+
+impl<'t, L: Language> Default for Vocabulary<'t, L> {
+  fn default() -> Vocabulary<'t, L> {
+    Vocabulary::new()
+  }
+}
+
+/// A vocabulary is serialized as a sequence of its tokens and their
+/// counts, in order of id.
+#[cfg(feature = "serde")]
+impl<'t, L: Language> ::serde::Serialize for Vocabulary<'t, L> {
+  fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(self.tokens.iter().zip(&self.counts))
+  }
+}
+
+/// Tokens are read as by the `Deserialize` implementation of `Token`,
+/// borrowing their words from the input.
+#[cfg(feature = "serde")]
+impl<'de: 't, 't, L: Language> ::serde::Deserialize<'de> for Vocabulary<'t, L> {
+  fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Vocabulary<'t, L>, D::Error> {
+    use serde::de::Error;
+    let entries: Vec<(Token<'t, L>, usize)> = ::serde::Deserialize::deserialize(deserializer)?;
+    match entries.first() {
+      Some(&(Token::Unknown, _)) => {},
+      _ => return Err(D::Error::custom("vocabulary does not begin with `Token::Unknown`"))
+    }
+    let mut vocabulary = Vocabulary { tokens: vec![], counts: vec![], ids: HashMap::new() };
+    for (id, (token, count)) in entries.into_iter().enumerate() {
+      if vocabulary.ids.insert(token, id).is_some() {
+        return Err(D::Error::custom("vocabulary contains a token twice"));
+      }
+      vocabulary.tokens.push(token);
+      vocabulary.counts.push(count);
+    }
+    Ok(vocabulary)
+  }
+}
+
+/// Lines of tokens encoded as ids: by default, the ids of a
+/// [`Vocabulary`], and in an [`IdDocument`], those of an [`Interner`].
+///
+/// [`Vocabulary`]: struct.Vocabulary.html
+/// [`IdDocument`]: type.IdDocument.html
+/// [`Interner`]: struct.Interner.html
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EncodedDocument<I = usize> {
+  ids: Vec<I>,
+  /// The start and end of each line in `ids`.
+  lines: Vec<(usize, usize)>,
+}
+
+impl<I> EncodedDocument<I> {
+  /// Creates a document of the given lines of ids.
+  pub(crate) fn from_lines<J>(lines: J) -> EncodedDocument<I>
+    where J: IntoIterator,
+          J::Item: IntoIterator<Item=I>
+  {
+    let mut ids = vec![];
+    let mut bounds = vec![];
+    for line in lines {
+      let start = ids.len();
+      ids.extend(line);
+      bounds.push((start, ids.len()));
+    }
+    EncodedDocument { ids: ids, lines: bounds }
+  }
+
+  /// Returns the ids of every token of the document.
+  pub fn ids(&self) -> &[I] {
+    &self.ids
+  }
+
+  /// Returns the ids of each line of the document.
+  pub fn lines<'e>(&'e self) -> impl 'e + Iterator<Item=&'e [I]> {
+    self.lines.iter().map(move |&(start, end)| &self.ids[start..end])
+  }
+
+  /// Returns the n-grams of ids within each line of the document, in
+  /// order. Lines shorter than `n` have no n-grams.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `n` is `0`.
+  pub fn ngrams<'e>(&'e self, n: usize) -> impl 'e + Iterator<Item=&'e [I]> {
+    self.lines().flat_map(move |line| line.windows(n))
+  }
+
+  /// Returns the number of lines in the document.
+  pub fn len(&self) -> usize {
+    self.lines.len()
+  }
+
+  /// Returns `true` if the document has no lines.
+  pub fn is_empty(&self) -> bool {
+    self.lines.is_empty()
+  }
+}
+
+impl EncodedDocument {
+  /// Rewrites each id to its entry in `mapping`, such as the mapping
+  /// returned by [`Vocabulary::truncate`]. Ids beyond the end of the
+  /// mapping are rewritten to [`UNKNOWN_ID`].
+  ///
+  /// [`Vocabulary::truncate`]: struct.Vocabulary.html#method.truncate
+  /// [`UNKNOWN_ID`]: constant.UNKNOWN_ID.html
+  pub fn remap(&mut self, mapping: &[usize]) {
+    for id in &mut self.ids {
+      *id = mapping.get(*id).cloned().unwrap_or(UNKNOWN_ID);
+    }
+  }
+}
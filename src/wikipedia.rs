@@ -0,0 +1,362 @@
+use corpus::Document;
+
+use std::io::{self, BufRead};
+
+/// An article of a Wikipedia dump, with its wiki markup stripped.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct WikiArticle {
+  pub title: String,
+  pub text: String,
+}
+
+impl WikiArticle {
+  /// Creates a document of the text of the article, in which each line
+  /// is a paragraph, heading or list item.
+  pub fn document<L>(&self) -> Document<L> {
+    self.text.as_str().into()
+  }
+}
+
+/// A streaming extractor of the articles of a [Wikipedia XML
+/// dump][dumps], such as `enwiki-latest-pages-articles.xml`, which need
+/// not be decompressed to disk: any reader of the XML will do. Only one
+/// page of the dump is held in memory at a time.
+///
+/// Only the pages of the main namespace are extracted, and redirects are
+/// skipped, by default. The text of each article is stripped of wiki
+/// markup by [`strip_wiki_markup`].
+///
+/// ```rust
+/// let dump = BufReader::new(File::open("enwiki-latest-pages-articles.xml")?);
+/// for article in WikiDump::new(dump) {
+///   let article = article?;
+///   let document: Document<English> = article.document();
+///   println!("{}: {} tokens", article.title, document.tokens().len());
+/// }
+/// ```
+///
+/// [dumps]: https://dumps.wikimedia.org/
+/// [`strip_wiki_markup`]: fn.strip_wiki_markup.html
+#[derive(Debug)]
+pub struct WikiDump<R> {
+  reader: R,
+  namespaces: Option<Vec<i64>>,
+  redirects: bool,
+}
+
+impl<R: BufRead> WikiDump<R> {
+  /// Creates an extractor of the articles of the main namespace of a
+  /// dump, skipping redirects.
+  pub fn new(reader: R) -> WikiDump<R> {
+    WikiDump { reader: reader, namespaces: Some(vec![0]), redirects: false }
+  }
+
+  /// Sets the namespaces whose pages are extracted, such as `0` for
+  /// articles and `14` for categories, or `None` for every namespace.
+  pub fn with_namespaces(mut self, namespaces: Option<Vec<i64>>) -> WikiDump<R> {
+    self.namespaces = namespaces;
+    self
+  }
+
+  /// Sets whether redirect pages are extracted. The text of a redirect
+  /// is stripped to `REDIRECT` and the title it redirects to.
+  pub fn with_redirects(mut self, redirects: bool) -> WikiDump<R> {
+    self.redirects = redirects;
+    self
+  }
+
+  /// Consumes the extractor, returning its reader.
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+
+  /// Reads the XML of the next page of the dump, or returns `None` at
+  /// the end of the dump.
+  fn next_page(&mut self) -> io::Result<Option<String>> {
+    let mut page = String::new();
+    let mut line = String::new();
+    loop {
+      line.clear();
+      if self.reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+      }
+      if !page.is_empty() || line.trim_start().starts_with("<page>") {
+        page.push_str(&line);
+        if line.trim_end().ends_with("</page>") {
+          return Ok(Some(page));
+        }
+      }
+    }
+  }
+}
+
+impl<R: BufRead> Iterator for WikiDump<R> {
+  type Item = io::Result<WikiArticle>;
+
+  fn next(&mut self) -> Option<io::Result<WikiArticle>> {
+    loop {
+      let page = match self.next_page() {
+        Ok(Some(page)) => page,
+        Ok(None) => return None,
+        Err(error) => return Some(Err(error))
+      };
+      let namespace = element(&page, "ns").and_then(|ns| ns.trim().parse().ok()).unwrap_or(0);
+      if let Some(ref namespaces) = self.namespaces {
+        if !namespaces.contains(&namespace) {
+          continue;
+        }
+      }
+      if !self.redirects && page.contains("<redirect") {
+        continue;
+      }
+      let title = element(&page, "title").map(unescape).unwrap_or_default();
+      let text = element(&page, "text").map(unescape).unwrap_or_default();
+      return Some(Ok(WikiArticle { title: title, text: strip_wiki_markup(&text) }));
+    }
+  }
+}
+
+/// Returns the contents of the first element named `name` of some XML,
+/// or an empty string if it is empty.
+fn element<'x>(xml: &'x str, name: &str) -> Option<&'x str> {
+  let open = format!("<{}", name);
+  let mut start = 0;
+  while let Some(i) = xml[start..].find(&open) {
+    let tag = start + i;
+    let rest = &xml[tag + open.len()..];
+    // Skip elements whose names merely begin with `name`.
+    if !rest.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+      start = tag + open.len();
+      continue;
+    }
+    let end = rest.find('>')?;
+    if rest[..end].ends_with('/') {
+      return Some("");
+    }
+    let contents = &rest[end + 1..];
+    return contents.find(&format!("</{}>", name)).map(|close| &contents[..close]);
+  }
+  None
+}
+
+/// Replaces the character references of XML, and the common named
+/// references of HTML, with their characters.
+fn unescape(text: &str) -> String {
+  let mut unescaped = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(i) = rest.find('&') {
+    unescaped.push_str(&rest[..i]);
+    rest = &rest[i..];
+    let end = match rest.char_indices().take(12).find(|&(_, c)| c == ';') {
+      Some((end, _)) => end,
+      None => {
+        unescaped.push('&');
+        rest = &rest[1..];
+        continue;
+      }
+    };
+    let character = match &rest[1..end] {
+      "amp" => Some('&'),
+      "lt" => Some('<'),
+      "gt" => Some('>'),
+      "quot" => Some('"'),
+      "apos" => Some('\''),
+      "nbsp" => Some(' '),
+      "ndash" => Some('\u{2013}'),
+      "mdash" => Some('\u{2014}'),
+      reference if reference.starts_with("#x") || reference.starts_with("#X") =>
+        u32::from_str_radix(&reference[2..], 16).ok().and_then(::std::char::from_u32),
+      reference if reference.starts_with('#') =>
+        reference[1..].parse().ok().and_then(::std::char::from_u32),
+      _ => None
+    };
+    match character {
+      Some(character) => {
+        unescaped.push(character);
+        rest = &rest[end + 1..];
+      },
+      None => {
+        unescaped.push('&');
+        rest = &rest[1..];
+      }
+    }
+  }
+  unescaped.push_str(rest);
+  unescaped
+}
+
+/// The elements whose contents are removed along with their tags.
+const REMOVED_ELEMENTS: [&str; 8] =
+  ["ref", "math", "gallery", "syntaxhighlight", "source", "score", "timeline", "references"];
+
+/// The namespaces of links that are removed rather than replaced by
+/// their text.
+const REMOVED_LINKS: [&str; 5] = ["file", "image", "category", "media", "wikipedia"];
+
+/// Strips the wiki markup of the text of a Wikipedia article, leaving
+/// its prose: templates, tables, comments, references, files and
+/// categories are removed; internal and external links are replaced by
+/// their labels; emphasis, heading and list markup is removed, keeping
+/// each heading and list item on a line of its own. Consecutive blank
+/// lines are collapsed.
+///
+/// ```rust
+/// let text = strip_wiki_markup("'''Rust''' is a [[programming language|language]].{{cite}}");
+/// assert_eq!(text, "Rust is a language.");
+/// ```
+pub fn strip_wiki_markup(text: &str) -> String {
+  let text = remove_between(text, "<!--", "-->");
+  let text = remove_nested(&text, "{{", "}}");
+  let text = remove_nested(&text, "{|", "|}");
+  let text = unescape(&remove_tags(&text));
+  let text = replace_links(&text);
+  let text = text.replace("'''", "").replace("''", "");
+
+  let mut lines: Vec<&str> = vec![];
+  for line in text.lines() {
+    let line = line.trim();
+    let line = if line.len() > 1 && line.starts_with('=') && line.ends_with('=') {
+      line.trim_matches('=').trim()
+    } else {
+      line.trim_start_matches(['*', '#', ':', ';']).trim_start()
+    };
+    let magic = line.starts_with("__") && line.ends_with("__");
+    if magic || (line.is_empty() && lines.last().is_none_or(|last| last.is_empty())) {
+      continue;
+    }
+    lines.push(line);
+  }
+  while lines.last() == Some(&"") {
+    lines.pop();
+  }
+  lines.join("\n")
+}
+
+/// Removes every span of text from `open` to the next `close`.
+fn remove_between(text: &str, open: &str, close: &str) -> String {
+  let mut kept = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find(open) {
+    kept.push_str(&rest[..start]);
+    rest = match rest[start..].find(close) {
+      Some(end) => &rest[start + end + close.len()..],
+      None => ""
+    };
+  }
+  kept.push_str(rest);
+  kept
+}
+
+/// Removes every span of text from `open` to its matching `close`,
+/// counting nested pairs.
+fn remove_nested(text: &str, open: &str, close: &str) -> String {
+  let mut kept = String::with_capacity(text.len());
+  let mut depth = 0;
+  let mut i = 0;
+  while i < text.len() {
+    let rest = &text[i..];
+    if rest.starts_with(open) {
+      depth += 1;
+      i += open.len();
+    } else if depth > 0 && rest.starts_with(close) {
+      depth -= 1;
+      i += close.len();
+    } else {
+      let c = rest.chars().next().unwrap_or_default();
+      if depth == 0 {
+        kept.push(c);
+      }
+      i += c.len_utf8();
+    }
+  }
+  kept
+}
+
+/// Removes HTML tags, keeping the text within them, except for the
+/// elements of `REMOVED_ELEMENTS`, which are removed whole.
+fn remove_tags(text: &str) -> String {
+  let mut kept = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find('<') {
+    kept.push_str(&rest[..start]);
+    rest = &rest[start..];
+    let name: String = rest[1..].trim_start_matches('/').chars()
+      .take_while(|c| c.is_ascii_alphanumeric())
+      .collect::<String>()
+      .to_lowercase();
+    let end = match rest.find('>') {
+      Some(end) if !name.is_empty() => end,
+      _ => {
+        kept.push('<');
+        rest = &rest[1..];
+        continue;
+      }
+    };
+    let self_closing = rest[..end].ends_with('/');
+    let closing = rest[1..].starts_with('/');
+    rest = &rest[end + 1..];
+    if REMOVED_ELEMENTS.contains(&name.as_str()) && !self_closing && !closing {
+      let close = format!("</{}", name);
+      rest = match rest.find(&close) {
+        Some(i) => rest[i..].find('>').map_or("", |end| &rest[i + end + 1..]),
+        None => ""
+      };
+    }
+  }
+  kept.push_str(rest);
+  kept
+}
+
+/// Replaces internal links by their labels, or their targets if they
+/// have none, and external links by their labels, removing links to
+/// files and categories, and external links without labels.
+fn replace_links(text: &str) -> String {
+  let mut kept = String::with_capacity(text.len());
+  let mut i = 0;
+  while i < text.len() {
+    let rest = &text[i..];
+    if rest.starts_with("[[") {
+      let end = matching_link(rest);
+      let inner = &rest[2..end];
+      let namespace = inner.split(':').next().unwrap_or("").trim().to_lowercase();
+      if !inner.contains(':') || !REMOVED_LINKS.contains(&namespace.as_str()) {
+        kept.push_str(&replace_links(inner.rsplit('|').next().unwrap_or(inner)));
+      }
+      i += (end + 2).min(rest.len());
+    } else if rest.starts_with("[http") || rest.starts_with("[//") {
+      let end = rest.find(']').unwrap_or(rest.len());
+      if let Some(space) = rest[..end].find(' ') {
+        kept.push_str(rest[space + 1..end].trim());
+      }
+      i += (end + 1).min(rest.len());
+    } else {
+      let c = rest.chars().next().unwrap_or_default();
+      kept.push(c);
+      i += c.len_utf8();
+    }
+  }
+  kept
+}
+
+/// Returns the offset of the `]]` closing the internal link at the start
+/// of `text`, counting links nested within it, such as in the captions
+/// of files, or the length of the text if it is not closed.
+fn matching_link(text: &str) -> usize {
+  let mut depth = 0;
+  let mut i = 0;
+  while i < text.len() {
+    if text[i..].starts_with("[[") {
+      depth += 1;
+      i += 2;
+    } else if text[i..].starts_with("]]") {
+      depth -= 1;
+      if depth == 0 {
+        return i;
+      }
+      i += 2;
+    } else {
+      i += text[i..].chars().next().map_or(1, char::len_utf8);
+    }
+  }
+  text.len()
+}